@@ -0,0 +1,76 @@
+#ifndef JSON_CHECKER_H
+#define JSON_CHECKER_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/*
+ A checker and the results of its last run
+ */
+typedef struct JsonChecker JsonChecker;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/*
+ Create a checker from a JSON config. Relative `json_file` paths are
+ resolved against the working directory. Returns `NULL` on error.
+
+ # Safety
+ `config_json` must be null or a valid NUL-terminated string.
+ */
+JsonChecker *json_checker_new(const char *config_json);
+
+/*
+ Run every rule, keeping the results for [`json_checker_results_json`].
+ Returns the number of failed rules, or `-1` on error.
+
+ # Safety
+ `checker` must be null or a pointer returned by [`json_checker_new`]
+ that hasn't been freed, and not be used from another thread meanwhile.
+ */
+int json_checker_run(JsonChecker *checker);
+
+/*
+ The results of the last run as a JSON array, empty before the first run.
+ Free the string with [`json_checker_string_free`]. Returns `NULL` on error.
+
+ # Safety
+ `checker` must be null or a pointer returned by [`json_checker_new`]
+ that hasn't been freed.
+ */
+char *json_checker_results_json(const JsonChecker *checker);
+
+/*
+ The message of the last error on this thread, or `NULL` if there was
+ none. The string is owned by the library and valid until the next call
+ on this thread.
+ */
+const char *json_checker_last_error(void);
+
+/*
+ Free a checker. Does nothing for `NULL`.
+
+ # Safety
+ `checker` must be null or a pointer returned by [`json_checker_new`]
+ that hasn't been freed.
+ */
+void json_checker_free(JsonChecker *checker);
+
+/*
+ Free a string returned by this library. Does nothing for `NULL`.
+
+ # Safety
+ `text` must be null or a string returned by this library that hasn't
+ been freed.
+ */
+void json_checker_string_free(char *text);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* JSON_CHECKER_H */
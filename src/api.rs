@@ -0,0 +1,260 @@
+//! Validation service: check documents posted over HTTP
+//!
+//! `serve-api` answers `POST /validate` with the results of running a rule
+//! set on the posted `document`. The rules come from the config the service
+//! was started with, or, with `--allow-request-config`, from the request's
+//! `config`. Rules that don't name a document of their own (`json_file`,
+//! `json_inline`, `source` or `applies_to`) check the posted one, so other
+//! services can validate their payloads centrally. `GET /healthz` answers 200
+//! while the service is up.
+//!
+//! A posted config runs on the server, so it may only read the posted
+//! document and what it holds itself: no documents of the server, no
+//! environment variables, no programs, no expressions and no jq selectors.
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use std::net::{TcpListener, TcpStream};
+use std::sync::Arc;
+use std::thread;
+
+use crate::checker::JsonChecker;
+use crate::config::{CheckResult, Config, Rule};
+use crate::rules::CheckRule;
+use crate::serve::{self, Response};
+use crate::source::InlineSource;
+use crate::vars;
+
+/// Largest request body accepted, in bytes
+pub const MAX_BODY: usize = 16 * 1024 * 1024;
+
+/// Scheme of the posted document, which rules without a document read
+const PAYLOAD_SCHEME: &str = "payload";
+
+/// What `serve-api` checks posted documents with
+#[derive(Clone, Default)]
+pub struct Service {
+    /// Checker of the config the service was started with
+    pub preloaded: Option<JsonChecker>,
+    /// Run the rules a request brings as `config`
+    pub allow_request_config: bool,
+}
+
+/// Body of a `POST /validate` request
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ValidateRequest {
+    /// Config to run instead of the service's own, if the service allows it
+    #[serde(default)]
+    pub config: Option<Value>,
+    /// Document the rules without a document of their own check
+    pub document: Value,
+}
+
+/// Point the rules that don't name a document at the posted one
+pub fn bind_payload(config: &mut Config) {
+    for rule in &mut config.rules {
+        if rule.json_file.is_none() && rule.json_inline.is_none() && rule.source.is_none() && rule.applies_to.is_none() {
+            rule.json_file = Some(format!("{}://document", PAYLOAD_SCHEME));
+        }
+    }
+}
+
+/// Run `checker`'s rules with `document` as the payload the rules bound
+/// by [`bind_payload`] read
+pub fn run_payload(checker: &JsonChecker, document: Value) -> crate::error::Result<Vec<CheckResult>> {
+    let payload = InlineSource::new().with_document("document", document);
+    checker.clone().with_source(PAYLOAD_SCHEME, payload).run()
+}
+
+/// Run `checker`'s rules with `document` as the posted document
+pub fn validate(checker: &JsonChecker, document: Value) -> crate::error::Result<Value> {
+    let results = run_payload(checker, document)?;
+    Ok(json!({
+        "passed": results.iter().all(|result| result.passed),
+        "results": results,
+    }))
+}
+
+/// Answer a request with the body's `content_type`
+pub fn respond(method: &str, path: &str, content_type: Option<&str>, body: &[u8], service: &Service) -> Response {
+    match (method, path) {
+        ("GET", "/healthz") => (200, "text/plain", "ok\n".to_string()),
+        ("POST", "/validate") if !is_json(content_type) => {
+            (415, "application/json", json!({"error": "Content-Type must be application/json"}).to_string() + "\n")
+        }
+        ("POST", "/validate") => match validate_request(body, service) {
+            Ok(outcome) => (200, "application/json", outcome.to_string() + "\n"),
+            Err(e) => (400, "application/json", json!({"error": format!("{:#}", e)}).to_string() + "\n"),
+        },
+        (_, "/healthz" | "/validate") => (405, "text/plain", "method not allowed\n".to_string()),
+        _ => (404, "text/plain", "not found\n".to_string()),
+    }
+}
+
+/// Whether a `Content-Type` header names JSON, e.g. `application/json; charset=utf-8`
+fn is_json(content_type: Option<&str>) -> bool {
+    content_type
+        .and_then(|content_type| content_type.split(';').next())
+        .is_some_and(|media_type| media_type.trim().eq_ignore_ascii_case("application/json"))
+}
+
+fn validate_request(body: &[u8], service: &Service) -> Result<Value> {
+    let request: ValidateRequest = serde_json::from_slice(body).context("Invalid request body")?;
+    let checker = match (request.config, &service.preloaded) {
+        (Some(config), _) if service.allow_request_config => request_checker(config)?,
+        (Some(_), _) => anyhow::bail!("The service only runs its own rules; `config` needs --allow-request-config"),
+        (None, Some(checker)) => checker.clone(),
+        (None, None) => anyhow::bail!("The service has no config of its own; pass one as `config`"),
+    };
+    Ok(validate(&checker, request.document)?)
+}
+
+/// Checker of a config posted with a request, refused if it would reach
+/// beyond the posted document and what the config holds
+fn request_checker(raw: Value) -> Result<JsonChecker> {
+    let declared = raw.get("vars").and_then(Value::as_object).cloned().unwrap_or_default();
+    let mut fields = raw.as_object().into_iter().flatten().filter(|(key, _)| key.as_str() != "vars");
+    if let Some(reason) = fields.find_map(|(_, value)| environment_access(value, &declared)) {
+        anyhow::bail!("Request configs can't read environment variables ({})", reason);
+    }
+
+    let mut config = Config::from_json(&raw.to_string()).context("Invalid config")?;
+    if config.scan.is_some() {
+        anyhow::bail!("Request configs can't scan the server's files");
+    }
+    for rule in &config.rules {
+        if let Some(reason) = server_access(rule) {
+            anyhow::bail!("Rule '{}': request configs can't {}", rule.name, reason);
+        }
+    }
+    bind_payload(&mut config);
+    Ok(JsonChecker::from_config(config))
+}
+
+/// The `value_from_env` or `${NAME}` reference to an undeclared variable in
+/// a raw config, which would be read from the server's environment. Strings
+/// in `json_inline` documents count too, although they aren't substituted.
+fn environment_access(raw: &Value, declared: &Map<String, Value>) -> Option<String> {
+    match raw {
+        Value::String(s) => vars::references(s)
+            .into_iter()
+            .find(|name| !declared.contains_key(*name))
+            .map(|name| format!("${{{}}}", name)),
+        Value::Array(items) => items.iter().find_map(|item| environment_access(item, declared)),
+        Value::Object(map) if map.contains_key(vars::VALUE_FROM_ENV) => Some(vars::VALUE_FROM_ENV.to_string()),
+        Value::Object(map) => map.values().find_map(|value| environment_access(value, declared)),
+        _ => None,
+    }
+}
+
+/// What a posted rule would do on the server beyond checking the posted
+/// document, if anything
+fn server_access(rule: &Rule) -> Option<&'static str> {
+    if rule.json_file.is_some() || rule.source.is_some() || rule.applies_to.is_some() {
+        return Some("read documents of their own (json_file, source or applies_to)");
+    }
+    if rule.selector.is_some() {
+        return Some("evaluate jq selectors");
+    }
+    match &rule.check {
+        CheckRule::Command { .. } => Some("run programs (command checks)"),
+        CheckRule::Expr { .. } => Some("evaluate expressions (expr checks)"),
+        check if check.referenced_file().is_some() => Some("compare with other documents (ref_integrity, diff or matches_document)"),
+        _ => None,
+    }
+}
+
+/// Serve `POST /validate` on `listen`, answering each connection on its own
+/// thread. Runs until the process is stopped.
+pub fn serve_api(service: Service, listen: &str) -> Result<()> {
+    let listener = TcpListener::bind(listen).with_context(|| format!("Failed to listen on {}", listen))?;
+    let service = Arc::new(service);
+    for stream in listener.incoming().flatten() {
+        let service = Arc::clone(&service);
+        thread::spawn(move || {
+            // A broken connection only affects its own client
+            let _ = handle(stream, &service);
+        });
+    }
+    Ok(())
+}
+
+fn handle(stream: TcpStream, service: &Service) -> Result<()> {
+    let response = match serve::read_request(&stream, MAX_BODY) {
+        Ok(request) => respond(&request.method, &request.path, request.content_type.as_deref(), &request.body, service),
+        Err(e) => (413, "application/json", json!({"error": format!("{:#}", e)}).to_string() + "\n"),
+    };
+    serve::write_response(&stream, response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn post(body: &Value, service: &Service) -> (u16, Value) {
+        let (status, _, outcome) = respond("POST", "/validate", Some("application/json"), body.to_string().as_bytes(), service);
+        (status, serde_json::from_str(&outcome).unwrap())
+    }
+
+    #[test]
+    fn test_respond() {
+        let config = json!({"rules": [{"name": "Has id", "jsonpath": "$.id", "check": {"type": "non_empty"}}]});
+        let open = Service { preloaded: None, allow_request_config: true };
+        let (status, outcome) = post(&json!({"config": config, "document": {"id": ""}}), &open);
+        assert_eq!(status, 200);
+        assert_eq!(outcome["passed"], false);
+        assert_eq!(outcome["results"][0]["rule_name"], "Has id");
+
+        let mut preloaded = Config::from_json(&config.to_string()).unwrap();
+        bind_payload(&mut preloaded);
+        let service = Service { preloaded: Some(JsonChecker::from_config(preloaded)), allow_request_config: false };
+        let (_, outcome) = post(&json!({"document": {"id": 7}}), &service);
+        assert_eq!(outcome["passed"], true);
+
+        let (status, outcome) = post(&json!({"config": config, "document": {"id": ""}}), &service);
+        assert_eq!(status, 400);
+        assert!(outcome["error"].as_str().unwrap().contains("--allow-request-config"));
+        assert_eq!(post(&json!({"document": {}}), &open).0, 400);
+
+        let json = Some("application/json; charset=utf-8");
+        assert_eq!(respond("POST", "/validate", json, b"not json", &service).0, 400);
+        assert_eq!(respond("POST", "/validate", Some("text/plain"), br#"{"document": {}}"#, &service).0, 415);
+        assert_eq!(respond("POST", "/validate", None, br#"{"document": {}}"#, &service).0, 415);
+        assert_eq!(respond("GET", "/validate", None, b"", &service).0, 405);
+        assert_eq!(respond("GET", "/healthz", None, b"", &service).0, 200);
+    }
+
+    #[test]
+    fn test_request_config_stays_in_the_payload() {
+        let open = Service { preloaded: None, allow_request_config: true };
+        let refused = |rule: Value| {
+            let config = json!({"rules": [rule]});
+            let (status, outcome) = post(&json!({"config": config, "document": {}}), &open);
+            assert_eq!(status, 400, "{}", rule);
+            outcome["error"].as_str().unwrap().to_string()
+        };
+
+        let command = json!({"type": "command", "program": "sh", "args": ["-c", "touch pwned"]});
+        assert!(refused(json!({"name": "r", "jsonpath": "$", "check": command})).contains("run programs"));
+        assert!(refused(json!({"name": "r", "jsonpath": "$", "check": {"type": "expr", "expr": "true"}})).contains("expressions"));
+        let selector = json!({"engine": "jq", "expr": "env"});
+        assert!(refused(json!({"name": "r", "selector": selector, "check": {"type": "empty"}})).contains("jq selectors"));
+        assert!(refused(json!({"name": "r", "json_file": "/etc/passwd", "jsonpath": "$", "check": {"type": "non_empty"}})).contains("json_file"));
+        let reference = json!({"type": "matches_document", "file": "/etc/hosts"});
+        assert!(refused(json!({"name": "r", "jsonpath": "$", "check": reference})).contains("other documents"));
+        let secret = json!({"type": "equals", "value_from_env": "SECRET"});
+        assert!(refused(json!({"name": "r", "jsonpath": "$", "check": secret})).contains("value_from_env"));
+        let secret = json!({"type": "equals", "value": "${HOME}"});
+        assert!(refused(json!({"name": "r", "jsonpath": "$", "check": secret})).contains("${HOME}"));
+
+        let config = json!({
+            "vars": {"ID": "id"},
+            "rules": [{"name": "r", "jsonpath": "$.${ID}", "check": {"type": "equals", "value": "$${HOME}"}}],
+        });
+        let (status, outcome) = post(&json!({"config": config, "document": {"id": "${HOME}"}}), &open);
+        assert_eq!(status, 200);
+        assert_eq!(outcome["passed"], true);
+    }
+}
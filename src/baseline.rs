@@ -0,0 +1,188 @@
+//! Baselines of known failures
+//!
+//! A baseline records a fingerprint of every failure of a run. Later runs
+//! filtered through it only fail on new violations, so the checker can be
+//! adopted on legacy data one fix at a time.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+
+use crate::config::{CheckResult, Status};
+use crate::messages::Message;
+
+/// Fingerprints of the failures that are known and tolerated
+#[derive(Debug, Default, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Baseline {
+    pub fingerprints: BTreeSet<String>,
+}
+
+impl Baseline {
+    /// Record every failure in `results`
+    pub fn from_results(results: &[CheckResult]) -> Self {
+        Baseline {
+            fingerprints: results.iter().flat_map(fingerprints).collect(),
+        }
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path).with_context(|| {
+            format!("Failed to read baseline {} (create it with --update-baseline)", path.display())
+        })?;
+        serde_json::from_str(&content).with_context(|| format!("Failed to parse baseline {}", path.display()))
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let content = serde_json::to_string_pretty(self)?;
+        fs::write(path, content + "\n").with_context(|| format!("Failed to write baseline {}", path.display()))
+    }
+
+    /// Remove known failures from `results`.
+    ///
+    /// Known invalid nodes are dropped from each failed result, and a result
+    /// whose failures are all known passes. A result that lists only some of
+    /// its failures never passes, since the ones left out may be new. The
+    /// number of suppressed failures is kept in `CheckResult::baselined`.
+    pub fn apply(&self, results: &mut [CheckResult]) {
+        for result in results.iter_mut().filter(|r| !r.passed) {
+            if result.invalid_positions.is_empty() {
+                if self.fingerprints.contains(&fingerprint(&result.rule_name, "", &headline(result))) {
+                    result.baselined = 1;
+                    result.status = Status::Pass;
+                    result.passed = true;
+                    result.set_lines(vec![Message::PassedInBaseline { rule: result.rule_name.clone() }]);
+                }
+                continue;
+            }
+
+            let known: Vec<bool> = result
+                .invalid_positions
+                .iter()
+                .enumerate()
+                .map(|(i, path)| {
+                    let value = result.values_found.get(i).unwrap_or(&Value::Null);
+                    self.fingerprints.contains(&fingerprint(&result.rule_name, path, value))
+                })
+                .collect();
+            result.baselined = known.iter().filter(|&&k| k).count();
+            if result.baselined == 0 {
+                continue;
+            }
+
+            let truncated = result.lines.iter().any(|line| matches!(line, Message::Truncated { .. }));
+            if result.baselined == known.len() && !truncated {
+                result.status = Status::Pass;
+                result.passed = true;
+                let count = result.baselined;
+                result.set_lines(vec![Message::PassedWithKnownFailures { rule: result.rule_name.clone(), count }]);
+            } else if result.lines.is_empty() {
+                result.message.push_str(&format!(
+                    "\n   {} known failure(s) suppressed by the baseline",
+                    result.baselined
+                ));
+            } else {
+                let mut lines = std::mem::take(&mut result.lines);
+                lines.push(Message::BaselineSuppressed { count: result.baselined });
+                result.set_lines(lines);
+            }
+            retain_new(&mut result.invalid_positions, &known);
+            retain_new(&mut result.values_found, &known);
+            retain_new(&mut result.locations, &known);
+        }
+    }
+}
+
+/// Keep the elements whose failure is not known; shorter vectors are left
+/// as they are so parallel fields stay aligned
+fn retain_new<T>(items: &mut Vec<T>, known: &[bool]) {
+    if items.len() == known.len() {
+        let mut flags = known.iter();
+        items.retain(|_| !flags.next().copied().unwrap_or(false));
+    }
+}
+
+/// Fingerprints of the failures of a result: one per invalid node, or one
+/// for the whole rule when it failed without pointing at nodes
+pub fn fingerprints(result: &CheckResult) -> Vec<String> {
+    if result.passed {
+        return Vec::new();
+    }
+    if result.invalid_positions.is_empty() {
+        return vec![fingerprint(&result.rule_name, "", &headline(result))];
+    }
+
+    result
+        .invalid_positions
+        .iter()
+        .enumerate()
+        .map(|(i, path)| fingerprint(&result.rule_name, path, result.values_found.get(i).unwrap_or(&Value::Null)))
+        .collect()
+}
+
+/// Stable fingerprint of a failure: rule name, path and a hash of the value
+pub fn fingerprint(rule_name: &str, path: &str, value: &Value) -> String {
+    format!("{}|{}|{:016x}", rule_name, path, fnv1a(value.to_string().as_bytes()))
+}
+
+fn headline(result: &CheckResult) -> Value {
+    Value::String(result.message.lines().next().unwrap_or_default().to_string())
+}
+
+/// 64-bit FNV-1a; unlike `DefaultHasher` it is stable across Rust releases
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn failed(positions: &[(&str, Value)]) -> CheckResult {
+        let (paths, values) = positions.iter().map(|(p, v)| (p.to_string(), v.clone())).unzip();
+        CheckResult::with_positions("Ages".to_string(), false, "✗ Rule 'Ages' failed".to_string(), paths, values)
+    }
+
+    #[test]
+    fn test_only_new_failures_remain() {
+        let baseline = Baseline::from_results(&[failed(&[("$.users[0].age", json!(-1))])]);
+        assert_eq!(fnv1a(b"a"), 0xaf63dc4c8601ec8c);
+
+        let mut results = vec![
+            failed(&[("$.users[0].age", json!(-1))]),
+            failed(&[("$.users[0].age", json!(-1)), ("$.users[3].age", json!(-7))]),
+            failed(&[("$.users[0].age", json!(-2))]),
+        ];
+        baseline.apply(&mut results);
+
+        assert!(results[0].passed);
+        assert_eq!(results[0].baselined, 1);
+        assert!(!results[1].passed);
+        assert_eq!(results[1].invalid_positions, vec!["$.users[3].age"]);
+        assert_eq!(results[1].values_found, vec![json!(-7)]);
+        assert!(results[1].message.contains("1 known failure(s) suppressed"));
+        // The value changed, so it's a new failure
+        assert!(!results[2].passed);
+    }
+
+    #[test]
+    fn test_truncated_result_never_passes() {
+        let baseline = Baseline::from_results(&[failed(&[("$.users[0].age", json!(-1))])]);
+        let mut result = failed(&[("$.users[0].age", json!(-1)), ("$.users[5].age", json!(-99))]);
+        result.set_lines(vec![Message::Text { text: "✗ Rule 'Ages' failed".to_string() }]);
+        result.truncate_failures(1);
+
+        let mut results = vec![result];
+        baseline.apply(&mut results);
+        assert!(!results[0].passed);
+        assert_eq!(results[0].baselined, 1);
+        assert!(results[0].message.contains("2 invalid nodes; showing the first 1"));
+    }
+}
@@ -0,0 +1,393 @@
+//! Builders for configuring rules, configs and checkers in code
+//!
+//! Library users who don't keep their rules in a config file can assemble
+//! them with [`Rule::builder`] and [`Config::builder`]. `build` checks what
+//! serde would have caught for a file and runs the same variable
+//! substitution, template expansion and JSONPath compilation as
+//! [`Config::from_file`]:
+//!
+//! ```
+//! use json_checker_rs::{CheckRule, Config, JsonChecker, Rule};
+//!
+//! let rule = Rule::builder()
+//!     .name("Has users")
+//!     .inline(serde_json::json!({"users": ["ann"]}))
+//!     .path("$.users")
+//!     .check(CheckRule::NonEmpty)
+//!     .build()?;
+//! let config = Config::builder().rule(rule).build()?;
+//! let checker = JsonChecker::builder().config(config).parallelism(4).build();
+//! assert!(checker.run()?[0].passed);
+//! # Ok::<(), json_checker_rs::CheckerError>(())
+//! ```
+
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+use crate::canonicalize::Canonicalize;
+use crate::capture::CaptureValues;
+use crate::checker::JsonChecker;
+use crate::config::{Config, Rule, Severity};
+use crate::error::{self, CheckerError};
+use crate::format::DocumentFormat;
+use crate::jsonpath::JsonPathEngine;
+use crate::query::QuerySource;
+use crate::parser::ParserOptions;
+use crate::retry::RetryPolicy;
+use crate::rules::CheckRule;
+use crate::scan::ScanConfig;
+use crate::schedule::RunCondition;
+use crate::source::DocumentSource;
+use crate::transform::Transform;
+
+impl Rule {
+    /// Start building a rule in code
+    pub fn builder() -> RuleBuilder {
+        RuleBuilder::default()
+    }
+}
+
+impl Config {
+    /// Start building a configuration in code
+    pub fn builder() -> ConfigBuilder {
+        ConfigBuilder::default()
+    }
+}
+
+impl JsonChecker {
+    /// Start building a checker with caching, parallelism or custom sources
+    pub fn builder() -> CheckerBuilder {
+        CheckerBuilder::default()
+    }
+}
+
+/// Builds a [`Rule`]; a name, a document, a path and a check are required
+#[derive(Debug, Default)]
+pub struct RuleBuilder {
+    rule: Rule,
+    check: Option<CheckRule>,
+}
+
+impl RuleBuilder {
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.rule.name = name.into();
+        self
+    }
+
+    /// Check the document at this path or URI; a glob checks every matching file
+    pub fn file(mut self, uri: impl Into<String>) -> Self {
+        self.rule.json_file = Some(uri.into());
+        self
+    }
+
+    /// Check a document given directly instead of a file
+    pub fn inline(mut self, document: Value) -> Self {
+        self.rule.json_inline = Some(document);
+        self
+    }
+
+    /// Check the rows returned by a database query
+    pub fn source(mut self, source: QuerySource) -> Self {
+        self.rule.source = Some(source);
+        self
+    }
+
+    /// Check the scanned files matching this pattern
+    pub fn applies_to(mut self, pattern: impl Into<String>) -> Self {
+        self.rule.applies_to = Some(pattern.into());
+        self
+    }
+
+    pub fn format(mut self, format: DocumentFormat) -> Self {
+        self.rule.format = Some(format);
+        self
+    }
+
+    /// JSONPath selecting the values to check
+    pub fn path(mut self, jsonpath: impl Into<String>) -> Self {
+        self.rule.jsonpath = jsonpath.into();
+        self
+    }
+
+    /// JSON Pointer to the node to check, used instead of a JSONPath
+    pub fn pointer(mut self, pointer: impl Into<String>) -> Self {
+        self.rule.json_pointer = Some(pointer.into());
+        self
+    }
+
+    pub fn engine(mut self, engine: JsonPathEngine) -> Self {
+        self.rule.jsonpath_engine = Some(engine);
+        self
+    }
+
+    pub fn check(mut self, check: CheckRule) -> Self {
+        self.check = Some(check);
+        self
+    }
+
+    pub fn severity(mut self, severity: Severity) -> Self {
+        self.rule.severity = severity;
+        self
+    }
+
+    /// Report the rule as skipped for `reason` instead of running it
+    pub fn disabled(mut self, reason: impl Into<String>) -> Self {
+        self.rule.enabled = Some(false);
+        self.rule.skip_reason = Some(reason.into());
+        self
+    }
+
+    /// Suite the rule is summarized with
+    pub fn suite(mut self, suite: impl Into<String>) -> Self {
+        self.rule.suite = Some(suite.into());
+        self
+    }
+
+    /// How to fix a failure, shown with the rule's failures
+    pub fn hint(mut self, hint: impl Into<String>) -> Self {
+        self.rule.hint = Some(hint.into());
+        self
+    }
+
+    pub fn docs_url(mut self, url: impl Into<String>) -> Self {
+        self.rule.docs_url = Some(url.into());
+        self
+    }
+
+    /// Only run once the named rule has passed
+    pub fn depends_on(mut self, rule: impl Into<String>) -> Self {
+        self.rule.depends_on.push(rule.into());
+        self
+    }
+
+    pub fn run_if(mut self, condition: RunCondition) -> Self {
+        self.rule.run_if = Some(condition);
+        self
+    }
+
+    /// Transform each selected value before the check; transforms apply in the order added
+    pub fn transform(mut self, transform: Transform) -> Self {
+        self.rule.transform.push(transform);
+        self
+    }
+
+    pub fn canonicalize(mut self, options: Canonicalize) -> Self {
+        self.rule.canonicalize = options;
+        self
+    }
+
+    pub fn capture_values(mut self, capture: CaptureValues) -> Self {
+        self.rule.capture_values = Some(capture);
+        self
+    }
+
+    pub fn protobuf_mode(mut self, protobuf_mode: bool) -> Self {
+        self.rule.protobuf_mode = protobuf_mode;
+        self
+    }
+
+    pub fn stream(mut self, stream: bool) -> Self {
+        self.rule.stream = stream;
+        self
+    }
+
+    pub fn retry(mut self, policy: RetryPolicy) -> Self {
+        self.rule.retry = Some(policy);
+        self
+    }
+
+    pub fn timeout_ms(mut self, timeout_ms: u64) -> Self {
+        self.rule.timeout_ms = Some(timeout_ms);
+        self
+    }
+
+    pub fn parser(mut self, options: ParserOptions) -> Self {
+        self.rule.parser = Some(options);
+        self
+    }
+
+    /// Finish the rule, failing if a required part is missing
+    pub fn build(self) -> error::Result<Rule> {
+        let RuleBuilder { mut rule, check } = self;
+        let missing = |what: &str| {
+            let name = if rule.name.is_empty() { "<unnamed>" } else { rule.name.as_str() };
+            Err(CheckerError::InvalidConfig(format!("Rule '{}': {}", name, what)))
+        };
+        if rule.name.is_empty() {
+            return missing("needs a name");
+        }
+        if rule.json_file.is_none() && rule.json_inline.is_none() && rule.source.is_none() && rule.applies_to.is_none() {
+            return missing("needs a file, inline document, source or applies_to pattern");
+        }
+        if rule.jsonpath.is_empty() && rule.json_pointer.is_none() {
+            return missing("needs a path or pointer");
+        }
+        let Some(check) = check else {
+            return missing("needs a check");
+        };
+        rule.check = check;
+        Ok(rule)
+    }
+}
+
+/// Builds a [`Config`] from rules and the settings a config file can hold
+#[derive(Debug, Default)]
+pub struct ConfigBuilder {
+    config: Config,
+}
+
+impl ConfigBuilder {
+    pub fn rule(mut self, rule: Rule) -> Self {
+        self.config.rules.push(rule);
+        self
+    }
+
+    pub fn rules(mut self, rules: impl IntoIterator<Item = Rule>) -> Self {
+        self.config.rules.extend(rules);
+        self
+    }
+
+    /// Define a variable available as `${NAME}` in rules
+    pub fn var(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.config.vars.insert(name.into(), value.into());
+        self
+    }
+
+    /// Define a check template rules can refer to with a `template` check
+    pub fn check_template(mut self, name: impl Into<String>, check: Value) -> Self {
+        self.config.check_templates.insert(name.into(), check);
+        self
+    }
+
+    pub fn scan(mut self, scan: ScanConfig) -> Self {
+        self.config.scan = Some(scan);
+        self
+    }
+
+    pub fn jsonpath_engine(mut self, engine: JsonPathEngine) -> Self {
+        self.config.jsonpath_engine = Some(engine);
+        self
+    }
+
+    /// Retry policy for rules that don't set their own
+    pub fn retry(mut self, policy: RetryPolicy) -> Self {
+        self.config.retry = Some(policy);
+        self
+    }
+
+    /// Time limit for rules that don't set their own
+    pub fn timeout_ms(mut self, timeout_ms: u64) -> Self {
+        self.config.timeout_ms = Some(timeout_ms);
+        self
+    }
+
+    /// Parser options for rules that don't set their own
+    pub fn parser(mut self, options: ParserOptions) -> Self {
+        self.config.parser = Some(options);
+        self
+    }
+
+    /// Finish the config, substituting variables, expanding templates and
+    /// compiling JSONPaths like a config loaded from a file
+    pub fn build(self) -> error::Result<Config> {
+        let mut config = self.config;
+        let mut names = HashSet::new();
+        if let Some(rule) = config.rules.iter().find(|rule| !names.insert(rule.name.as_str())) {
+            return Err(CheckerError::InvalidConfig(format!("Duplicate rule name '{}'", rule.name)));
+        }
+        config.resolve()?;
+        Ok(config)
+    }
+}
+
+/// Builds a [`JsonChecker`] with options a config file doesn't cover
+#[derive(Default)]
+pub struct CheckerBuilder {
+    config: Config,
+    sources: Vec<(String, Arc<dyn DocumentSource>)>,
+    cache_documents: bool,
+    parallelism: Option<usize>,
+    max_failures_shown: Option<usize>,
+}
+
+impl CheckerBuilder {
+    pub fn config(mut self, config: Config) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Register the document source for `json_file` URIs with this scheme,
+    /// like [`JsonChecker::with_source`]
+    pub fn source(mut self, scheme: impl Into<String>, source: impl DocumentSource + 'static) -> Self {
+        self.sources.push((scheme.into(), Arc::new(source)));
+        self
+    }
+
+    /// Read each document once, however many rules check it. The cache
+    /// lives as long as the checker, so documents changing between runs of
+    /// the same checker aren't seen
+    pub fn cache_documents(mut self, cache: bool) -> Self {
+        self.cache_documents = cache;
+        self
+    }
+
+    /// Evaluate up to `threads` rules at the same time. Rules still wait for
+    /// the rules they depend on, and results keep the config's order
+    pub fn parallelism(mut self, threads: usize) -> Self {
+        self.parallelism = Some(threads);
+        self
+    }
+
+    /// List at most `count` failing nodes per result; a failed result notes
+    /// how many there were in all. Only the listed nodes' paths are looked
+    /// up, which keeps failures of huge selections cheap
+    pub fn max_failures_shown(mut self, count: usize) -> Self {
+        self.max_failures_shown = Some(count);
+        self
+    }
+
+    pub fn build(self) -> JsonChecker {
+        let mut checker = JsonChecker::from_config(self.config);
+        Arc::make_mut(&mut checker.sources).extend(self.sources);
+        if self.cache_documents {
+            checker.documents = Some(Arc::new(Mutex::new(HashMap::new())));
+        }
+        if let Some(threads) = self.parallelism {
+            checker.parallelism = threads.max(1);
+        }
+        checker.max_failures_shown = self.max_failures_shown;
+        checker
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_rule_builder_requires_parts() {
+        let rule = Rule::builder().name("Has users").file("users.json").path("$.users").check(CheckRule::NonEmpty).build();
+        let rule = rule.unwrap();
+        assert_eq!(rule.json_file.as_deref(), Some("users.json"));
+        assert!(matches!(rule.check, CheckRule::NonEmpty));
+
+        let err = Rule::builder().name("No path").inline(json!({})).check(CheckRule::NonEmpty).build().unwrap_err();
+        assert_eq!(err.to_string(), "Rule 'No path': needs a path or pointer");
+        let err = Rule::builder().name("No check").inline(json!({})).path("$").build().unwrap_err();
+        assert_eq!(err.to_string(), "Rule 'No check': needs a check");
+    }
+
+    #[test]
+    fn test_config_builder_resolves_rules() {
+        let rule = |name: &str| {
+            Rule::builder().name(name).file("${DIR}/users.json").path("$.users").check(CheckRule::NonEmpty).build().unwrap()
+        };
+        let config = Config::builder().var("DIR", "data").rule(rule("a")).build().unwrap();
+        assert_eq!(config.rules[0].json_file.as_deref(), Some("data/users.json"));
+
+        let err = Config::builder().var("DIR", "data").rules([rule("a"), rule("a")]).build().unwrap_err();
+        assert_eq!(err.to_string(), "Duplicate rule name 'a'");
+    }
+}
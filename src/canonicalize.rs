@@ -0,0 +1,104 @@
+//! Canonical forms of values for comparisons
+//!
+//! A rule's `canonicalize` options rewrite both the selected values and the
+//! values written in its check before they are compared, so `" Admin "`
+//! can equal `"admin"` and `1.0` can equal `1`. The same options can be
+//! applied to both sides of a [`diff`](crate::diff::diff).
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Number, Value};
+
+use crate::rules::CheckRule;
+
+/// How values are normalized before they are compared
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, JsonSchema, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Canonicalize {
+    /// Order object keys alphabetically, which matters where values are
+    /// compared in serialized form (`unique`, `ref_integrity`)
+    pub sort_keys: bool,
+    /// Strip leading and trailing whitespace from strings
+    pub trim: bool,
+    /// Compare strings case-insensitively
+    pub ignore_case: bool,
+    /// Write whole floats as integers, so `1.0` equals `1`
+    pub normalize_numbers: bool,
+}
+
+impl Canonicalize {
+    /// Whether these options leave every value unchanged
+    pub fn is_identity(&self) -> bool {
+        *self == Canonicalize::default()
+    }
+
+    /// The canonical form of `value`, applied recursively
+    pub fn apply(&self, value: &Value) -> Value {
+        match value {
+            Value::String(s) => Value::String(self.string(s)),
+            Value::Number(n) => Value::Number(self.number(n)),
+            Value::Array(items) => Value::Array(items.iter().map(|item| self.apply(item)).collect()),
+            Value::Object(map) => {
+                let mut entries: Vec<(&String, &Value)> = map.iter().collect();
+                if self.sort_keys {
+                    entries.sort_by_key(|(key, _)| *key);
+                }
+                Value::Object(entries.into_iter().map(|(key, v)| (key.clone(), self.apply(v))).collect::<Map<_, _>>())
+            }
+            other => other.clone(),
+        }
+    }
+
+    /// `check` with the values it compares against in canonical form
+    pub fn apply_to_check(&self, check: &CheckRule) -> CheckRule {
+        let mut check = check.clone();
+        match &mut check {
+            CheckRule::Equals { value, .. }
+            | CheckRule::NotEquals { value }
+            | CheckRule::Contains { value }
+            | CheckRule::ContainedBy { value }
+            | CheckRule::JsonbContains { value }
+            | CheckRule::JsonbContainedBy { value } => *value = self.apply(value),
+            CheckRule::StartsWith { value } | CheckRule::EndsWith { value } if self.ignore_case => {
+                *value = value.to_lowercase();
+            }
+            _ => {}
+        }
+        check
+    }
+
+    fn string(&self, s: &str) -> String {
+        let s = if self.trim { s.trim() } else { s };
+        if self.ignore_case { s.to_lowercase() } else { s.to_string() }
+    }
+
+    fn number(&self, n: &Number) -> Number {
+        match n.as_f64() {
+            Some(f) if self.normalize_numbers && n.is_f64() && f.fract() == 0.0 => {
+                if f >= 0.0 && f <= u64::MAX as f64 {
+                    Number::from(f as u64)
+                } else if f >= i64::MIN as f64 {
+                    Number::from(f as i64)
+                } else {
+                    n.clone()
+                }
+            }
+            _ => n.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_apply() {
+        let options = Canonicalize { sort_keys: true, trim: true, ignore_case: true, normalize_numbers: true };
+        let canonical = options.apply(&json!({"b": [" Admin ", 1.0, 1.5], "a": -2.0}));
+        assert_eq!(canonical, json!({"a": -2, "b": ["admin", 1, 1.5]}));
+        assert_eq!(canonical.to_string(), r#"{"a":-2,"b":["admin",1,1.5]}"#);
+        assert_eq!(Canonicalize::default().apply(&json!(1.0)), json!(1.0));
+    }
+}
@@ -0,0 +1,109 @@
+//! How much of each failing value a result keeps
+//!
+//! `CheckResult::values_found` holds the failing values. Cloning every one
+//! of them doubles the memory of a rule failing on a huge selection, so by
+//! default a value is kept whole only when it is small: long strings are cut
+//! short, and large arrays and objects are replaced by a summary of their
+//! type, size and the start of their JSON. A rule with
+//! `"capture_values": "full"` keeps its failing values whole.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::io::{self, Write};
+
+/// Bytes of JSON a value may take before it is summarized
+pub const PREVIEW_BYTES: usize = 200;
+
+/// How failing values are stored in results
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, JsonSchema, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CaptureValues {
+    /// Small values whole, summaries of large ones
+    #[default]
+    Summary,
+    /// Every value whole
+    Full,
+}
+
+impl CaptureValues {
+    /// The form of `value` stored in a result
+    pub fn capture(self, value: &Value) -> Value {
+        match self {
+            CaptureValues::Full => value.clone(),
+            CaptureValues::Summary => summarize(value),
+        }
+    }
+}
+
+/// `value` if it is small, otherwise a shortened string or a summary like
+/// `{"type": "array", "length": 1000, "preview": "[1,2,3,…"}`
+pub fn summarize(value: &Value) -> Value {
+    match value {
+        Value::String(s) if s.len() > PREVIEW_BYTES => Value::String(format!("{}…", truncate(s, PREVIEW_BYTES))),
+        Value::Array(_) | Value::Object(_) => {
+            let mut preview = Preview(Vec::with_capacity(PREVIEW_BYTES));
+            // Serialization stops with an error once the preview is full
+            if serde_json::to_writer(&mut preview, value).is_ok() {
+                return value.clone();
+            }
+            let (kind, length) = match value {
+                Value::Array(items) => ("array", items.len()),
+                Value::Object(map) => ("object", map.len()),
+                _ => unreachable!(),
+            };
+            let text = String::from_utf8_lossy(&preview.0);
+            json!({"type": kind, "length": length, "preview": format!("{}…", truncate(&text, PREVIEW_BYTES))})
+        }
+        _ => value.clone(),
+    }
+}
+
+/// The longest prefix of `s` of at most `max` bytes ending on a character boundary
+fn truncate(s: &str, max: usize) -> &str {
+    let mut end = max.min(s.len());
+    while !s.is_char_boundary(end) {
+        end -= 1;
+    }
+    &s[..end]
+}
+
+/// Buffer that refuses writes beyond `PREVIEW_BYTES`
+struct Preview(Vec<u8>);
+
+impl Write for Preview {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let room = PREVIEW_BYTES - self.0.len();
+        if buf.len() > room {
+            self.0.extend_from_slice(&buf[..room]);
+            return Err(io::Error::other("preview is full"));
+        }
+        self.0.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summarize() {
+        assert_eq!(summarize(&json!({"id": 1})), json!({"id": 1}));
+        assert_eq!(summarize(&json!(3.5)), json!(3.5));
+
+        let summary = summarize(&json!((0..1000).collect::<Vec<_>>()));
+        assert_eq!(summary["type"], "array");
+        assert_eq!(summary["length"], 1000);
+        assert!(summary["preview"].as_str().unwrap().starts_with("[0,1,2,"));
+
+        let long = "é".repeat(PREVIEW_BYTES);
+        let short = summarize(&json!(long));
+        assert_eq!(short.as_str().unwrap().chars().count(), PREVIEW_BYTES / 2 + 1);
+        assert_eq!(CaptureValues::Full.capture(&json!(long)), json!(long));
+    }
+}
@@ -1,441 +1,1806 @@
-//! Core JSON validation logic
-
-use anyhow::{Context, Result};
-use serde_json::Value;
-use std::fs;
-
-use crate::config::{Config, Rule, CheckResult};
-use crate::rules::CheckRule;
-
-/// Main JSON validation engine
-pub struct JsonChecker {
-    config: Config,
-}
-
-impl JsonChecker {
-    /// Create a new JsonChecker from a configuration file
-    pub fn new(config_path: &str) -> Result<Self> {
-        let config_content = fs::read_to_string(config_path)
-            .context("Failed to read config file")?;
-        let config: Config = serde_json::from_str(&config_content)
-            .context("Failed to parse config")?;
-        
-        Ok(JsonChecker { config })
-    }
-
-    /// Create a new JsonChecker from a Config struct
-    pub fn from_config(config: Config) -> Self {
-        JsonChecker { config }
-    }
-
-    /// Run all validation rules and return results
-    pub fn run(&self) -> Result<Vec<CheckResult>> {
-        let mut results = Vec::new();
-
-        for rule in &self.config.rules {
-            match self.check_rule(rule) {
-                Ok(result) => results.push(result),
-                Err(e) => {
-                    results.push(CheckResult::new(
-                        rule.name.clone(),
-                        false,
-                        format!("Error: {}", e),
-                    ));
-                }
-            }
-        }
-
-        Ok(results)
-    }
-
-    /// Execute a single validation rule
-    pub fn check_rule(&self, rule: &Rule) -> Result<CheckResult> {
-        // Read JSON file
-        let json_content = fs::read_to_string(&rule.json_file)
-            .context(format!("Failed to read file: {}", rule.json_file))?;
-        
-        let json: Value = serde_json::from_str(&json_content)
-            .context("Failed to parse JSON")?;
-
-        // Apply JSONPath
-        let mut selector = jsonpath_lib::selector(&json);
-        let selected = selector(&rule.jsonpath)
-            .context(format!("JSONPath query failed: {}", rule.jsonpath))?;
-
-        // Check rule
-        let passed = self.apply_check(&selected, &rule.check)?;
-        
-        let message = if passed {
-            format!("✓ Rule '{}' passed", rule.name)
-        } else {
-            let mut error_msg = format!("✗ Rule '{}' failed at JSONPath '{}'", rule.name, rule.jsonpath);
-            
-            // Find and show invalid values with their exact positions
-            if !selected.is_empty() {
-                let invalid_indices = self.find_invalid_value_indices(&selected, &rule.check);
-                
-                if !invalid_indices.is_empty() {
-                    error_msg.push_str("\n   Invalid nodes found at:");
-                    
-                    // Use a set to track unique paths and avoid duplicates
-                    let mut unique_paths = std::collections::HashSet::new();
-                    
-                    for &index in &invalid_indices {
-                        if index < selected.len() {
-                            let invalid_value = selected[index];
-                            // Use JSONPath context to find the correct path for this specific selected value
-                            if let Some(path) = self.find_path_for_selected_value(&json, &rule.jsonpath, index) {
-                                let path_with_value = match invalid_value {
-                                    Value::String(s) => format!("{} = \"{}\"", path, s),
-                                    Value::Number(n) => format!("{} = {}", path, n),
-                                    Value::Bool(b) => format!("{} = {}", path, b),
-                                    Value::Null => format!("{} = null", path),
-                                    Value::Array(arr) => format!("{} = array[{}]", path, arr.len()),
-                                    Value::Object(obj) => format!("{} = object{{{}}}", path, obj.len()),
-                                };
-                                
-                                // Only add if we haven't seen this path+value combination before
-                                if unique_paths.insert(path_with_value.clone()) {
-                                    error_msg.push_str(&format!("\n   • {}", path_with_value));
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-            
-            error_msg
-        };
-
-        Ok(CheckResult::new(rule.name.clone(), passed, message))
-    }
-
-    /// Apply a check rule to a set of JSON values
-    pub fn apply_check(&self, values: &[&Value], check: &CheckRule) -> Result<bool> {
-        // Check if no values found
-        if values.is_empty() {
-            return Ok(matches!(check, CheckRule::Empty));
-        }
-
-        match check {
-            CheckRule::Empty => {
-                // Check if all values are "empty" (null, empty string, empty array, empty object)
-                Ok(values.iter().all(|v| self.is_empty_value(v)))
-            }
-            CheckRule::NonEmpty => {
-                // Check if all values are non-empty
-                Ok(values.iter().all(|v| !self.is_empty_value(v)))
-            }
-            
-            CheckRule::Equals { value } => {
-                Ok(values.iter().any(|v| *v == value))
-            }
-            
-            CheckRule::NotEquals { value } => {
-                Ok(values.iter().all(|v| *v != value))
-            }
-            
-            CheckRule::Contains { value } => {
-                Ok(values.iter().any(|v| self.contains(v, value)))
-            }
-            
-            CheckRule::ContainedBy { value } => {
-                Ok(values.iter().all(|v| self.contains(value, v)))
-            }
-            
-            CheckRule::JsonbContains { value } => {
-                // PostgreSQL @> operator: left contains right
-                Ok(values.iter().any(|v| self.jsonb_contains(v, value)))
-            }
-            
-            CheckRule::JsonbContainedBy { value } => {
-                // PostgreSQL <@ operator: left is contained by right
-                Ok(values.iter().all(|v| self.jsonb_contains(value, v)))
-            }
-            
-            CheckRule::JsonbExists { key } => {
-                Ok(values.iter().any(|v| {
-                    if let Value::Object(obj) = v {
-                        obj.contains_key(key)
-                    } else {
-                        false
-                    }
-                }))
-            }
-            
-            CheckRule::JsonbExistsAny { keys } => {
-                Ok(values.iter().any(|v| {
-                    if let Value::Object(obj) = v {
-                        keys.iter().any(|k| obj.contains_key(k))
-                    } else {
-                        false
-                    }
-                }))
-            }
-            
-            CheckRule::JsonbExistsAll { keys } => {
-                Ok(values.iter().any(|v| {
-                    if let Value::Object(obj) = v {
-                        keys.iter().all(|k| obj.contains_key(k))
-                    } else {
-                        false
-                    }
-                }))
-            }
-            
-            CheckRule::JsonbPathMatch { path: _ } => {
-                // Simplified JSONPath matching (would need jsonpath parser for full impl)
-                Ok(true)
-            }
-            
-            CheckRule::Regex { pattern } => {
-                let re = regex::Regex::new(pattern)?;
-                Ok(values.iter().any(|v| {
-                    if let Value::String(s) = v {
-                        re.is_match(s)
-                    } else {
-                        false
-                    }
-                }))
-            }
-            
-            CheckRule::GreaterThan { value } => {
-                Ok(values.iter().any(|v| {
-                    if let Some(n) = v.as_f64() {
-                        n > *value
-                    } else {
-                        false
-                    }
-                }))
-            }
-            
-            CheckRule::LessThan { value } => {
-                Ok(values.iter().any(|v| {
-                    if let Some(n) = v.as_f64() {
-                        n < *value
-                    } else {
-                        false
-                    }
-                }))
-            }
-            
-            CheckRule::ArrayLength { min, max } => {
-                Ok(values.iter().any(|v| {
-                    if let Value::Array(arr) = v {
-                        let len = arr.len();
-                        let min_ok = min.map_or(true, |m| len >= m);
-                        let max_ok = max.map_or(true, |m| len <= m);
-                        min_ok && max_ok
-                    } else {
-                        false
-                    }
-                }))
-            }
-        }
-    }
-
-    /// Check if a container contains a value
-    pub fn contains(&self, container: &Value, contained: &Value) -> bool {
-        match (container, contained) {
-            (Value::Array(arr), val) => arr.contains(val),
-            (Value::String(s), Value::String(sub)) => s.contains(sub.as_str()),
-            (Value::Object(obj1), Value::Object(obj2)) => {
-                obj2.iter().all(|(k, v)| obj1.get(k) == Some(v))
-            }
-            _ => false,
-        }
-    }
-
-    /// JSONB contains operation (PostgreSQL @> operator)
-    pub fn jsonb_contains(&self, left: &Value, right: &Value) -> bool {
-        match (left, right) {
-            (Value::Object(l), Value::Object(r)) => {
-                r.iter().all(|(k, v)| {
-                    l.get(k).map_or(false, |lv| self.jsonb_contains(lv, v))
-                })
-            }
-            (Value::Array(l), Value::Array(r)) => {
-                r.iter().all(|rv| l.iter().any(|lv| self.jsonb_contains(lv, rv)))
-            }
-            (l, r) => l == r,
-        }
-    }
-
-    /// Check if a value is considered "empty"
-    pub fn is_empty_value(&self, value: &Value) -> bool {
-        match value {
-            Value::Null => true,
-            Value::String(s) => s.is_empty(),
-            Value::Array(arr) => arr.is_empty(),
-            Value::Object(obj) => obj.is_empty(),
-            _ => false,
-        }
-    }
-
-    /// Find indices of values that are causing validation failures
-    pub fn find_invalid_value_indices(&self, values: &[&Value], check: &CheckRule) -> Vec<usize> {
-        let mut invalid_indices = Vec::new();
-
-        match check {
-            CheckRule::Empty => {
-                for (i, v) in values.iter().enumerate() {
-                    if !self.is_empty_value(v) {
-                        invalid_indices.push(i);
-                    }
-                }
-            }
-            CheckRule::NonEmpty => {
-                for (i, v) in values.iter().enumerate() {
-                    if self.is_empty_value(v) {
-                        invalid_indices.push(i);
-                    }
-                }
-            }
-            CheckRule::Equals { value: target } => {
-                let has_match = values.iter().any(|v| *v == target);
-                if !has_match {
-                    // If no values match, all are invalid
-                    invalid_indices.extend(0..values.len());
-                }
-            }
-            CheckRule::NotEquals { value: target } => {
-                for (i, v) in values.iter().enumerate() {
-                    if *v == target {
-                        invalid_indices.push(i);
-                    }
-                }
-            }
-            CheckRule::Contains { value: target } => {
-                let has_match = values.iter().any(|v| self.contains(v, target));
-                if !has_match {
-                    invalid_indices.extend(0..values.len());
-                }
-            }
-            CheckRule::ContainedBy { value: container } => {
-                for (i, v) in values.iter().enumerate() {
-                    if !self.contains(container, v) {
-                        invalid_indices.push(i);
-                    }
-                }
-            }
-            CheckRule::Regex { pattern } => {
-                if let Ok(re) = regex::Regex::new(pattern) {
-                    for (i, v) in values.iter().enumerate() {
-                        let matches = if let Value::String(s) = v {
-                            re.is_match(s)
-                        } else {
-                            false
-                        };
-                        if !matches {
-                            invalid_indices.push(i);
-                        }
-                    }
-                }
-            }
-            CheckRule::GreaterThan { value: threshold } => {
-                for (i, v) in values.iter().enumerate() {
-                    let is_valid = if let Some(n) = v.as_f64() {
-                        n > *threshold
-                    } else {
-                        false
-                    };
-                    if !is_valid {
-                        invalid_indices.push(i);
-                    }
-                }
-            }
-            CheckRule::LessThan { value: threshold } => {
-                for (i, v) in values.iter().enumerate() {
-                    let is_valid = if let Some(n) = v.as_f64() {
-                        n < *threshold
-                    } else {
-                        false
-                    };
-                    if !is_valid {
-                        invalid_indices.push(i);
-                    }
-                }
-            }
-            CheckRule::ArrayLength { min, max } => {
-                for (i, v) in values.iter().enumerate() {
-                    let is_valid = if let Value::Array(arr) = v {
-                        let len = arr.len();
-                        let min_ok = min.map_or(true, |m| len >= m);
-                        let max_ok = max.map_or(true, |m| len <= m);
-                        min_ok && max_ok
-                    } else {
-                        false
-                    };
-                    if !is_valid {
-                        invalid_indices.push(i);
-                    }
-                }
-            }
-            _ => {
-                // For other rules, if validation failed, consider all values as potentially invalid
-                invalid_indices.extend(0..values.len());
-            }
-        }
-
-        invalid_indices
-    }
-
-    /// Find the correct path for a selected value based on JSONPath and its index
-    pub fn find_path_for_selected_value(&self, json: &Value, jsonpath: &str, index: usize) -> Option<String> {
-        // For simple cases like $.users[*].name or $.users[*].email, we can reconstruct the path
-        if jsonpath.contains("[*]") {
-            let base_path = jsonpath.replace("[*]", &format!("[{}]", index));
-            return Some(base_path);
-        }
-        
-        // For complex JSONPath queries, we need to find all matching paths and return the correct one
-        let mut selector = jsonpath_lib::selector(json);
-        if let Ok(results) = selector(jsonpath) {
-            if index < results.len() {
-                let target_value = results[index];
-                // Find all occurrences of this value and return the one at the correct index
-                return self.find_nth_occurrence_path(json, target_value, index);
-            }
-        }
-        
-        None
-    }
-
-    /// Find the nth occurrence of a value in JSON and return its path
-    fn find_nth_occurrence_path(&self, json: &Value, target: &Value, target_index: usize) -> Option<String> {
-        let mut found_paths = Vec::new();
-        self.find_all_paths_recursive(json, target, vec!["$".to_string()], &mut found_paths);
-        
-        if target_index < found_paths.len() {
-            Some(found_paths[target_index].clone())
-        } else {
-            None
-        }
-    }
-
-    /// Recursively find all paths to a target value
-    fn find_all_paths_recursive(&self, value: &Value, target: &Value, path: Vec<String>, found_paths: &mut Vec<String>) {
-        if value == target {
-            found_paths.push(path.join("."));
-        }
-
-        match value {
-            Value::Object(map) => {
-                for (key, val) in map {
-                    let mut new_path = path.clone();
-                    new_path.push(key.clone());
-                    self.find_all_paths_recursive(val, target, new_path, found_paths);
-                }
-            }
-            Value::Array(arr) => {
-                for (index, val) in arr.iter().enumerate() {
-                    let mut new_path = path.clone();
-                    new_path.push(format!("[{}]", index));
-                    self.find_all_paths_recursive(val, target, new_path, found_paths);
-                }
-            }
-            _ => {}
-        }
-    }
-
-}
+//! Core JSON validation logic
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde_json::Value;
+use std::borrow::Cow;
+use std::cmp;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread;
+use std::time::Duration;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use std::time::Instant;
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+use web_time::Instant;
+
+use crate::command::{self, CommandSpec};
+use crate::compiled::CompileCache;
+use crate::config::{Config, Rule, CheckResult, Status};
+use crate::canonicalize::Canonicalize;
+use crate::diff::{self, Difference};
+use crate::error::{self, CheckerError};
+use crate::explain::{Explanation, NodeOutcome, Selection};
+use crate::fix::{self, PatchOperation};
+use crate::format::{self, CsvOptions, Document, DocumentFormat};
+use crate::jq::{self, Selector, SelectorEngine};
+use crate::jsonpath::JsonPathEngine;
+use crate::loader::DocumentRef;
+use crate::locate;
+use crate::messages::Message;
+use crate::number;
+use crate::parser::{self, ParserOptions};
+use crate::path_finder::{self, canonical_paths, push_key};
+use crate::protobuf;
+use crate::query;
+use crate::reporter::{ProgressReporter, Reporter};
+use crate::response::ResponseAssertions;
+use crate::retry::{self, RetryPolicy};
+use crate::rules::{self, CheckRule, KeyConvention, Quantifier};
+use crate::scan::{self, ScanConfig, ScannedFile};
+use crate::schedule::{self, RunCondition};
+use crate::source::{self, DocumentSource};
+use crate::stream;
+use crate::transform;
+
+/// JSONB containment (PostgreSQL @> operator): does `left` contain `right`
+pub(crate) fn jsonb_contains(left: &Value, right: &Value) -> bool {
+    match (left, right) {
+        (Value::Object(l), Value::Object(r)) => {
+            r.iter().all(|(k, v)| {
+                l.get(k).is_some_and(|lv| jsonb_contains(lv, v))
+            })
+        }
+        (Value::Array(l), Value::Array(r)) => {
+            r.iter().all(|rv| l.iter().any(|lv| jsonb_contains(lv, rv)))
+        }
+        (l, r) => l == r,
+    }
+}
+
+/// Structural differences explaining why a container node failed an
+/// `equals`, `jsonb_contains` or `required_keys` check, and the measured
+/// depth or size of a node over a limit
+fn node_differences(check: &CheckRule, path: &str, value: &Value) -> Vec<Difference> {
+    match (check, value) {
+        (CheckRule::MaxDepth { depth }, _) => {
+            vec![Difference::Changed { path: path.to_string(), expected: (*depth).into(), actual: rules::depth(value).into() }]
+        }
+        (CheckRule::MaxSizeBytes { bytes }, _) => vec![Difference::Changed {
+            path: path.to_string(),
+            expected: (*bytes).into(),
+            actual: rules::compact_size(value).into(),
+        }],
+        (CheckRule::RequiredKeys { keys }, Value::Object(object)) => keys
+            .iter()
+            .filter(|key| !key.is_satisfied_by(object))
+            .map(|key| {
+                let mut key_path = path.to_string();
+                push_key(&mut key_path, &key.name);
+                let expected = Value::String(key.kind.map_or("any value".to_string(), |kind| kind.to_string()));
+                match object.get(&key.name) {
+                    Some(actual) => Difference::Changed { path: key_path, expected, actual: actual.clone() },
+                    None => Difference::Missing { path: key_path, expected },
+                }
+            })
+            .collect(),
+        (CheckRule::Equals { value: expected, .. }, Value::Object(_) | Value::Array(_))
+            if std::mem::discriminant(expected) == std::mem::discriminant(value) =>
+        {
+            diff::diff(expected, value, path)
+        }
+        (CheckRule::JsonbContains { value: expected @ Value::Object(_) }, Value::Object(_)) => {
+            diff::diff_contained(expected, value, path)
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// The selected values as the rule's check sees them: after its transforms
+/// and, in protobuf mode, with protobuf JSON's numbers read as numbers
+fn transformed<'a>(rule: &Rule, selected: &[&'a Value]) -> Vec<Cow<'a, Value>> {
+    let transformed = transform::apply_all(&rule.transform, selected);
+    if rule.protobuf_mode && rule.check.compares_numbers() {
+        protobuf::read_numbers(transformed)
+    } else {
+        transformed
+    }
+}
+
+/// The rule and the values to check, in the canonical form the rule's
+/// `canonicalize` options give; `canonical` holds what the result borrows
+fn canonical_form<'a>(
+    rule: &'a Rule,
+    transformed: &'a [Cow<'a, Value>],
+    canonical: &'a mut Option<(Rule, Vec<Value>)>,
+) -> (&'a Rule, Vec<&'a Value>) {
+    if rule.canonicalize.is_identity() {
+        return (rule, transformed.iter().map(AsRef::as_ref).collect());
+    }
+    let options = rule.canonicalize;
+    let canonical_rule = Rule { check: options.apply_to_check(&rule.check), ..rule.clone() };
+    let values = transformed.iter().map(|value| options.apply(value)).collect();
+    let (rule, values) = canonical.insert((canonical_rule, values));
+    (rule, values.iter().collect())
+}
+
+/// Read a rule's document once; `source` is the source of its `json_file`
+fn read_document(source: Option<&dyn DocumentSource>, rule: &Rule, options: ParserOptions) -> Result<Document> {
+    Ok(match DocumentRef::of(rule)? {
+        DocumentRef::File(uri) => {
+            let source = source.ok_or_else(|| anyhow!("No document source for {}", uri))?;
+            match rule.format.unwrap_or_else(|| DocumentFormat::detect(uri)) {
+                DocumentFormat::Json if options.is_lenient() => Document::new(source.resolve(uri)?),
+                format => parser::parse(&source.read_text(uri)?, format, rule.csv.as_ref(), options)?,
+            }
+        }
+        DocumentRef::Inline(json) => Document::new(json.clone()),
+        DocumentRef::Query(query) => Document::new(query::fetch(query)?),
+    })
+}
+
+/// Fail `result` for each key repeated in the rule's document, listing the
+/// repeated keys after the check's own failures
+pub(crate) fn report_duplicate_keys(rule: &Rule, result: &mut CheckResult, document: &Document) {
+    if document.duplicate_keys.is_empty() {
+        return;
+    }
+    let count = document.duplicate_keys.len();
+    let mut lines = if result.passed { Vec::new() } else { result.lines.clone() };
+    lines.push(if result.passed {
+        Message::DuplicateKeys { rule: rule.name.clone(), count }
+    } else {
+        Message::DuplicateKeysFound { count }
+    });
+    let capture = rule.capture_values.unwrap_or_default();
+    for path in &document.duplicate_keys {
+        // serde_json kept the last value of the key
+        let value = path_finder::JsonPath::parse(path).and_then(|p| p.resolve(&document.value).cloned());
+        result.invalid_positions.push(path.clone());
+        result.values_found.push(capture.capture(&value.unwrap_or(Value::Null)));
+    }
+    result.passed = false;
+    result.status = Status::Fail;
+    result.set_lines(lines);
+}
+
+/// Apply the rule's document-wide preprocessing
+fn prepare(rule: &Rule, mut document: Document) -> Result<Document> {
+    if rule.decode_embedded_json {
+        transform::decode_embedded_json(&mut document.value, "$")?;
+    }
+    Ok(document)
+}
+
+/// Run a command check's program once per value, returning the indices of
+/// the values it rejects; `None` for other checks and for no values. A
+/// program that can't be run or times out is an error, not a failing value.
+fn command_failures(check: &CheckRule, values: &[&Value]) -> Result<Option<Vec<usize>>> {
+    let Some(spec) = CommandSpec::of(check).filter(|_| !values.is_empty()) else {
+        return Ok(None);
+    };
+    let mut failures = Vec::new();
+    for (i, v) in values.iter().enumerate() {
+        if !command::run_check_command(&spec, v)? {
+            failures.push(i);
+        }
+    }
+    Ok(Some(failures))
+}
+
+/// Run `job` once, or as often as `policy` allows
+fn with_retry<T, F>(policy: Option<&RetryPolicy>, job: F) -> Result<T>
+where
+    T: Send + 'static,
+    F: Fn() -> Result<T> + Send + Sync + 'static,
+{
+    match policy {
+        Some(policy) if HAS_THREADS => retry::run(policy, job),
+        _ => job(),
+    }
+}
+
+/// What a rule needs that `run_async` can't give it: it checks several
+/// files, streams its document or asserts on the response
+#[cfg(feature = "async")]
+fn async_unsupported(rule: &Rule) -> Option<&'static str> {
+    if rule.json_file.as_deref().and_then(source::local_path).is_some_and(source::is_glob) {
+        Some("a glob json_file")
+    } else if rule.applies_to.is_some() && rule.json_file.is_none() && rule.json_inline.is_none() && rule.source.is_none() {
+        Some("applies_to")
+    } else if rule.stream {
+        Some("stream")
+    } else if rule.response.is_some() {
+        Some("response assertions")
+    } else {
+        None
+    }
+}
+
+/// Whether rules can be evaluated, and documents retried, on worker threads.
+/// Browsers running the `wasm` build can't spawn threads or sleep, so time
+/// limits and retries don't apply there.
+const HAS_THREADS: bool = cfg!(not(all(target_arch = "wasm32", target_os = "unknown")));
+
+/// Documents are cached by URI and the way they are parsed
+type DocumentKey = (String, DocumentFormat, Option<CsvOptions>, ParserOptions);
+/// Cached documents; each entry has its own lock so rules running at the
+/// same time wait for one read of their document instead of reading it again
+type DocumentCache = Mutex<HashMap<DocumentKey, Arc<Mutex<Option<Document>>>>>;
+
+/// Main JSON validation engine
+///
+/// The checker is `Send + Sync`, and clones are cheap: they share the config,
+/// sources, scan, document cache and compiled regexes and JSONPaths. One
+/// checker can serve many threads behind an `Arc`, and rules with a time
+/// limit are evaluated on a worker thread.
+#[derive(Clone)]
+pub struct JsonChecker {
+    config: Arc<Config>,
+    pub(crate) sources: Arc<HashMap<String, Arc<dyn DocumentSource>>>,
+    /// Files found by the config's scan, discovered on first use
+    scanned: Arc<OnceLock<std::result::Result<Vec<ScannedFile>, String>>>,
+    /// Set when the rule being evaluated timed out, so long loops stop early
+    cancelled: Arc<AtomicBool>,
+    /// Documents already loaded, when rules sharing a `json_file` should read it once
+    pub(crate) documents: Option<Arc<DocumentCache>>,
+    /// Number of rules evaluated at the same time
+    pub(crate) parallelism: usize,
+    /// Most failing nodes listed per result; the rest are only counted
+    pub(crate) max_failures_shown: Option<usize>,
+    /// Regexes and JSONPaths of the config's rules, and any compiled since
+    compiled: Arc<CompileCache>,
+}
+
+impl JsonChecker {
+    /// Create a new JsonChecker from a configuration file
+    pub fn new(config_path: &str) -> error::Result<Self> {
+        let config = Config::from_file(config_path)?;
+        
+        Ok(Self::from_config(config))
+    }
+
+    /// Create a new JsonChecker from a Config struct
+    pub fn from_config(config: Config) -> Self {
+        let mut sources: HashMap<String, Arc<dyn DocumentSource>> = HashMap::new();
+        sources.insert("file".to_string(), Arc::new(source::FileSource));
+        sources.insert("stdin".to_string(), Arc::new(source::StdinSource::default()));
+        #[cfg(feature = "http")]
+        {
+            sources.insert("http".to_string(), Arc::new(source::HttpSource));
+            sources.insert("https".to_string(), Arc::new(source::HttpSource));
+        }
+        #[cfg(feature = "s3")]
+        sources.insert("s3".to_string(), Arc::new(source::ObjectStoreSource::s3()));
+        #[cfg(feature = "gcs")]
+        sources.insert("gs".to_string(), Arc::new(source::ObjectStoreSource::gcs()));
+
+        JsonChecker {
+            compiled: Arc::new(CompileCache::for_config(&config)),
+            config: Arc::new(config),
+            sources: Arc::new(sources),
+            scanned: Default::default(),
+            cancelled: Default::default(),
+            documents: None,
+            parallelism: 1,
+            max_failures_shown: None,
+        }
+    }
+
+    /// Register the document source used for `json_file` URIs with the given scheme,
+    /// replacing any built-in source for that scheme
+    pub fn with_source(mut self, scheme: &str, source: impl DocumentSource + 'static) -> Self {
+        Arc::make_mut(&mut self.sources).insert(scheme.to_string(), Arc::new(source));
+        self
+    }
+
+    /// Get the configuration this checker runs
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Run all validation rules and return results
+    pub fn run(&self) -> error::Result<Vec<CheckResult>> {
+        self.run_with_reporter(&mut ())
+    }
+
+    /// Run all validation rules, calling `on_result(done, total, result)` after each one
+    pub fn run_with_progress<F>(&self, on_result: F) -> error::Result<Vec<CheckResult>>
+    where
+        F: FnMut(usize, usize, &CheckResult),
+    {
+        let total = self.config.rules.len();
+        self.run_with_reporter(&mut ProgressReporter { on_result, done: 0, total })
+    }
+
+    /// Run all validation rules, reporting each rule's start and result as it happens
+    pub fn run_with_reporter(&self, reporter: &mut dyn Reporter) -> error::Result<Vec<CheckResult>> {
+        let rules = &self.config.rules;
+        let invalid = |e: anyhow::Error| CheckerError::InvalidConfig(format!("{:#}", e));
+        // Run one rule at a time in execution order, or whole dependency levels at once
+        let batches = match self.parallelism {
+            0 | 1 => schedule::execution_order(rules).map_err(invalid)?.into_iter().map(|i| vec![i]).collect(),
+            _ => schedule::execution_levels(rules).map_err(invalid)?,
+        };
+        let index: HashMap<&str, usize> = rules.iter().enumerate().map(|(i, r)| (r.name.as_str(), i)).collect();
+        let mut results: Vec<Option<CheckResult>> = rules.iter().map(|_| None).collect();
+
+        for batch in &batches {
+            let mut runnable = Vec::new();
+            for &i in batch {
+                let finished = |name: &str| index.get(name).and_then(|&j| results[j].as_ref());
+                match schedule::skip_reason(&rules[i], finished) {
+                    Some(reason) => {
+                        let result = CheckResult {
+                            suite: rules[i].suite.clone(),
+                            ..CheckResult::skipped(rules[i].name.clone(), &reason)
+                        };
+                        reporter.on_rule_start(&rules[i]);
+                        reporter.on_rule_result(&rules[i], &result);
+                        results[i] = Some(result);
+                    }
+                    None => runnable.push(i),
+                }
+            }
+            self.run_batch(&runnable, reporter, &mut results);
+        }
+
+        // Results are reported in config order, whatever order rules ran in
+        let results: Vec<CheckResult> = results.into_iter().flatten().collect();
+        reporter.on_finish(&results);
+        Ok(results)
+    }
+
+    /// Run rules that don't depend on each other, on up to `parallelism` threads
+    fn run_batch(&self, batch: &[usize], reporter: &mut dyn Reporter, results: &mut [Option<CheckResult>]) {
+        let rules = &self.config.rules;
+        if self.parallelism <= 1 || batch.len() <= 1 || !HAS_THREADS {
+            for &i in batch {
+                reporter.on_rule_start(&rules[i]);
+                let result = self.run_rule(&rules[i]);
+                reporter.on_rule_result(&rules[i], &result);
+                results[i] = Some(result);
+            }
+            return;
+        }
+
+        // Workers take the next rule of the batch; the reporter is only called from this thread
+        let next = AtomicUsize::new(0);
+        let (sender, receiver) = mpsc::channel();
+        thread::scope(|scope| {
+            for _ in 0..self.parallelism.min(batch.len()) {
+                let sender = sender.clone();
+                let next = &next;
+                scope.spawn(move || {
+                    while let Some(&i) = batch.get(next.fetch_add(1, Ordering::Relaxed)) {
+                        let _ = sender.send((i, None));
+                        let _ = sender.send((i, Some(self.run_rule(&rules[i]))));
+                    }
+                });
+            }
+            drop(sender);
+
+            for (i, result) in receiver {
+                match result {
+                    None => reporter.on_rule_start(&rules[i]),
+                    Some(result) => {
+                        reporter.on_rule_result(&rules[i], &result);
+                        results[i] = Some(result);
+                    }
+                }
+            }
+        });
+    }
+
+    /// Execute a single rule, turning evaluation errors into a failed result
+    /// and giving up once the rule's time limit is exceeded
+    pub fn run_rule(&self, rule: &Rule) -> CheckResult {
+        let started = Instant::now();
+        let outcome = match self.config.timeout_for(rule) {
+            Some(limit) if HAS_THREADS => self.check_rule_within(rule, limit),
+            _ => self.check_rule(rule),
+        };
+        self.finish_result(rule, outcome, started)
+    }
+
+    /// Evaluate a rule on a worker thread, abandoning it after `limit`.
+    ///
+    /// A regex or JSONPath in progress can't be interrupted, so the worker
+    /// may linger until it finishes; it stops at the next file or streamed
+    /// element, and its result is discarded.
+    fn check_rule_within(&self, rule: &Rule, limit: Duration) -> error::Result<CheckResult> {
+        let worker = JsonChecker { cancelled: Default::default(), ..self.clone() };
+        let cancelled = Arc::clone(&worker.cancelled);
+        let owned = rule.clone();
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            // The receiver is gone if the rule timed out
+            let _ = sender.send(worker.check_rule(&owned));
+        });
+
+        match receiver.recv_timeout(limit) {
+            Ok(outcome) => outcome,
+            Err(RecvTimeoutError::Timeout) => {
+                cancelled.store(true, Ordering::Relaxed);
+                Ok(CheckResult::timed_out(rule.name.clone(), limit))
+            }
+            Err(RecvTimeoutError::Disconnected) => Err(anyhow!("Evaluating rule '{}' panicked", rule.name).into()),
+        }
+    }
+
+    /// Fail if the rule being evaluated has timed out
+    pub(crate) fn check_cancelled(&self) -> Result<()> {
+        if self.cancelled.load(Ordering::Relaxed) {
+            bail!("Evaluation cancelled");
+        }
+        Ok(())
+    }
+
+    /// Run all validation rules, loading documents asynchronously from disk
+    #[cfg(feature = "async")]
+    pub async fn run_async(&self) -> error::Result<Vec<CheckResult>> {
+        self.run_async_with(&crate::loader::FileLoader).await
+    }
+
+    /// Run all validation rules, loading documents with a custom async loader.
+    ///
+    /// Rules checking several files, streaming their document or asserting on
+    /// the response fail with an error. Retries and time limits use tokio's
+    /// timer, so the runtime must have it enabled when the config sets them.
+    #[cfg(feature = "async")]
+    pub async fn run_async_with<L>(&self, loader: &L) -> error::Result<Vec<CheckResult>>
+    where
+        L: crate::loader::AsyncDocumentLoader + ?Sized,
+    {
+        let rules = &self.config.rules;
+        let order = schedule::execution_order(rules).map_err(|e| CheckerError::InvalidConfig(format!("{:#}", e)))?;
+        let index: HashMap<&str, usize> = rules.iter().enumerate().map(|(i, r)| (r.name.as_str(), i)).collect();
+        let mut results: Vec<Option<CheckResult>> = rules.iter().map(|_| None).collect();
+
+        for &i in &order {
+            let rule = &rules[i];
+            let finished = |name: &str| index.get(name).and_then(|&j| results[j].as_ref());
+            if let Some(reason) = schedule::skip_reason(rule, finished) {
+                results[i] = Some(CheckResult {
+                    suite: rule.suite.clone(),
+                    ..CheckResult::skipped(rule.name.clone(), &reason)
+                });
+                continue;
+            }
+
+            let started = Instant::now();
+            let outcome = match self.config.timeout_for(rule) {
+                Some(limit) => {
+                    let worker = JsonChecker { cancelled: Default::default(), ..self.clone() };
+                    match tokio::time::timeout(limit, worker.check_rule_async(rule, loader)).await {
+                        Ok(outcome) => outcome,
+                        Err(_) => {
+                            worker.cancelled.store(true, Ordering::Relaxed);
+                            Ok(CheckResult::timed_out(rule.name.clone(), limit))
+                        }
+                    }
+                }
+                None => self.check_rule_async(rule, loader).await,
+            };
+            results[i] = Some(self.finish_result(rule, outcome, started));
+        }
+
+        Ok(results.into_iter().flatten().collect())
+    }
+
+    /// Load a rule's document with `loader`, retried according to its retry
+    /// policy, then check it on tokio's blocking thread pool
+    #[cfg(feature = "async")]
+    async fn check_rule_async<L>(&self, rule: &Rule, loader: &L) -> error::Result<CheckResult>
+    where
+        L: crate::loader::AsyncDocumentLoader + ?Sized,
+    {
+        if let Some(feature) = async_unsupported(rule) {
+            return Err(anyhow!("Rule '{}' uses {}, which run_async doesn't support", rule.name, feature).into());
+        }
+        let options = self.config.parser_for(rule);
+        let load = || loader.load(rule, options);
+        let document = match self.config.retry_for(rule) {
+            Some(policy) => retry::run_async(policy, load).await?,
+            None => load().await?,
+        };
+        let document = prepare(rule, document)?;
+
+        let worker = self.clone();
+        let owned = rule.clone();
+        tokio::task::spawn_blocking(move || worker.check_loaded(&owned, &document))
+            .await
+            .map_err(|_| anyhow!("Evaluating rule '{}' panicked", rule.name))?
+    }
+
+    /// Turn the outcome of evaluating a rule into its final result
+    fn finish_result(&self, rule: &Rule, outcome: error::Result<CheckResult>, started: Instant) -> CheckResult {
+        let mut result = match outcome {
+            Ok(result) => result,
+            Err(e) => {
+                // The alternate form keeps the causes, such as where a document fails to parse
+                let mut result = CheckResult::error(rule.name.clone(), format!("Error: {:#}", e));
+                result.lines = vec![Message::Error { error: format!("{:#}", e) }];
+                if let CheckerError::RetriesExhausted(exhausted) = e.kind() {
+                    result.retry_log = exhausted.log.clone();
+                }
+                result
+            }
+        };
+        result.severity = rule.severity;
+        result.suite = rule.suite.clone();
+        result.hint = rule.hint.clone();
+        result.docs_url = rule.docs_url.clone();
+        result.duration = started.elapsed();
+        result.document = rule.json_file.clone();
+        result
+    }
+
+    /// Load the JSON document a rule applies to
+    pub fn load_document(&self, rule: &Rule) -> error::Result<Value> {
+        Ok(self.load(rule)?.value)
+    }
+
+    /// Load the document a rule applies to, parsed according to its format
+    /// and retried according to its retry policy
+    pub fn load(&self, rule: &Rule) -> error::Result<Document> {
+        let document = match (&self.documents, rule.json_file.as_deref()) {
+            (Some(documents), Some(uri)) => {
+                let format = rule.format.unwrap_or_else(|| DocumentFormat::detect(uri));
+                let key = (uri.to_string(), format, rule.csv.clone(), self.config.parser_for(rule));
+                let entry = Arc::clone(documents.lock().unwrap_or_else(|e| e.into_inner()).entry(key).or_default());
+                let mut cached = entry.lock().unwrap_or_else(|e| e.into_inner());
+                match &*cached {
+                    Some(document) => document.clone(),
+                    None => cached.insert(self.read(rule)?).clone(),
+                }
+            }
+            _ => self.read(rule)?,
+        };
+        Ok(prepare(rule, document)?)
+    }
+
+    /// Read the document a rule applies to, bypassing the cache
+    fn read(&self, rule: &Rule) -> Result<Document> {
+        let source = rule.json_file.as_deref().map(|uri| self.source_for(uri).cloned()).transpose()?;
+        let owned = rule.clone();
+        let options = self.config.parser_for(rule);
+        with_retry(self.config.retry_for(rule), move || read_document(source.as_deref(), &owned, options))
+    }
+
+    /// Fetch a rule's document and check its `response` assertions first;
+    /// failed assertions are returned instead of the document
+    fn load_asserting_response(
+        &self,
+        rule: &Rule,
+        assertions: &ResponseAssertions,
+    ) -> Result<std::result::Result<Document, CheckResult>> {
+        let Some(uri) = rule.json_file.as_deref() else {
+            bail!("Rule '{}' asserts on the response but has no json_file", rule.name);
+        };
+        let source = Arc::clone(self.source_for(uri)?);
+        let owned_uri = uri.to_string();
+        let response = with_retry(self.config.retry_for(rule), move || source.fetch_response(&owned_uri))?;
+
+        let failures = assertions.failures(&response);
+        if !failures.is_empty() {
+            let mut lines = vec![Message::UnexpectedResponse { rule: rule.name.clone(), uri: uri.to_string() }];
+            lines.extend(failures.into_iter().map(|failure| Message::Text { text: failure.to_string() }));
+            return Ok(Err(CheckResult::from_lines(rule.name.clone(), false, lines)));
+        }
+
+        let format = rule.format.unwrap_or_else(|| DocumentFormat::detect(uri));
+        let document = parser::parse(&response.body, format, rule.csv.as_ref(), self.config.parser_for(rule))?;
+        Ok(Ok(prepare(rule, document)?))
+    }
+
+    /// Resolve a document URI with the source registered for its scheme
+    pub fn resolve_document(&self, uri: &str) -> error::Result<Value> {
+        Ok(self.source_for(uri)?.resolve(uri)?)
+    }
+
+    fn source_for(&self, uri: &str) -> Result<&Arc<dyn DocumentSource>> {
+        let (scheme, _) = source::split_uri(uri);
+        self.sources
+            .get(scheme)
+            .ok_or_else(|| anyhow!("No document source registered for '{}://' ({})", scheme, uri))
+    }
+
+    /// Execute a single validation rule
+    pub fn check_rule(&self, rule: &Rule) -> error::Result<CheckResult> {
+        if let Some(reason) = rule.disabled_reason() {
+            return Ok(CheckResult::skipped(rule.name.clone(), &reason));
+        }
+        if let Some(pattern) = rule.json_file.as_deref().and_then(source::local_path)
+            && source::is_glob(pattern)
+        {
+            return Ok(self.check_each_file(rule, pattern)?);
+        }
+        if let Some(pattern) = &rule.applies_to
+            && rule.json_file.is_none()
+            && rule.json_inline.is_none()
+            && rule.source.is_none()
+        {
+            return Ok(self.check_scanned(rule, pattern)?);
+        }
+
+        if rule.stream {
+            // Conditions need the whole document, so only load it when there is one
+            if matches!(rule.run_if, Some(RunCondition::PathExists(_)))
+                && let Some(reason) = self.unmet_condition(rule, &self.load(rule)?.value)?
+            {
+                return Ok(CheckResult::skipped(rule.name.clone(), &reason));
+            }
+            return Ok(stream::check_streaming(self, rule)?);
+        }
+
+        let document = match &rule.response {
+            Some(assertions) => match self.load_asserting_response(rule, assertions)? {
+                Ok(document) => document,
+                Err(failed) => return Ok(failed),
+            },
+            None => self.load(rule)?,
+        };
+        self.check_loaded(rule, &document)
+    }
+
+    /// Check a rule against its loaded document, reporting duplicate keys and
+    /// failing positions the way the document's format and the rule ask for
+    fn check_loaded(&self, rule: &Rule, document: &Document) -> error::Result<CheckResult> {
+        if let Some(reason) = self.unmet_condition(rule, &document.value)? {
+            return Ok(CheckResult::skipped(rule.name.clone(), &reason));
+        }
+        let mut result = self.check_document(rule, &document.value)?;
+        report_duplicate_keys(rule, &mut result, document);
+
+        if rule.selector.is_none()
+            && !result.invalid_positions.is_empty()
+            && let Some(path) = rule.json_file.as_deref().and_then(source::local_path)
+            && let format = rule.format.unwrap_or_else(|| DocumentFormat::detect(path))
+            && format.is_json()
+        {
+            // Re-read the file to find where each failing node starts; positions
+            // are a nicety, so a file that changed or vanished meanwhile is ignored
+            let options = self.config.parser_for(rule).for_format(format);
+            if let Ok(text) = std::fs::read_to_string(path)
+                && let Ok(locations) = locate::locate(&options.source_text(&text), &result.invalid_positions)
+            {
+                result.locations = locations;
+            }
+        }
+
+        // Report failing paths in the syntax the rule selected with
+        if rule.json_pointer.is_some() {
+            for position in &mut result.invalid_positions {
+                if let Some(pointer) = path_finder::to_json_pointer(position) {
+                    *position = pointer;
+                }
+            }
+        }
+
+        // Line-delimited documents report the source line of each failing element
+        if let Some(lines) = &document.lines {
+            for position in &mut result.invalid_positions {
+                if let Some(line) = format::top_level_index(position).and_then(|i| lines.get(i)) {
+                    *position = format!("line {}: {}", line, position);
+                }
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Check a rule against every file matching a glob pattern.
+    ///
+    /// The combined result passes only if every file passes. Positions of
+    /// invalid nodes are prefixed with their file, and the result of each file
+    /// is kept in `per_document`.
+    fn check_each_file(&self, rule: &Rule, pattern: &str) -> Result<CheckResult> {
+        let files = source::expand_glob(pattern)?;
+        if files.is_empty() {
+            bail!("No files match '{}'", pattern);
+        }
+        self.check_files(rule, &files, None, pattern)
+    }
+
+    /// Check a rule with `applies_to` against the matching files found by the config's scan
+    fn check_scanned(&self, rule: &Rule, pattern: &str) -> Result<CheckResult> {
+        let (scan, files) = self.scanned_matching(rule, pattern)?;
+        if files.is_empty() {
+            let message = Message::PassedNoFiles {
+                rule: rule.name.clone(),
+                root: scan.root.clone(),
+                pattern: pattern.to_string(),
+            };
+            return Ok(CheckResult::from_lines(rule.name.clone(), true, vec![message]));
+        }
+        let root = scan.root.clone();
+        self.check_files(rule, &files, Some(&root), pattern)
+    }
+
+    /// The config's scan and the scanned files an `applies_to` pattern matches
+    fn scanned_matching(&self, rule: &Rule, pattern: &str) -> Result<(&ScanConfig, Vec<String>)> {
+        let scan = self
+            .config
+            .scan
+            .as_ref()
+            .ok_or_else(|| anyhow!("Rule '{}' uses applies_to but the config has no scan section", rule.name))?;
+        let scanned = self
+            .scanned
+            .get_or_init(|| scan::discover(scan).map_err(|e| format!("{:#}", e)))
+            .as_ref()
+            .map_err(|e| anyhow!("{}", e))?;
+
+        let files = scanned
+            .iter()
+            .filter(|file| scan::applies_to(pattern, file))
+            .map(|file| file.path.clone())
+            .collect();
+        Ok((scan, files))
+    }
+
+    /// Check a rule against each of the given files and combine the results;
+    /// `root` is the scanned directory the files were found under, if any
+    fn check_files(&self, rule: &Rule, files: &[String], root: Option<&str>, pattern: &str) -> Result<CheckResult> {
+        // The time limit applies to the rule as a whole, not to each file
+        let per_document: Vec<CheckResult> = files
+            .iter()
+            .map(|file| {
+                self.check_cancelled()?;
+                let started = Instant::now();
+                let rule = Rule {
+                    name: format!("{} [{}]", rule.name, file),
+                    json_file: Some(file.clone()),
+                    applies_to: None,
+                    ..rule.clone()
+                };
+                let outcome = self.check_rule(&rule);
+                Ok(self.finish_result(&rule, outcome, started))
+            })
+            .collect::<Result<_>>()?;
+
+        let failed: Vec<(&String, &CheckResult)> = files
+            .iter()
+            .zip(&per_document)
+            .filter(|(_, result)| !result.passed)
+            .collect();
+        if failed.is_empty() {
+            let (rule_name, count, pattern) = (rule.name.clone(), files.len(), pattern.to_string());
+            let message = match root {
+                Some(root) => Message::PassedForScannedFiles { rule: rule_name, count, root: root.to_string(), pattern },
+                None => Message::PassedForFiles { rule: rule_name, count, pattern },
+            };
+            let mut result = CheckResult::from_lines(rule.name.clone(), true, vec![message]);
+            result.per_document = per_document;
+            return Ok(result);
+        }
+
+        let (rule_name, failed_count, total, pattern) = (rule.name.clone(), failed.len(), files.len(), pattern.to_string());
+        let mut lines = vec![match root {
+            Some(root) => Message::FailedForScannedFiles { rule: rule_name, failed: failed_count, total, root: root.to_string(), pattern },
+            None => Message::FailedForFiles { rule: rule_name, failed: failed_count, total, pattern },
+        }];
+        let mut invalid_positions = Vec::new();
+        let mut values_found = Vec::new();
+        let mut locations = Vec::new();
+        let mut differences = Vec::new();
+        for (file, result) in &failed {
+            let headline = match result.lines.first() {
+                Some(headline) => headline.clone(),
+                None => Message::Text { text: result.message.lines().next().unwrap_or_default().to_string() },
+            };
+            lines.push(Message::FileFailed { file: file.to_string(), headline: Box::new(headline) });
+            invalid_positions.extend(result.invalid_positions.iter().map(|p| format!("{}: {}", file, p)));
+            locations.extend((0..result.invalid_positions.len()).map(|i| result.location(i)));
+            values_found.extend(result.values_found.iter().cloned());
+            differences.extend(result.differences.iter().cloned());
+        }
+
+        let mut result = CheckResult::with_positions(rule.name.clone(), false, String::new(), invalid_positions, values_found);
+        result.set_lines(lines);
+        result.locations = locations;
+        result.differences = differences;
+        result.per_document = per_document;
+        Ok(result)
+    }
+
+    /// Check a rule's `run_if` condition on its document, returning why it isn't met
+    pub(crate) fn unmet_condition(&self, rule: &Rule, json: &Value) -> Result<Option<String>> {
+        match &rule.run_if {
+            Some(RunCondition::PathExists(jsonpath)) => {
+                let selected = self.select_path(json, jsonpath, self.config.engine_for(rule))?;
+                Ok(selected
+                    .is_empty()
+                    .then(|| format!("JSONPath '{}' matched nothing", jsonpath)))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Execute a single validation rule against an already loaded document
+    pub fn check_document(&self, rule: &Rule, json: &Value) -> error::Result<CheckResult> {
+        // A jq selector outputs new values, which stand in for the document
+        let output;
+        let (json, selected) = match &rule.selector {
+            Some(Selector { engine: SelectorEngine::Jq, expr }) => {
+                output = Value::Array(jq::run(expr, json)?);
+                (&output, output.as_array().into_iter().flatten().collect())
+            }
+            None => (json, self.select(json, rule)?),
+        };
+        let transformed = transformed(rule, &selected);
+        let mut canonical = None;
+        let (rule, checked) = canonical_form(rule, &transformed, &mut canonical);
+
+        // Check rule
+        let failures = command_failures(&rule.check, &checked)?;
+        let passed = match &failures {
+            Some(failures) => failures.is_empty(),
+            None => self.apply_check_with(&checked, &rule.check, &rule.canonicalize)?,
+        };
+        if passed {
+            let message = Message::Passed { rule: rule.name.clone() };
+            return Ok(CheckResult::from_lines(rule.name.clone(), true, vec![message]));
+        }
+
+        let (kind, selector) = rule.selected_by();
+        let mut lines = vec![Message::Failed {
+            rule: rule.name.clone(),
+            selector_kind: kind.to_string(),
+            selector: selector.to_string(),
+        }];
+
+        // Set checks fail as a whole, so explain the value they were compared on
+        match &rule.check {
+            CheckRule::Aggregate { function, .. } => match function.apply(&checked)? {
+                Some(value) => lines.push(Message::Aggregate { function: function.to_string(), value }),
+                None => lines.push(Message::AggregateUndefined { function: function.to_string() }),
+            },
+            CheckRule::MatchCount { .. } => lines.push(Message::MatchCount { count: checked.len() }),
+            CheckRule::Unique => {
+                let duplicated = rules::duplicate_indices(&checked).len();
+                lines.push(Message::Duplicates { duplicated, total: checked.len() });
+            }
+            CheckRule::SameLength => {
+                let (length, outliers) = rules::length_outliers(&checked);
+                lines.push(match length {
+                    Some(length) => Message::LengthOutliers { outliers: outliers.len(), total: checked.len(), length },
+                    None => Message::NoArrays,
+                });
+            }
+            _ => {}
+        }
+
+        // Record the invalid nodes with their exact positions
+        let invalid_indices = match failures {
+            Some(failures) => failures,
+            None => self.find_invalid_value_indices_with(&checked, &rule.check, &rule.canonicalize),
+        };
+        let (invalid, total) = self.invalid_paths_in(json, rule, &selected, &checked, invalid_indices, self.max_failures_shown);
+        if let Some(shown) = self.max_failures_shown.filter(|&shown| total > shown) {
+            lines.push(Message::Truncated { total, shown });
+        }
+        let differences = match rule.check.reference_document() {
+            Some((baseline_file, allowed_paths)) => {
+                let baseline = self.baseline(baseline_file, &rule.canonicalize)?;
+                let mut differences = Vec::new();
+                for (path, value) in &invalid {
+                    differences.extend(self.baseline_differences(&baseline, value, path, allowed_paths)?);
+                }
+                lines.push(Message::DiffersFrom { file: baseline_file.to_string(), summary: diff::summary(&differences) });
+                differences
+            }
+            // Each outlier's length differs from the one most arrays have
+            None if matches!(rule.check, CheckRule::SameLength) => match rules::length_outliers(&checked).0 {
+                Some(length) => invalid
+                    .iter()
+                    .map(|(path, value)| Difference::Changed {
+                        path: path.clone(),
+                        expected: length.into(),
+                        actual: value.as_array().map_or_else(|| (*value).clone(), |items| items.len().into()),
+                    })
+                    .collect(),
+                None => Vec::new(),
+            },
+            None => invalid
+                .iter()
+                .flat_map(|(path, value)| node_differences(&rule.check, path, value))
+                .collect(),
+        };
+        // Fixes patch the document, so only nodes checked as they appear in it get one
+        let as_written = rule.selector.is_none()
+            && rule.transform.is_empty()
+            && !rule.protobuf_mode
+            && rule.canonicalize.is_identity()
+            && !rule.decode_embedded_json;
+        let fixes = if as_written {
+            invalid.iter().filter_map(|(path, value)| self.fix(rule, path, value)).collect()
+        } else {
+            Vec::new()
+        };
+        let capture = rule.capture_values.unwrap_or_default();
+        let (invalid_positions, values_found) = invalid
+            .into_iter()
+            .map(|(path, value)| (path, capture.capture(value)))
+            .unzip();
+
+        let mut result = CheckResult::with_positions(
+            rule.name.clone(),
+            false,
+            String::new(),
+            invalid_positions,
+            values_found,
+        );
+        result.set_lines(lines);
+        result.differences = differences;
+        result.fixes = fixes;
+        Ok(result)
+    }
+
+    /// The patch operation correcting the failing node at `path`, if the
+    /// check suggests a value that passes
+    fn fix(&self, rule: &Rule, path: &str, value: &Value) -> Option<PatchOperation> {
+        let fixed = fix::suggest(&rule.check, value)?;
+        if !self.apply_check(&[&fixed], &rule.check).ok()? {
+            return None;
+        }
+        Some(PatchOperation::Replace { path: path_finder::to_json_pointer(path)?, value: fixed })
+    }
+
+    /// Load a rule's document and trace how the rule is evaluated on it:
+    /// every selected node, the check's outcome on each, and the verdict
+    pub fn explain_rule(&self, rule: &Rule) -> error::Result<Explanation> {
+        if rule.json_file.as_deref().and_then(source::local_path).is_some_and(source::is_glob) || rule.applies_to.is_some() {
+            return Err(CheckerError::InvalidConfig(format!(
+                "Rule '{}' checks several documents; explain it against one with explain_document",
+                rule.name
+            )));
+        }
+        let document = self.load(rule)?;
+        self.explain_document(rule, &document.value)
+    }
+
+    /// The nodes a rule's selector matches in each of its documents, without
+    /// applying the check
+    pub fn select_rule(&self, rule: &Rule) -> error::Result<Vec<Selection>> {
+        let files = match (rule.json_file.as_deref().and_then(source::local_path), &rule.applies_to) {
+            (Some(pattern), _) if source::is_glob(pattern) => source::expand_glob(pattern)?,
+            (None, Some(pattern)) if rule.json_inline.is_none() && rule.source.is_none() => {
+                self.scanned_matching(rule, pattern)?.1
+            }
+            _ => {
+                let document = self.load(rule)?;
+                return Ok(vec![self.selection(rule, rule.json_file.clone(), &document.value)?]);
+            }
+        };
+
+        files
+            .into_iter()
+            .map(|file| {
+                let rule = Rule { json_file: Some(file.clone()), applies_to: None, ..rule.clone() };
+                let document = self.load(&rule)?;
+                self.selection(&rule, Some(file), &document.value)
+            })
+            .collect()
+    }
+
+    fn selection(&self, rule: &Rule, document: Option<String>, json: &Value) -> error::Result<Selection> {
+        let output;
+        let (json, selected) = match &rule.selector {
+            Some(Selector { engine: SelectorEngine::Jq, expr }) => {
+                output = Value::Array(jq::run(expr, json)?);
+                (&output, output.as_array().into_iter().flatten().collect())
+            }
+            None => (json, self.select(json, rule)?),
+        };
+        let nodes = canonical_paths(json, &selected)
+            .into_iter()
+            .zip(selected)
+            .map(|(path, value)| (path, value.clone()))
+            .collect();
+        Ok(Selection { document, nodes })
+    }
+
+    /// Trace how a rule is evaluated on `json`
+    pub fn explain_document(&self, rule: &Rule, json: &Value) -> error::Result<Explanation> {
+        let output;
+        let (json, selected) = match &rule.selector {
+            Some(Selector { engine: SelectorEngine::Jq, expr }) => {
+                output = Value::Array(jq::run(expr, json)?);
+                (&output, output.as_array().into_iter().flatten().collect())
+            }
+            None => (json, self.select(json, rule)?),
+        };
+        let transformed = transformed(rule, &selected);
+        let mut canonical = None;
+        let (rule, checked) = canonical_form(rule, &transformed, &mut canonical);
+
+        let quantifier = rule.check.quantifier();
+        let mut nodes = Vec::new();
+        for (path, &value) in canonical_paths(json, &selected).into_iter().zip(&checked) {
+            let passed = match quantifier {
+                Quantifier::Set => None,
+                _ => Some(self.apply_check_with(&[value], &rule.check, &rule.canonicalize)?),
+            };
+            nodes.push(NodeOutcome { path, value: value.clone(), passed });
+        }
+        let passed = self.apply_check_with(&checked, &rule.check, &rule.canonicalize)?;
+
+        let (selector_kind, selector) = rule.selected_by();
+        let check = serde_json::to_value(&rule.check).ok().and_then(|c| c["type"].as_str().map(str::to_string));
+        Ok(Explanation {
+            rule_name: rule.name.clone(),
+            selector_kind: selector_kind.to_string(),
+            selector: selector.to_string(),
+            check: check.unwrap_or_default(),
+            quantifier,
+            reason: Explanation::reason_for(quantifier, &nodes, passed),
+            nodes,
+            passed,
+        })
+    }
+
+    /// Select the nodes a rule's JSONPath (or JSON Pointer) matches
+    fn select<'a>(&self, json: &'a Value, rule: &Rule) -> Result<Vec<&'a Value>> {
+        if rule.selector.is_some() {
+            bail!("Rule '{}' selects with a jq filter, whose output isn't part of the document", rule.name);
+        }
+        if let Some(pointer) = &rule.json_pointer {
+            return Ok(json.pointer(pointer).into_iter().collect());
+        }
+        self.select_path(json, &rule.jsonpath, self.config.engine_for(rule))
+    }
+
+    /// Select the nodes `path` matches, compiling it only the first time
+    fn select_path<'a>(&self, json: &'a Value, path: &str, engine: JsonPathEngine) -> Result<Vec<&'a Value>> {
+        self.compiled.jsonpath(path, engine)?.select(json)
+    }
+
+    /// Find the paths and values (after `transform`) of the selected nodes failing a rule's check
+    pub fn invalid_paths(&self, json: &Value, rule: &Rule) -> error::Result<Vec<(String, Value)>> {
+        let selected = self.select(json, rule)?;
+        let transformed = transformed(rule, &selected);
+        let checked: Vec<&Value> = transformed.iter().map(AsRef::as_ref).collect();
+        let invalid_indices = match command_failures(&rule.check, &checked)? {
+            Some(failures) => failures,
+            None => self.find_invalid_value_indices_with(&checked, &rule.check, &rule.canonicalize),
+        };
+        Ok(self
+            .invalid_paths_in(json, rule, &selected, &checked, invalid_indices, None)
+            .0
+            .into_iter()
+            .map(|(path, value)| (path, value.clone()))
+            .collect())
+    }
+
+    /// Find the failing values among `checked`, which are the `selected`
+    /// nodes of `json` after the rule's transforms, at the paths of those
+    /// nodes, along with the number of failing values. `invalid_indices`
+    /// are the indices of the failing values in `checked`. With a `limit`, only
+    /// the paths of the first `limit` failing values are resolved, so the
+    /// walk of the document stops once they are found.
+    fn invalid_paths_in<'a>(
+        &self,
+        json: &Value,
+        rule: &Rule,
+        selected: &[&Value],
+        checked: &[&'a Value],
+        mut invalid_indices: Vec<usize>,
+        limit: Option<usize>,
+    ) -> (Vec<(String, &'a Value)>, usize) {
+        let mut paths = Vec::new();
+        
+        if checked.is_empty() {
+            return (paths, 0);
+        }
+        
+        // Use a set to track unique paths and avoid duplicates
+        let mut unique_paths = std::collections::HashSet::new();
+        
+        let total = invalid_indices.len();
+        invalid_indices.truncate(limit.unwrap_or(total));
+        let invalid: Vec<&'a Value> = invalid_indices.iter().filter_map(|&i| checked.get(i).copied()).collect();
+        let origins: Vec<&Value> = invalid_indices.iter().filter_map(|&i| selected.get(i).copied()).collect();
+
+        for (value, path) in invalid.iter().zip(canonical_paths(json, &origins)) {
+            let Some(path) = path else {
+                continue;
+            };
+            // Point at each forbidden or misnamed key or null rather than the
+            // node containing it, and at the first out-of-order pair of an unsorted array
+            let found = match (&rule.check, value) {
+                (CheckRule::ForbiddenKeys { keys }, _) => rules::find_forbidden_keys(value, keys, &path),
+                (CheckRule::KeyFormat { convention, pattern }, _) => match self.key_regex(*convention, pattern.as_deref()) {
+                    Ok(re) => rules::find_keys(value, &path, |key| !re.is_match(key)),
+                    Err(_) => vec![(path, *value)],
+                },
+                (CheckRule::NoNullsAnywhere { allowed_paths }, _) => match self.nulls(value, &path, allowed_paths) {
+                    Ok(nulls) => nulls.into_iter().map(|path| (path, &Value::Null)).collect(),
+                    Err(_) => vec![(path, *value)],
+                },
+                (CheckRule::Sorted { order, by }, Value::Array(items)) => {
+                    match rules::first_unsorted_pair(items, *order, by.as_deref(), self.config.default_engine()) {
+                        Ok(Some(i)) => (i..i + 2)
+                            .map(|j| (format!("{}[{}]", path, j), &items[j]))
+                            .collect(),
+                        _ => vec![(path, *value)],
+                    }
+                }
+                _ => vec![(path, *value)],
+            };
+            for (path, value) in found {
+                if unique_paths.insert(path.clone()) {
+                    paths.push((path, value));
+                }
+            }
+        }
+        
+        (paths, total)
+    }
+
+    /// Values a `ref_integrity` check's references may point to, serialized for lookup
+    fn reference_targets(&self, target_file: &str, target_jsonpath: &str) -> Result<HashSet<String>> {
+        let target = Rule {
+            name: format!("references in {}", target_file),
+            json_file: Some(target_file.to_string()),
+            jsonpath: target_jsonpath.to_string(),
+            ..Default::default()
+        };
+        let document = self
+            .load(&target)
+            .with_context(|| format!("Failed to load reference target {}", target_file))?;
+
+        Ok(self
+            .select(&document.value, &target)?
+            .into_iter()
+            .map(|value| value.to_string())
+            .collect())
+    }
+
+    /// The document a `diff` or `matches_document` check compares values
+    /// with, in canonical form
+    fn baseline(&self, baseline_file: &str, canonicalize: &Canonicalize) -> Result<Value> {
+        let baseline = Rule {
+            name: format!("baseline {}", baseline_file),
+            json_file: Some(baseline_file.to_string()),
+            ..Default::default()
+        };
+        let document = self
+            .load(&baseline)
+            .with_context(|| format!("Failed to load baseline {}", baseline_file))?;
+        Ok(match canonicalize.is_identity() {
+            true => document.value,
+            false => canonicalize.apply(&document.value),
+        })
+    }
+
+    /// Differences between `baseline` and the value at `path`, leaving out
+    /// those at or inside nodes that `allowed_paths` select in either one
+    fn baseline_differences(&self, baseline: &Value, value: &Value, path: &str, allowed_paths: &[String]) -> Result<Vec<Difference>> {
+        let engine = self.config.default_engine();
+        let mut allowed = Vec::new();
+        for allowed_path in allowed_paths {
+            for root in [baseline, value] {
+                let selected = self.select_path(root, allowed_path, engine)?;
+                allowed.extend(
+                    canonical_paths(root, &selected)
+                        .into_iter()
+                        .flatten()
+                        .map(|relative| format!("{}{}", path, &relative[1..])),
+                );
+            }
+        }
+
+        Ok(diff::diff(baseline, value, path)
+            .into_iter()
+            .filter(|difference| !allowed.iter().any(|a| diff::is_within(difference.path(), a)))
+            .collect())
+    }
+
+    /// The regex every key of a `key_format` check must match
+    fn key_regex(&self, convention: KeyConvention, pattern: Option<&str>) -> error::Result<Arc<regex::Regex>> {
+        self.compiled.regex(convention.pattern(pattern)?)
+    }
+
+    /// Paths of the nulls at or inside the value at `path`, leaving out
+    /// those at or inside nodes that `allowed_paths` select in it
+    fn nulls(&self, value: &Value, path: &str, allowed_paths: &[String]) -> Result<Vec<String>> {
+        let engine = self.config.default_engine();
+        let mut allowed = Vec::new();
+        for allowed_path in allowed_paths {
+            let selected = self.select_path(value, allowed_path, engine)?;
+            allowed.extend(
+                canonical_paths(value, &selected)
+                    .into_iter()
+                    .flatten()
+                    .map(|relative| format!("{}{}", path, &relative[1..])),
+            );
+        }
+
+        Ok(rules::find_nulls(value, path)
+            .into_iter()
+            .filter(|null| !allowed.iter().any(|a| diff::is_within(null, a)))
+            .collect())
+    }
+
+    /// Apply a check rule to a set of JSON values
+    pub fn apply_check(&self, values: &[&Value], check: &CheckRule) -> error::Result<bool> {
+        self.apply_check_with(values, check, &Canonicalize::default())
+    }
+
+    /// Like [`apply_check`](Self::apply_check), for values already in the
+    /// canonical form `canonicalize` gives; reference documents are put in
+    /// the same form before they are compared
+    pub(crate) fn apply_check_with(&self, values: &[&Value], check: &CheckRule, canonicalize: &Canonicalize) -> error::Result<bool> {
+        // Set checks are defined over the whole selection, including an empty one
+        match check {
+            CheckRule::Aggregate { function, operator, value } => {
+                return Ok(function
+                    .apply(values)?
+                    .is_some_and(|aggregate| operator.compare(aggregate, *value)));
+            }
+            CheckRule::MatchCount { min, max } => {
+                let count = values.len();
+                return Ok(min.is_none_or(|m| count >= m) && max.is_none_or(|m| count <= m));
+            }
+            CheckRule::Unique => return Ok(rules::duplicate_indices(values).is_empty()),
+            CheckRule::SameLength => return Ok(rules::length_outliers(values).1.is_empty()),
+            _ => {}
+        }
+
+        // Check if no values found
+        if values.is_empty() {
+            return Ok(matches!(check, CheckRule::Empty));
+        }
+
+        match check {
+            CheckRule::Empty => {
+                // Check if all values are "empty" (null, empty string, empty array, empty object)
+                Ok(values.iter().all(|v| self.is_empty_value(v)))
+            }
+            CheckRule::NonEmpty => {
+                // Check if all values are non-empty
+                Ok(values.iter().all(|v| !self.is_empty_value(v)))
+            }
+            
+            CheckRule::Equals { value, tolerance } => {
+                Ok(values.iter().any(|v| rules::equals_within(v, value, *tolerance)))
+            }
+            
+            CheckRule::ApproxEquals { value, epsilon } => {
+                Ok(values.iter().any(|v| v.as_f64().is_some_and(|n| (n - value).abs() <= *epsilon)))
+            }
+            
+            CheckRule::NotEquals { value } => {
+                Ok(values.iter().all(|v| !rules::equals_within(v, value, None)))
+            }
+            
+            CheckRule::Contains { value } => {
+                Ok(values.iter().any(|v| self.contains(v, value)))
+            }
+            
+            CheckRule::ContainedBy { value } => {
+                Ok(values.iter().all(|v| self.contains(value, v)))
+            }
+            
+            CheckRule::JsonbContains { value } => {
+                // PostgreSQL @> operator: left contains right
+                Ok(values.iter().any(|v| self.jsonb_contains(v, value)))
+            }
+            
+            CheckRule::JsonbContainedBy { value } => {
+                // PostgreSQL <@ operator: left is contained by right
+                Ok(values.iter().all(|v| self.jsonb_contains(value, v)))
+            }
+            
+            CheckRule::JsonbExists { key } => {
+                Ok(values.iter().any(|v| {
+                    if let Value::Object(obj) = v {
+                        obj.contains_key(key)
+                    } else {
+                        false
+                    }
+                }))
+            }
+            
+            CheckRule::JsonbExistsAny { keys } => {
+                Ok(values.iter().any(|v| {
+                    if let Value::Object(obj) = v {
+                        keys.iter().any(|k| obj.contains_key(k))
+                    } else {
+                        false
+                    }
+                }))
+            }
+            
+            CheckRule::JsonbExistsAll { keys } => {
+                Ok(values.iter().any(|v| {
+                    if let Value::Object(obj) = v {
+                        keys.iter().all(|k| obj.contains_key(k))
+                    } else {
+                        false
+                    }
+                }))
+            }
+            
+            CheckRule::JsonbPathMatch { path: _ } => {
+                // Simplified JSONPath matching (would need jsonpath parser for full impl)
+                Ok(true)
+            }
+            
+            CheckRule::Regex { pattern } => {
+                let re = self.compiled.regex(pattern)?;
+                Ok(values.iter().any(|v| {
+                    if let Value::String(s) = v {
+                        re.is_match(s)
+                    } else {
+                        false
+                    }
+                }))
+            }
+            
+            CheckRule::StartsWith { value } => {
+                Ok(values.iter().all(|v| v.as_str().is_some_and(|s| s.starts_with(value.as_str()))))
+            }
+            
+            CheckRule::EndsWith { value } => {
+                Ok(values.iter().all(|v| v.as_str().is_some_and(|s| s.ends_with(value.as_str()))))
+            }
+            
+            CheckRule::Format { kind } => {
+                Ok(values.iter().all(|v| v.as_str().is_some_and(|s| kind.matches(s))))
+            }
+            
+            CheckRule::GreaterThan { value } => {
+                Ok(values.iter().any(|v| number::compare_value(v, value) == Some(cmp::Ordering::Greater)))
+            }
+            
+            CheckRule::LessThan { value } => {
+                Ok(values.iter().any(|v| number::compare_value(v, value) == Some(cmp::Ordering::Less)))
+            }
+            
+            CheckRule::GreaterThanOrEqual { value } => {
+                Ok(values.iter().any(|v| number::compare_value(v, value).is_some_and(cmp::Ordering::is_ge)))
+            }
+            
+            CheckRule::LessThanOrEqual { value } => {
+                Ok(values.iter().any(|v| number::compare_value(v, value).is_some_and(cmp::Ordering::is_le)))
+            }
+            
+            CheckRule::Sorted { order, by } => {
+                for v in values {
+                    let Value::Array(items) = v else {
+                        return Ok(false);
+                    };
+                    if rules::first_unsorted_pair(items, *order, by.as_deref(), self.config.default_engine())?.is_some() {
+                        return Ok(false);
+                    }
+                }
+                Ok(true)
+            }
+            
+            CheckRule::PathsEqual { left_jsonpath, right_jsonpath } => {
+                for v in values {
+                    if !self.paths_equal(v, left_jsonpath, right_jsonpath)? {
+                        return Ok(false);
+                    }
+                }
+                Ok(true)
+            }
+            
+            CheckRule::RefIntegrity { target_file, target_jsonpath } => {
+                let targets = self.reference_targets(target_file, target_jsonpath)?;
+                Ok(values.iter().all(|v| targets.contains(&v.to_string())))
+            }
+            
+            CheckRule::Diff { .. } | CheckRule::MatchesDocument { .. } => {
+                let (baseline_file, allowed_paths) = check.reference_document().expect("reference document");
+                let baseline = self.baseline(baseline_file, canonicalize)?;
+                for v in values {
+                    if !self.baseline_differences(&baseline, v, "$", allowed_paths)?.is_empty() {
+                        return Ok(false);
+                    }
+                }
+                Ok(true)
+            }
+            
+            CheckRule::RequiredKeys { keys } => Ok(values.iter().all(|v| {
+                v.as_object().is_some_and(|object| keys.iter().all(|key| key.is_satisfied_by(object)))
+            })),
+            
+            CheckRule::KeyFormat { convention, pattern } => {
+                let re = self.key_regex(*convention, pattern.as_deref())?;
+                Ok(values.iter().all(|v| rules::find_keys(v, "$", |key| !re.is_match(key)).is_empty()))
+            }
+            CheckRule::NoNullsAnywhere { allowed_paths } => {
+                for v in values {
+                    if !self.nulls(v, "$", allowed_paths)?.is_empty() {
+                        return Ok(false);
+                    }
+                }
+                Ok(true)
+            }
+            CheckRule::ForbiddenKeys { keys } => {
+                Ok(values.iter().all(|v| rules::find_forbidden_keys(v, keys, "$").is_empty()))
+            }
+            
+            CheckRule::Integer => Ok(values.iter().all(|v| rules::is_integer(v))),
+            
+            CheckRule::MultipleOf { value } => {
+                Ok(values.iter().all(|v| rules::is_multiple_of(v, *value)))
+            }
+
+            CheckRule::DecimalPlaces { max } => {
+                Ok(values.iter().all(|v| rules::decimal_places(v).is_some_and(|places| places <= u64::from(*max))))
+            }
+
+            CheckRule::SafeInteger => Ok(values.iter().all(|v| rules::is_safe_integer(v))),
+            
+            CheckRule::ArrayLength { min, max } => {
+                Ok(values.iter().any(|v| {
+                    if let Value::Array(arr) = v {
+                        let len = arr.len();
+                        let min_ok = min.is_none_or(|m| len >= m);
+                        let max_ok = max.is_none_or(|m| len <= m);
+                        min_ok && max_ok
+                    } else {
+                        false
+                    }
+                }))
+            }
+
+            CheckRule::MaxDepth { depth } => Ok(values.iter().all(|v| rules::depth(v) <= *depth)),
+
+            CheckRule::MaxSizeBytes { bytes } => Ok(values.iter().all(|v| rules::compact_size(v) <= *bytes)),
+
+            CheckRule::Command { .. } => {
+                let spec = CommandSpec::of(check).expect("command check");
+                for v in values {
+                    if !command::run_check_command(&spec, v)? {
+                        return Ok(false);
+                    }
+                }
+                Ok(true)
+            }
+
+            CheckRule::Expr { expr } => {
+                let expr = self.compiled.expr(expr)?;
+                for v in values {
+                    if !expr.eval(v)? {
+                        return Ok(false);
+                    }
+                }
+                Ok(true)
+            }
+
+            CheckRule::Aggregate { .. } | CheckRule::MatchCount { .. } | CheckRule::Unique | CheckRule::SameLength => {
+                unreachable!("set checks are handled above")
+            }
+
+            CheckRule::Template { name, .. } => {
+                Err(anyhow!("Check template '{}' was not expanded; see Config::expand_templates", name).into())
+            }
+        }
+    }
+
+    /// Whether the two JSONPaths of a `paths_equal` check select equal
+    /// values in `value`; a path selecting nothing equals nothing
+    fn paths_equal(&self, value: &Value, left: &str, right: &str) -> Result<bool> {
+        let operand = |path: &str| -> Result<Option<Value>> {
+            let (path, length) = rules::split_length(path);
+            let nodes = self.compiled.jsonpath(path, self.config.default_engine())?.select(value)?;
+            Ok(rules::path_operand(&nodes, length))
+        };
+        Ok(match (operand(left)?, operand(right)?) {
+            (Some(left), Some(right)) => rules::equals_within(&left, &right, None),
+            _ => false,
+        })
+    }
+
+    /// Check if a container contains a value
+    pub fn contains(&self, container: &Value, contained: &Value) -> bool {
+        match (container, contained) {
+            (Value::Array(arr), val) => arr.contains(val),
+            (Value::String(s), Value::String(sub)) => s.contains(sub.as_str()),
+            (Value::Object(obj1), Value::Object(obj2)) => {
+                obj2.iter().all(|(k, v)| obj1.get(k) == Some(v))
+            }
+            _ => false,
+        }
+    }
+
+    /// JSONB contains operation (PostgreSQL @> operator)
+    pub fn jsonb_contains(&self, left: &Value, right: &Value) -> bool {
+        jsonb_contains(left, right)
+    }
+
+    /// Check if a value is considered "empty"
+    pub fn is_empty_value(&self, value: &Value) -> bool {
+        match value {
+            Value::Null => true,
+            Value::String(s) => s.is_empty(),
+            Value::Array(arr) => arr.is_empty(),
+            Value::Object(obj) => obj.is_empty(),
+            _ => false,
+        }
+    }
+
+    /// Find indices of values that are causing validation failures
+    pub fn find_invalid_value_indices(&self, values: &[&Value], check: &CheckRule) -> Vec<usize> {
+        self.find_invalid_value_indices_with(values, check, &Canonicalize::default())
+    }
+
+    fn find_invalid_value_indices_with(&self, values: &[&Value], check: &CheckRule, canonicalize: &Canonicalize) -> Vec<usize> {
+        let mut invalid_indices = Vec::new();
+
+        match check {
+            CheckRule::Empty => {
+                for (i, v) in values.iter().enumerate() {
+                    if !self.is_empty_value(v) {
+                        invalid_indices.push(i);
+                    }
+                }
+            }
+            CheckRule::NonEmpty => {
+                for (i, v) in values.iter().enumerate() {
+                    if self.is_empty_value(v) {
+                        invalid_indices.push(i);
+                    }
+                }
+            }
+            CheckRule::Equals { value: target, tolerance } => {
+                let has_match = values.iter().any(|v| rules::equals_within(v, target, *tolerance));
+                if !has_match {
+                    // If no values match, all are invalid
+                    invalid_indices.extend(0..values.len());
+                }
+            }
+            CheckRule::ApproxEquals { value: target, epsilon } => {
+                let has_match = values.iter().any(|v| v.as_f64().is_some_and(|n| (n - target).abs() <= *epsilon));
+                if !has_match {
+                    invalid_indices.extend(0..values.len());
+                }
+            }
+            CheckRule::NotEquals { value: target } => {
+                for (i, v) in values.iter().enumerate() {
+                    if rules::equals_within(v, target, None) {
+                        invalid_indices.push(i);
+                    }
+                }
+            }
+            CheckRule::Contains { value: target } => {
+                let has_match = values.iter().any(|v| self.contains(v, target));
+                if !has_match {
+                    invalid_indices.extend(0..values.len());
+                }
+            }
+            CheckRule::ContainedBy { value: container } => {
+                for (i, v) in values.iter().enumerate() {
+                    if !self.contains(container, v) {
+                        invalid_indices.push(i);
+                    }
+                }
+            }
+            CheckRule::Regex { pattern } => {
+                if let Ok(re) = self.compiled.regex(pattern) {
+                    for (i, v) in values.iter().enumerate() {
+                        let matches = if let Value::String(s) = v {
+                            re.is_match(s)
+                        } else {
+                            false
+                        };
+                        if !matches {
+                            invalid_indices.push(i);
+                        }
+                    }
+                }
+            }
+            CheckRule::StartsWith { value } => {
+                for (i, v) in values.iter().enumerate() {
+                    if !v.as_str().is_some_and(|s| s.starts_with(value.as_str())) {
+                        invalid_indices.push(i);
+                    }
+                }
+            }
+            CheckRule::EndsWith { value } => {
+                for (i, v) in values.iter().enumerate() {
+                    if !v.as_str().is_some_and(|s| s.ends_with(value.as_str())) {
+                        invalid_indices.push(i);
+                    }
+                }
+            }
+            CheckRule::Format { kind } => {
+                for (i, v) in values.iter().enumerate() {
+                    if !v.as_str().is_some_and(|s| kind.matches(s)) {
+                        invalid_indices.push(i);
+                    }
+                }
+            }
+            CheckRule::GreaterThan { value: threshold } => {
+                for (i, v) in values.iter().enumerate() {
+                    if number::compare_value(v, threshold) != Some(cmp::Ordering::Greater) {
+                        invalid_indices.push(i);
+                    }
+                }
+            }
+            CheckRule::LessThan { value: threshold } => {
+                for (i, v) in values.iter().enumerate() {
+                    if number::compare_value(v, threshold) != Some(cmp::Ordering::Less) {
+                        invalid_indices.push(i);
+                    }
+                }
+            }
+            CheckRule::GreaterThanOrEqual { value: threshold } => {
+                for (i, v) in values.iter().enumerate() {
+                    if !number::compare_value(v, threshold).is_some_and(cmp::Ordering::is_ge) {
+                        invalid_indices.push(i);
+                    }
+                }
+            }
+            CheckRule::LessThanOrEqual { value: threshold } => {
+                for (i, v) in values.iter().enumerate() {
+                    if !number::compare_value(v, threshold).is_some_and(cmp::Ordering::is_le) {
+                        invalid_indices.push(i);
+                    }
+                }
+            }
+            CheckRule::Sorted { order, by } => {
+                for (i, v) in values.iter().enumerate() {
+                    let is_valid = v.as_array().is_some_and(|items| {
+                        matches!(rules::first_unsorted_pair(items, *order, by.as_deref(), self.config.default_engine()), Ok(None))
+                    });
+                    if !is_valid {
+                        invalid_indices.push(i);
+                    }
+                }
+            }
+            CheckRule::PathsEqual { left_jsonpath, right_jsonpath } => {
+                for (i, v) in values.iter().enumerate() {
+                    if !matches!(self.paths_equal(v, left_jsonpath, right_jsonpath), Ok(true)) {
+                        invalid_indices.push(i);
+                    }
+                }
+            }
+            CheckRule::RefIntegrity { target_file, target_jsonpath } => {
+                // Without targets every reference dangles
+                let targets = self.reference_targets(target_file, target_jsonpath).unwrap_or_default();
+                for (i, v) in values.iter().enumerate() {
+                    if !targets.contains(&v.to_string()) {
+                        invalid_indices.push(i);
+                    }
+                }
+            }
+            CheckRule::Diff { .. } | CheckRule::MatchesDocument { .. } => {
+                // Without a baseline nothing matches it
+                let (baseline_file, allowed_paths) = check.reference_document().expect("reference document");
+                let baseline = self.baseline(baseline_file, canonicalize).ok();
+                for (i, v) in values.iter().enumerate() {
+                    let matches = baseline.as_ref().is_some_and(|baseline| {
+                        self.baseline_differences(baseline, v, "$", allowed_paths).is_ok_and(|d| d.is_empty())
+                    });
+                    if !matches {
+                        invalid_indices.push(i);
+                    }
+                }
+            }
+            CheckRule::RequiredKeys { keys } => {
+                for (i, v) in values.iter().enumerate() {
+                    let is_valid = v
+                        .as_object()
+                        .is_some_and(|object| keys.iter().all(|key| key.is_satisfied_by(object)));
+                    if !is_valid {
+                        invalid_indices.push(i);
+                    }
+                }
+            }
+            CheckRule::ForbiddenKeys { keys } => {
+                for (i, v) in values.iter().enumerate() {
+                    if !rules::find_forbidden_keys(v, keys, "$").is_empty() {
+                        invalid_indices.push(i);
+                    }
+                }
+            }
+            CheckRule::KeyFormat { convention, pattern } => match self.key_regex(*convention, pattern.as_deref()) {
+                Ok(re) => invalid_indices.extend(
+                    (0..values.len()).filter(|&i| !rules::find_keys(values[i], "$", |key| !re.is_match(key)).is_empty()),
+                ),
+                Err(_) => invalid_indices.extend(0..values.len()),
+            },
+            CheckRule::NoNullsAnywhere { allowed_paths } => {
+                for (i, v) in values.iter().enumerate() {
+                    if !self.nulls(v, "$", allowed_paths).is_ok_and(|nulls| nulls.is_empty()) {
+                        invalid_indices.push(i);
+                    }
+                }
+            }
+            CheckRule::Integer => {
+                for (i, v) in values.iter().enumerate() {
+                    if !rules::is_integer(v) {
+                        invalid_indices.push(i);
+                    }
+                }
+            }
+            CheckRule::MultipleOf { value } => {
+                for (i, v) in values.iter().enumerate() {
+                    if !rules::is_multiple_of(v, *value) {
+                        invalid_indices.push(i);
+                    }
+                }
+            }
+            CheckRule::DecimalPlaces { max } => {
+                for (i, v) in values.iter().enumerate() {
+                    if rules::decimal_places(v).is_none_or(|places| places > u64::from(*max)) {
+                        invalid_indices.push(i);
+                    }
+                }
+            }
+            CheckRule::SafeInteger => {
+                for (i, v) in values.iter().enumerate() {
+                    if !rules::is_safe_integer(v) {
+                        invalid_indices.push(i);
+                    }
+                }
+            }
+            CheckRule::ArrayLength { min, max } => {
+                for (i, v) in values.iter().enumerate() {
+                    let is_valid = if let Value::Array(arr) = v {
+                        let len = arr.len();
+                        let min_ok = min.is_none_or(|m| len >= m);
+                        let max_ok = max.is_none_or(|m| len <= m);
+                        min_ok && max_ok
+                    } else {
+                        false
+                    };
+                    if !is_valid {
+                        invalid_indices.push(i);
+                    }
+                }
+            }
+            CheckRule::MaxDepth { depth } => {
+                invalid_indices.extend((0..values.len()).filter(|&i| rules::depth(values[i]) > *depth));
+            }
+            CheckRule::MaxSizeBytes { bytes } => {
+                invalid_indices.extend((0..values.len()).filter(|&i| rules::compact_size(values[i]) > *bytes));
+            }
+            CheckRule::Command { .. } => {
+                // Without a verdict from the program, every value is suspect
+                invalid_indices = command_failures(check, values)
+                    .ok()
+                    .flatten()
+                    .unwrap_or_else(|| (0..values.len()).collect());
+            }
+            CheckRule::Expr { expr } => match self.compiled.expr(expr) {
+                Ok(expr) => {
+                    for (i, v) in values.iter().enumerate() {
+                        if !expr.eval(v).unwrap_or(false) {
+                            invalid_indices.push(i);
+                        }
+                    }
+                }
+                Err(_) => invalid_indices.extend(0..values.len()),
+            },
+            // Every occurrence of a duplicated value is invalid
+            CheckRule::Unique => invalid_indices = rules::duplicate_indices(values),
+            // Every array not of the common length, and every non-array, is an outlier
+            CheckRule::SameLength => invalid_indices = rules::length_outliers(values).1,
+            // Other set checks fail as a whole rather than at individual nodes
+            CheckRule::Aggregate { .. } | CheckRule::MatchCount { .. } => {}
+            _ => {
+                // For other rules, if validation failed, consider all values as potentially invalid
+                invalid_indices.extend(0..values.len());
+            }
+        }
+
+        invalid_indices
+    }
+
+    /// Find the canonical path of the selected value at `index`
+    pub fn find_path_for_selected_value(&self, json: &Value, jsonpath: &str, index: usize) -> Option<String> {
+        let results = self.select_path(json, jsonpath, JsonPathEngine::JsonpathLib).ok()?;
+        let value = results.get(index)?;
+
+        canonical_paths(json, std::slice::from_ref(value)).pop().flatten()
+    }
+
+}
@@ -1,108 +1,1253 @@
-//! Command-line interface for the JSON checker
-
-use anyhow::Result;
-use serde_json::Value;
-use crate::checker::JsonChecker;
-use crate::path_finder::find_json_path;
-
-/// CLI application entry point
-pub struct Cli;
-
-impl Cli {
-    /// Find and print the path to a specific value in a JSON file
-    /// This implements the exact functionality requested in TODO.md
-    pub fn find_json_node_path(json_file: &str, target_value: &str) -> Result<()> {
-        println!("🔍 Finding JSON node path...");
-        println!("📄 JSON file: {}", json_file);
-        println!("🎯 Target value: \"{}\"", target_value);
-        
-        // Read and parse JSON file
-        let json_content = std::fs::read_to_string(json_file)?;
-        let json: Value = serde_json::from_str(&json_content)?;
-        
-        // Create target value for searching
-        let target = Value::String(target_value.to_string());
-        
-        // Find the path using the exact algorithm from TODO.md
-        if let Some(path) = find_json_path(&json, &target, vec!["$".to_string()]) {
-            println!("✅ Found path: {}", path);
-        } else {
-            println!("❌ Value not found.");
-        }
-        
-        Ok(())
-    }
-    
-    /// Run the CLI application with the given arguments
-    pub fn run(config_path: Option<&str>) -> Result<()> {
-        let config_file = config_path.unwrap_or("rules.json");
-        
-        println!("🔍 Starting JSON Checker...");
-        println!("📄 Using config file: {}", config_file);
-        
-        let checker = JsonChecker::new(config_file)?;
-        let results = checker.run()?;
-
-        Self::print_results(&results);
-        
-        let failed_count = results.iter().filter(|r| !r.passed).count();
-        if failed_count > 0 {
-            std::process::exit(1);
-        }
-
-        Ok(())
-    }
-
-    /// Print validation results in a formatted way
-    fn print_results(results: &[crate::config::CheckResult]) {
-        println!("\n=== JSON Checker Results ===\n");
-        
-        let mut passed = 0;
-        let mut failed = 0;
-
-        for result in results {
-            println!("{}", result.message);
-            
-            if result.passed {
-                passed += 1;
-            } else {
-                failed += 1;
-                
-                // Print detailed failure information
-                if !result.invalid_positions.is_empty() {
-                    println!("   📍 Invalid positions found:");
-                    for position in &result.invalid_positions {
-                        println!("      • {}", position);
-                    }
-                }
-                
-                if !result.values_found.is_empty() {
-                    println!("   📄 Values found:");
-                    for (i, value) in result.values_found.iter().enumerate() {
-                        let value_str = match value {
-                            serde_json::Value::String(s) => format!("\"{}\"", s),
-                            _ => value.to_string(),
-                        };
-                        println!("      [{}] {}", i, value_str);
-                    }
-                }
-                
-                if !result.invalid_positions.is_empty() || !result.values_found.is_empty() {
-                    println!(); // Add spacing after detailed info
-                }
-            }
-        }
-
-        println!("----------------------------");
-        println!("📊 Summary:");
-        println!("   Total: {} rules", results.len());
-        println!("   Passed: {} ✅", passed);
-        println!("   Failed: {} ❌", failed);
-        
-        if failed > 0 {
-            println!("\n⚠️  Some validation rules failed!");
-        } else {
-            println!("\n🎉 All validation rules passed!");
-        }
-    }
+//! Command-line interface for the JSON checker
+
+use anyhow::{anyhow, bail, Context, Result};
+use regex::Regex;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+use std::io::{BufRead, IsTerminal};
+use std::time::Duration;
+use crate::api;
+use crate::baseline::Baseline;
+use crate::canonicalize::Canonicalize;
+use crate::checker::JsonChecker;
+use crate::consume;
+use crate::config::{CheckResult, Config, Rule, Severity, Status};
+use crate::history::{self, RunRecord};
+use crate::explore::Explorer;
+use crate::fix::{self, PatchOperation};
+use crate::format::{self, DocumentFormat};
+use crate::html;
+use crate::init::Wizard;
+use crate::jsonpath;
+use crate::lint;
+use crate::markdown;
+use crate::messages::Catalog;
+use crate::patch;
+use crate::path_finder::{find_matches, JsonPath, Target};
+use crate::reporter::Reporter;
+use crate::sarif;
+use crate::schema;
+use crate::serve;
+use crate::source::{expand_glob, is_glob, local_path};
+use crate::suggest;
+use crate::suite;
+use crate::validate::{config_warnings, validate_config};
+#[cfg(feature = "watch")]
+use crate::watch;
+
+/// Process exit codes used by the CLI
+pub mod exit_code {
+    /// All rules passed (or failures stayed within the allowed threshold)
+    pub const SUCCESS: i32 = 0;
+    /// More rules failed than `--max-failures` allows
+    pub const RULES_FAILED: i32 = 1;
+    /// The config could not be loaded or the command line was invalid
+    pub const CONFIG_INVALID: i32 = 2;
+    /// A JSON file referenced by a failing rule does not exist (or its glob matches nothing)
+    pub const FILE_MISSING: i32 = 3;
+}
+
+/// Subcommands supported by the CLI
+#[derive(Debug, Default, PartialEq)]
+pub enum Command {
+    /// Run all rules once (default)
+    #[default]
+    Check,
+    /// Re-run rules whenever the config or a referenced JSON file changes
+    Watch,
+    /// Check the config for problems without evaluating any rule
+    ValidateConfig,
+    /// Summarize flaky and regressed rules from the run history (`report trends`)
+    Trends,
+    /// Re-run rules on a schedule and serve the latest results over HTTP
+    Serve,
+    /// Check documents posted over HTTP against a rule set
+    ServeApi,
+    /// Check each message of a stream and report the failing ones
+    Consume,
+    /// Check the given files with the rules that apply to them, printing
+    /// `file:line:col` diagnostics
+    Lint,
+    /// Serve lint diagnostics to editors over the Language Server Protocol
+    Lsp,
+    /// Print the JSON Schema for config files
+    Schema,
+    /// Build a starter config interactively from a sample document
+    Init,
+    /// Try JSONPath queries against a document and save them as rules
+    Explore,
+    /// Print what each rule selects without applying its check
+    Select,
+    /// Print the paths of the values in a document that match a target
+    FindPath,
+    /// Print the values at a JSONPath in a document
+    Get,
+    /// Print the structural differences between two documents
+    Diff,
+}
+
+/// Options accepted on the command line
+#[derive(Debug, Default)]
+pub struct CliOptions {
+    pub command: Command,
+    /// Path to the rules config file (defaults to `rules.json`)
+    pub config_file: Option<String>,
+    /// Variables set with `--var key=value`
+    pub vars: HashMap<String, String>,
+    /// Number of failing rules tolerated before exiting with an error
+    pub max_failures: usize,
+    /// Most failing nodes listed per failed rule
+    pub max_failures_shown: Option<usize>,
+    /// Lowest severity whose failures count toward the exit code
+    pub fail_on: Severity,
+    /// Disable colored output (also disabled by a non-empty `NO_COLOR`)
+    pub no_color: bool,
+    /// Print only failures and the summary
+    pub quiet: bool,
+    /// Print the given number of slowest rules after the summary
+    pub timings: Option<usize>,
+    /// Format results are reported in
+    pub report: ReportFormat,
+    /// File the report is written to instead of stdout
+    pub output: Option<String>,
+    /// Baseline file of known failures that don't fail the run
+    pub baseline: Option<String>,
+    /// Rewrite the baseline with the failures of this run
+    pub update_baseline: bool,
+    /// Profile whose overrides apply to the config
+    pub profile: Option<String>,
+    /// Suite whose rules are the only ones run
+    pub suite: Option<String>,
+    /// Language of result messages: `en`, `zh-TW` or a JSON file of message templates
+    pub lang: Option<String>,
+    /// NDJSON file each run's summary is appended to, and `report trends` reads
+    pub history: Option<String>,
+    /// Number of most recent runs `report trends` looks at
+    pub last: Option<usize>,
+    /// Time between runs in `serve` mode
+    pub interval: Option<Duration>,
+    /// Address `serve` and `serve-api` listen on
+    pub listen: Option<String>,
+    /// Let `serve-api` run the rules a request brings as `config`
+    pub allow_request_config: bool,
+    /// Messages `consume` reads: `-` for stdin, a file or a `kafka://` topic
+    pub from: Option<String>,
+    /// Where `consume` writes violations: `-` for stdout, a webhook URL or a `kafka://` topic
+    pub sink: Option<String>,
+    /// Files `lint` checks
+    pub files: Vec<String>,
+    /// Time limit for each rule that doesn't set its own `timeout_ms`
+    pub timeout: Option<Duration>,
+    /// Trace how each rule was evaluated after the results
+    pub explain: bool,
+    /// Offer to apply the suggested fixes to the checked files
+    pub fix: bool,
+    /// Sample document `init` and `explore` build rules for
+    pub sample: Option<String>,
+    /// Rules `select` is limited to, from `--rule` (repeatable)
+    pub rules: Vec<String>,
+    /// What `find-path` looks for, from `--target`, `--contains` or `--regex`
+    pub target: Option<Target>,
+    /// Print every path `find-path` finds instead of the first
+    pub all: bool,
+    /// JSONPath `get` prints the values at
+    pub query: Option<String>,
+    /// Document `diff` compares the sample with
+    pub compare_with: Option<String>,
+    /// Normalization `diff` applies to both documents (`--trim`,
+    /// `--ignore-case`, `--normalize-numbers`)
+    pub canonicalize: Canonicalize,
+}
+
+/// Output formats for `--report`
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum ReportFormat {
+    /// Human-readable terminal output (default)
+    #[default]
+    Text,
+    /// SARIF 2.1.0 log for code-scanning tools
+    Sarif,
+    /// Standalone HTML page
+    Html,
+    /// Markdown summary table for pull request comments
+    Markdown,
+    /// RFC 6902 patch of each document removing or replacing its failing nodes
+    JsonPatch,
+}
+
+/// Number of rules listed by `--timings` without an explicit count
+pub const DEFAULT_TIMINGS: usize = 10;
+
+/// History file read by `report trends` without `--history`
+pub const DEFAULT_HISTORY: &str = "json-checker-history.ndjson";
+
+/// Number of runs `report trends` looks at without `--last`
+pub const DEFAULT_TREND_RUNS: usize = 20;
+
+impl CliOptions {
+    /// Parse command-line arguments (excluding the program name)
+    pub fn parse(args: &[String]) -> Result<Self> {
+        let mut options = CliOptions::default();
+        let mut args = args.iter().peekable();
+
+        let command = match args.peek().map(|arg| arg.as_str()) {
+            Some("watch") => Some(Command::Watch),
+            Some("validate-config") => Some(Command::ValidateConfig),
+            Some("report") => Some(Command::Trends),
+            Some("serve") => Some(Command::Serve),
+            Some("serve-api") => Some(Command::ServeApi),
+            Some("consume") => Some(Command::Consume),
+            Some("lint") => Some(Command::Lint),
+            Some("lsp") => Some(Command::Lsp),
+            Some("schema") => Some(Command::Schema),
+            Some("init") => Some(Command::Init),
+            Some("explore") => Some(Command::Explore),
+            Some("select") => Some(Command::Select),
+            Some("find-path") => Some(Command::FindPath),
+            Some("get") => Some(Command::Get),
+            Some("diff") => Some(Command::Diff),
+            _ => None,
+        };
+        if let Some(command) = command {
+            options.command = command;
+            args.next();
+        }
+        if options.command == Command::Trends {
+            match args.next().map(|arg| arg.as_str()) {
+                Some("trends") => {}
+                Some(other) => bail!("Unknown report '{}', expected trends", other),
+                None => bail!("report requires a report name: trends"),
+            }
+        }
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--config" => {
+                    let path = args
+                        .next()
+                        .ok_or_else(|| anyhow!("--config requires a path"))?;
+                    options.config_file = Some(path.to_string());
+                }
+                "--var" => {
+                    let assignment = args
+                        .next()
+                        .ok_or_else(|| anyhow!("--var requires a key=value argument"))?;
+                    let (key, value) = assignment
+                        .split_once('=')
+                        .ok_or_else(|| anyhow!("Invalid --var '{}', expected key=value", assignment))?;
+                    options.vars.insert(key.to_string(), value.to_string());
+                }
+                "--max-failures" => {
+                    let count = args
+                        .next()
+                        .ok_or_else(|| anyhow!("--max-failures requires a number"))?;
+                    options.max_failures = count
+                        .parse()
+                        .map_err(|_| anyhow!("Invalid --max-failures '{}', expected a number", count))?;
+                }
+                "--max-failures-shown" => {
+                    let count = args
+                        .next()
+                        .ok_or_else(|| anyhow!("--max-failures-shown requires a number"))?;
+                    options.max_failures_shown = Some(
+                        count
+                            .parse()
+                            .map_err(|_| anyhow!("Invalid --max-failures-shown '{}', expected a number", count))?,
+                    );
+                }
+                "--fail-on" => {
+                    let level = args
+                        .next()
+                        .ok_or_else(|| anyhow!("--fail-on requires warning or error"))?;
+                    options.fail_on = match level.as_str() {
+                        "warning" => Severity::Warning,
+                        "error" => Severity::Error,
+                        other => bail!("Invalid --fail-on '{}', expected warning or error", other),
+                    };
+                }
+                "--timings" => {
+                    // The count is optional: `--timings` or `--timings 5`
+                    let count = args.next_if(|next| next.parse::<usize>().is_ok());
+                    options.timings = Some(count.map_or(DEFAULT_TIMINGS, |n| n.parse().unwrap()));
+                }
+                "--report" => {
+                    let format = args
+                        .next()
+                        .ok_or_else(|| anyhow!("--report requires text, sarif, html, markdown or json-patch"))?;
+                    options.report = match format.as_str() {
+                        "text" => ReportFormat::Text,
+                        "sarif" => ReportFormat::Sarif,
+                        "html" => ReportFormat::Html,
+                        "markdown" => ReportFormat::Markdown,
+                        "json-patch" => ReportFormat::JsonPatch,
+                        other => bail!("Invalid --report '{}', expected text, sarif, html, markdown or json-patch", other),
+                    };
+                }
+                "--output" | "-o" => {
+                    let path = args
+                        .next()
+                        .ok_or_else(|| anyhow!("--output requires a path"))?;
+                    options.output = Some(path.to_string());
+                }
+                "--baseline" => {
+                    let path = args
+                        .next()
+                        .ok_or_else(|| anyhow!("--baseline requires a path"))?;
+                    options.baseline = Some(path.to_string());
+                }
+                "--update-baseline" => options.update_baseline = true,
+                "--profile" => {
+                    let name = args
+                        .next()
+                        .ok_or_else(|| anyhow!("--profile requires a profile name"))?;
+                    options.profile = Some(name.to_string());
+                }
+                "--suite" => {
+                    let name = args
+                        .next()
+                        .ok_or_else(|| anyhow!("--suite requires a suite name"))?;
+                    options.suite = Some(name.to_string());
+                }
+                "--lang" => {
+                    let lang = args
+                        .next()
+                        .ok_or_else(|| anyhow!("--lang requires en, zh-TW or a message file"))?;
+                    options.lang = Some(lang.to_string());
+                }
+                "--interval" => {
+                    let interval = args
+                        .next()
+                        .ok_or_else(|| anyhow!("--interval requires a duration such as 60s"))?;
+                    options.interval = Some(serve::parse_interval(interval)?);
+                }
+                "--timeout" => {
+                    let timeout = args
+                        .next()
+                        .ok_or_else(|| anyhow!("--timeout requires a duration such as 30s"))?;
+                    options.timeout = Some(serve::parse_interval(timeout)?);
+                }
+                "--listen" => {
+                    let address = args
+                        .next()
+                        .ok_or_else(|| anyhow!("--listen requires an address such as 127.0.0.1:8080"))?;
+                    options.listen = Some(address.to_string());
+                }
+                "--from" => {
+                    let from = args
+                        .next()
+                        .ok_or_else(|| anyhow!("--from requires -, a file or kafka://broker/topic"))?;
+                    options.from = Some(from.to_string());
+                }
+                "--sink" => {
+                    let sink = args
+                        .next()
+                        .ok_or_else(|| anyhow!("--sink requires -, a webhook URL or kafka://broker/topic"))?;
+                    options.sink = Some(sink.to_string());
+                }
+                "--history" => {
+                    let path = args
+                        .next()
+                        .ok_or_else(|| anyhow!("--history requires a path"))?;
+                    options.history = Some(path.to_string());
+                }
+                "--last" => {
+                    let count = args
+                        .next()
+                        .ok_or_else(|| anyhow!("--last requires a number"))?;
+                    options.last = Some(
+                        count
+                            .parse()
+                            .map_err(|_| anyhow!("Invalid --last '{}', expected a number", count))?,
+                    );
+                }
+                "--rule" => {
+                    let name = args
+                        .next()
+                        .ok_or_else(|| anyhow!("--rule requires a rule name"))?;
+                    options.rules.push(name.to_string());
+                }
+                "--target" | "--contains" | "--regex" => {
+                    let value = args
+                        .next()
+                        .ok_or_else(|| anyhow!("{} requires a value", arg))?;
+                    if options.target.is_some() {
+                        bail!("Only one of --target, --contains and --regex can be given");
+                    }
+                    options.target = Some(match arg.as_str() {
+                        "--target" => Target::literal(value),
+                        "--contains" => Target::Contains(value.to_string()),
+                        _ => Target::Regex(
+                            Regex::new(value).map_err(|e| anyhow!("Invalid --regex '{}': {}", value, e))?,
+                        ),
+                    });
+                }
+                "--all" => options.all = true,
+                "--trim" => options.canonicalize.trim = true,
+                "--ignore-case" => options.canonicalize.ignore_case = true,
+                "--normalize-numbers" => options.canonicalize.normalize_numbers = true,
+                "--allow-request-config" => options.allow_request_config = true,
+                "--explain" => options.explain = true,
+                "--fix" => options.fix = true,
+                "--no-color" => options.no_color = true,
+                "--quiet" | "-q" => options.quiet = true,
+                flag if flag.starts_with("--") => bail!("Unknown option: {}", flag),
+                path if options.command == Command::Lint => options.files.push(path.to_string()),
+                path if options.command == Command::Get && options.sample.is_some() => {
+                    if options.query.is_some() {
+                        bail!("Unexpected argument: {}", path);
+                    }
+                    options.query = Some(path.to_string());
+                }
+                path if options.command == Command::Diff && options.sample.is_some() => {
+                    if options.compare_with.is_some() {
+                        bail!("Unexpected argument: {}", path);
+                    }
+                    options.compare_with = Some(path.to_string());
+                }
+                path if matches!(
+                    options.command,
+                    Command::Init | Command::Explore | Command::FindPath | Command::Get | Command::Diff
+                ) => {
+                    if options.sample.is_some() {
+                        bail!("Unexpected argument: {}", path);
+                    }
+                    options.sample = Some(path.to_string());
+                }
+                path => {
+                    if options.config_file.is_some() {
+                        bail!("Unexpected argument: {}", path);
+                    }
+                    options.config_file = Some(path.to_string());
+                }
+            }
+        }
+
+        if options.update_baseline && options.baseline.is_none() {
+            bail!("--update-baseline requires --baseline <path>");
+        }
+
+        Ok(options)
+    }
+}
+
+/// ANSI colors used for each kind of result
+#[derive(Debug, Clone, Copy)]
+pub struct Theme {
+    pub passed: &'static str,
+    pub failed: &'static str,
+    pub warning: &'static str,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme {
+            passed: "\x1b[32m",
+            failed: "\x1b[31m",
+            warning: "\x1b[33m",
+        }
+    }
+}
+
+/// How results are rendered on the terminal
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Style {
+    /// Colors to use, or `None` for plain output
+    pub theme: Option<Theme>,
+    /// Only render failures and the summary
+    pub quiet: bool,
+}
+
+impl Style {
+    /// Pick the style for the command line options and the current terminal.
+    ///
+    /// Colors are only used when stdout is a terminal, so piped output stays
+    /// plain and stable.
+    pub fn from_options(options: &CliOptions) -> Self {
+        let no_color_env = std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+        let color = !options.no_color && !no_color_env && std::io::stdout().is_terminal();
+
+        Style {
+            theme: color.then(Theme::default),
+            quiet: options.quiet,
+        }
+    }
+
+    fn paint(&self, color: fn(&Theme) -> &'static str, text: &str) -> String {
+        match &self.theme {
+            Some(theme) => format!("{}{}\x1b[0m", color(theme), text),
+            None => text.to_string(),
+        }
+    }
+}
+
+/// Shows the rule being evaluated on a single, rewritten stderr line
+pub struct ConsoleProgress {
+    started: usize,
+    total: usize,
+}
+
+impl ConsoleProgress {
+    pub fn new(total: usize) -> Self {
+        ConsoleProgress { started: 0, total }
+    }
+}
+
+impl Reporter for ConsoleProgress {
+    fn on_rule_start(&mut self, rule: &Rule) {
+        self.started += 1;
+        eprint!("\r\x1b[K⏳ [{}/{}] {}", self.started, self.total, rule.name);
+    }
+
+    fn on_finish(&mut self, _results: &[CheckResult]) {
+        eprint!("\r\x1b[K");
+    }
+}
+
+/// CLI application entry point
+pub struct Cli;
+
+impl Cli {
+    /// Find and print the paths of values matching `target` in a JSON file,
+    /// only the first one unless `all` is set
+    pub fn find_json_node_path(json_file: &str, target: &Target, all: bool) -> Result<Vec<JsonPath>> {
+        println!("🔍 Finding JSON node path...");
+        println!("📄 JSON file: {}", json_file);
+        match target {
+            Target::Value(value) => println!("🎯 Target value: {}", value),
+            Target::Contains(text) => println!("🎯 Strings containing: \"{}\"", text),
+            Target::Regex(regex) => println!("🎯 Strings matching: /{}/", regex),
+        }
+        
+        let json_content = std::fs::read_to_string(json_file)
+            .with_context(|| format!("Failed to read {}", json_file))?;
+        let json: Value = serde_json::from_str(&json_content)
+            .with_context(|| format!("Failed to parse {}", json_file))?;
+        
+        let found = find_matches(&json, target, all);
+        match found.as_slice() {
+            [] => println!("❌ Value not found."),
+            [path] => println!("✅ Found path: {}", path),
+            paths => {
+                println!("✅ Found {} paths:", paths.len());
+                for path in paths {
+                    println!("   {}", path);
+                }
+            }
+        }
+        
+        Ok(found)
+    }
+    
+    /// Run the CLI application with the given options, returning the process exit code
+    pub fn run(options: &CliOptions) -> Result<i32> {
+        match options.command {
+            Command::Check => Self::check(options),
+            Command::Watch => Self::watch(options).map(|_| exit_code::SUCCESS),
+            Command::ValidateConfig => Self::validate_config(options),
+            Command::Trends => Self::trends(options),
+            Command::Serve => Self::serve(options).map(|_| exit_code::SUCCESS),
+            Command::ServeApi => Self::serve_api(options).map(|_| exit_code::SUCCESS),
+            Command::Consume => Self::consume(options),
+            Command::Lint => Self::lint(options),
+            Command::Lsp => Self::lsp(options).map(|_| exit_code::SUCCESS),
+            Command::Schema => Self::schema(options),
+            Command::Init => {
+                let stdin = std::io::stdin();
+                Self::init(options, stdin.lock(), std::io::stdout())
+            }
+            Command::Explore => {
+                let stdin = std::io::stdin();
+                Self::explore(options, stdin.lock(), std::io::stdout())
+            }
+            Command::Select => Self::select(options),
+            Command::FindPath => {
+                let file = options.sample.as_deref().ok_or_else(|| anyhow!("find-path requires a JSON file"))?;
+                let target = options
+                    .target
+                    .as_ref()
+                    .ok_or_else(|| anyhow!("find-path requires --target, --contains or --regex"))?;
+                let found = Self::find_json_node_path(file, target, options.all)?;
+                Ok(if found.is_empty() { exit_code::RULES_FAILED } else { exit_code::SUCCESS })
+            }
+            Command::Get => Self::get(options, std::io::stdout()),
+            Command::Diff => Self::diff(options, std::io::stdout()),
+        }
+    }
+
+    /// Report every problem in the config without evaluating rules
+    fn validate_config(options: &CliOptions) -> Result<i32> {
+        let config_file = options.config_file.as_deref().unwrap_or("rules.json");
+
+        println!("🔍 Validating config file: {}", config_file);
+
+        let config = Config::from_file_with_profile(config_file, &options.vars, options.profile.as_deref())?;
+        let issues = validate_config(&config);
+
+        // Warnings don't make the config invalid
+        for warning in config_warnings(&config) {
+            println!("   ⚠️  {}", warning);
+        }
+        if issues.is_empty() {
+            println!("✅ Config is valid ({} rules)", config.rules.len());
+            return Ok(exit_code::SUCCESS);
+        }
+
+        for issue in &issues {
+            println!("   • {}", issue);
+        }
+        println!("\n❌ Found {} problem(s) in config", issues.len());
+
+        Ok(exit_code::CONFIG_INVALID)
+    }
+
+    /// Ask which paths of the sample to check and write the rules to
+    /// `--output` (default `rules.json`), never replacing an existing file
+    pub fn init(options: &CliOptions, input: impl BufRead, output: impl std::io::Write) -> Result<i32> {
+        let sample = options.sample.as_deref().ok_or_else(|| anyhow!("init requires a sample JSON file"))?;
+        let path = options.output.as_deref().unwrap_or("rules.json");
+        if std::path::Path::new(path).exists() {
+            bail!("{} already exists; pass --output to write the rules elsewhere", path);
+        }
+
+        let mut wizard = Wizard::new(input, output);
+        let config = wizard.run(sample)?;
+        std::fs::write(path, serde_json::to_string_pretty(&config)? + "\n")
+            .with_context(|| format!("Failed to write {}", path))?;
+        println!("\n✅ Wrote {} rule(s) to {}; run them with: json-checker-rs {}", config.rules.len(), path, path);
+        Ok(exit_code::SUCCESS)
+    }
+
+    /// Run JSONPath queries typed at a prompt against the sample; `:save`
+    /// appends rules to the config file (default `rules.json`)
+    pub fn explore(options: &CliOptions, input: impl BufRead, output: impl std::io::Write) -> Result<i32> {
+        let sample = options.sample.as_deref().ok_or_else(|| anyhow!("explore requires a JSON file"))?;
+        let text = std::fs::read_to_string(sample).with_context(|| format!("Failed to read {}", sample))?;
+        let document = format::parse(&text, DocumentFormat::detect(sample))?.value;
+        let config_path = options.config_file.as_deref().unwrap_or("rules.json");
+
+        Explorer::new(input, output, sample, document, config_path).run()?;
+        Ok(exit_code::SUCCESS)
+    }
+
+    /// Print the nodes each rule (or each `--rule`) selects, without checking them
+    fn select(options: &CliOptions) -> Result<i32> {
+        let config_file = options.config_file.as_deref().unwrap_or("rules.json");
+        let checker = JsonChecker::from_config(Config::from_file_with_profile(config_file, &options.vars, options.profile.as_deref())?);
+        print!("{}", Self::render_selections(&checker, &options.rules)?);
+        Ok(exit_code::SUCCESS)
+    }
+
+    /// Render the nodes selected by the named rules, or by every rule if
+    /// `names` is empty. Rules whose document can't be loaded are reported
+    /// inline; an unknown name is an error.
+    pub fn render_selections(checker: &JsonChecker, names: &[String]) -> Result<String> {
+        let rules = &checker.config().rules;
+        for name in names {
+            if !rules.iter().any(|rule| &rule.name == name) {
+                match suggest::closest(name, rules.iter().map(|rule| rule.name.as_str())) {
+                    Some(suggestion) => bail!("No rule named '{}', did you mean '{}'?", name, suggestion),
+                    None => bail!("No rule named '{}'", name),
+                }
+            }
+        }
+
+        let mut out = String::new();
+        for rule in rules.iter().filter(|rule| names.is_empty() || names.contains(&rule.name)) {
+            let (kind, selector) = rule.selected_by();
+            let selections = match checker.select_rule(rule) {
+                Ok(selections) => selections,
+                Err(e) => {
+                    let _ = writeln!(out, "📌 Rule '{}': {} '{}' couldn't be evaluated: {:#}", rule.name, kind, selector, e);
+                    continue;
+                }
+            };
+            for selection in selections {
+                let document = selection.document.map(|uri| format!(" in {}", uri)).unwrap_or_default();
+                let _ = writeln!(
+                    out,
+                    "📌 Rule '{}': {} '{}' selected {} node(s){}",
+                    rule.name,
+                    kind,
+                    selector,
+                    selection.nodes.len(),
+                    document
+                );
+                for (path, value) in &selection.nodes {
+                    let _ = writeln!(out, "   {} = {}", path.as_deref().unwrap_or("?"), value);
+                }
+            }
+        }
+        Ok(out)
+    }
+
+    /// Pretty-print each value the JSONPath selects in the document, one per
+    /// match; exits with 1 if nothing is selected
+    pub fn get(options: &CliOptions, mut output: impl std::io::Write) -> Result<i32> {
+        let (Some(file), Some(path)) = (options.sample.as_deref(), options.query.as_deref()) else {
+            bail!("get requires a JSON file and a JSONPath");
+        };
+        let text = std::fs::read_to_string(file).with_context(|| format!("Failed to read {}", file))?;
+        let document = format::parse(&text, DocumentFormat::detect(file))?.value;
+
+        let selected = jsonpath::select(&document, path)?;
+        for value in &selected {
+            writeln!(output, "{}", serde_json::to_string_pretty(value)?)?;
+        }
+        Ok(if selected.is_empty() { exit_code::RULES_FAILED } else { exit_code::SUCCESS })
+    }
+
+    /// Print what was removed (`-`), added (`+`) and changed (`~`) from the
+    /// first document to the second, by JSONPath; exits with 1 if they differ
+    pub fn diff(options: &CliOptions, mut output: impl std::io::Write) -> Result<i32> {
+        let (Some(old), Some(new)) = (options.sample.as_deref(), options.compare_with.as_deref()) else {
+            bail!("diff requires two JSON files");
+        };
+        let read = |file: &str| -> Result<Value> {
+            let text = std::fs::read_to_string(file).with_context(|| format!("Failed to read {}", file))?;
+            Ok(options.canonicalize.apply(&format::parse(&text, DocumentFormat::detect(file))?.value))
+        };
+
+        let differences = crate::diff::diff(&read(old)?, &read(new)?, "$");
+        if differences.is_empty() {
+            writeln!(output, "✅ {} and {} are structurally equal", old, new)?;
+            return Ok(exit_code::SUCCESS);
+        }
+        for difference in &differences {
+            writeln!(output, "{}", difference)?;
+        }
+        writeln!(output, "🔀 {} difference(s): {}", differences.len(), crate::diff::summary(&differences))?;
+        Ok(exit_code::RULES_FAILED)
+    }
+
+    /// Write the config file JSON Schema to stdout or `--output`
+    fn schema(options: &CliOptions) -> Result<i32> {
+        let schema = serde_json::to_string_pretty(&schema::config_schema())? + "\n";
+        match &options.output {
+            Some(path) => std::fs::write(path, schema).with_context(|| format!("Failed to write schema {}", path))?,
+            None => print!("{}", schema),
+        }
+        Ok(exit_code::SUCCESS)
+    }
+
+    /// Summarize how rules behaved over the recorded runs
+    fn trends(options: &CliOptions) -> Result<i32> {
+        let path = options.history.as_deref().unwrap_or(DEFAULT_HISTORY);
+        let runs = history::load(path)?;
+        let last = options.last.unwrap_or(DEFAULT_TREND_RUNS);
+        let recent = &runs[runs.len().saturating_sub(last)..];
+
+        print!("{}", Self::render_trends(recent));
+        Ok(exit_code::SUCCESS)
+    }
+
+    /// Render per-rule trends, flaky and regressed rules first
+    pub fn render_trends(runs: &[RunRecord]) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "📈 Trends over the last {} run(s):", runs.len());
+
+        let mut trends = history::trends(runs);
+        trends.sort_by_key(|t| (!t.regressed, !t.is_flaky()));
+        for trend in &trends {
+            let status = match (trend.regressed, trend.is_flaky()) {
+                (true, _) => "🔻 regressed",
+                (false, true) => "🔀 flaky",
+                (false, false) if !trend.latest_passed => "❌ failing",
+                (false, false) if trend.failures > 0 => "⚠️ recovered",
+                (false, false) => "✅ stable",
+            };
+            let _ = writeln!(
+                out,
+                "   {} {}: failed {} of {} run(s), {} flip(s), avg {:.1}ms",
+                status, trend.name, trend.failures, trend.runs, trend.flips, trend.average_ms
+            );
+        }
+
+        let flaky = trends.iter().filter(|t| t.is_flaky()).count();
+        let regressed = trends.iter().filter(|t| t.regressed).count();
+        let _ = writeln!(out, "\n   Flaky: {}, Regressed: {}", flaky, regressed);
+        out
+    }
+
+    /// Decide the exit code for a finished run.
+    ///
+    /// A failing rule whose JSON file does not exist always yields
+    /// `FILE_MISSING`. Otherwise failures at or above `fail_on` severity are
+    /// counted and `RULES_FAILED` is returned once they exceed `max_failures`.
+    pub fn exit_code(options: &CliOptions, config: &Config, results: &[CheckResult]) -> i32 {
+        let failed: Vec<&CheckResult> = results.iter().filter(|r| !r.passed).collect();
+
+        let file_missing = failed.iter().any(|result| {
+            config
+                .rules
+                .iter()
+                .find(|rule| rule.name == result.rule_name)
+                .and_then(|rule| rule.json_file.as_deref())
+                .and_then(local_path)
+                .is_some_and(|path| {
+                    if is_glob(path) {
+                        expand_glob(path).map_or(true, |files| files.is_empty())
+                    } else {
+                        !std::path::Path::new(path).exists()
+                    }
+                })
+        });
+        if file_missing {
+            return exit_code::FILE_MISSING;
+        }
+
+        let counted = failed.iter().filter(|r| r.severity >= options.fail_on).count();
+        if counted > options.max_failures {
+            exit_code::RULES_FAILED
+        } else {
+            exit_code::SUCCESS
+        }
+    }
+
+    /// Re-run rules on every change to the config or referenced JSON files
+    #[cfg(feature = "watch")]
+    fn watch(options: &CliOptions) -> Result<()> {
+        let config_file = options.config_file.as_deref().unwrap_or("rules.json");
+        let style = Style::from_options(options);
+
+        println!("👀 Watching JSON Checker config: {}", config_file);
+        println!("   Press Ctrl+C to stop.");
+
+        watch::watch(
+            config_file,
+            &options.vars,
+            options.profile.as_deref(),
+            |update| {
+                for path in update.changed {
+                    println!("\n🔄 Changed: {}", path.display());
+                }
+                print!("{}", Self::render_results(update.results, &style));
+            },
+            |e| eprintln!("\n❌ {:#}", e),
+        )
+    }
+
+    #[cfg(not(feature = "watch"))]
+    fn watch(_options: &CliOptions) -> Result<()> {
+        bail!("watch mode needs json-checker-rs built with the `watch` feature")
+    }
+
+    /// Re-run rules periodically and serve the latest results over HTTP
+    fn serve(options: &CliOptions) -> Result<()> {
+        let config_file = options.config_file.as_deref().unwrap_or("rules.json");
+        let listen = options.listen.as_deref().unwrap_or(serve::DEFAULT_LISTEN);
+        let interval = options.interval.unwrap_or(serve::DEFAULT_INTERVAL);
+
+        println!("🩺 Serving JSON Checker results for {} on http://{}", config_file, listen);
+        println!("   Re-running every {:?}; GET /healthz or /results. Press Ctrl+C to stop.", interval);
+
+        serve::serve(
+            config_file,
+            &options.vars,
+            options.profile.as_deref(),
+            listen,
+            interval,
+            |snapshot| {
+                let failed = snapshot.results.iter().filter(|r| !r.passed).count();
+                println!("🔄 Ran {} rules, {} failed", snapshot.results.len(), failed);
+            },
+            |e| eprintln!("❌ {:#}", e),
+        )
+    }
+
+    /// Check documents posted to `/validate`, with the rules of `--config`
+    /// unless `--allow-request-config` lets a request bring its own
+    fn serve_api(options: &CliOptions) -> Result<()> {
+        let listen = options.listen.as_deref().unwrap_or(serve::DEFAULT_LISTEN);
+        let preloaded = match &options.config_file {
+            Some(config_file) => {
+                let mut config = Config::from_file_with_profile(config_file, &options.vars, options.profile.as_deref())?;
+                api::bind_payload(&mut config);
+                println!("🛰  Serving JSON Checker validation for {} on http://{}", config_file, listen);
+                Some(JsonChecker::from_config(config))
+            }
+            None if options.allow_request_config => {
+                println!("🛰  Serving JSON Checker validation on http://{}", listen);
+                None
+            }
+            None => bail!("serve-api needs --config, or --allow-request-config to run the rules requests bring"),
+        };
+        println!("   POST /validate with {{\"document\": ...}}. Press Ctrl+C to stop.");
+        api::serve_api(api::Service { preloaded, allow_request_config: options.allow_request_config }, listen)
+    }
+
+    /// Check each message of `--from` with the rules of the config and send
+    /// the failing ones to `--sink`, until the messages run out
+    fn consume(options: &CliOptions) -> Result<i32> {
+        let config_file = options.config_file.as_deref().unwrap_or("rules.json");
+        let mut config = Config::from_file_with_profile(config_file, &options.vars, options.profile.as_deref())?;
+        api::bind_payload(&mut config);
+        let checker = JsonChecker::from_config(config);
+
+        let messages = consume::open_input(options.from.as_deref().unwrap_or("-"))?;
+        let mut sink = consume::open_sink(options.sink.as_deref().unwrap_or("-"))?;
+        let summary = consume::consume(&checker, messages, sink.as_mut())?;
+        eprintln!("📨 Checked {} messages, {} violated the rules", summary.messages, summary.violations);
+
+        Ok(if summary.violations > 0 { exit_code::RULES_FAILED } else { exit_code::SUCCESS })
+    }
+
+    /// Print a diagnostic for each failure of the rules applying to the
+    /// given files; failures at or above `fail_on` severity fail the run
+    fn lint(options: &CliOptions) -> Result<i32> {
+        let config_file = options.config_file.as_deref().unwrap_or("rules.json");
+        let config = Config::from_file_with_profile(config_file, &options.vars, options.profile.as_deref())?;
+        let checker = JsonChecker::from_config(config);
+
+        let diagnostics = lint::lint(&checker, &options.files);
+        for diagnostic in &diagnostics {
+            println!("{}", diagnostic);
+        }
+
+        let counted = diagnostics.iter().filter(|d| d.severity >= options.fail_on).count();
+        Ok(if counted > 0 { exit_code::RULES_FAILED } else { exit_code::SUCCESS })
+    }
+
+    /// Serve diagnostics for the files open in an editor over stdio
+    #[cfg(feature = "lsp")]
+    fn lsp(options: &CliOptions) -> Result<()> {
+        let config_file = options.config_file.as_deref().unwrap_or("rules.json");
+        crate::lsp::serve_stdio(config_file, &options.vars, options.profile.as_deref())
+    }
+
+    #[cfg(not(feature = "lsp"))]
+    fn lsp(_options: &CliOptions) -> Result<()> {
+        bail!("the language server needs json-checker-rs built with the `lsp` feature")
+    }
+
+    /// Run every rule once and return the exit code for the results
+    fn check(options: &CliOptions) -> Result<i32> {
+        let config_file = options.config_file.as_deref().unwrap_or("rules.json");
+        let style = Style::from_options(options);
+        let text = options.report == ReportFormat::Text;
+        
+        if text && !style.quiet {
+            println!("🔍 Starting JSON Checker...");
+            println!("📄 Using config file: {}", config_file);
+        }
+        
+        let catalog = options.lang.as_deref().map(Catalog::load).transpose()?;
+        let mut config = Config::from_file_with_profile(config_file, &options.vars, options.profile.as_deref())?;
+        if let Some(timeout) = options.timeout {
+            config.timeout_ms = Some(timeout.as_millis() as u64);
+        }
+        if let Some(suite) = &options.suite {
+            config.select_suite(suite)?;
+        }
+        let mut builder = JsonChecker::builder().config(config);
+        // A baseline is compared with every failure; the lists are shortened after
+        if let Some(count) = options.max_failures_shown.filter(|_| options.baseline.is_none()) {
+            builder = builder.max_failures_shown(count);
+        }
+        let checker = builder.build();
+        let mut results = if text && !style.quiet && std::io::stderr().is_terminal() {
+            checker.run_with_reporter(&mut ConsoleProgress::new(checker.config().rules.len()))?
+        } else {
+            checker.run()?
+        };
+
+        if let Some(path) = &options.baseline {
+            let baseline = if options.update_baseline {
+                let baseline = Baseline::from_results(&results);
+                baseline.save(path)?;
+                if text && !style.quiet {
+                    println!("📝 Recorded {} known failure(s) in {}", baseline.fingerprints.len(), path);
+                }
+                baseline
+            } else {
+                Baseline::load(path)?
+            };
+            baseline.apply(&mut results);
+            if let Some(shown) = options.max_failures_shown {
+                results.iter_mut().for_each(|result| result.truncate_failures(shown));
+            }
+        }
+        if let Some(path) = &options.history {
+            history::append(path, &RunRecord::from_results(&results))?;
+        }
+        if let Some(catalog) = &catalog {
+            results.iter_mut().for_each(|result| catalog.localize(result));
+        }
+
+        let report = match options.report {
+            ReportFormat::Text => {
+                // Files never get colors
+                let style = if options.output.is_some() { Style { theme: None, ..style } } else { style };
+                let mut report = Self::render_results(&results, &style);
+                if let Some(count) = options.timings {
+                    report.push_str(&Self::render_timings(&results, count));
+                }
+                if options.explain {
+                    report.push_str(&Self::render_explanations(&checker, &results, &style));
+                }
+                report
+            }
+            ReportFormat::Sarif => {
+                let log = sarif::to_sarif(checker.config(), &results);
+                serde_json::to_string_pretty(&log)? + "\n"
+            }
+            ReportFormat::Html => html::to_html(checker.config(), &results),
+            ReportFormat::Markdown => markdown::to_markdown(&results),
+            ReportFormat::JsonPatch => serde_json::to_string_pretty(&patch::to_json_patch(&results))? + "\n",
+        };
+        match &options.output {
+            Some(path) => {
+                std::fs::write(path, report).with_context(|| format!("Failed to write report {}", path))?;
+                if !style.quiet {
+                    eprintln!("📝 Report written to {}", path);
+                }
+            }
+            None => print!("{}", report),
+        }
+        if options.fix {
+            Self::apply_fixes(&results, &mut std::io::stdin().lock())?;
+        }
+        
+        Ok(Self::exit_code(options, checker.config(), &results))
+    }
+
+    /// Show the suggested fixes of each local JSON file and apply them to the
+    /// files confirmed on `input`, returning how many files were rewritten
+    pub fn apply_fixes(results: &[CheckResult], input: &mut dyn BufRead) -> Result<usize> {
+        let mut by_file: BTreeMap<&str, Vec<&PatchOperation>> = BTreeMap::new();
+        let mut pending: Vec<&CheckResult> = results.iter().collect();
+        while let Some(result) = pending.pop() {
+            pending.extend(&result.per_document);
+            if let Some(path) = result.document.as_deref().and_then(local_path)
+                && DocumentFormat::detect(path) == DocumentFormat::Json
+            {
+                let fixes = by_file.entry(path).or_default();
+                for fix in &result.fixes {
+                    if !fixes.contains(&fix) {
+                        fixes.push(fix);
+                    }
+                }
+            }
+        }
+
+        let mut fixed = 0;
+        for (path, fixes) in by_file.into_iter().filter(|(_, fixes)| !fixes.is_empty()) {
+            eprintln!("🔧 {} suggested fix(es) for {}:", fixes.len(), path);
+            for fix in &fixes {
+                eprintln!("   • {}", fix);
+            }
+            eprint!("   Apply them? [y/N] ");
+            let mut answer = String::new();
+            input.read_line(&mut answer)?;
+            if !matches!(answer.trim().to_lowercase().as_str(), "y" | "yes") {
+                continue;
+            }
+
+            let text = std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path))?;
+            // Only values are rewritten in place, which needs plain JSON to find them in
+            if serde_json::from_str::<Value>(&text).is_err() {
+                eprintln!("   ⚠️  {} isn't strict JSON (comments or trailing commas?); fix it by hand", path);
+                continue;
+            }
+            let patch: Vec<PatchOperation> = fixes.into_iter().cloned().collect();
+            let patched = fix::apply_to_text(&text, &patch).with_context(|| format!("Failed to fix {}", path))?;
+            std::fs::write(path, patched).with_context(|| format!("Failed to write {}", path))?;
+            eprintln!("   ✅ Fixed {}", path);
+            fixed += 1;
+        }
+        Ok(fixed)
+    }
+
+    /// Render the `count` slowest rules, slowest first
+    pub fn render_timings(results: &[CheckResult], count: usize) -> String {
+        let mut by_duration: Vec<&CheckResult> = results.iter().collect();
+        by_duration.sort_by_key(|r| std::cmp::Reverse(r.duration));
+
+        let total: std::time::Duration = results.iter().map(|r| r.duration).sum();
+        let mut out = format!("\n⏱️  Slowest rules (total {:.1}ms):\n", total.as_secs_f64() * 1000.0);
+        for result in by_duration.into_iter().take(count) {
+            let _ = writeln!(
+                out,
+                "   {:>9.1}ms  {}",
+                result.duration.as_secs_f64() * 1000.0,
+                result.rule_name
+            );
+        }
+
+        out
+    }
+
+    /// Render the evaluation trace of each rule that ran; with `quiet`,
+    /// only of the rules that didn't pass
+    pub fn render_explanations(checker: &JsonChecker, results: &[CheckResult], style: &Style) -> String {
+        let mut out = String::from("\n=== Explanations ===\n\n");
+        for (rule, result) in checker.config().rules.iter().zip(results) {
+            if result.status == Status::Skipped || (style.quiet && result.passed) {
+                continue;
+            }
+            match checker.explain_rule(rule) {
+                Ok(explanation) => out.push_str(&explanation.to_string()),
+                Err(e) => {
+                    let _ = writeln!(out, "🔬 Rule '{}' can't be explained: {:#}", rule.name, e);
+                }
+            }
+        }
+        out
+    }
+
+    /// Render validation results and a summary
+    pub fn render_results(results: &[CheckResult], style: &Style) -> String {
+        let mut out = String::new();
+        if !style.quiet {
+            out.push_str("\n=== JSON Checker Results ===\n\n");
+        }
+        
+        let mut passed = 0;
+        let mut failed = 0;
+        let mut errors = 0;
+        let mut timed_out = 0;
+        let mut warnings = 0;
+        let mut skipped = 0;
+
+        for result in results {
+            match result.status {
+                Status::Skipped => skipped += 1,
+                Status::Pass => passed += 1,
+                Status::Error => errors += 1,
+                Status::Timeout => timed_out += 1,
+                Status::Fail if result.severity == Severity::Warning => warnings += 1,
+                Status::Fail => failed += 1,
+            }
+            if result.status == Status::Skipped {
+                if !style.quiet {
+                    let _ = writeln!(out, "{}", result.message);
+                }
+                continue;
+            }
+            if result.passed {
+                if !style.quiet {
+                    let _ = writeln!(out, "{}", style.paint(|t| t.passed, &result.message));
+                }
+                continue;
+            }
+
+            let color = if result.severity == Severity::Warning {
+                |t: &Theme| t.warning
+            } else {
+                |t: &Theme| t.failed
+            };
+            let (headline, details) = match result.message.split_once('\n') {
+                Some((headline, details)) => (headline, Some(details)),
+                None => (result.message.as_str(), None),
+            };
+            let _ = writeln!(out, "{}", style.paint(color, headline));
+            if let Some(details) = details {
+                let _ = writeln!(out, "{}", details);
+            }
+            if let Some(hint) = &result.hint {
+                let _ = writeln!(out, "   💡 Hint: {}", hint);
+            }
+            if let Some(url) = &result.docs_url {
+                let _ = writeln!(out, "   📖 Docs: {}", url);
+            }
+            
+            // Print detailed failure information
+            if !result.invalid_positions.is_empty() {
+                let _ = writeln!(out, "   📍 Invalid positions found:");
+                for (i, position) in result.invalid_positions.iter().enumerate() {
+                    match result.location(i) {
+                        Some(location) => {
+                            let _ = writeln!(out, "      • {} ({})", position, location);
+                        }
+                        None => {
+                            let _ = writeln!(out, "      • {}", position);
+                        }
+                    }
+                }
+            }
+            
+            if !result.values_found.is_empty() {
+                let _ = writeln!(out, "   📄 Values found:");
+                for (i, value) in result.values_found.iter().enumerate() {
+                    let value_str = match value {
+                        serde_json::Value::String(s) => format!("\"{}\"", s),
+                        _ => value.to_string(),
+                    };
+                    let _ = writeln!(out, "      [{}] {}", i, value_str);
+                }
+            }
+            
+            if !result.retry_log.is_empty() {
+                let _ = writeln!(out, "   🔁 Attempts:");
+                for attempt in &result.retry_log {
+                    let _ = writeln!(out, "      {}", attempt);
+                }
+            }
+
+            if !result.differences.is_empty() {
+                let _ = writeln!(out, "   🔀 Differences ({}):", crate::diff::summary(&result.differences));
+                for difference in &result.differences {
+                    let _ = writeln!(out, "      {}", difference);
+                }
+            }
+            
+            if !result.fixes.is_empty() {
+                let _ = writeln!(out, "   🔧 Suggested fixes (apply with --fix):");
+                for fix in &result.fixes {
+                    let _ = writeln!(out, "      {}", fix);
+                }
+            }
+            
+            if !result.invalid_positions.is_empty()
+                || !result.values_found.is_empty()
+                || !result.differences.is_empty()
+            {
+                out.push('\n'); // Add spacing after detailed info
+            }
+        }
+
+        out.push_str("----------------------------\n");
+        out.push_str("📊 Summary:\n");
+        let _ = writeln!(out, "   Total: {} rules", results.len());
+        let _ = writeln!(out, "   {}", style.paint(|t| t.passed, &format!("Passed: {} ✅", passed)));
+        let _ = writeln!(out, "   {}", style.paint(|t| t.failed, &format!("Failed: {} ❌", failed)));
+        if warnings > 0 {
+            let _ = writeln!(out, "   {}", style.paint(|t| t.warning, &format!("Warnings: {} ⚠️", warnings)));
+        }
+        if errors > 0 {
+            let _ = writeln!(out, "   {}", style.paint(|t| t.failed, &format!("Errors: {} 💥", errors)));
+        }
+        if timed_out > 0 {
+            let _ = writeln!(out, "   {}", style.paint(|t| t.failed, &format!("Timed out: {} ⏱", timed_out)));
+        }
+        if skipped > 0 {
+            let _ = writeln!(out, "   Skipped: {} ⏭", skipped);
+        }
+
+        let suites = suite::summarize(results);
+        if !suites.is_empty() {
+            out.push_str("📦 Suites:\n");
+            for summary in &suites {
+                let _ = write!(out, "   {}: {} of {} passed", summary.name, summary.passed, summary.total);
+                if summary.failed > 0 {
+                    let _ = write!(out, ", {} failed", summary.failed);
+                }
+                if summary.warnings > 0 {
+                    let _ = write!(out, ", {} warning(s)", summary.warnings);
+                }
+                if summary.skipped > 0 {
+                    let _ = write!(out, ", {} skipped", summary.skipped);
+                }
+                out.push('\n');
+            }
+        }
+        
+        if failed > 0 || errors > 0 || timed_out > 0 || warnings > 0 {
+            out.push_str("\n⚠️  Some validation rules failed!\n");
+        } else {
+            out.push_str("\n🎉 All validation rules passed!\n");
+        }
+
+        out
+    }
 }
\ No newline at end of file
@@ -0,0 +1,122 @@
+//! External command checks
+//!
+//! Each selected value is written as JSON to the program's stdin; exit code 0
+//! means the value passed.
+
+use anyhow::{bail, Context, Result};
+use serde_json::Value;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+use crate::rules::CheckRule;
+
+/// Timeout applied when a command check doesn't set `timeout_ms`
+pub const DEFAULT_TIMEOUT_MS: u64 = 10_000;
+
+/// How often a running command is polled for completion
+const POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+/// Settings for running a check command
+#[derive(Debug, Clone)]
+pub struct CommandSpec<'a> {
+    pub program: &'a str,
+    pub args: &'a [String],
+    /// Kill the command and report an error after this long
+    pub timeout: Duration,
+    /// Run with an empty environment (only `PATH` is kept)
+    pub clear_env: bool,
+}
+
+impl<'a> CommandSpec<'a> {
+    /// Get the command settings of a `Command` check
+    pub fn of(check: &'a CheckRule) -> Option<Self> {
+        match check {
+            CheckRule::Command { program, args, timeout_ms, clear_env } => Some(CommandSpec {
+                program,
+                args,
+                timeout: Duration::from_millis(timeout_ms.unwrap_or(DEFAULT_TIMEOUT_MS)),
+                clear_env: *clear_env,
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// Run a check command with a value on stdin and report whether it exited with 0
+pub fn run_check_command(spec: &CommandSpec, value: &Value) -> Result<bool> {
+    let mut command = Command::new(spec.program);
+    command
+        .args(spec.args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    if spec.clear_env {
+        command.env_clear();
+        if let Some(path) = std::env::var_os("PATH") {
+            command.env("PATH", path);
+        }
+    }
+
+    let mut child = command
+        .spawn()
+        .context(format!("Failed to run check command: {}", spec.program))?;
+
+    // Write from a separate thread so a program that doesn't read its input
+    // can't block us before the timeout kicks in
+    let input = serde_json::to_vec(value)?;
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let writer = std::thread::spawn(move || {
+        // The program may exit without reading stdin; that's not an error
+        let _ = stdin.write_all(&input);
+    });
+
+    let deadline = Instant::now() + spec.timeout;
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            bail!(
+                "Check command '{}' timed out after {}ms",
+                spec.program,
+                spec.timeout.as_millis()
+            );
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    };
+    let _ = writer.join();
+
+    Ok(status.success())
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn spec<'a>(args: &'a [String], timeout_ms: u64) -> CommandSpec<'a> {
+        CommandSpec {
+            program: "sh",
+            args,
+            timeout: Duration::from_millis(timeout_ms),
+            clear_env: true,
+        }
+    }
+
+    #[test]
+    fn test_exit_code_decides_outcome() {
+        let args = vec!["-c".to_string(), "grep -q admin".to_string()];
+        assert!(run_check_command(&spec(&args, 5_000), &json!({"role": "admin"})).unwrap());
+        assert!(!run_check_command(&spec(&args, 5_000), &json!({"role": "user"})).unwrap());
+    }
+
+    #[test]
+    fn test_timeout_kills_command() {
+        let args = vec!["-c".to_string(), "sleep 5".to_string()];
+        let err = run_check_command(&spec(&args, 50), &json!(1)).unwrap_err();
+        assert!(err.to_string().contains("timed out"));
+    }
+}
@@ -0,0 +1,127 @@
+//! Regexes, JSONPaths and expressions compiled once per checker
+//!
+//! A checker compiles the JSONPaths, regexes and expressions of its
+//! config's rules when it is created, so evaluating a rule against
+//! thousands of values only looks them up. Patterns met later, such as those of a rule passed to
+//! `check_document`, are compiled the first time they are needed. Every
+//! clone of a [`JsonChecker`](crate::JsonChecker) shares one cache.
+
+use regex::Regex;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use crate::config::Config;
+use crate::error::{self, CheckerError};
+use crate::expr::Expr;
+use crate::jsonpath::{self, CompiledPath, JsonPathEngine, JsonPathError};
+use crate::rules::CheckRule;
+
+/// Compiled forms of the patterns a checker has evaluated
+#[derive(Default)]
+pub(crate) struct CompileCache {
+    regexes: RwLock<HashMap<String, Arc<Regex>>>,
+    jsonpaths: RwLock<HashMap<(String, JsonPathEngine), Arc<CompiledPath>>>,
+    exprs: RwLock<HashMap<String, Arc<Expr>>>,
+}
+
+impl CompileCache {
+    /// A cache holding every JSONPath, regex and expression of the config's rules.
+    /// Invalid ones are left out; they fail their rule when it runs.
+    pub(crate) fn for_config(config: &Config) -> Self {
+        let cache = CompileCache::default();
+        for rule in &config.rules {
+            for (path, engine) in config.jsonpaths_of(rule) {
+                let _ = cache.jsonpath(&path, engine);
+            }
+            match &rule.check {
+                CheckRule::Regex { pattern } => {
+                    let _ = cache.regex(pattern);
+                }
+                CheckRule::KeyFormat { convention, pattern } => {
+                    if let Ok(pattern) = convention.pattern(pattern.as_deref()) {
+                        let _ = cache.regex(pattern);
+                    }
+                }
+                CheckRule::Expr { expr } => {
+                    let _ = cache.expr(expr);
+                }
+                _ => {}
+            }
+        }
+        cache
+    }
+
+    /// `pattern` compiled as a regex. Clones of a `Regex` don't share its
+    /// search cache, so it is handed out behind an `Arc` instead.
+    pub(crate) fn regex(&self, pattern: &str) -> error::Result<Arc<Regex>> {
+        if let Some(re) = self.regexes.read().unwrap_or_else(|e| e.into_inner()).get(pattern) {
+            return Ok(Arc::clone(re));
+        }
+        let re = Regex::new(pattern).map_err(|error| CheckerError::Regex { pattern: pattern.to_string(), error })?;
+        let re = Arc::new(re);
+        self.regexes.write().unwrap_or_else(|e| e.into_inner()).insert(pattern.to_string(), Arc::clone(&re));
+        Ok(re)
+    }
+
+    /// `path` compiled for `engine`
+    pub(crate) fn jsonpath(&self, path: &str, engine: JsonPathEngine) -> Result<Arc<CompiledPath>, JsonPathError> {
+        let key = (path.to_string(), engine);
+        if let Some(compiled) = self.jsonpaths.read().unwrap_or_else(|e| e.into_inner()).get(&key) {
+            return Ok(Arc::clone(compiled));
+        }
+        let compiled = Arc::new(jsonpath::compile_with(path, engine)?);
+        self.jsonpaths.write().unwrap_or_else(|e| e.into_inner()).insert(key, Arc::clone(&compiled));
+        Ok(compiled)
+    }
+
+    /// `source` compiled as an `expr` check
+    pub(crate) fn expr(&self, source: &str) -> error::Result<Arc<Expr>> {
+        if let Some(expr) = self.exprs.read().unwrap_or_else(|e| e.into_inner()).get(source) {
+            return Ok(Arc::clone(expr));
+        }
+        let expr = Arc::new(Expr::compile(source)?);
+        self.exprs.write().unwrap_or_else(|e| e.into_inner()).insert(source.to_string(), Arc::clone(&expr));
+        Ok(expr)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compiles_once() {
+        let cache = CompileCache::default();
+        let first = cache.jsonpath("$.a[*]", JsonPathEngine::JsonpathLib).unwrap();
+        let second = cache.jsonpath("$.a[*]", JsonPathEngine::JsonpathLib).unwrap();
+        assert!(Arc::ptr_eq(&first, &second));
+        assert!(cache.regex("^a+$").unwrap().is_match("aaa"));
+        assert!(cache.regex("(").is_err());
+    }
+
+    #[cfg(feature = "expr")]
+    #[test]
+    fn test_compiles_expr_once() {
+        let cache = CompileCache::default();
+        let first = cache.expr("value > 0").unwrap();
+        assert!(Arc::ptr_eq(&first, &cache.expr("value > 0").unwrap()));
+        assert!(cache.expr("value >").is_err());
+        assert_eq!(cache.exprs.read().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_for_config() {
+        use crate::config::Rule;
+
+        let rule = |jsonpath: &str, pattern: &str| Rule {
+            jsonpath: jsonpath.to_string(),
+            check: CheckRule::Regex { pattern: pattern.to_string() },
+            ..Default::default()
+        };
+        let config = Config { rules: vec![rule("$.a[*]", "^a+$"), rule("$.b[", "(")], ..Default::default() };
+        let cache = CompileCache::for_config(&config);
+        assert_eq!(cache.jsonpaths.read().unwrap().len(), 1);
+        assert_eq!(cache.regexes.read().unwrap().len(), 1);
+        assert!(cache.regexes.read().unwrap().contains_key("^a+$"));
+    }
+}
@@ -1,57 +1,831 @@
-//! Configuration structures for JSON validation rules
-
-use serde::{Deserialize, Serialize};
-use crate::rules::CheckRule;
-
-/// Main configuration structure containing all validation rules
-#[derive(Debug, Deserialize, Serialize)]
-pub struct Config {
-    pub rules: Vec<Rule>,
-}
-
-/// Individual validation rule configuration
-#[derive(Debug, Deserialize, Serialize, Clone)]
-pub struct Rule {
-    pub name: String,
-    pub json_file: String,
-    pub jsonpath: String,
-    pub check: CheckRule,
-}
-
-/// Result of executing a validation rule
-#[derive(Debug)]
-pub struct CheckResult {
-    pub rule_name: String,
-    pub passed: bool,
-    pub message: String,
-    pub invalid_positions: Vec<String>,
-    pub values_found: Vec<serde_json::Value>,
-}
-
-impl CheckResult {
-    pub fn new(rule_name: String, passed: bool, message: String) -> Self {
-        Self {
-            rule_name,
-            passed,
-            message,
-            invalid_positions: Vec::new(),
-            values_found: Vec::new(),
-        }
-    }
-
-    pub fn with_positions(
-        rule_name: String,
-        passed: bool,
-        message: String,
-        invalid_positions: Vec<String>,
-        values_found: Vec<serde_json::Value>,
-    ) -> Self {
-        Self {
-            rule_name,
-            passed,
-            message,
-            invalid_positions,
-            values_found,
-        }
-    }
-}
\ No newline at end of file
+//! Configuration structures for JSON validation rules
+
+use anyhow::{Context, Result};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use crate::canonicalize::Canonicalize;
+use crate::capture::CaptureValues;
+use crate::diff::Difference;
+use crate::fix::PatchOperation;
+use crate::error::{self, CheckerError};
+use crate::format::{CsvOptions, DocumentFormat};
+use crate::jq::{Selector, SelectorEngine};
+use crate::jsonpath::{self, JsonPathEngine};
+use crate::locate::SourceLocation;
+use crate::messages::{Catalog, Message};
+use crate::parser::ParserOptions;
+use crate::query::QuerySource;
+use crate::response::ResponseAssertions;
+use crate::retry::RetryPolicy;
+use crate::rules::CheckRule;
+use crate::scan::ScanConfig;
+use crate::schedule::{self, RunCondition};
+use crate::source;
+use crate::suggest;
+use crate::suite::Suite;
+use crate::templates;
+use crate::transform::Transform;
+use crate::vars;
+
+/// Main configuration structure containing all validation rules
+#[derive(Debug, Default, Deserialize, JsonSchema, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Location of the JSON Schema for config files, used by editors
+    #[serde(default, rename = "$schema", skip_serializing_if = "Option::is_none")]
+    pub schema: Option<String>,
+    /// Other rule files to merge into this one (glob patterns allowed,
+    /// resolved relative to the including file)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<String>,
+    /// User-defined variables available as `${NAME}` in rules
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub vars: HashMap<String, String>,
+    /// Named check definitions rules can refer to with a `template` check
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub check_templates: HashMap<String, serde_json::Value>,
+    /// Directory whose files are checked by rules with `applies_to`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scan: Option<ScanConfig>,
+    /// JSONPath engine for rules that don't pick one; `jsonpath_lib` when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jsonpath_engine: Option<JsonPathEngine>,
+    /// Retry policy for loading the documents of rules that don't set one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry: Option<RetryPolicy>,
+    /// Time limit for evaluating rules that don't set their own `timeout_ms`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_ms: Option<u64>,
+    /// How strictly JSON documents are parsed for rules that don't set `parser`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parser: Option<ParserOptions>,
+    /// Per-environment overrides, applied when a profile is selected
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub profiles: HashMap<String, Profile>,
+    /// Named groups of rules; loading the config moves their rules into `rules`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub suites: Vec<Suite>,
+    #[serde(default)]
+    pub rules: Vec<Rule>,
+    /// Config files this configuration was loaded from, including includes
+    #[serde(skip)]
+    pub files: Vec<PathBuf>,
+}
+
+impl Config {
+    /// Load a configuration file and merge in every file it includes.
+    ///
+    /// Included rules come before the including file's own rules, in the
+    /// order the `include` entries are listed (glob matches are sorted).
+    /// A file reachable through several includes is only loaded once;
+    /// include cycles and duplicate rule names are reported as errors.
+    pub fn from_file(path: &str) -> error::Result<Self> {
+        Self::from_file_with_vars(path, &HashMap::new())
+    }
+
+    /// Load a configuration file and substitute `${NAME}` references.
+    ///
+    /// `overrides` take precedence over the `vars` declared in the config
+    /// files, which in turn take precedence over environment variables.
+    pub fn from_file_with_vars(path: &str, overrides: &HashMap<String, String>) -> error::Result<Self> {
+        Self::from_file_with_profile(path, overrides, None)
+    }
+
+    /// Parse a configuration from JSON text, for embedders that don't keep
+    /// it in a file. Errors name the file as `<json>`, and `include` isn't
+    /// allowed since there is no directory to resolve it from.
+    pub fn from_json(text: &str) -> error::Result<Self> {
+        let path = Path::new("<json>");
+        let mut raw: serde_json::Value = serde_json::from_str(text).map_err(|source| CheckerError::config_parse(path, source))?;
+        vars::resolve_env_values(&mut raw).map_err(|e| CheckerError::InvalidConfig(format!("{:#}", e)))?;
+        let mut config: Config = serde_json::from_value(raw).map_err(|source| CheckerError::config_parse(path, source))?;
+        if !config.include.is_empty() {
+            return Err(CheckerError::InvalidConfig("include needs a config file to resolve paths from".to_string()));
+        }
+        config.resolve()?;
+        Ok(config)
+    }
+
+    /// Load a configuration file with the overrides of the named profile.
+    ///
+    /// The profile's `vars` take precedence over the config's, but not over
+    /// `overrides`. Its rule overrides apply before variables are substituted.
+    pub fn from_file_with_profile(
+        path: &str,
+        overrides: &HashMap<String, String>,
+        profile: Option<&str>,
+    ) -> error::Result<Self> {
+        let mut loader = ConfigLoader::default();
+        loader.load(Path::new(path))?;
+
+        let mut vars = loader.vars;
+        if let Some(name) = profile {
+            let profile = find_profile(&loader.profiles, name)?;
+            vars.extend(profile.vars.iter().map(|(k, v)| (k.clone(), v.clone())));
+            for (rule_name, patch) in &profile.rules {
+                let rule = loader.rules.iter_mut().find(|rule| &rule.name == rule_name).ok_or_else(|| {
+                    CheckerError::InvalidConfig(format!("Profile '{}' overrides unknown rule '{}'", name, rule_name))
+                })?;
+                override_rule(rule, patch).map_err(|e| {
+                    CheckerError::InvalidConfig(format!("Profile '{}': invalid override of rule '{}': {:#}", name, rule_name, e))
+                })?;
+            }
+        }
+        vars.extend(overrides.iter().map(|(k, v)| (k.clone(), v.clone())));
+
+        let mut config = Config {
+            schema: None,
+            include: Vec::new(),
+            vars,
+            check_templates: loader.check_templates,
+            scan: loader.scan,
+            jsonpath_engine: loader.jsonpath_engine,
+            retry: loader.retry,
+            timeout_ms: loader.timeout_ms,
+            parser: loader.parser,
+            profiles: loader.profiles,
+            suites: loader.suites,
+            rules: loader.rules,
+            files: loader.files,
+        };
+        config.resolve()?;
+
+        Ok(config)
+    }
+
+    /// Substitute variables, expand check templates and compile JSONPaths,
+    /// turning the rules as written into the rules that run
+    pub(crate) fn resolve(&mut self) -> error::Result<()> {
+        for suite in &mut self.suites {
+            self.rules.extend(suite.take_rules());
+        }
+        self.substitute_vars()?;
+        self.expand_templates()?;
+        self.compile_jsonpaths()
+    }
+
+    /// Keep only the rules of the named suite, and the rules they depend on
+    pub fn select_suite(&mut self, name: &str) -> error::Result<()> {
+        let mut names: Vec<&str> = self.suites.iter().map(|suite| suite.name.as_str()).collect();
+        names.extend(self.rules.iter().filter_map(|rule| rule.suite.as_deref()));
+        if !names.contains(&name) {
+            return Err(CheckerError::InvalidConfig(match suggest::closest(name, names) {
+                Some(suggestion) => format!("No suite named '{}', did you mean '{}'?", name, suggestion),
+                None => format!("No suite named '{}'", name),
+            }));
+        }
+
+        let mut keep: HashSet<String> = HashSet::new();
+        let mut pending: Vec<&Rule> = self.rules.iter().filter(|rule| rule.suite.as_deref() == Some(name)).collect();
+        while let Some(rule) = pending.pop() {
+            if keep.insert(rule.name.clone()) {
+                pending.extend(schedule::prerequisites(rule).filter_map(|n| self.rules.iter().find(|r| r.name == n)));
+            }
+        }
+        self.rules.retain(|rule| keep.contains(&rule.name));
+        Ok(())
+    }
+
+    /// JSONPath engine `rule` is evaluated with
+    pub fn engine_for(&self, rule: &Rule) -> JsonPathEngine {
+        rule.jsonpath_engine.or(self.jsonpath_engine).unwrap_or_default()
+    }
+
+    /// Retry policy for loading the document of `rule`, if any
+    pub fn retry_for<'a>(&'a self, rule: &'a Rule) -> Option<&'a RetryPolicy> {
+        rule.retry.as_ref().or(self.retry.as_ref())
+    }
+
+    /// Time limit for evaluating `rule`, if any
+    pub fn timeout_for(&self, rule: &Rule) -> Option<Duration> {
+        rule.timeout_ms.or(self.timeout_ms).map(Duration::from_millis)
+    }
+
+    /// How strictly the document of `rule` is parsed
+    pub fn parser_for(&self, rule: &Rule) -> ParserOptions {
+        rule.parser.or(self.parser).unwrap_or_default()
+    }
+
+    /// Engine for JSONPaths inside checks, such as a `sorted` key
+    pub fn default_engine(&self) -> JsonPathEngine {
+        self.jsonpath_engine.unwrap_or_default()
+    }
+
+    /// Every JSONPath of `rule`, paired with the engine that evaluates it
+    pub fn jsonpaths_of(&self, rule: &Rule) -> Vec<(String, JsonPathEngine)> {
+        let mut paths = Vec::new();
+        if rule.json_pointer.is_none() && rule.selector.is_none() {
+            paths.push((rule.jsonpath.clone(), self.engine_for(rule)));
+        }
+        if let Some(RunCondition::PathExists(path)) = &rule.run_if {
+            paths.push((path.clone(), self.engine_for(rule)));
+        }
+        paths.extend(rule.check.jsonpaths().into_iter().map(|path| (path, self.default_engine())));
+        paths
+    }
+
+    /// Compile every JSONPath up front so a typo is reported with its
+    /// position when the config is loaded, not halfway through a run
+    pub fn compile_jsonpaths(&self) -> error::Result<()> {
+        let errors: Vec<_> = self
+            .rules
+            .iter()
+            .flat_map(|rule| {
+                self.jsonpaths_of(rule)
+                    .into_iter()
+                    .filter_map(|(path, engine)| jsonpath::validate(&path, engine).err())
+                    .map(move |e| (rule.name.clone(), e))
+            })
+            .collect();
+
+        if !errors.is_empty() {
+            return Err(CheckerError::JsonPath(errors));
+        }
+        Ok(())
+    }
+
+    /// Replace every `template` check with the check template it names
+    pub fn expand_templates(&mut self) -> error::Result<()> {
+        for rule in &mut self.rules {
+            if let CheckRule::Template { name, params } = &rule.check {
+                rule.check = templates::expand(&self.check_templates, name, params, &self.vars).map_err(|e| {
+                    CheckerError::InvalidConfig(format!("Rule '{}': invalid check: {:#}", rule.name, e))
+                })?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Substitute variables in the file paths, JSONPaths and check values of every rule
+    pub fn substitute_vars(&mut self) -> error::Result<()> {
+        for rule in &mut self.rules {
+            substitute_rule_vars(rule, &self.vars)
+                .map_err(|e| CheckerError::InvalidConfig(format!("Rule '{}': {:#}", rule.name, e)))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Overrides applied to the config for one environment, such as `prod`
+#[derive(Debug, Default, Deserialize, JsonSchema, Serialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct Profile {
+    /// Variables overriding the config's `vars`
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub vars: HashMap<String, String>,
+    /// Fields to change per rule name, merged into the rule as written
+    /// (objects such as `check` are merged key by key, `null` removes a field)
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub rules: HashMap<String, serde_json::Value>,
+}
+
+/// The named profile, or an error suggesting the closest known name
+fn find_profile<'a>(profiles: &'a HashMap<String, Profile>, name: &str) -> error::Result<&'a Profile> {
+    profiles.get(name).ok_or_else(|| {
+        CheckerError::InvalidConfig(match suggest::closest(name, profiles.keys().map(String::as_str)) {
+            Some(suggestion) => format!("No profile named '{}', did you mean '{}'?", name, suggestion),
+            None => format!("No profile named '{}'", name),
+        })
+    })
+}
+
+/// Merge a profile's overrides into a rule as written
+fn override_rule(rule: &mut Rule, patch: &serde_json::Value) -> Result<()> {
+    let mut value = serde_json::to_value(&*rule)?;
+    merge_patch(&mut value, patch);
+    *rule = serde_json::from_value(value)?;
+    Ok(())
+}
+
+/// Apply an RFC 7396 JSON merge patch
+fn merge_patch(target: &mut serde_json::Value, patch: &serde_json::Value) {
+    let serde_json::Value::Object(fields) = patch else {
+        *target = patch.clone();
+        return;
+    };
+    if !target.is_object() {
+        *target = serde_json::Value::Object(Default::default());
+    }
+    if let serde_json::Value::Object(map) = target {
+        for (key, value) in fields {
+            if value.is_null() {
+                map.remove(key);
+            } else {
+                merge_patch(map.entry(key.clone()).or_insert(serde_json::Value::Null), value);
+            }
+        }
+    }
+}
+
+/// Substitute variables in the file path, JSONPath and check values of one rule
+fn substitute_rule_vars(rule: &mut Rule, vars: &HashMap<String, String>) -> Result<()> {
+    if let Some(json_file) = &rule.json_file {
+        rule.json_file = Some(vars::substitute(json_file, vars).context("invalid json_file")?);
+    }
+    if let Some(QuerySource::Postgres(query)) = &mut rule.source {
+        query.url = vars::substitute(&query.url, vars).context("invalid source url")?;
+        query.query = vars::substitute(&query.query, vars).context("invalid source query")?;
+    }
+    rule.jsonpath = vars::substitute(&rule.jsonpath, vars).context("invalid jsonpath")?;
+    if let Some(selector) = &mut rule.selector {
+        selector.expr = vars::substitute(&selector.expr, vars).context("invalid selector")?;
+    }
+    if let Some(pointer) = &rule.json_pointer {
+        rule.json_pointer = Some(vars::substitute(pointer, vars).context("invalid json_pointer")?);
+    }
+
+    let check = serde_json::to_value(&rule.check)?;
+    let check = vars::substitute_value(&check, vars).context("invalid check")?;
+    rule.check = serde_json::from_value(check).context("invalid check after substitution")?;
+
+    Ok(())
+}
+
+/// Recursive loader state used to resolve `include` entries
+#[derive(Default)]
+struct ConfigLoader {
+    /// Files currently being loaded, used for cycle detection
+    stack: Vec<PathBuf>,
+    /// Files that have already been merged
+    loaded: HashSet<PathBuf>,
+    /// Loaded files in load order
+    files: Vec<PathBuf>,
+    /// Merged rules in load order
+    rules: Vec<Rule>,
+    /// File each rule name was first defined in
+    origins: HashMap<String, PathBuf>,
+    /// Merged variables; the including file overrides included ones
+    vars: HashMap<String, String>,
+    /// Merged check templates; the including file overrides included ones
+    check_templates: HashMap<String, serde_json::Value>,
+    /// Scan settings; the including file overrides included ones
+    scan: Option<ScanConfig>,
+    /// Default JSONPath engine; the including file overrides included ones
+    jsonpath_engine: Option<JsonPathEngine>,
+    /// Default retry policy; the including file overrides included ones
+    retry: Option<RetryPolicy>,
+    /// Default rule timeout; the including file overrides included ones
+    timeout_ms: Option<u64>,
+    /// Default parser options; the including file overrides included ones
+    parser: Option<ParserOptions>,
+    /// Suites without their rules, which are merged into `rules`
+    suites: Vec<Suite>,
+    /// Merged profiles; the including file overrides included ones
+    profiles: HashMap<String, Profile>,
+}
+
+impl ConfigLoader {
+    fn load(&mut self, path: &Path) -> error::Result<()> {
+        let canonical = path.canonicalize().map_err(|e| CheckerError::read(path, e))?;
+
+        if let Some(start) = self.stack.iter().position(|p| *p == canonical) {
+            let chain: Vec<String> = self.stack[start..]
+                .iter()
+                .chain(std::iter::once(&canonical))
+                .map(|p| p.display().to_string())
+                .collect();
+            return Err(CheckerError::InvalidConfig(format!(
+                "Config include cycle detected: {}",
+                chain.join(" -> ")
+            )));
+        }
+
+        if !self.loaded.insert(canonical.clone()) {
+            return Ok(());
+        }
+        self.files.push(canonical.clone());
+
+        let content = fs::read_to_string(path).map_err(|e| CheckerError::read(path, e))?;
+        let format = DocumentFormat::detect(&path.to_string_lossy());
+        // JSONC keeps its lines and columns with comments and trailing commas blanked out
+        let content = ParserOptions::default().for_format(format).source_text(&content).into_owned();
+        // Going through a Value loses line numbers in errors, so only do it when needed
+        let config: Config = if format == DocumentFormat::Json5 || content.contains(vars::VALUE_FROM_ENV) {
+            let mut raw: serde_json::Value = match format {
+                DocumentFormat::Json5 => json5::from_str(&content).map_err(|e| {
+                    CheckerError::InvalidConfig(format!("Failed to parse config: {}: {}", path.display(), e))
+                })?,
+                _ => serde_json::from_str(&content).map_err(|source| CheckerError::config_parse(path, source))?,
+            };
+            vars::resolve_env_values(&mut raw)
+                .map_err(|e| CheckerError::InvalidConfig(format!("{}: {:#}", path.display(), e)))?;
+            serde_json::from_value(raw).map_err(|source| CheckerError::config_parse(path, source))?
+        } else {
+            serde_json::from_str(&content).map_err(|source| CheckerError::config_parse(path, source))?
+        };
+
+        self.stack.push(canonical);
+        let base_dir = path.parent().unwrap_or_else(|| Path::new(""));
+        for pattern in &config.include {
+            for included in expand_include(base_dir, pattern)? {
+                self.load(&included)?;
+            }
+        }
+        self.stack.pop();
+
+        self.vars.extend(config.vars);
+        self.check_templates.extend(config.check_templates);
+        if config.scan.is_some() {
+            self.scan = config.scan;
+        }
+        if config.jsonpath_engine.is_some() {
+            self.jsonpath_engine = config.jsonpath_engine;
+        }
+        if config.retry.is_some() {
+            self.retry = config.retry;
+        }
+        if config.timeout_ms.is_some() {
+            self.timeout_ms = config.timeout_ms;
+        }
+        if config.parser.is_some() {
+            self.parser = config.parser;
+        }
+        for (name, profile) in config.profiles {
+            let merged = self.profiles.entry(name).or_default();
+            merged.vars.extend(profile.vars);
+            merged.rules.extend(profile.rules);
+        }
+        let mut rules = config.rules;
+        for mut suite in config.suites {
+            rules.extend(suite.take_rules());
+            // A suite may collect rules from several files
+            match self.suites.iter_mut().find(|s| s.name == suite.name) {
+                Some(existing) => existing.description = existing.description.take().or(suite.description),
+                None => self.suites.push(suite),
+            }
+        }
+        for rule in rules {
+            if let Some(previous) = self.origins.get(&rule.name) {
+                return Err(CheckerError::InvalidConfig(format!(
+                    "Duplicate rule name '{}' in {} (first defined in {})",
+                    rule.name,
+                    path.display(),
+                    previous.display()
+                )));
+            }
+            self.origins.insert(rule.name.clone(), path.to_path_buf());
+            self.rules.push(rule);
+        }
+
+        Ok(())
+    }
+}
+
+/// Resolve an include entry to the list of files it refers to.
+///
+/// Plain paths must exist; glob patterns may match nothing.
+fn expand_include(base_dir: &Path, pattern: &str) -> Result<Vec<PathBuf>> {
+    let joined = base_dir.join(pattern);
+
+    if !source::is_glob(pattern) {
+        return Ok(vec![joined]);
+    }
+
+    let joined = joined.to_string_lossy().into_owned();
+    let mut paths = glob::glob(&joined)
+        .with_context(|| format!("Invalid include pattern: {}", pattern))?
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .with_context(|| format!("Failed to expand include pattern: {}", pattern))?;
+    paths.sort();
+
+    Ok(paths)
+}
+
+/// Individual validation rule configuration
+#[derive(Debug, Default, Deserialize, JsonSchema, Serialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct Rule {
+    pub name: String,
+    /// Path of the JSON document to check; a glob pattern checks every matching file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub json_file: Option<String>,
+    /// File name (or path under the scan root) pattern selecting the scanned
+    /// files this rule checks, used instead of `json_file`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub applies_to: Option<String>,
+    /// JSON document embedded directly in the config, used instead of `json_file`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub json_inline: Option<serde_json::Value>,
+    /// Database query whose rows are checked, used instead of `json_file`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<QuerySource>,
+    /// Format of `json_file`; detected from its extension when omitted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<DocumentFormat>,
+    /// Status, content type and latency the `http(s)://` response of
+    /// `json_file` must have
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response: Option<ResponseAssertions>,
+    /// Retry policy for loading the document, overriding the config's
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry: Option<RetryPolicy>,
+    /// Give up on evaluating the rule after this long and report it as timed out
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_ms: Option<u64>,
+    /// Delimiter, column names and type inference for CSV documents
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub csv: Option<CsvOptions>,
+    /// How strictly the JSON document is parsed, overriding the config's
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parser: Option<ParserOptions>,
+    /// Validate array elements while reading instead of loading the whole document
+    #[serde(default)]
+    pub stream: bool,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub jsonpath: String,
+    /// RFC 6901 pointer to the node to check, used instead of `jsonpath`;
+    /// failing paths are then reported as pointers too
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub json_pointer: Option<String>,
+    /// Filter expression used instead of `jsonpath`; its outputs are checked
+    /// and reported by their position in the output, e.g. `$[1]`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub selector: Option<Selector>,
+    /// Parse strings holding JSON objects or arrays before selecting, so the
+    /// JSONPath and check apply to the decoded structure
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub decode_embedded_json: bool,
+    /// Read numbers the way the protobuf JSON mapping writes them (int64
+    /// strings, durations, timestamps) as numbers for checks that compare numbers
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub protobuf_mode: bool,
+    /// Transformations applied in order to each selected value before the check
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub transform: Vec<Transform>,
+    /// Normalization applied to the checked values and the values in the
+    /// check before they are compared
+    #[serde(default, skip_serializing_if = "Canonicalize::is_identity")]
+    pub canonicalize: Canonicalize,
+    /// How much of each failing value the result keeps; summaries of large
+    /// values unless `full`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub capture_values: Option<CaptureValues>,
+    pub check: CheckRule,
+    #[serde(default)]
+    pub severity: Severity,
+    /// `false` keeps the rule in the config and its reports, skipped
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+    /// Why the rule is disabled, shown where it is reported as skipped
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub skip_reason: Option<String>,
+    /// Suite the rule belongs to; set for the rules listed in a suite
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub suite: Option<String>,
+    /// How to fix a failure of this rule, shown with its failures
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hint: Option<String>,
+    /// Runbook or documentation page for this rule, linked from its failures
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub docs_url: Option<String>,
+    /// Rules that must pass before this one runs; otherwise it is skipped
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub depends_on: Vec<String>,
+    /// Condition that must hold for this rule to run; otherwise it is skipped
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub run_if: Option<RunCondition>,
+    /// JSONPath engine for `jsonpath` and `run_if`, overriding the config's
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jsonpath_engine: Option<JsonPathEngine>,
+}
+
+impl Rule {
+    /// Why the rule doesn't run, if it is disabled
+    pub fn disabled_reason(&self) -> Option<String> {
+        if self.enabled != Some(false) {
+            return None;
+        }
+        Some(match &self.skip_reason {
+            Some(reason) => format!("disabled ({})", reason),
+            None => "disabled".to_string(),
+        })
+    }
+
+    /// Kind of selector the rule uses and its expression, e.g. `("JSONPath", "$.a")`
+    pub fn selected_by(&self) -> (&'static str, &str) {
+        match (&self.selector, &self.json_pointer) {
+            (Some(Selector { engine: SelectorEngine::Jq, expr }), _) => ("jq filter", expr),
+            (None, Some(pointer)) => ("JSON Pointer", pointer),
+            (None, None) => ("JSONPath", &self.jsonpath),
+        }
+    }
+}
+
+/// How serious a rule failure is
+#[derive(Debug, Default, Deserialize, JsonSchema, Serialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Warning,
+    #[default]
+    Error,
+}
+
+/// Outcome of running a rule
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    /// The check held
+    Pass,
+    /// The check didn't hold
+    Fail,
+    /// The rule couldn't be evaluated, e.g. its document failed to load
+    Error,
+    /// A prerequisite or `run_if` condition wasn't met, so the rule didn't run
+    Skipped,
+    /// Evaluation exceeded the rule's time limit
+    Timeout,
+}
+
+impl Status {
+    /// Whether a rule with this status counts as passed
+    pub fn is_passing(self) -> bool {
+        matches!(self, Status::Pass | Status::Skipped)
+    }
+}
+
+/// Result of executing a validation rule
+#[derive(Debug, Serialize)]
+pub struct CheckResult {
+    pub rule_name: String,
+    pub status: Status,
+    /// Whether the rule counts as passed: its status is `pass` or `skipped`
+    pub passed: bool,
+    pub message: String,
+    /// Structured form of `message`, one entry per line; empty for results
+    /// built from a plain string
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub lines: Vec<Message>,
+    pub invalid_positions: Vec<String>,
+    /// Failing value at each invalid position, summarized if it is large
+    /// and the rule doesn't capture values in full
+    pub values_found: Vec<serde_json::Value>,
+    /// Source position of each invalid position, when known; either empty or
+    /// as long as `invalid_positions`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub locations: Vec<Option<SourceLocation>>,
+    /// Structural differences of failing `equals` / `jsonb_contains` nodes
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub differences: Vec<Difference>,
+    /// JSON Patch correcting the failing nodes that have an obvious fix
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub fixes: Vec<PatchOperation>,
+    pub severity: Severity,
+    /// Time spent loading the document and evaluating the rule
+    #[serde(rename = "duration_ms", serialize_with = "serialize_millis")]
+    pub duration: Duration,
+    /// For rules whose `json_file` is a glob pattern, the result for each matching file
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub per_document: Vec<CheckResult>,
+    /// URI of the document the rule was checked against, if it came from `json_file`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub document: Option<String>,
+    /// Number of failures suppressed because they are in the baseline
+    #[serde(skip_serializing_if = "is_zero")]
+    pub baselined: usize,
+    /// When loading the document failed despite retries, what went wrong on each attempt
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub retry_log: Vec<String>,
+    /// Suite of the rule the result is for
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suite: Option<String>,
+    /// The rule's `hint` on fixing a failure
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hint: Option<String>,
+    /// The rule's `docs_url`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub docs_url: Option<String>,
+}
+
+fn serialize_millis<S: serde::Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_f64(duration.as_secs_f64() * 1000.0)
+}
+
+fn is_zero(n: &usize) -> bool {
+    *n == 0
+}
+
+impl CheckResult {
+    pub fn new(rule_name: String, passed: bool, message: String) -> Self {
+        Self {
+            rule_name,
+            status: if passed { Status::Pass } else { Status::Fail },
+            passed,
+            message,
+            lines: Vec::new(),
+            invalid_positions: Vec::new(),
+            values_found: Vec::new(),
+            locations: Vec::new(),
+            differences: Vec::new(),
+            fixes: Vec::new(),
+            severity: Severity::default(),
+            duration: Duration::ZERO,
+            per_document: Vec::new(),
+            document: None,
+            baselined: 0,
+            retry_log: Vec::new(),
+            suite: None,
+            hint: None,
+            docs_url: None,
+        }
+    }
+
+    /// Result whose message is made of `lines`, rendered in English
+    pub fn from_lines(rule_name: String, passed: bool, lines: Vec<Message>) -> Self {
+        let mut result = Self::new(rule_name, passed, String::new());
+        result.set_lines(lines);
+        result
+    }
+
+    /// Replace the message with `lines`, rendered in English
+    pub fn set_lines(&mut self, lines: Vec<Message>) {
+        self.message = Catalog::english().render(&lines);
+        self.lines = lines;
+    }
+
+    /// Result of a rule that was skipped; skipped rules count as passed
+    pub fn skipped(rule_name: String, reason: &str) -> Self {
+        let message = Message::Skipped { rule: rule_name.clone(), reason: reason.to_string() };
+        Self {
+            status: Status::Skipped,
+            ..Self::from_lines(rule_name, true, vec![message])
+        }
+    }
+
+    /// Result of a rule that couldn't be evaluated
+    pub fn error(rule_name: String, message: String) -> Self {
+        Self {
+            status: Status::Error,
+            ..Self::new(rule_name, false, message)
+        }
+    }
+
+    /// Result of a rule whose evaluation took longer than `limit`
+    pub fn timed_out(rule_name: String, limit: Duration) -> Self {
+        let message = Message::TimedOut { rule: rule_name.clone(), limit_ms: limit.as_millis() };
+        Self {
+            status: Status::Timeout,
+            ..Self::from_lines(rule_name, false, vec![message])
+        }
+    }
+
+    /// Keep only the first `shown` invalid positions, noting how many failed
+    pub fn truncate_failures(&mut self, shown: usize) {
+        let total = self.invalid_positions.len();
+        if total <= shown {
+            return;
+        }
+        self.invalid_positions.truncate(shown);
+        self.values_found.truncate(shown);
+        self.locations.truncate(shown);
+        let truncated = Message::Truncated { total, shown };
+        if self.lines.is_empty() {
+            self.message.push_str("\n   ");
+            self.message.push_str(&Catalog::english().render_line(&truncated));
+        } else {
+            let mut lines = std::mem::take(&mut self.lines);
+            lines.push(truncated);
+            self.set_lines(lines);
+        }
+    }
+
+    /// Source position of the invalid position at `index`, if known
+    pub fn location(&self, index: usize) -> Option<SourceLocation> {
+        self.locations.get(index).copied().flatten()
+    }
+
+    pub fn with_positions(
+        rule_name: String,
+        passed: bool,
+        message: String,
+        invalid_positions: Vec<String>,
+        values_found: Vec<serde_json::Value>,
+    ) -> Self {
+        Self {
+            rule_name,
+            status: if passed { Status::Pass } else { Status::Fail },
+            passed,
+            message,
+            lines: Vec::new(),
+            invalid_positions,
+            values_found,
+            locations: Vec::new(),
+            differences: Vec::new(),
+            fixes: Vec::new(),
+            severity: Severity::default(),
+            duration: Duration::ZERO,
+            per_document: Vec::new(),
+            document: None,
+            baselined: 0,
+            retry_log: Vec::new(),
+            suite: None,
+            hint: None,
+            docs_url: None,
+        }
+    }
+}
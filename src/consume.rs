@@ -0,0 +1,304 @@
+//! Consumer mode: check every message of a stream
+//!
+//! `consume` reads JSON messages one at a time, from the lines of a file,
+//! named pipe or stdin, or from a Kafka topic (`kafka` feature). It runs the
+//! config's rules on each message and writes the messages that fail to a
+//! sink: stdout as NDJSON, a webhook (`http` feature) or another Kafka topic.
+//! Rules check a message the way `serve-api` rules check a posted document.
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde::Serialize;
+use serde_json::Value;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Write};
+
+use crate::api;
+use crate::checker::JsonChecker;
+use crate::config::CheckResult;
+
+/// One message read from a stream
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueueMessage {
+    /// Where the message was read, e.g. `line 3` or `orders/0@42`
+    pub position: String,
+    pub key: Option<String>,
+    pub payload: Vec<u8>,
+}
+
+/// A message that failed some of the rules
+#[derive(Debug, Serialize)]
+pub struct Violation {
+    pub position: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+    /// Results of the rules the message failed
+    pub failures: Vec<CheckResult>,
+}
+
+/// Where violations are written
+pub trait ViolationSink {
+    fn send(&mut self, violation: &Violation) -> Result<()>;
+
+    /// Deliver anything still buffered; called once the stream ends
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Writes each violation as a line of JSON
+pub struct NdjsonSink<W: Write>(pub W);
+
+impl<W: Write> ViolationSink for NdjsonSink<W> {
+    fn send(&mut self, violation: &Violation) -> Result<()> {
+        serde_json::to_writer(&mut self.0, violation)?;
+        writeln!(self.0)?;
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        Ok(self.0.flush()?)
+    }
+}
+
+/// POSTs each violation as JSON to a URL
+#[cfg(feature = "http")]
+pub struct WebhookSink {
+    pub url: String,
+}
+
+#[cfg(feature = "http")]
+impl ViolationSink for WebhookSink {
+    fn send(&mut self, violation: &Violation) -> Result<()> {
+        ureq::post(&self.url)
+            .header("content-type", "application/json")
+            .send(serde_json::to_string(violation)?)
+            .with_context(|| format!("Failed to post violation to {}", self.url))?;
+        Ok(())
+    }
+}
+
+/// A Kafka topic written as `kafka://broker1:9092,broker2:9092/topic`,
+/// with an optional `?group=...` naming the consumer group
+#[derive(Debug, Clone, PartialEq)]
+pub struct KafkaTopic {
+    pub brokers: String,
+    pub topic: String,
+    pub group: Option<String>,
+}
+
+impl KafkaTopic {
+    /// Consumer group used when the URI names none
+    pub const DEFAULT_GROUP: &str = "json-checker-rs";
+
+    pub fn parse(uri: &str) -> Result<Self> {
+        let invalid = || anyhow!("Invalid Kafka topic '{}', expected kafka://broker:9092/topic", uri);
+        let rest = uri.strip_prefix("kafka://").ok_or_else(invalid)?;
+        let (brokers, rest) = rest.split_once('/').ok_or_else(invalid)?;
+        let (topic, query) = rest.split_once('?').unwrap_or((rest, ""));
+        if brokers.is_empty() || topic.is_empty() {
+            return Err(invalid());
+        }
+        let mut group = None;
+        for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+            match pair.split_once('=') {
+                Some(("group", name)) => group = Some(name.to_string()),
+                _ => bail!("Unknown option '{}' in Kafka topic '{}', expected group=...", pair, uri),
+            }
+        }
+        Ok(KafkaTopic { brokers: brokers.to_string(), topic: topic.to_string(), group })
+    }
+}
+
+/// Messages read from `spec`: `-` for stdin, a `kafka://` topic, or the
+/// path of a file or named pipe with one JSON message per line
+pub fn open_input(spec: &str) -> Result<Box<dyn Iterator<Item = Result<QueueMessage>>>> {
+    if spec == "-" {
+        return Ok(Box::new(lines(BufReader::new(io::stdin()))));
+    }
+    if spec.starts_with("kafka://") {
+        return kafka::consumer(&KafkaTopic::parse(spec)?);
+    }
+    let file = File::open(spec).with_context(|| format!("Failed to open {}", spec))?;
+    Ok(Box::new(lines(BufReader::new(file))))
+}
+
+/// The sink `spec` names: `-` for stdout, an `http(s)://` webhook or a
+/// `kafka://` topic
+pub fn open_sink(spec: &str) -> Result<Box<dyn ViolationSink>> {
+    match spec {
+        "-" => Ok(Box::new(NdjsonSink(io::stdout()))),
+        #[cfg(feature = "http")]
+        url if url.starts_with("http://") || url.starts_with("https://") => Ok(Box::new(WebhookSink { url: url.to_string() })),
+        #[cfg(not(feature = "http"))]
+        url if url.starts_with("http://") || url.starts_with("https://") => {
+            bail!("Webhook sink '{}' needs json-checker-rs built with the `http` feature", url)
+        }
+        topic if topic.starts_with("kafka://") => kafka::producer(&KafkaTopic::parse(topic)?),
+        _ => bail!("Unknown sink '{}', expected -, a webhook URL or kafka://broker/topic", spec),
+    }
+}
+
+/// Each non-empty line of `reader` as a message
+pub fn lines(reader: impl BufRead + 'static) -> impl Iterator<Item = Result<QueueMessage>> {
+    reader
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !matches!(line, Ok(line) if line.trim().is_empty()))
+        .map(|(i, line)| {
+            Ok(QueueMessage { position: format!("line {}", i + 1), key: None, payload: line?.into_bytes() })
+        })
+}
+
+/// Counts of a finished consumer run
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct ConsumeSummary {
+    pub messages: usize,
+    pub violations: usize,
+}
+
+/// Check every message against `checker`'s rules (bound to the message with
+/// [`api::bind_payload`]) and send those failing one to `sink`. A message
+/// that isn't JSON is a violation too. Returns once the messages run out.
+pub fn consume(
+    checker: &JsonChecker,
+    messages: impl Iterator<Item = Result<QueueMessage>>,
+    sink: &mut dyn ViolationSink,
+) -> Result<ConsumeSummary> {
+    let mut summary = ConsumeSummary::default();
+    for message in messages {
+        let message = message?;
+        summary.messages += 1;
+
+        let failures = match serde_json::from_slice::<Value>(&message.payload) {
+            Ok(document) => api::run_payload(checker, document)?.into_iter().filter(|result| !result.passed).collect(),
+            Err(e) => vec![CheckResult::error("message".to_string(), format!("Message is not JSON: {}", e))],
+        };
+        if !failures.is_empty() {
+            summary.violations += 1;
+            sink.send(&Violation { position: message.position, key: message.key, failures })?;
+        }
+    }
+    sink.flush()?;
+    Ok(summary)
+}
+
+#[cfg(feature = "kafka")]
+mod kafka {
+    use anyhow::{Context, Result};
+    use rdkafka::config::ClientConfig;
+    use rdkafka::consumer::{BaseConsumer, Consumer};
+    use rdkafka::message::Message;
+    use rdkafka::producer::{BaseRecord, Producer, ThreadedProducer};
+    use std::time::Duration;
+
+    use super::{KafkaTopic, QueueMessage, Violation, ViolationSink};
+
+    /// Messages of the topic, with offsets committed automatically; waits for more
+    /// when the topic is drained
+    pub fn consumer(topic: &KafkaTopic) -> Result<Box<dyn Iterator<Item = Result<QueueMessage>>>> {
+        let consumer: BaseConsumer = ClientConfig::new()
+            .set("bootstrap.servers", &topic.brokers)
+            .set("group.id", topic.group.as_deref().unwrap_or(KafkaTopic::DEFAULT_GROUP))
+            .set("auto.offset.reset", "earliest")
+            .create()
+            .context("Failed to create Kafka consumer")?;
+        consumer.subscribe(&[&topic.topic]).with_context(|| format!("Failed to subscribe to {}", topic.topic))?;
+
+        Ok(Box::new(std::iter::from_fn(move || {
+            loop {
+                match consumer.poll(Duration::from_secs(1)) {
+                    // Nothing new yet
+                    None => continue,
+                    Some(Ok(message)) => {
+                        return Some(Ok(QueueMessage {
+                            position: format!("{}/{}@{}", message.topic(), message.partition(), message.offset()),
+                            key: message.key().map(|key| String::from_utf8_lossy(key).into_owned()),
+                            payload: message.payload().unwrap_or_default().to_vec(),
+                        }));
+                    }
+                    Some(Err(e)) => return Some(Err(anyhow::Error::new(e).context("Failed to read from Kafka"))),
+                }
+            }
+        })))
+    }
+
+    /// Sink producing each violation to the topic, keyed like the message
+    pub fn producer(topic: &KafkaTopic) -> Result<Box<dyn ViolationSink>> {
+        let producer: ThreadedProducer<_> = ClientConfig::new()
+            .set("bootstrap.servers", &topic.brokers)
+            .create()
+            .context("Failed to create Kafka producer")?;
+        Ok(Box::new(KafkaSink { producer, topic: topic.topic.clone() }))
+    }
+
+    struct KafkaSink {
+        producer: ThreadedProducer<rdkafka::producer::DefaultProducerContext>,
+        topic: String,
+    }
+
+    impl ViolationSink for KafkaSink {
+        fn send(&mut self, violation: &Violation) -> Result<()> {
+            let payload = serde_json::to_string(violation)?;
+            let mut record = BaseRecord::to(&self.topic).payload(&payload);
+            if let Some(key) = &violation.key {
+                record = record.key(key);
+            }
+            self.producer
+                .send(record)
+                .map_err(|(e, _)| anyhow::Error::new(e).context(format!("Failed to produce to {}", self.topic)))
+        }
+
+        fn flush(&mut self) -> Result<()> {
+            self.producer.flush(Duration::from_secs(30)).context("Failed to flush Kafka producer")
+        }
+    }
+}
+
+#[cfg(not(feature = "kafka"))]
+mod kafka {
+    use anyhow::{bail, Result};
+
+    use super::{KafkaTopic, QueueMessage, ViolationSink};
+
+    pub fn consumer(topic: &KafkaTopic) -> Result<Box<dyn Iterator<Item = Result<QueueMessage>>>> {
+        bail!("Reading kafka://{}/{} needs json-checker-rs built with the `kafka` feature", topic.brokers, topic.topic)
+    }
+
+    pub fn producer(topic: &KafkaTopic) -> Result<Box<dyn ViolationSink>> {
+        bail!("Writing kafka://{}/{} needs json-checker-rs built with the `kafka` feature", topic.brokers, topic.topic)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_consume() {
+        let mut config = Config::from_json(r#"{"rules": [{"name": "Has id", "jsonpath": "$.id", "check": {"type": "non_empty"}}]}"#).unwrap();
+        api::bind_payload(&mut config);
+        let checker = JsonChecker::from_config(config);
+        let input = Cursor::new("{\"id\": 1}\n\n{\"id\": \"\"}\nnot json\n");
+
+        let mut sink = NdjsonSink(Vec::new());
+        let summary = consume(&checker, lines(input), &mut sink).unwrap();
+        assert_eq!(summary, ConsumeSummary { messages: 3, violations: 2 });
+
+        let output = String::from_utf8(sink.0).unwrap();
+        let violations: Vec<Value> = output.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(violations[0]["position"], "line 3");
+        assert_eq!(violations[0]["failures"][0]["rule_name"], "Has id");
+        assert_eq!(violations[1]["failures"][0]["status"], "error");
+    }
+
+    #[test]
+    fn test_parse_kafka_topic() {
+        let topic = KafkaTopic::parse("kafka://a:9092,b:9092/orders?group=qa").unwrap();
+        assert_eq!(topic, KafkaTopic { brokers: "a:9092,b:9092".to_string(), topic: "orders".to_string(), group: Some("qa".to_string()) });
+        assert_eq!(KafkaTopic::parse("kafka://a:9092/orders").unwrap().group, None);
+        assert!(KafkaTopic::parse("kafka://a:9092").is_err());
+        assert!(KafkaTopic::parse("kafka://a:9092/orders?partition=1").is_err());
+    }
+}
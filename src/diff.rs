@@ -0,0 +1,171 @@
+//! Structural differences between JSON values
+//!
+//! Used to explain why an `equals` or `jsonb_contains` check failed on an
+//! object: which keys are missing, which values differ and which keys are extra.
+
+use serde::Serialize;
+use serde_json::Value;
+use std::fmt;
+
+use crate::path_finder::{push_index, push_key};
+
+/// A single difference between an expected and an actual value
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Difference {
+    /// The expected value has a key or element the actual value lacks
+    Missing { path: String, expected: Value },
+    /// The actual value has a key or element that wasn't expected
+    Extra { path: String, actual: Value },
+    /// Both values have the node but it differs
+    Changed { path: String, expected: Value, actual: Value },
+}
+
+impl Difference {
+    pub fn path(&self) -> &str {
+        match self {
+            Difference::Missing { path, .. }
+            | Difference::Extra { path, .. }
+            | Difference::Changed { path, .. } => path,
+        }
+    }
+}
+
+impl fmt::Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Difference::Missing { path, expected } => write!(f, "- {}: {}", path, expected),
+            Difference::Extra { path, actual } => write!(f, "+ {}: {}", path, actual),
+            Difference::Changed { path, expected, actual } => {
+                write!(f, "~ {}: expected {}, found {}", path, expected, actual)
+            }
+        }
+    }
+}
+
+/// Compute the differences between `expected` and `actual`, with paths rooted at `path`
+pub fn diff(expected: &Value, actual: &Value, path: &str) -> Vec<Difference> {
+    let mut differences = Vec::new();
+    diff_into(expected, actual, path, false, &mut differences);
+    differences
+}
+
+/// Like [`diff`], but extra keys in `actual` are allowed (JSONB `@>` semantics)
+pub fn diff_contained(expected: &Value, actual: &Value, path: &str) -> Vec<Difference> {
+    let mut differences = Vec::new();
+    diff_into(expected, actual, path, true, &mut differences);
+    differences
+}
+
+/// Summarize differences as e.g. `1 missing, 2 changed`
+pub fn summary(differences: &[Difference]) -> String {
+    let count = |f: fn(&Difference) -> bool| differences.iter().filter(|d| f(d)).count();
+    let parts = [
+        (count(|d| matches!(d, Difference::Missing { .. })), "missing"),
+        (count(|d| matches!(d, Difference::Changed { .. })), "changed"),
+        (count(|d| matches!(d, Difference::Extra { .. })), "extra"),
+    ];
+
+    parts
+        .iter()
+        .filter(|(n, _)| *n > 0)
+        .map(|(n, kind)| format!("{} {}", n, kind))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Whether `path` is `ancestor` or a node inside it, for canonical paths
+pub fn is_within(path: &str, ancestor: &str) -> bool {
+    path.strip_prefix(ancestor)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with(['.', '[']))
+}
+
+fn diff_into(expected: &Value, actual: &Value, path: &str, allow_extra: bool, out: &mut Vec<Difference>) {
+    match (expected, actual) {
+        (Value::Object(e), Value::Object(a)) => {
+            for (key, expected_value) in e {
+                let mut child = path.to_string();
+                push_key(&mut child, key);
+                match a.get(key) {
+                    Some(actual_value) => diff_into(expected_value, actual_value, &child, allow_extra, out),
+                    None => out.push(Difference::Missing { path: child, expected: expected_value.clone() }),
+                }
+            }
+            if !allow_extra {
+                for (key, actual_value) in a {
+                    if !e.contains_key(key) {
+                        let mut child = path.to_string();
+                        push_key(&mut child, key);
+                        out.push(Difference::Extra { path: child, actual: actual_value.clone() });
+                    }
+                }
+            }
+        }
+        // Arrays are compared element by element; containment of arrays is
+        // order-independent, so a mismatch is reported for the whole array
+        (Value::Array(e), Value::Array(a)) if !allow_extra => {
+            for (index, expected_value) in e.iter().enumerate() {
+                let mut child = path.to_string();
+                push_index(&mut child, index);
+                match a.get(index) {
+                    Some(actual_value) => diff_into(expected_value, actual_value, &child, allow_extra, out),
+                    None => out.push(Difference::Missing { path: child, expected: expected_value.clone() }),
+                }
+            }
+            for (index, actual_value) in a.iter().enumerate().skip(e.len()) {
+                let mut child = path.to_string();
+                push_index(&mut child, index);
+                out.push(Difference::Extra { path: child, actual: actual_value.clone() });
+            }
+        }
+        _ => {
+            let differs = if allow_extra {
+                !crate::checker::jsonb_contains(actual, expected)
+            } else {
+                expected != actual
+            };
+            if differs {
+                out.push(Difference::Changed {
+                    path: path.to_string(),
+                    expected: expected.clone(),
+                    actual: actual.clone(),
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_diff_objects() {
+        let expected = json!({"host": "localhost", "port": 8080, "tls": {"enabled": true}});
+        let actual = json!({"host": "localhost", "tls": {"enabled": false}, "debug": true});
+
+        assert_eq!(
+            diff(&expected, &actual, "$.config"),
+            vec![
+                Difference::Missing { path: "$.config.port".to_string(), expected: json!(8080) },
+                Difference::Changed {
+                    path: "$.config.tls.enabled".to_string(),
+                    expected: json!(true),
+                    actual: json!(false),
+                },
+                Difference::Extra { path: "$.config.debug".to_string(), actual: json!(true) },
+            ]
+        );
+        assert_eq!(diff_contained(&expected, &actual, "$").len(), 2);
+        assert_eq!(summary(&diff(&expected, &actual, "$")), "1 missing, 1 changed, 1 extra");
+    }
+
+    #[test]
+    fn test_diff_paths_are_canonical() {
+        let differences = diff(&json!({"a b": [1]}), &json!({"a b": [1, 2]}), "$");
+        assert_eq!(differences, vec![Difference::Extra { path: "$['a b'][1]".to_string(), actual: json!(2) }]);
+        assert!(is_within("$['a b'][1]", "$['a b']"));
+        assert!(!is_within("$.ab", "$.a"));
+    }
+}
@@ -0,0 +1,193 @@
+//! Errors returned by the library API
+//!
+//! Loading a config and running rules fail with a [`CheckerError`], whose
+//! variants let callers tell kinds of failure apart, e.g. to retry when a
+//! document couldn't be read but not when a JSONPath is invalid. Errors
+//! without a variant of their own keep their full context in
+//! [`CheckerError::Other`]; a typed error that was given context on the way
+//! up is wrapped in [`CheckerError::Context`], which [`CheckerError::kind`]
+//! looks through.
+
+use std::io;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+use crate::jsonpath::JsonPathError;
+use crate::retry::RetriesExhausted;
+use crate::suggest;
+
+/// `Result` with [`CheckerError`] as the default error
+pub type Result<T, E = CheckerError> = std::result::Result<T, E>;
+
+/// What went wrong while loading a config or running its rules
+#[derive(Debug, Error)]
+pub enum CheckerError {
+    /// A config file isn't valid JSON or doesn't have the config's shape
+    #[error("Failed to parse config: {}", path.display())]
+    ConfigParse {
+        path: PathBuf,
+        #[source]
+        source: serde_json::Error,
+    },
+    /// A config file has a key, or a value such as a check type, that
+    /// doesn't exist; `suggestion` is the valid one it most likely meant
+    #[error("Unknown {what} `{name}` in {}:{line}{}", path.display(), did_you_mean(suggestion))]
+    UnknownKey {
+        path: PathBuf,
+        line: usize,
+        /// `field` for a key, `variant` for a value
+        what: &'static str,
+        name: String,
+        /// Every valid key or value at that position
+        expected: Vec<String>,
+        suggestion: Option<String>,
+    },
+    /// A config parses but can't be used, e.g. an include cycle, a duplicate
+    /// rule name or an undefined variable
+    #[error("{0}")]
+    InvalidConfig(String),
+    /// A config or document file doesn't exist
+    #[error("File not found: {}", path.display())]
+    FileNotFound { path: PathBuf },
+    /// A config or document file exists but couldn't be read
+    #[error("Failed to read file: {}", path.display())]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    /// JSONPaths that don't compile, with the rule each belongs to (empty
+    /// when a path outside any rule is invalid)
+    #[error("{}", json_path_errors(.0))]
+    JsonPath(Vec<(String, JsonPathError)>),
+    /// A regex pattern in a check doesn't compile
+    #[error("{error}")]
+    Regex { pattern: String, error: regex::Error },
+    /// Loading a document failed on every attempt its retry policy allows
+    #[error(transparent)]
+    RetriesExhausted(#[from] RetriesExhausted),
+    /// A typed error with a description of what was being done when it happened
+    #[error("{context}")]
+    Context {
+        context: String,
+        #[source]
+        source: Box<CheckerError>,
+    },
+    /// Any other failure, such as an unreadable document format or a failed query
+    #[error(transparent)]
+    Other(anyhow::Error),
+}
+
+impl CheckerError {
+    /// Error for a config file serde couldn't parse, suggesting the nearest
+    /// valid key when the problem is an unknown one
+    pub fn config_parse(path: impl AsRef<Path>, source: serde_json::Error) -> Self {
+        let path = path.as_ref().to_path_buf();
+        // serde reports e.g. "unknown field `jsonpth`, expected one of `name`, `json_file`, ..."
+        let message = source.to_string();
+        let what = ["field", "variant"].into_iter().find(|what| message.starts_with(&format!("unknown {} `", what)));
+        let Some(what) = what else {
+            return CheckerError::ConfigParse { path, source };
+        };
+
+        let mut quoted = message.split('`').skip(1).step_by(2).map(str::to_string);
+        let name = quoted.next().unwrap_or_default();
+        let expected: Vec<String> = quoted.collect();
+        let suggestion = suggest::closest(&name, expected.iter().map(String::as_str)).map(str::to_string);
+        CheckerError::UnknownKey { path, line: source.line(), what, name, expected, suggestion }
+    }
+
+    /// Error for a file that couldn't be read, telling a missing file apart
+    pub fn read(path: impl AsRef<Path>, source: io::Error) -> Self {
+        let path = path.as_ref().to_path_buf();
+        match source.kind() {
+            io::ErrorKind::NotFound => CheckerError::FileNotFound { path },
+            _ => CheckerError::Io { path, source },
+        }
+    }
+
+    /// The error itself, without the context it was given
+    pub fn kind(&self) -> &CheckerError {
+        match self {
+            CheckerError::Context { source, .. } => source.kind(),
+            error => error,
+        }
+    }
+
+    /// Whether trying again might succeed: the failure came from reading a
+    /// document, not from the config or its rules
+    pub fn is_transient(&self) -> bool {
+        matches!(
+            self.kind(),
+            CheckerError::FileNotFound { .. } | CheckerError::Io { .. } | CheckerError::RetriesExhausted(_)
+        )
+    }
+}
+
+impl From<anyhow::Error> for CheckerError {
+    /// Recover the kind of an error that passed through `anyhow`, keeping
+    /// the context it was given on the way
+    fn from(error: anyhow::Error) -> Self {
+        let context: Vec<String> = error
+            .chain()
+            .take_while(|e| !(e.is::<CheckerError>() || e.is::<RetriesExhausted>() || e.is::<JsonPathError>()))
+            .map(ToString::to_string)
+            .collect();
+        if context.len() == error.chain().count() {
+            return CheckerError::Other(error);
+        }
+
+        let typed = match error.downcast::<CheckerError>() {
+            Ok(error) => error,
+            Err(error) => match error.downcast::<RetriesExhausted>() {
+                Ok(exhausted) => CheckerError::RetriesExhausted(exhausted),
+                Err(error) => match error.downcast::<JsonPathError>() {
+                    Ok(invalid) => CheckerError::JsonPath(vec![(String::new(), invalid)]),
+                    // The typed error is the source of some other error
+                    Err(error) => return CheckerError::Other(error),
+                },
+            },
+        };
+        if context.is_empty() {
+            return typed;
+        }
+        CheckerError::Context { context: context.join(": "), source: Box::new(typed) }
+    }
+}
+
+fn did_you_mean(suggestion: &Option<String>) -> String {
+    suggestion.as_ref().map(|s| format!(", did you mean `{}`?", s)).unwrap_or_default()
+}
+
+fn json_path_errors(errors: &[(String, JsonPathError)]) -> String {
+    errors
+        .iter()
+        .map(|(rule, error)| match rule.as_str() {
+            "" => error.to_string(),
+            rule => format!("Rule '{}': {}", rule, error),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_kind_survives_anyhow() {
+        use anyhow::Context;
+
+        let missing = std::fs::read_to_string("/nonexistent/users.json")
+            .map_err(|e| CheckerError::read("/nonexistent/users.json", e))
+            .context("Failed to load reference target");
+        let error = CheckerError::from(missing.unwrap_err());
+        assert_eq!(error.to_string(), "Failed to load reference target");
+        assert!(matches!(error.kind(), CheckerError::FileNotFound { path } if path == Path::new("/nonexistent/users.json")));
+        assert!(error.is_transient());
+
+        let other = CheckerError::from(anyhow::anyhow!("Unknown inline document: users"));
+        assert!(!other.is_transient());
+        assert_eq!(other.to_string(), "Unknown inline document: users");
+    }
+}
@@ -0,0 +1,86 @@
+//! Checking a single value, without a config
+//!
+//! [`evaluate`] applies a [`CheckRule`] to one value with the same semantics
+//! as a rule whose JSONPath is `$`, for embedders that want the checks but
+//! not the loading, selection and reporting around them.
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::capture::CaptureValues;
+use crate::checker::JsonChecker;
+use crate::config::{Config, Rule};
+use crate::diff::Difference;
+use crate::messages::Message;
+use crate::rules::{CheckRule, Quantifier};
+
+/// Outcome of checking one value
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RuleOutcome {
+    pub passed: bool,
+    /// Details of a failure, such as the value an `aggregate` computed
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub reasons: Vec<Message>,
+    /// Path (`$` for the value itself) and value of each failing node
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub invalid: Vec<(String, Value)>,
+    /// Structural differences from the expected value, for checks that compare documents
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub differences: Vec<Difference>,
+    /// Why the check couldn't be evaluated, e.g. an invalid regex
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Check `value` against `check`. Checks over a whole selection, such as
+/// `aggregate` or `unique`, take the elements of an array value as the
+/// selection. Files a check refers to, such as a `ref_integrity` target,
+/// are resolved relative to the working directory.
+pub fn evaluate(value: &Value, check: &CheckRule) -> RuleOutcome {
+    let jsonpath = match (check.quantifier(), value) {
+        (Quantifier::Set, Value::Array(_)) => "$[*]",
+        _ => "$",
+    };
+    let rule = Rule {
+        name: "evaluate".to_string(),
+        jsonpath: jsonpath.to_string(),
+        check: check.clone(),
+        capture_values: Some(CaptureValues::Full),
+        ..Default::default()
+    };
+    match JsonChecker::from_config(Config::default()).check_document(&rule, value) {
+        Ok(result) => RuleOutcome {
+            passed: result.passed,
+            // The first line only names the rule
+            reasons: result.lines.into_iter().skip(1).collect(),
+            invalid: result.invalid_positions.into_iter().zip(result.values_found).collect(),
+            differences: result.differences,
+            error: None,
+        },
+        Err(e) => RuleOutcome {
+            passed: false,
+            reasons: Vec::new(),
+            invalid: Vec::new(),
+            differences: Vec::new(),
+            error: Some(e.to_string()),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_evaluate() {
+        assert!(evaluate(&json!(5), &CheckRule::GreaterThan { value: 3.into() }).passed);
+
+        let outcome = evaluate(&json!({"id": 1, "secret": "x"}), &CheckRule::ForbiddenKeys { keys: vec!["secret".to_string()] });
+        assert!(!outcome.passed);
+        assert_eq!(outcome.invalid, vec![("$.secret".to_string(), json!("x"))]);
+
+        let outcome = evaluate(&json!("abc"), &CheckRule::Regex { pattern: "(".to_string() });
+        assert!(outcome.error.unwrap().contains("unclosed group"));
+    }
+}
@@ -0,0 +1,106 @@
+//! Evaluation traces explaining why a rule passed or failed
+//!
+//! An [`Explanation`] lists every node a rule selected with the outcome of
+//! the check on that node alone, and how those outcomes combine into the
+//! verdict under the check's [`Quantifier`]. Checks such as `equals` pass
+//! if any node passes, which is easy to mistake for "every node"; the trace
+//! makes that visible. A [`Selection`] is the dry run of a rule: only what
+//! its selector matches, without the check.
+
+use serde::Serialize;
+use serde_json::Value;
+use std::fmt;
+
+use crate::rules::Quantifier;
+
+/// How a rule was evaluated, from [`JsonChecker::explain_rule`](crate::JsonChecker::explain_rule)
+#[derive(Debug, Clone, Serialize)]
+pub struct Explanation {
+    pub rule_name: String,
+    /// Kind of selector, e.g. `JSONPath`
+    pub selector_kind: String,
+    pub selector: String,
+    /// Check type, e.g. `greater_than`
+    pub check: String,
+    pub quantifier: Quantifier,
+    /// Selected nodes after `transform`, in selection order
+    pub nodes: Vec<NodeOutcome>,
+    pub passed: bool,
+    /// How the node outcomes add up to the verdict
+    pub reason: String,
+}
+
+/// A selected node and whether the check holds for it alone
+#[derive(Debug, Clone, Serialize)]
+pub struct NodeOutcome {
+    /// Canonical path of the node, if it could be located
+    pub path: Option<String>,
+    pub value: Value,
+    /// `None` for checks over the whole selection, which have no per-node outcome
+    pub passed: Option<bool>,
+}
+
+impl Explanation {
+    /// Describe how per-node outcomes combine into `passed`
+    pub(crate) fn reason_for(quantifier: Quantifier, nodes: &[NodeOutcome], passed: bool) -> String {
+        let total = nodes.len();
+        let passing = nodes.iter().filter(|node| node.passed == Some(true)).count();
+        match quantifier {
+            Quantifier::Set => format!("checked against all {} selected node(s) as a whole", total),
+            _ if total == 0 => "nothing was selected, which only an `empty` check accepts".to_string(),
+            Quantifier::Any if passed => format!("passes if any node passes; {} of {} did", passing, total),
+            Quantifier::Any => format!("passes if any node passes; none of {} did", total),
+            Quantifier::All if passed => format!("passes only if every node passes; all {} did", total),
+            Quantifier::All => format!("passes only if every node passes; {} of {} failed", total - passing, total),
+        }
+    }
+}
+
+impl fmt::Display for Explanation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "🔬 Rule '{}': {} '{}' selected {} node(s), check {}",
+            self.rule_name,
+            self.selector_kind,
+            self.selector,
+            self.nodes.len(),
+            self.check
+        )?;
+        for node in &self.nodes {
+            let mark = match node.passed {
+                Some(true) => "✓",
+                Some(false) => "✗",
+                None => "•",
+            };
+            writeln!(f, "   {} {} = {}", mark, node.path.as_deref().unwrap_or("?"), node.value)?;
+        }
+        let verdict = if self.passed { "passed" } else { "failed" };
+        writeln!(f, "   → {}: {}", verdict, self.reason)
+    }
+}
+
+/// Nodes a rule's selector matched in one document, from
+/// [`JsonChecker::select_rule`](crate::JsonChecker::select_rule)
+#[derive(Debug, Clone, Serialize)]
+pub struct Selection {
+    /// URI of the document, or `None` for inline documents and query results
+    pub document: Option<String>,
+    /// Canonical path (if it could be located) and value of each matched node
+    pub nodes: Vec<(Option<String>, Value)>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_reason_names_the_quantifier() {
+        let node = |passed| NodeOutcome { path: None, value: json!(1), passed: Some(passed) };
+        let nodes = [node(true), node(false), node(false)];
+        assert_eq!(Explanation::reason_for(Quantifier::Any, &nodes, true), "passes if any node passes; 1 of 3 did");
+        assert_eq!(Explanation::reason_for(Quantifier::All, &nodes, false), "passes only if every node passes; 2 of 3 failed");
+        assert_eq!(Explanation::reason_for(Quantifier::All, &[], false), "nothing was selected, which only an `empty` check accepts");
+    }
+}
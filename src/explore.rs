@@ -0,0 +1,178 @@
+//! Interactive prompt for trying JSONPath queries against a document
+//!
+//! `explore` evaluates each JSONPath typed at the prompt and prints the
+//! matched values with their canonical paths. Once a query selects the
+//! right nodes, `:save` turns it into a rule appended to the config file.
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde_json::{json, Value};
+use std::io::{BufRead, Write};
+use std::path::Path;
+
+use crate::checker::JsonChecker;
+use crate::config::{Config, Rule};
+use crate::jsonpath::{self, JsonPathEngine};
+use crate::path_finder::canonical_paths;
+use crate::rules::CheckRule;
+
+const HELP: &str = "\
+Type a JSONPath such as $.users[*].email to see what it selects.
+  :save <rule-name> <check>  append a rule for the last query to the config;
+                             <check> is a type such as non_empty or a JSON
+                             check such as {\"type\": \"regex\", \"pattern\": \"^a\"}.
+                             Quote names with spaces: :save \"Emails set\" non_empty
+  :help                      show this help
+  :quit                      leave (end of input works too)";
+
+/// Runs queries typed on `input` against a document, writing to `output`
+pub struct Explorer<R, W> {
+    input: R,
+    output: W,
+    /// URI of the document, used as the `json_file` of saved rules
+    uri: String,
+    document: Value,
+    /// Config file saved rules are appended to
+    config_path: String,
+    engine: JsonPathEngine,
+    /// Last query that compiled, which `:save` uses
+    last_query: Option<String>,
+}
+
+impl<R: BufRead, W: Write> Explorer<R, W> {
+    pub fn new(input: R, output: W, uri: &str, document: Value, config_path: &str) -> Self {
+        Explorer {
+            input,
+            output,
+            uri: uri.to_string(),
+            document,
+            config_path: config_path.to_string(),
+            engine: JsonPathEngine::default(),
+            last_query: None,
+        }
+    }
+
+    /// Evaluate queries with `engine` instead of the default one
+    pub fn with_engine(mut self, engine: JsonPathEngine) -> Self {
+        self.engine = engine;
+        self
+    }
+
+    /// Read commands until `:quit` or the end of input, returning the number
+    /// of rules saved
+    pub fn run(&mut self) -> Result<usize> {
+        writeln!(self.output, "🔎 Exploring {} (:help for commands)", self.uri)?;
+        let mut saved = 0;
+        loop {
+            write!(self.output, "json-checker> ")?;
+            self.output.flush()?;
+            let mut line = String::new();
+            if self.input.read_line(&mut line)? == 0 {
+                writeln!(self.output)?;
+                break;
+            }
+
+            let line = line.trim();
+            let outcome = match line.split_once(' ').map_or(line, |(command, _)| command) {
+                "" => Ok(()),
+                ":quit" | ":q" => break,
+                ":help" => writeln!(self.output, "{}", HELP).map_err(Into::into),
+                ":save" => self.save(line[":save".len()..].trim()).map(|()| saved += 1),
+                command if command.starts_with(':') => Err(anyhow!("Unknown command {}; try :help", command)),
+                _ => self.query(line),
+            };
+            if let Err(e) = outcome {
+                writeln!(self.output, "❌ {:#}", e)?;
+            }
+        }
+        Ok(saved)
+    }
+
+    /// Print what `path` selects, with the canonical path of each node
+    fn query(&mut self, path: &str) -> Result<()> {
+        let compiled = jsonpath::compile_with(path, self.engine)?;
+        let selected = compiled.select(&self.document)?;
+        self.last_query = Some(path.to_string());
+
+        writeln!(self.output, "{} match(es)", selected.len())?;
+        for (node, value) in canonical_paths(&self.document, &selected).into_iter().zip(&selected) {
+            writeln!(self.output, "  {} = {}", node.as_deref().unwrap_or("?"), value)?;
+        }
+        Ok(())
+    }
+
+    /// Append a rule checking the last query to the config file
+    fn save(&mut self, arguments: &str) -> Result<()> {
+        let path = self.last_query.clone().ok_or_else(|| anyhow!("Run a query before saving it as a rule"))?;
+        let (name, check) = parse_save(arguments)?;
+        let rule = Rule {
+            name,
+            json_file: Some(self.uri.clone()),
+            jsonpath: path,
+            check,
+            ..Default::default()
+        };
+
+        let result = JsonChecker::from_config(Config::default()).check_document(&rule, &self.document)?;
+        append_rule(Path::new(&self.config_path), &rule)?;
+        let verdict = if result.passed { "passes" } else { "fails" };
+        writeln!(self.output, "💾 Saved rule '{}' to {}; it {} on this document", rule.name, self.config_path, verdict)?;
+        Ok(())
+    }
+}
+
+/// Split `:save` arguments into the rule name and its check
+fn parse_save(arguments: &str) -> Result<(String, CheckRule)> {
+    let (name, check) = match arguments.strip_prefix('"') {
+        Some(quoted) => quoted.split_once('"').ok_or_else(|| anyhow!("Unterminated rule name"))?,
+        None => arguments.split_once(' ').unwrap_or((arguments, "")),
+    };
+    let check = check.trim();
+    if name.is_empty() || check.is_empty() {
+        bail!("Usage: :save <rule-name> <check>");
+    }
+
+    let check = if check.starts_with('{') { serde_json::from_str(check)? } else { json!({"type": check}) };
+    let check = serde_json::from_value(check).context("Invalid check")?;
+    Ok((name.to_string(), check))
+}
+
+/// Add `rule` to the config file at `path`, creating the file if needed.
+/// The file is edited as plain JSON so its includes and variables are kept.
+fn append_rule(path: &Path, rule: &Rule) -> Result<()> {
+    let mut config = match std::fs::read_to_string(path) {
+        Ok(text) => serde_json::from_str(&text).with_context(|| format!("Failed to parse config: {}", path.display()))?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => json!({"rules": []}),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read config: {}", path.display())),
+    };
+
+    let rules = config
+        .get_mut("rules")
+        .and_then(Value::as_array_mut)
+        .ok_or_else(|| anyhow!("{} has no rules array", path.display()))?;
+    if rules.iter().any(|r| r["name"] == rule.name.as_str()) {
+        bail!("{} already has a rule named '{}'", path.display(), rule.name);
+    }
+    rules.push(serde_json::to_value(rule)?);
+
+    std::fs::write(path, serde_json::to_string_pretty(&config)? + "\n")
+        .with_context(|| format!("Failed to write config: {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_save() {
+        let (name, check) = parse_save("\"Emails set\" non_empty").unwrap();
+        assert_eq!(name, "Emails set");
+        assert!(matches!(check, CheckRule::NonEmpty));
+
+        let (name, check) = parse_save(r#"emails {"type": "regex", "pattern": "@"}"#).unwrap();
+        assert_eq!(name, "emails");
+        assert!(matches!(check, CheckRule::Regex { pattern } if pattern == "@"));
+
+        assert!(parse_save("emails").is_err());
+        assert!(parse_save("emails non_empy").is_err());
+    }
+}
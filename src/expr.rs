@@ -0,0 +1,147 @@
+//! Expression checks evaluated with the embedded Rhai engine
+//!
+//! Each selected node is bound to `value`, so an expression like
+//! `value.price * value.qty == value.total` can express business rules
+//! without a dedicated check type. Expressions are evaluated by Rhai behind
+//! the `expr` feature.
+
+use anyhow::{bail, Result};
+#[cfg(feature = "expr")]
+use anyhow::anyhow;
+#[cfg(feature = "expr")]
+use rhai::{Dynamic, Engine, Scope, AST};
+#[cfg(feature = "expr")]
+use std::cell::Cell;
+use serde_json::Value;
+
+/// Operations an expression may take on one node before it is stopped, so
+/// a runaway expression fails its rule instead of hanging the run
+#[cfg(feature = "expr")]
+const MAX_OPERATIONS: u64 = 100_000;
+
+#[cfg(feature = "expr")]
+thread_local! {
+    /// Operations taken by the evaluation running on this thread
+    static OPERATIONS: Cell<u64> = const { Cell::new(0) };
+}
+
+/// A compiled expression check
+#[cfg(feature = "expr")]
+pub struct Expr {
+    engine: Engine,
+    ast: AST,
+    source: String,
+}
+
+#[cfg(not(feature = "expr"))]
+pub struct Expr {
+    never: std::convert::Infallible,
+}
+
+#[cfg(feature = "expr")]
+impl Expr {
+    /// Compile an expression
+    pub fn compile(source: &str) -> Result<Self> {
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+        // The limit counts each function call on its own, and every closure
+        // an array function calls starts afresh, so the total is kept here
+        engine.on_progress(|_| {
+            let operations = OPERATIONS.with(|count| {
+                count.set(count.get() + 1);
+                count.get()
+            });
+            (operations > MAX_OPERATIONS).then_some(Dynamic::UNIT)
+        });
+        let ast = engine
+            .compile_expression(source)
+            .map_err(|e| anyhow!("Invalid expression '{}': {}", source, e))?;
+
+        Ok(Expr { engine, ast, source: source.to_string() })
+    }
+
+    /// Evaluate the expression with `value` bound to a node; it must return a bool
+    pub fn eval(&self, value: &Value) -> Result<bool> {
+        let value = to_dynamic(value);
+        let mut scope = Scope::new();
+        scope.push("value", value);
+
+        OPERATIONS.with(|count| count.set(0));
+        let result: Dynamic = self
+            .engine
+            .eval_ast_with_scope(&mut scope, &self.ast)
+            .map_err(|e| {
+                if OPERATIONS.with(Cell::get) > MAX_OPERATIONS {
+                    anyhow!("Expression '{}' took more than {} operations", self.source, MAX_OPERATIONS)
+                } else {
+                    anyhow!("Expression '{}' failed: {}", self.source, e)
+                }
+            })?;
+        match result.as_bool() {
+            Ok(passed) => Ok(passed),
+            Err(type_name) => bail!(
+                "Expression '{}' returned {} instead of a bool",
+                self.source,
+                type_name
+            ),
+        }
+    }
+}
+
+#[cfg(not(feature = "expr"))]
+impl Expr {
+    pub fn compile(source: &str) -> Result<Self> {
+        bail!("Expression '{}' needs json-checker-rs built with the `expr` feature", source)
+    }
+
+    pub fn eval(&self, _value: &Value) -> Result<bool> {
+        match self.never {}
+    }
+}
+
+/// `value` as a Rhai value. Numbers become integers where they fit and
+/// floats otherwise, however serde_json stores them.
+#[cfg(feature = "expr")]
+fn to_dynamic(value: &Value) -> Dynamic {
+    match value {
+        Value::Null => Dynamic::UNIT,
+        Value::Bool(b) => Dynamic::from_bool(*b),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => Dynamic::from_int(i),
+            None => Dynamic::from_float(n.as_f64().unwrap_or(f64::NAN)),
+        },
+        Value::String(s) => Dynamic::from(s.clone()),
+        Value::Array(items) => Dynamic::from_array(items.iter().map(to_dynamic).collect()),
+        Value::Object(map) => {
+            Dynamic::from_map(map.iter().map(|(key, value)| (key.as_str().into(), to_dynamic(value))).collect())
+        }
+    }
+}
+
+#[cfg(all(test, feature = "expr"))]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_eval_business_rule() {
+        let expr = Expr::compile("value.price * value.qty == value.total").unwrap();
+        assert!(expr.eval(&json!({"price": 2.5, "qty": 4, "total": 10.0})).unwrap());
+        assert!(!expr.eval(&json!({"price": 2.5, "qty": 4, "total": 9.0})).unwrap());
+    }
+
+    #[test]
+    fn test_expression_errors() {
+        assert!(Expr::compile("value.price *").is_err());
+        let err = Expr::compile("value.name").unwrap().eval(&json!({"name": "x"})).unwrap_err();
+        assert!(err.to_string().contains("instead of a bool"));
+    }
+
+    #[test]
+    fn test_runaway_expression_is_stopped() {
+        let expr = Expr::compile("value.map(|i| i * 2).len() > 0").unwrap();
+        assert!(expr.eval(&json!([1, 2, 3])).unwrap());
+        let err = expr.eval(&json!(vec![1; 200_000])).unwrap_err();
+        assert!(err.to_string().ends_with("took more than 100000 operations"), "{}", err);
+    }
+}
@@ -0,0 +1,193 @@
+//! C-compatible API for embedding the checker
+//!
+//! Behind the `ffi` feature, the `cdylib` exports functions declared in
+//! `include/json_checker.h` (generated with `cbindgen --config cbindgen.toml
+//! --output include/json_checker.h`), so Go, C++ and other services can run
+//! rules in-process instead of shelling out to the CLI:
+//!
+//! ```c
+//! JsonChecker *checker = json_checker_new(config_json);
+//! if (!checker) { fprintf(stderr, "%s\n", json_checker_last_error()); return 1; }
+//! int failed = json_checker_run(checker);
+//! char *results = json_checker_results_json(checker);
+//! /* ... */
+//! json_checker_string_free(results);
+//! json_checker_free(checker);
+//! ```
+//!
+//! Strings are UTF-8 and NUL-terminated. Functions that fail return `NULL`
+//! or `-1` and leave a message for [`json_checker_last_error`].
+
+use std::cell::RefCell;
+use std::ffi::{c_char, c_int, CStr, CString};
+use std::ptr;
+
+use crate::checker;
+use crate::config::{CheckResult, Config};
+
+/// A checker and the results of its last run
+pub struct JsonChecker {
+    checker: checker::JsonChecker,
+    results: Vec<CheckResult>,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_last_error(message: String) {
+    let message = CString::new(message.replace('\0', " ")).unwrap_or_default();
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some(message));
+}
+
+/// The text of a C string argument
+///
+/// # Safety
+/// `text` must be null or a valid NUL-terminated string.
+unsafe fn text<'a>(text: *const c_char, name: &str) -> Option<&'a str> {
+    if text.is_null() {
+        set_last_error(format!("{} is NULL", name));
+        return None;
+    }
+    // SAFETY: the caller passes a valid NUL-terminated string
+    match unsafe { CStr::from_ptr(text) }.to_str() {
+        Ok(text) => Some(text),
+        Err(e) => {
+            set_last_error(format!("{} is not UTF-8: {}", name, e));
+            None
+        }
+    }
+}
+
+/// Create a checker from a JSON config. Relative `json_file` paths are
+/// resolved against the working directory. Returns `NULL` on error.
+///
+/// # Safety
+/// `config_json` must be null or a valid NUL-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn json_checker_new(config_json: *const c_char) -> *mut JsonChecker {
+    // SAFETY: forwarded from the caller
+    let Some(config_json) = (unsafe { text(config_json, "config_json") }) else {
+        return ptr::null_mut();
+    };
+    match Config::from_json(config_json) {
+        Ok(config) => Box::into_raw(Box::new(JsonChecker {
+            checker: checker::JsonChecker::from_config(config),
+            results: Vec::new(),
+        })),
+        Err(e) => {
+            set_last_error(format!("{:#}", anyhow::Error::from(e)));
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Run every rule, keeping the results for [`json_checker_results_json`].
+/// Returns the number of failed rules, or `-1` on error.
+///
+/// # Safety
+/// `checker` must be null or a pointer returned by [`json_checker_new`]
+/// that hasn't been freed, and not be used from another thread meanwhile.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn json_checker_run(checker: *mut JsonChecker) -> c_int {
+    // SAFETY: the caller passes a live checker it has exclusive access to
+    let Some(checker) = (unsafe { checker.as_mut() }) else {
+        set_last_error("checker is NULL".to_string());
+        return -1;
+    };
+    match checker.checker.run() {
+        Ok(results) => {
+            checker.results = results;
+            checker.results.iter().filter(|result| !result.passed).count() as c_int
+        }
+        Err(e) => {
+            set_last_error(format!("{:#}", anyhow::Error::from(e)));
+            -1
+        }
+    }
+}
+
+/// The results of the last run as a JSON array, empty before the first run.
+/// Free the string with [`json_checker_string_free`]. Returns `NULL` on error.
+///
+/// # Safety
+/// `checker` must be null or a pointer returned by [`json_checker_new`]
+/// that hasn't been freed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn json_checker_results_json(checker: *const JsonChecker) -> *mut c_char {
+    // SAFETY: the caller passes a live checker
+    let Some(checker) = (unsafe { checker.as_ref() }) else {
+        set_last_error("checker is NULL".to_string());
+        return ptr::null_mut();
+    };
+    match serde_json::to_string(&checker.results) {
+        // JSON escapes control characters, so it holds no NUL
+        Ok(json) => CString::new(json).map_or(ptr::null_mut(), CString::into_raw),
+        Err(e) => {
+            set_last_error(format!("Failed to serialize results: {}", e));
+            ptr::null_mut()
+        }
+    }
+}
+
+/// The message of the last error on this thread, or `NULL` if there was
+/// none. The string is owned by the library and valid until the next call
+/// on this thread.
+#[unsafe(no_mangle)]
+pub extern "C" fn json_checker_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| last.borrow().as_ref().map_or(ptr::null(), |message| message.as_ptr()))
+}
+
+/// Free a checker. Does nothing for `NULL`.
+///
+/// # Safety
+/// `checker` must be null or a pointer returned by [`json_checker_new`]
+/// that hasn't been freed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn json_checker_free(checker: *mut JsonChecker) {
+    if !checker.is_null() {
+        // SAFETY: the pointer came from Box::into_raw in json_checker_new
+        drop(unsafe { Box::from_raw(checker) });
+    }
+}
+
+/// Free a string returned by this library. Does nothing for `NULL`.
+///
+/// # Safety
+/// `text` must be null or a string returned by this library that hasn't
+/// been freed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn json_checker_string_free(text: *mut c_char) {
+    if !text.is_null() {
+        // SAFETY: the pointer came from CString::into_raw
+        drop(unsafe { CString::from_raw(text) });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let config = CString::new(
+            r#"{"rules": [{"name": "Positive", "json_inline": {"n": -1}, "jsonpath": "$.n", "check": {"type": "greater_than", "value": 0}}]}"#,
+        )
+        .unwrap();
+        unsafe {
+            let checker = json_checker_new(config.as_ptr());
+            assert!(!checker.is_null());
+            assert_eq!(json_checker_run(checker), 1);
+            let results = json_checker_results_json(checker);
+            let json: serde_json::Value = serde_json::from_str(CStr::from_ptr(results).to_str().unwrap()).unwrap();
+            assert_eq!(json[0]["rule_name"], "Positive");
+            json_checker_string_free(results);
+            json_checker_free(checker);
+
+            let invalid = CString::new(r#"{"rules": [{"nme": "x"}]}"#).unwrap();
+            assert!(json_checker_new(invalid.as_ptr()).is_null());
+            let error = CStr::from_ptr(json_checker_last_error()).to_str().unwrap();
+            assert!(error.contains("nme"), "{}", error);
+        }
+    }
+}
@@ -0,0 +1,230 @@
+//! Quickfix suggestions for failures with an obvious remediation
+//!
+//! Some failing values have one likely correction: the value an `equals`
+//! check expects, the allowed value of a `contained_by` list that a string
+//! misspells, or the number a numeric string such as `"42"` holds. Failed
+//! results propose these corrections as an RFC 6902 JSON Patch in `fixes`,
+//! and `--fix` applies them to the checked files after confirmation.
+
+use anyhow::{anyhow, bail, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt;
+use std::ops::Range;
+
+use crate::locate::{self, Span};
+use crate::path_finder::{push_index, push_key};
+use crate::rules::CheckRule;
+use crate::suggest;
+
+/// One operation of a JSON Patch (RFC 6902)
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum PatchOperation {
+    /// Replace the value at `path`, a JSON Pointer
+    Replace { path: String, value: Value },
+    /// Remove the value at `path`
+    Remove { path: String },
+}
+
+impl PatchOperation {
+    /// JSON Pointer of the value the operation changes
+    pub fn path(&self) -> &str {
+        match self {
+            PatchOperation::Replace { path, .. } | PatchOperation::Remove { path } => path,
+        }
+    }
+}
+
+impl fmt::Display for PatchOperation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PatchOperation::Replace { path, value } => write!(f, "replace {} with {}", path, value),
+            PatchOperation::Remove { path } => write!(f, "remove {}", path),
+        }
+    }
+}
+
+/// The corrected value `check` likely wants instead of `value`. Callers
+/// should confirm the suggestion passes before proposing it.
+pub fn suggest(check: &CheckRule, value: &Value) -> Option<Value> {
+    match check {
+        CheckRule::Equals { value: expected, .. } => Some(expected.clone()),
+        CheckRule::ContainedBy { value: Value::Array(allowed) } => closest_allowed(value, allowed),
+        CheckRule::GreaterThan { .. }
+        | CheckRule::LessThan { .. }
+        | CheckRule::GreaterThanOrEqual { .. }
+        | CheckRule::LessThanOrEqual { .. }
+        | CheckRule::ApproxEquals { .. }
+        | CheckRule::Integer
+        | CheckRule::MultipleOf { .. }
+        | CheckRule::DecimalPlaces { .. }
+        | CheckRule::SafeInteger => coerce(value).filter(Value::is_number),
+        _ => None,
+    }
+}
+
+/// The allowed value `value` is most likely meant to be: the same value in
+/// another JSON type, the same string in another case, or a close spelling
+fn closest_allowed(value: &Value, allowed: &[Value]) -> Option<Value> {
+    if let Some(coerced) = coerce(value).filter(|coerced| allowed.contains(coerced)) {
+        return Some(coerced);
+    }
+    let text = value.as_str()?;
+    let strings = allowed.iter().filter_map(Value::as_str);
+    if let Some(same) = strings.clone().find(|candidate| candidate.eq_ignore_ascii_case(text)) {
+        return Some(Value::String(same.to_string()));
+    }
+    suggest::closest(text, strings).map(|closest| Value::String(closest.to_string()))
+}
+
+/// `value` in the other JSON type it plainly spells: numbers and booleans
+/// for strings like `"42"` and `"true"`, and strings for numbers
+fn coerce(value: &Value) -> Option<Value> {
+    match value {
+        Value::String(text) => match serde_json::from_str(text.trim()) {
+            Ok(parsed @ (Value::Number(_) | Value::Bool(_))) => Some(parsed),
+            _ => None,
+        },
+        Value::Number(number) => Some(Value::String(number.to_string())),
+        _ => None,
+    }
+}
+
+/// Apply the operations to `document` in order
+pub fn apply(document: &mut Value, patch: &[PatchOperation]) -> Result<()> {
+    for operation in patch {
+        match operation {
+            PatchOperation::Replace { path, value } => {
+                let target = document.pointer_mut(path).ok_or_else(|| anyhow!("No value at {}", path))?;
+                *target = value.clone();
+            }
+            PatchOperation::Remove { path } => {
+                let (parent, key) = path.rsplit_once('/').ok_or_else(|| anyhow!("Cannot remove the whole document"))?;
+                let key = key.replace("~1", "/").replace("~0", "~");
+                let removed = match document.pointer_mut(parent) {
+                    Some(Value::Object(map)) => map.remove(&key).is_some(),
+                    Some(Value::Array(items)) => match key.parse::<usize>() {
+                        Ok(index) if index < items.len() => {
+                            items.remove(index);
+                            true
+                        }
+                        _ => false,
+                    },
+                    _ => false,
+                };
+                if !removed {
+                    return Err(anyhow!("No value at {}", path));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Apply the operations to a JSON text in order, rewriting only the values
+/// they touch so the rest of the text keeps its layout and key order
+pub fn apply_to_text(text: &str, patch: &[PatchOperation]) -> Result<String> {
+    let mut text = text.to_string();
+    for operation in patch {
+        let document: Value = serde_json::from_str(&text)?;
+        let pointer = operation.path();
+        let span = canonical_path(&document, pointer)
+            .map(|path| locate::spans(&text, &[path]))
+            .transpose()?
+            .and_then(|mut spans| spans.remove(0))
+            .ok_or_else(|| anyhow!("No value at {}", pointer))?;
+        match operation {
+            PatchOperation::Replace { value, .. } => text.replace_range(span.start..span.end, &value.to_string()),
+            PatchOperation::Remove { .. } if pointer.is_empty() => bail!("Cannot remove the whole document"),
+            PatchOperation::Remove { .. } => text.replace_range(removal(&text, span), ""),
+        }
+    }
+    Ok(text)
+}
+
+/// Canonical path of the value a JSON Pointer points at in `document`
+fn canonical_path(document: &Value, pointer: &str) -> Option<String> {
+    if !pointer.is_empty() && !pointer.starts_with('/') {
+        return None;
+    }
+    let mut path = "$".to_string();
+    let mut node = document;
+    for token in pointer.split('/').skip(1) {
+        let token = token.replace("~1", "/").replace("~0", "~");
+        match node {
+            Value::Object(map) => {
+                node = map.get(&token)?;
+                push_key(&mut path, &token);
+            }
+            Value::Array(items) => {
+                let index = token.parse().ok()?;
+                node = items.get(index)?;
+                push_index(&mut path, index);
+            }
+            _ => return None,
+        }
+    }
+    Some(path)
+}
+
+/// Text to cut to remove a member: the member and the comma after it up to
+/// the next member, or the comma before it if it is the last one
+fn removal(text: &str, span: Span) -> Range<usize> {
+    let is_space = |c: char| c.is_ascii_whitespace();
+    let after = text.len() - text[span.end..].trim_start_matches(is_space).len();
+    if text[after..].starts_with(',') {
+        let next = text.len() - text[after + 1..].trim_start_matches(is_space).len();
+        return span.member..next;
+    }
+    let before = text[..span.member].trim_end_matches(is_space);
+    match before.strip_suffix(',') {
+        Some(rest) => rest.len()..span.end,
+        None => span.member..span.end,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_suggest() {
+        let allowed = CheckRule::ContainedBy { value: json!(["staging", "production", 3]) };
+        assert_eq!(suggest(&allowed, &json!("Production")), Some(json!("production")));
+        assert_eq!(suggest(&allowed, &json!("prodution")), Some(json!("production")));
+        assert_eq!(suggest(&allowed, &json!("3")), Some(json!(3)));
+        assert_eq!(suggest(&allowed, &json!("dev")), None);
+        assert_eq!(suggest(&CheckRule::GreaterThan { value: 0.into() }, &json!(" 42 ")), Some(json!(42)));
+        assert_eq!(suggest(&CheckRule::GreaterThan { value: 0.into() }, &json!("many")), None);
+
+        let mut document = json!({"env": "prod", "ports": [1, "2"]});
+        let patch = [
+            PatchOperation::Replace { path: "/env".to_string(), value: json!("production") },
+            PatchOperation::Replace { path: "/ports/1".to_string(), value: json!(2) },
+        ];
+        apply(&mut document, &patch).unwrap();
+        assert_eq!(document, json!({"env": "production", "ports": [1, 2]}));
+        assert_eq!(serde_json::to_value(&patch[0]).unwrap(), json!({"op": "replace", "path": "/env", "value": "production"}));
+    }
+
+    #[test]
+    fn test_apply_to_text() {
+        let text = "{\n  \"zone\": \"eu\",\n  \"env\": \"prod\",\n  \"ports\": [1, \"2\", 3],\n  \"a/b\": {\"x\": 1}\n}\n";
+        let replace = |path: &str, value| PatchOperation::Replace { path: path.to_string(), value };
+        let remove = |path: &str| PatchOperation::Remove { path: path.to_string() };
+
+        let fixed = apply_to_text(text, &[replace("/env", json!("production")), replace("/ports/1", json!(2))]).unwrap();
+        assert_eq!(fixed, "{\n  \"zone\": \"eu\",\n  \"env\": \"production\",\n  \"ports\": [1, 2, 3],\n  \"a/b\": {\"x\": 1}\n}\n");
+
+        let fixed = apply_to_text(text, &[remove("/zone"), remove("/ports/2"), remove("/a~1b/x")]).unwrap();
+        assert_eq!(fixed, "{\n  \"env\": \"prod\",\n  \"ports\": [1, \"2\"],\n  \"a/b\": {}\n}\n");
+        let fixed = apply_to_text(text, &[remove("/a~1b"), remove("/ports/0")]).unwrap();
+        assert_eq!(fixed, "{\n  \"zone\": \"eu\",\n  \"env\": \"prod\",\n  \"ports\": [\"2\", 3]\n}\n");
+
+        assert_eq!(apply_to_text(text, &[remove("/missing")]).unwrap_err().to_string(), "No value at /missing");
+        assert!(apply_to_text(text, &[remove("")]).is_err());
+        assert!(apply_to_text("{\"a\": 1, // note\n}", &[replace("/a", json!(2))]).is_err());
+    }
+}
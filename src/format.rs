@@ -0,0 +1,417 @@
+//! Document formats a rule can read
+//!
+//! Every format is converted to a `serde_json::Value` so JSONPath queries and
+//! checks work the same regardless of the source format.
+
+use anyhow::{bail, Context, Result};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::parser::{self, ParserOptions};
+
+/// Format of a rule's document
+#[derive(Debug, Deserialize, JsonSchema, Serialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum DocumentFormat {
+    /// A single JSON document
+    Json,
+    /// JSON with comments and trailing commas, as in editor settings
+    Jsonc,
+    /// A JSON5 document, which also allows unquoted keys, single-quoted
+    /// strings, hexadecimal numbers and more
+    Json5,
+    /// Newline-delimited JSON, read as a virtual array with one element per line
+    Ndjson,
+    /// Comma-separated values, read as an array with one object per row
+    Csv,
+    /// A YAML document
+    Yaml,
+    /// A TOML document; dates and times become RFC 3339 strings
+    Toml,
+}
+
+impl DocumentFormat {
+    /// Guess the format of a document from its file extension
+    pub fn detect(uri: &str) -> Self {
+        let extension = uri.rsplit('.').next().unwrap_or_default().to_ascii_lowercase();
+        match extension.as_str() {
+            "jsonc" => DocumentFormat::Jsonc,
+            "json5" => DocumentFormat::Json5,
+            "jsonl" | "ndjson" => DocumentFormat::Ndjson,
+            "csv" => DocumentFormat::Csv,
+            "yaml" | "yml" => DocumentFormat::Yaml,
+            "toml" => DocumentFormat::Toml,
+            _ => DocumentFormat::Json,
+        }
+    }
+
+    /// Whether documents in this format are JSON text, whose failing nodes
+    /// can be located in the source
+    pub fn is_json(self) -> bool {
+        matches!(self, DocumentFormat::Json | DocumentFormat::Jsonc)
+    }
+}
+
+/// How CSV rows are turned into objects
+#[derive(Debug, Deserialize, JsonSchema, Serialize, Clone, PartialEq, Eq, Hash)]
+#[serde(default, deny_unknown_fields)]
+pub struct CsvOptions {
+    /// Field separator, e.g. `";"` or `"\t"`; must be a single ASCII character
+    pub delimiter: char,
+    /// Whether the first row holds the column names
+    pub has_header: bool,
+    /// Keys for the columns in order, replacing the header's names;
+    /// columns past the end of the list keep their header name
+    pub columns: Vec<String>,
+    /// Read numbers and `true`/`false` as JSON numbers and booleans, and
+    /// empty fields as `null`; otherwise every field is a string
+    pub infer_types: bool,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        CsvOptions {
+            delimiter: ',',
+            has_header: true,
+            columns: Vec::new(),
+            infer_types: true,
+        }
+    }
+}
+
+/// A loaded document
+#[derive(Debug, Clone)]
+pub struct Document {
+    pub value: Value,
+    /// For formats read as a virtual array (NDJSON, CSV rows, multi-document
+    /// YAML), the 1-based source line where each element of `value` starts
+    pub lines: Option<Vec<usize>>,
+    /// Paths of object keys that appear more than once, when the parser
+    /// looks for them
+    pub duplicate_keys: Vec<String>,
+}
+
+impl Document {
+    pub fn new(value: Value) -> Self {
+        Document { value, lines: None, duplicate_keys: Vec::new() }
+    }
+}
+
+/// Parse document text in the given format
+pub fn parse(text: &str, format: DocumentFormat) -> Result<Document> {
+    parse_with(text, format, None)
+}
+
+/// Parse document text in the given format, reading CSV with `csv` if given
+pub fn parse_with(text: &str, format: DocumentFormat, csv: Option<&CsvOptions>) -> Result<Document> {
+    match format {
+        DocumentFormat::Json => {
+            let value = parser::parse_json(text, ParserOptions::default()).context("Failed to parse JSON")?;
+            Ok(Document::new(value))
+        }
+        DocumentFormat::Jsonc | DocumentFormat::Json5 => parser::parse(text, format, None, ParserOptions::default()),
+        DocumentFormat::Ndjson => parse_ndjson(text),
+        DocumentFormat::Csv => parse_csv(text, csv.unwrap_or(&CsvOptions::default())),
+        DocumentFormat::Yaml => parse_yaml(text),
+        DocumentFormat::Toml => {
+            let value = text.parse().context("Failed to parse TOML")?;
+            Ok(Document::new(toml_to_json(value)?))
+        }
+    }
+}
+
+/// Parse newline-delimited JSON into an array, skipping blank lines
+fn parse_ndjson(text: &str) -> Result<Document> {
+    let mut values = Vec::new();
+    let mut lines = Vec::new();
+
+    for (index, line) in text.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let value = serde_json::from_str(line)
+            .with_context(|| format!("Failed to parse JSON on line {}", index + 1))?;
+        values.push(value);
+        lines.push(index + 1);
+    }
+
+    Ok(Document {
+        value: Value::Array(values),
+        lines: Some(lines),
+        duplicate_keys: Vec::new(),
+    })
+}
+
+/// Parse CSV into an array of objects keyed by column name, one per row
+pub fn parse_csv(text: &str, options: &CsvOptions) -> Result<Document> {
+    if !options.delimiter.is_ascii() {
+        bail!("CSV delimiter must be a single ASCII character, got '{}'", options.delimiter);
+    }
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(options.delimiter as u8)
+        .has_headers(options.has_header)
+        .from_reader(text.as_bytes());
+
+    let header: Vec<String> = if options.has_header {
+        reader.headers().context("Failed to read CSV header")?.iter().map(String::from).collect()
+    } else {
+        Vec::new()
+    };
+    let key = |index: usize| -> Result<&str> {
+        match options.columns.get(index).or_else(|| header.get(index)) {
+            Some(name) => Ok(name),
+            None if options.has_header => bail!("CSV row has more fields than the header"),
+            None => bail!("CSV row has more fields than `columns` names"),
+        }
+    };
+
+    let mut values = Vec::new();
+    let mut lines = Vec::new();
+    for record in reader.records() {
+        let record = record.context("Failed to parse CSV")?;
+        let line = record.position().map_or(0, |p| p.line() as usize);
+        let mut row = Map::new();
+        for (index, field) in record.iter().enumerate() {
+            let key = key(index).with_context(|| format!("Failed to parse CSV on line {}", line))?;
+            row.insert(key.to_string(), csv_value(field, options.infer_types));
+        }
+        values.push(Value::Object(row));
+        lines.push(line);
+    }
+
+    Ok(Document {
+        value: Value::Array(values),
+        lines: Some(lines),
+        duplicate_keys: Vec::new(),
+    })
+}
+
+/// Convert a CSV field, inferring its type unless disabled.
+///
+/// Numbers with leading zeros (`"007"`) aren't valid JSON numbers and stay
+/// strings, so codes and identifiers keep their digits.
+fn csv_value(field: &str, infer_types: bool) -> Value {
+    if !infer_types {
+        return Value::String(field.to_string());
+    }
+    match field.trim() {
+        "" => Value::Null,
+        "true" => Value::Bool(true),
+        "false" => Value::Bool(false),
+        trimmed => serde_json::from_str::<serde_json::Number>(trimmed)
+            .map_or_else(|_| Value::String(field.to_string()), Value::Number),
+    }
+}
+
+/// Parse YAML; a file with several `---`-separated documents is read as a
+/// virtual array with one element per document, tracking where each starts
+fn parse_yaml(text: &str) -> Result<Document> {
+    let documents = yaml_documents(text);
+    if documents.len() <= 1 {
+        let value = serde_yaml_ng::from_str(text).context("Failed to parse YAML")?;
+        return Ok(Document::new(yaml_to_json(value)?));
+    }
+
+    let mut values = Vec::new();
+    let mut lines = Vec::new();
+    for (index, (line, source)) in documents.into_iter().enumerate() {
+        let value = serde_yaml_ng::from_str(&source)
+            .map_err(anyhow::Error::from)
+            .and_then(yaml_to_json)
+            .with_context(|| format!("Failed to parse YAML document {} starting on line {}", index, line))?;
+        values.push(value);
+        lines.push(line);
+    }
+
+    Ok(Document {
+        value: Value::Array(values),
+        lines: Some(lines),
+        duplicate_keys: Vec::new(),
+    })
+}
+
+/// Split YAML text at `---` markers into its non-empty documents, each with
+/// the 1-based line of its first content line
+fn yaml_documents(text: &str) -> Vec<(usize, String)> {
+    let mut documents = Vec::new();
+    let mut current = String::new();
+    let mut start = None;
+
+    for (index, line) in text.lines().enumerate() {
+        // A marker can only appear at column 0, so this never splits a scalar
+        let marker = line.strip_prefix("---").filter(|rest| rest.is_empty() || rest.starts_with([' ', '\t']));
+        if let Some(rest) = marker {
+            if let Some(line) = start.take() {
+                documents.push((line, std::mem::take(&mut current)));
+            }
+            current.clear();
+            if !rest.trim().is_empty() && !rest.trim_start().starts_with('#') {
+                start = Some(index + 1);
+            }
+            current.push_str(rest);
+            current.push('\n');
+            continue;
+        }
+        if line == "..." {
+            continue;
+        }
+        if start.is_none() && !line.trim().is_empty() && !line.trim_start().starts_with('#') && !line.starts_with('%') {
+            start = Some(index + 1);
+        }
+        current.push_str(line);
+        current.push('\n');
+    }
+    if let Some(line) = start {
+        documents.push((line, current));
+    }
+
+    documents
+}
+
+/// Convert YAML to JSON: mapping keys that are numbers or booleans become
+/// strings and tags are dropped, keeping the tagged value
+fn yaml_to_json(value: serde_yaml_ng::Value) -> Result<Value> {
+    use serde_yaml_ng::Value as Yaml;
+
+    Ok(match value {
+        Yaml::Null => Value::Null,
+        Yaml::Bool(b) => Value::Bool(b),
+        Yaml::Number(n) => {
+            if let Some(i) = n.as_i64() {
+                Value::from(i)
+            } else if let Some(u) = n.as_u64() {
+                Value::from(u)
+            } else {
+                let f = n.as_f64().unwrap_or(f64::NAN);
+                Value::Number(serde_json::Number::from_f64(f).with_context(|| format!("YAML number {} has no JSON equivalent", n))?)
+            }
+        }
+        Yaml::String(s) => Value::String(s),
+        Yaml::Sequence(items) => Value::Array(items.into_iter().map(yaml_to_json).collect::<Result<_>>()?),
+        Yaml::Mapping(mapping) => {
+            let mut map = Map::new();
+            for (key, value) in mapping {
+                let key = match key {
+                    Yaml::String(s) => s,
+                    Yaml::Number(n) => n.to_string(),
+                    Yaml::Bool(b) => b.to_string(),
+                    Yaml::Null => "null".to_string(),
+                    _ => bail!("YAML mapping keys must be scalars to convert to JSON"),
+                };
+                map.insert(key, yaml_to_json(value)?);
+            }
+            Value::Object(map)
+        }
+        Yaml::Tagged(tagged) => yaml_to_json(tagged.value)?,
+    })
+}
+
+fn toml_to_json(value: toml::Value) -> Result<Value> {
+    Ok(match value {
+        toml::Value::String(s) => Value::String(s),
+        toml::Value::Integer(i) => Value::from(i),
+        toml::Value::Float(f) => {
+            Value::Number(serde_json::Number::from_f64(f).with_context(|| format!("TOML float {} has no JSON equivalent", f))?)
+        }
+        toml::Value::Boolean(b) => Value::Bool(b),
+        toml::Value::Datetime(datetime) => Value::String(datetime.to_string()),
+        toml::Value::Array(items) => Value::Array(items.into_iter().map(toml_to_json).collect::<Result<_>>()?),
+        toml::Value::Table(table) => {
+            Value::Object(table.into_iter().map(|(k, v)| Ok((k, toml_to_json(v)?))).collect::<Result<_>>()?)
+        }
+    })
+}
+
+/// Index of the top-level array element a path like `$[3].email` points into
+pub fn top_level_index(path: &str) -> Option<usize> {
+    if let Some(pointer) = path.strip_prefix('/') {
+        return pointer.split('/').next()?.parse().ok();
+    }
+    let rest = path.strip_prefix('$')?;
+    let rest = rest.strip_prefix('.').unwrap_or(rest);
+    let rest = rest.strip_prefix('[')?;
+    let end = rest.find(']')?;
+    rest[..end].parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_detect_format() {
+        assert_eq!(DocumentFormat::detect("dumps/users.jsonl"), DocumentFormat::Ndjson);
+        assert_eq!(DocumentFormat::detect("dumps/users.NDJSON"), DocumentFormat::Ndjson);
+        assert_eq!(DocumentFormat::detect("data/users.json"), DocumentFormat::Json);
+        assert_eq!(DocumentFormat::detect("exports/users.csv"), DocumentFormat::Csv);
+        assert_eq!(DocumentFormat::detect("k8s/deploy.yml"), DocumentFormat::Yaml);
+        assert_eq!(DocumentFormat::detect("Cargo.toml"), DocumentFormat::Toml);
+    }
+
+    #[test]
+    fn test_parse_ndjson_tracks_lines() {
+        let doc = parse("{\"a\":1}\n\n{\"a\":2}\n", DocumentFormat::Ndjson).unwrap();
+        assert_eq!(doc.value, json!([{"a": 1}, {"a": 2}]));
+        assert_eq!(doc.lines, Some(vec![1, 3]));
+
+        let err = parse("{\"a\":1}\n{oops}\n", DocumentFormat::Ndjson).unwrap_err();
+        assert!(err.to_string().contains("line 2"));
+    }
+
+    #[test]
+    fn test_parse_csv() {
+        let text = "id,email,zip,active,score\n1,a@example.com,007,true,\n2,\"b, c\",12345,false,1.5\n";
+        let doc = parse(text, DocumentFormat::Csv).unwrap();
+        assert_eq!(
+            doc.value,
+            json!([
+                {"id": 1, "email": "a@example.com", "zip": "007", "active": true, "score": null},
+                {"id": 2, "email": "b, c", "zip": 12345, "active": false, "score": 1.5}
+            ])
+        );
+        assert_eq!(doc.lines, Some(vec![2, 3]));
+
+        let options = CsvOptions {
+            delimiter: ';',
+            has_header: false,
+            columns: vec!["id".to_string(), "name".to_string()],
+            infer_types: false,
+        };
+        let doc = parse_csv("1;Ann\n2;Bo\n", &options).unwrap();
+        assert_eq!(doc.value, json!([{"id": "1", "name": "Ann"}, {"id": "2", "name": "Bo"}]));
+
+        let err = parse_csv("1;Ann;x\n", &options).unwrap_err();
+        assert!(format!("{:#}", err).contains("line 1"), "{:#}", err);
+    }
+
+    #[test]
+    fn test_parse_yaml_and_toml() {
+        let yaml = "replicas: 3\nports:\n  80: http\n  true: yes\nimage: !ref app:1.2\nratio: 0.5\n";
+        let doc = parse(yaml, DocumentFormat::Yaml).unwrap();
+        assert_eq!(doc.value, json!({"replicas": 3, "ports": {"80": "http", "true": "yes"}, "image": "app:1.2", "ratio": 0.5}));
+        assert!(parse("a: .nan\n", DocumentFormat::Yaml).is_err());
+
+        let manifests = "# app\n---\nkind: Service\n---\n\nkind: Deployment\nspec: {}\n---\n";
+        let doc = parse(manifests, DocumentFormat::Yaml).unwrap();
+        assert_eq!(doc.value, json!([{"kind": "Service"}, {"kind": "Deployment", "spec": {}}]));
+        assert_eq!(doc.lines, Some(vec![3, 6]));
+
+        let err = parse("a: 1\n---\nb: [\n", DocumentFormat::Yaml).unwrap_err();
+        assert!(err.to_string().contains("document 1 starting on line 3"), "{}", err);
+
+        let toml = "[package]\nname = \"demo\"\nreleased = 2024-05-01\n\n[[bin]]\nname = \"cli\"\n";
+        let doc = parse(toml, DocumentFormat::Toml).unwrap();
+        assert_eq!(doc.value, json!({"package": {"name": "demo", "released": "2024-05-01"}, "bin": [{"name": "cli"}]}));
+        assert!(parse("name = ", DocumentFormat::Toml).is_err());
+    }
+
+    #[test]
+    fn test_top_level_index() {
+        assert_eq!(top_level_index("$[3].email"), Some(3));
+        assert_eq!(top_level_index("$.[12]"), Some(12));
+        assert_eq!(top_level_index("$.users[1]"), None);
+        assert_eq!(top_level_index("/4/email"), Some(4));
+    }
+}
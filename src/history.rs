@@ -0,0 +1,179 @@
+//! Run history and trends
+//!
+//! Each run can append a summary line to an NDJSON history file. Trends over
+//! the last runs show which rules are flaky and which started failing.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::config::{CheckResult, Status};
+
+/// Summary of one run, stored as one line of the history file
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct RunRecord {
+    /// Seconds since the Unix epoch when the run finished
+    pub timestamp: u64,
+    pub rules: Vec<RuleRecord>,
+}
+
+/// Outcome of one rule in a run
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct RuleRecord {
+    pub name: String,
+    pub passed: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub skipped: bool,
+    pub duration_ms: f64,
+}
+
+impl RunRecord {
+    /// Summarize the results of a run that finished now
+    pub fn from_results(results: &[CheckResult]) -> Self {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        RunRecord {
+            timestamp,
+            rules: results
+                .iter()
+                .map(|r| RuleRecord {
+                    name: r.rule_name.clone(),
+                    passed: r.passed,
+                    skipped: r.status == Status::Skipped,
+                    duration_ms: r.duration.as_secs_f64() * 1000.0,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Append a run to a history file, creating it if needed
+pub fn append(path: impl AsRef<Path>, run: &RunRecord) -> Result<()> {
+    let path = path.as_ref();
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Failed to open history file {}", path.display()))?;
+    writeln!(file, "{}", serde_json::to_string(run)?)
+        .with_context(|| format!("Failed to write history file {}", path.display()))
+}
+
+/// Read every run recorded in a history file, oldest first
+pub fn load(path: impl AsRef<Path>) -> Result<Vec<RunRecord>> {
+    let path = path.as_ref();
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read history file {}", path.display()))?;
+
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            serde_json::from_str(line)
+                .with_context(|| format!("Failed to parse {} on line {}", path.display(), i + 1))
+        })
+        .collect()
+}
+
+/// How a rule behaved over a series of runs
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuleTrend {
+    pub name: String,
+    /// Runs the rule was evaluated in (not skipped)
+    pub runs: usize,
+    pub failures: usize,
+    /// Times the outcome changed between consecutive evaluated runs
+    pub flips: usize,
+    /// Failed in the latest run after passing in the run before
+    pub regressed: bool,
+    /// Outcome of the latest run the rule was evaluated in
+    pub latest_passed: bool,
+    pub average_ms: f64,
+}
+
+impl RuleTrend {
+    /// A rule that both passed and failed more than once is considered flaky
+    pub fn is_flaky(&self) -> bool {
+        self.flips >= 2
+    }
+}
+
+/// Compute per-rule trends over runs (oldest first), in order of first appearance
+pub fn trends(runs: &[RunRecord]) -> Vec<RuleTrend> {
+    let mut names: Vec<&str> = Vec::new();
+    for rule in runs.iter().flat_map(|run| &run.rules) {
+        if !names.contains(&rule.name.as_str()) {
+            names.push(&rule.name);
+        }
+    }
+
+    names
+        .into_iter()
+        .map(|name| {
+            let outcomes: Vec<&RuleRecord> = runs
+                .iter()
+                .filter_map(|run| run.rules.iter().find(|r| r.name == name))
+                .filter(|r| !r.skipped)
+                .collect();
+            let passed: Vec<bool> = outcomes.iter().map(|r| r.passed).collect();
+            let total_ms: f64 = outcomes.iter().map(|r| r.duration_ms).sum();
+
+            RuleTrend {
+                name: name.to_string(),
+                runs: outcomes.len(),
+                failures: passed.iter().filter(|&&p| !p).count(),
+                flips: passed.windows(2).filter(|pair| pair[0] != pair[1]).count(),
+                regressed: matches!(passed.as_slice(), [.., true, false]),
+                latest_passed: passed.last().copied().unwrap_or(true),
+                average_ms: if outcomes.is_empty() { 0.0 } else { total_ms / outcomes.len() as f64 },
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(timestamp: u64, outcomes: &[(&str, bool)]) -> RunRecord {
+        RunRecord {
+            timestamp,
+            rules: outcomes
+                .iter()
+                .map(|&(name, passed)| RuleRecord {
+                    name: name.to_string(),
+                    passed,
+                    skipped: false,
+                    duration_ms: 2.0,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_trends() {
+        let runs = [
+            run(1, &[("stable", true), ("flaky", true), ("broken", true)]),
+            run(2, &[("stable", true), ("flaky", false), ("broken", true)]),
+            run(3, &[("stable", true), ("flaky", true), ("broken", false)]),
+        ];
+        let trends = trends(&runs);
+
+        assert_eq!(trends[0], RuleTrend {
+            name: "stable".to_string(),
+            runs: 3,
+            failures: 0,
+            flips: 0,
+            regressed: false,
+            latest_passed: true,
+            average_ms: 2.0,
+        });
+        assert!(trends[1].is_flaky() && !trends[1].regressed);
+        assert!(!trends[2].is_flaky() && trends[2].regressed);
+    }
+}
@@ -0,0 +1,177 @@
+//! Standalone HTML reports
+//!
+//! Renders results as a single self-contained page with a collapsible section
+//! per rule, listing each failing path with the offending JSON, so a run can
+//! be attached to CI artifacts and read without the terminal.
+
+use std::fmt::Write;
+
+use crate::config::{CheckResult, Config, Severity, Status};
+use crate::suite;
+
+const STYLE: &str = "\
+body{font-family:system-ui,sans-serif;margin:2rem;color:#1f2328}\
+h1{font-size:1.5rem}\
+.summary span{margin-right:1.5rem;font-weight:600}\
+details{border:1px solid #d0d7de;border-radius:6px;margin:.5rem 0;padding:.5rem 1rem}\
+summary{cursor:pointer;font-weight:600}\
+.passed summary{color:#1a7f37}.failed summary{color:#cf222e}\
+.warning summary{color:#9a6700}.skipped summary{color:#656d76}\
+.meta{color:#656d76;font-size:.9rem}\
+.hint{background:#ddf4ff;padding:.25rem .5rem;border-radius:6px}\
+.suites{border-collapse:collapse;margin-bottom:1rem}.suites td,.suites th{border:1px solid #d0d7de;padding:.25rem .75rem}\
+code.path{background:#fff8c5;padding:0 .25rem;border-radius:3px}\
+pre{background:#f6f8fa;padding:.5rem;border-radius:6px;overflow-x:auto}\
+li{margin:.5rem 0}";
+
+/// Render a standalone HTML report for the results of running a config
+pub fn to_html(config: &Config, results: &[CheckResult]) -> String {
+    let count = |f: fn(&CheckResult) -> bool| results.iter().filter(|r| f(r)).count();
+    let passed = count(|r| r.status == Status::Pass);
+    let skipped = count(|r| r.status == Status::Skipped);
+    let warnings = count(|r| !r.passed && r.severity == Severity::Warning);
+    let failed = count(|r| !r.passed && r.severity == Severity::Error);
+
+    let mut out = String::new();
+    out.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n");
+    out.push_str("<title>JSON Checker Report</title>\n");
+    let _ = writeln!(out, "<style>{}</style>\n</head>\n<body>", STYLE);
+    out.push_str("<h1>JSON Checker Report</h1>\n");
+    let _ = writeln!(
+        out,
+        "<p class=\"summary\"><span>Total: {}</span><span>Passed: {}</span><span>Failed: {}</span>\
+         <span>Warnings: {}</span><span>Skipped: {}</span></p>",
+        results.len(),
+        passed,
+        failed,
+        warnings,
+        skipped
+    );
+    render_suites(&mut out, config, results);
+
+    for result in results {
+        render_result(&mut out, config, result);
+    }
+
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+/// Table of the outcome counts of each suite, if rules are grouped in suites
+fn render_suites(out: &mut String, config: &Config, results: &[CheckResult]) {
+    let summaries = suite::summarize(results);
+    if summaries.is_empty() {
+        return;
+    }
+    out.push_str("<table class=\"suites\">\n");
+    out.push_str("<tr><th>Suite</th><th>Passed</th><th>Failed</th><th>Warnings</th><th>Skipped</th></tr>\n");
+    for summary in summaries {
+        let description = config
+            .suites
+            .iter()
+            .find(|s| s.name == summary.name)
+            .and_then(|s| s.description.as_deref())
+            .map(|d| format!(" <span class=\"meta\">{}</span>", escape(d)))
+            .unwrap_or_default();
+        let _ = writeln!(
+            out,
+            "<tr><td>{}{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+            escape(&summary.name),
+            description,
+            summary.passed,
+            summary.failed,
+            summary.warnings,
+            summary.skipped
+        );
+    }
+    out.push_str("</table>\n");
+}
+
+fn render_result(out: &mut String, config: &Config, result: &CheckResult) {
+    let class = match (result.status, result.severity) {
+        (Status::Skipped, _) => "skipped",
+        (Status::Pass, _) => "passed",
+        (_, Severity::Warning) => "warning",
+        (_, Severity::Error) => "failed",
+    };
+    let headline = result.message.lines().next().unwrap_or_default();
+
+    // Failures start expanded so reviewers see them first
+    let open = if class == "failed" || class == "warning" { " open" } else { "" };
+    let _ = writeln!(out, "<details class=\"{}\"{}>", class, open);
+    let _ = writeln!(out, "<summary>{}</summary>", escape(headline));
+
+    if let Some(rule) = config.rules.iter().find(|r| r.name == result.rule_name) {
+        let document = result.document.as_deref().or(rule.json_file.as_deref()).unwrap_or("inline document");
+        let (kind, selector) = rule.selected_by();
+        let _ = writeln!(
+            out,
+            "<p class=\"meta\">{} <code>{}</code> in {} · {:.1}ms</p>",
+            kind,
+            escape(selector),
+            escape(document),
+            result.duration.as_secs_f64() * 1000.0
+        );
+    }
+    for detail in result.message.lines().skip(1) {
+        let _ = writeln!(out, "<p>{}</p>", escape(detail.trim()));
+    }
+    if !result.passed && (result.hint.is_some() || result.docs_url.is_some()) {
+        out.push_str("<p class=\"hint\">💡");
+        if let Some(hint) = &result.hint {
+            let _ = write!(out, " {}", escape(hint));
+        }
+        if let Some(url) = &result.docs_url {
+            let _ = write!(out, " <a href=\"{}\">Docs</a>", escape(url));
+        }
+        out.push_str("</p>\n");
+    }
+
+    if !result.invalid_positions.is_empty() {
+        out.push_str("<ul>\n");
+        for (i, path) in result.invalid_positions.iter().enumerate() {
+            let _ = write!(out, "<li><code class=\"path\">{}</code>", escape(path));
+            if let Some(location) = result.location(i) {
+                let _ = write!(out, " <span class=\"meta\">({})</span>", location);
+            }
+            if let Some(value) = result.values_found.get(i) {
+                let snippet = serde_json::to_string_pretty(value).unwrap_or_else(|_| value.to_string());
+                let _ = write!(out, "\n<pre>{}</pre>", escape(&snippet));
+            }
+            out.push_str("</li>\n");
+        }
+        out.push_str("</ul>\n");
+    }
+
+    if !result.differences.is_empty() {
+        let lines: Vec<String> = result.differences.iter().map(|d| escape(&d.to_string())).collect();
+        let _ = writeln!(out, "<pre>{}</pre>", lines.join("\n"));
+    }
+
+    out.push_str("</details>\n");
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape(r#"<a href="x">Tom & 'Jerry'</a>"#), "&lt;a href=&quot;x&quot;&gt;Tom &amp; &#39;Jerry&#39;&lt;/a&gt;");
+    }
+}
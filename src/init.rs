@@ -0,0 +1,242 @@
+//! Interactive wizard writing a starter rules config
+//!
+//! `init` reads a sample document, lists its paths as a tree and asks which
+//! to check and how, offering checks that suit each path's sample value.
+//! Answers are read line by line and an empty answer takes the default shown
+//! in brackets, so the wizard can also be driven from a script or a test.
+
+use anyhow::{bail, Context, Result};
+use serde_json::{json, Number, Value};
+use std::io::{BufRead, Write};
+
+use crate::config::{Config, Rule};
+use crate::format::{self, DocumentFormat};
+use crate::path_finder::push_key;
+use crate::rules::{CheckRule, RequiredKey, StringFormat};
+
+/// A path of the sample document, as listed by the wizard
+#[derive(Debug, Clone, PartialEq)]
+pub struct Node {
+    /// JSONPath of the node; array elements are written as `[*]`
+    pub path: String,
+    /// Nesting level, 0 for the root
+    pub depth: usize,
+    /// Value at the path, or of the first element for `[*]` paths
+    pub sample: Value,
+}
+
+/// Every path in `document`, parents before their children. Each array is
+/// listed once with `[*]`, described by its first element.
+pub fn outline(document: &Value) -> Vec<Node> {
+    let mut nodes = Vec::new();
+    visit(document, "$".to_string(), 0, &mut nodes);
+    nodes
+}
+
+fn visit(value: &Value, path: String, depth: usize, nodes: &mut Vec<Node>) {
+    nodes.push(Node { path: path.clone(), depth, sample: value.clone() });
+    match value {
+        Value::Object(map) => {
+            for (key, child) in map {
+                let mut child_path = path.clone();
+                push_key(&mut child_path, key);
+                visit(child, child_path, depth + 1, nodes);
+            }
+        }
+        Value::Array(items) => {
+            if let Some(first) = items.first() {
+                visit(first, format!("{}[*]", path), depth + 1, nodes);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Asks questions on `output` and reads the answers from `input`
+pub struct Wizard<R, W> {
+    input: R,
+    output: W,
+}
+
+impl<R: BufRead, W: Write> Wizard<R, W> {
+    pub fn new(input: R, output: W) -> Self {
+        Wizard { input, output }
+    }
+
+    /// Build a config for the document at `uri` by asking which of its paths
+    /// to check and how
+    pub fn run(&mut self, uri: &str) -> Result<Config> {
+        let text = std::fs::read_to_string(uri).with_context(|| format!("Failed to read sample {}", uri))?;
+        let document = format::parse(&text, DocumentFormat::detect(uri))?.value;
+        let nodes = outline(&document);
+
+        writeln!(self.output, "📄 {}", uri)?;
+        for (i, node) in nodes.iter().enumerate() {
+            let label = format!("{}{}", "  ".repeat(node.depth), node.path);
+            writeln!(self.output, "{:>4}  {:<40} {}", i + 1, label, describe(&node.sample))?;
+        }
+
+        let answer = self.ask("Paths to check, by number (e.g. 2 5)", "")?;
+        let mut rules = Vec::new();
+        for choice in answer.split([' ', ',']).filter(|s| !s.is_empty()) {
+            let node = choice
+                .parse::<usize>()
+                .ok()
+                .and_then(|n| nodes.get(n.wrapping_sub(1)))
+                .with_context(|| format!("No path numbered '{}'", choice))?;
+            rules.push(self.rule_for(uri, node)?);
+        }
+        if rules.is_empty() {
+            bail!("No paths selected");
+        }
+
+        Ok(Config { rules, ..Default::default() })
+    }
+
+    fn rule_for(&mut self, uri: &str, node: &Node) -> Result<Rule> {
+        let choices = checks_for(&node.sample);
+        let menu: Vec<String> = choices.iter().enumerate().map(|(i, name)| format!("{}) {}", i + 1, name)).collect();
+        writeln!(self.output, "\n{}  {}", node.path, menu.join("  "))?;
+        let answer = self.ask("Check", "1")?;
+        let name = answer
+            .parse::<usize>()
+            .ok()
+            .and_then(|n| choices.get(n.wrapping_sub(1)))
+            .with_context(|| format!("No check numbered '{}'", answer))?;
+
+        let check = self.check(name, &node.sample)?;
+        let default_name = format!("{} {}", node.path.trim_start_matches("$."), name);
+        let rule_name = self.ask("Rule name", &default_name)?;
+        Ok(Rule {
+            name: rule_name,
+            json_file: Some(uri.to_string()),
+            jsonpath: node.path.clone(),
+            check,
+            ..Default::default()
+        })
+    }
+
+    /// Ask for the parameters of the named check, defaulting to what the sample holds
+    fn check(&mut self, name: &str, sample: &Value) -> Result<CheckRule> {
+        Ok(match name {
+            "non_empty" => CheckRule::NonEmpty,
+            "integer" => CheckRule::Integer,
+            "unique" => CheckRule::Unique,
+            "equals" => CheckRule::Equals { value: self.ask_value("Expected value", sample)?, tolerance: None },
+            "regex" => {
+                let pattern = format!("^{}$", regex::escape(sample.as_str().unwrap_or_default()));
+                CheckRule::Regex { pattern: self.ask("Pattern", &pattern)? }
+            }
+            "format" => {
+                let detected = sample.as_str().and_then(detect_format).unwrap_or("uuid");
+                let kind = self.ask("Format (uuid, email, url, ipv4, ipv6, hostname, base64, lowercase, uppercase)", detected)?;
+                CheckRule::Format { kind: serde_json::from_value::<StringFormat>(json!(kind)).context("Unknown format")? }
+            }
+            "greater_than_or_equal" => CheckRule::GreaterThanOrEqual { value: self.ask_number("Minimum", sample)? },
+            "less_than_or_equal" => CheckRule::LessThanOrEqual { value: self.ask_number("Maximum", sample)? },
+            "array_length" => {
+                let min = self.ask("Minimum length", "1")?;
+                let max = self.ask("Maximum length (empty for none)", "")?;
+                CheckRule::ArrayLength { min: parse_bound(&min)?, max: parse_bound(&max)? }
+            }
+            "required_keys" => {
+                let keys: Vec<&str> = sample.as_object().map(|map| map.keys().map(String::as_str).collect()).unwrap_or_default();
+                let answer = self.ask("Required keys", &keys.join(" "))?;
+                let keys = answer
+                    .split([' ', ','])
+                    .filter(|s| !s.is_empty())
+                    .map(|name| RequiredKey { name: name.to_string(), kind: None })
+                    .collect();
+                CheckRule::RequiredKeys { keys }
+            }
+            other => bail!("Unsupported check '{}'", other),
+        })
+    }
+
+    /// Print a question with its default and read the answer; an empty
+    /// answer or the end of input picks the default
+    fn ask(&mut self, question: &str, default: &str) -> Result<String> {
+        match default {
+            "" => write!(self.output, "{}: ", question)?,
+            default => write!(self.output, "{} [{}]: ", question, default)?,
+        }
+        self.output.flush()?;
+
+        let mut line = String::new();
+        self.input.read_line(&mut line)?;
+        Ok(match line.trim() {
+            "" => default.to_string(),
+            answer => answer.to_string(),
+        })
+    }
+
+    /// Ask for a JSON value; answers that aren't JSON are taken as strings
+    fn ask_value(&mut self, question: &str, default: &Value) -> Result<Value> {
+        let answer = self.ask(question, &default.to_string())?;
+        Ok(serde_json::from_str(&answer).unwrap_or(Value::String(answer)))
+    }
+
+    fn ask_number(&mut self, question: &str, default: &Value) -> Result<Number> {
+        let answer = self.ask(question, &default.to_string())?;
+        serde_json::from_str(answer.trim()).with_context(|| format!("Invalid number '{}'", answer))
+    }
+}
+
+/// Checks that suit a value like `sample`, the most likely first
+fn checks_for(sample: &Value) -> Vec<&'static str> {
+    match sample {
+        Value::String(_) => vec!["non_empty", "format", "regex", "equals"],
+        Value::Number(_) => vec!["greater_than_or_equal", "less_than_or_equal", "integer", "equals"],
+        Value::Array(_) => vec!["non_empty", "array_length", "unique"],
+        Value::Object(_) => vec!["required_keys", "non_empty"],
+        Value::Bool(_) | Value::Null => vec!["equals"],
+    }
+}
+
+/// A format the sample string is in, trying the most specific first
+fn detect_format(sample: &str) -> Option<&'static str> {
+    [
+        (StringFormat::Uuid, "uuid"),
+        (StringFormat::Email, "email"),
+        (StringFormat::Url, "url"),
+        (StringFormat::Ipv4, "ipv4"),
+        (StringFormat::Ipv6, "ipv6"),
+    ]
+    .into_iter()
+    .find(|(format, _)| format.matches(sample))
+    .map(|(_, name)| name)
+}
+
+fn parse_bound(answer: &str) -> Result<Option<usize>> {
+    match answer {
+        "" => Ok(None),
+        answer => answer.parse().map(Some).with_context(|| format!("Invalid length '{}'", answer)),
+    }
+}
+
+/// Type and a short preview of a sample value
+fn describe(value: &Value) -> String {
+    match value {
+        Value::Object(map) => format!("object ({} key{})", map.len(), if map.len() == 1 { "" } else { "s" }),
+        Value::Array(items) => format!("array ({} item{})", items.len(), if items.len() == 1 { "" } else { "s" }),
+        Value::String(s) if s.chars().count() > 30 => format!("string \"{}…\"", s.chars().take(30).collect::<String>()),
+        Value::String(s) => format!("string \"{}\"", s),
+        Value::Number(n) => format!("number {}", n),
+        Value::Bool(b) => format!("boolean {}", b),
+        Value::Null => "null".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_outline_lists_array_elements_once() {
+        let paths: Vec<String> = outline(&json!({"my key": 1, "users": [{"email": "a@b.io"}, {"email": "c@d.io"}]}))
+            .into_iter()
+            .map(|node| format!("{}{}", "  ".repeat(node.depth), node.path))
+            .collect();
+        assert_eq!(paths, ["$", "  $['my key']", "  $.users", "    $.users[*]", "      $.users[*].email"]);
+    }
+}
@@ -0,0 +1,136 @@
+//! jq filter selectors
+//!
+//! A rule with `selector: {"engine": "jq", "expr": "..."}` runs a jq filter
+//! over its document and checks the values the filter outputs, which allows
+//! reshaping data (`map`, `select`, `group_by`, ...) before checking it.
+//! Filters are evaluated by jaq behind the `jq` feature.
+
+use anyhow::Result;
+#[cfg(not(feature = "jq"))]
+use anyhow::bail;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Selector evaluated instead of a rule's `jsonpath`
+#[derive(Debug, Clone, PartialEq, Deserialize, JsonSchema, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Selector {
+    pub engine: SelectorEngine,
+    pub expr: String,
+}
+
+/// Language of a selector expression
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, JsonSchema, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SelectorEngine {
+    Jq,
+}
+
+/// Check that a jq filter compiles
+pub fn validate(expr: &str) -> Result<()> {
+    compile(expr).map(|_| ())
+}
+
+/// Run a jq filter over `input`, returning every value it outputs
+#[cfg(feature = "jq")]
+pub fn run(expr: &str, input: &Value) -> Result<Vec<Value>> {
+    use jaq_core::{Ctx, RcIter};
+    use jaq_json::Val;
+
+    let filter = compile(expr)?;
+    let inputs = RcIter::new(core::iter::empty());
+    filter
+        .run((Ctx::new([], &inputs), Val::from(input.clone())))
+        .map(|output| {
+            output
+                .map(Value::from)
+                .map_err(|e| anyhow::anyhow!("jq filter '{}' failed: {}", expr, e))
+        })
+        .collect()
+}
+
+#[cfg(not(feature = "jq"))]
+pub fn run(expr: &str, _input: &Value) -> Result<Vec<Value>> {
+    match compile(expr)? {}
+}
+
+/// Standard filters that reach past the document, to the environment, the
+/// process or further inputs; rules may only read their environment through
+/// `${NAME}` and `value_from_env`
+#[cfg(feature = "jq")]
+const EXCLUDED_FILTERS: [&str; 5] = ["env", "halt", "halt_error", "input", "inputs"];
+
+#[cfg(feature = "jq")]
+fn compile(expr: &str) -> Result<jaq_core::Filter<jaq_core::Native<jaq_json::Val>>> {
+    use anyhow::anyhow;
+    use jaq_core::load::{self, Arena, File, Loader};
+
+    // Column of a part of the expression an error points at
+    let column = |at: &str| (at.as_ptr() as usize).checked_sub(expr.as_ptr() as usize).map_or(1, |i| i + 1);
+
+    let program = File { code: expr, path: () };
+    let defs = jaq_std::defs().chain(jaq_json::defs()).filter(|def| !EXCLUDED_FILTERS.contains(&def.name));
+    let loader = Loader::new(defs);
+    let arena = Arena::default();
+    let modules = loader.load(&arena, program).map_err(|errors| {
+        let reasons: Vec<String> = errors
+            .into_iter()
+            .flat_map(|(_, error)| match error {
+                load::Error::Io(errors) => errors.into_iter().map(|(_, e)| e).collect(),
+                load::Error::Lex(errors) => errors
+                    .into_iter()
+                    .map(|(expected, at)| format!("expected {} at column {}", expected.as_str(), column(at)))
+                    .collect(),
+                load::Error::Parse(errors) => errors
+                    .into_iter()
+                    .map(|(expected, at)| format!("expected {} at column {}", expected.as_str(), column(at)))
+                    .collect::<Vec<_>>(),
+            })
+            .collect();
+        anyhow!("invalid jq filter '{}': {}", expr, reasons.join(", "))
+    })?;
+
+    jaq_core::Compiler::default()
+        .with_funs(jaq_std::funs().chain(jaq_json::funs()).filter(|(name, _, _)| !EXCLUDED_FILTERS.contains(name)))
+        .compile(modules)
+        .map_err(|errors| {
+            let reasons: Vec<String> = errors
+                .into_iter()
+                .flat_map(|(_, errors)| errors)
+                .map(|(name, undefined)| format!("undefined {} '{}'", undefined.as_str(), name))
+                .collect();
+            anyhow!("invalid jq filter '{}': {}", expr, reasons.join(", "))
+        })
+}
+
+#[cfg(not(feature = "jq"))]
+fn compile(expr: &str) -> Result<std::convert::Infallible> {
+    bail!("jq filter '{}' needs json-checker-rs built with the `jq` feature", expr)
+}
+
+#[cfg(all(test, feature = "jq"))]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_run_filter() {
+        let orders = json!({"orders": [{"id": 1, "qty": 2}, {"id": 2, "qty": 0}, {"id": 3, "qty": 5}]});
+        let output = run("[.orders[] | select(.qty > 1) | .id]", &orders).unwrap();
+        assert_eq!(output, vec![json!([1, 3])]);
+        assert_eq!(run(".orders | map(.qty) | add", &orders).unwrap(), vec![json!(7)]);
+
+        let err = validate(".orders | map(.qty").unwrap_err().to_string();
+        assert!(err.starts_with("invalid jq filter '.orders | map(.qty': expected closing parenthesis"), "{}", err);
+        assert!(validate("frobnicate").unwrap_err().to_string().contains("undefined filter 'frobnicate'"));
+    }
+
+    #[test]
+    fn test_filters_past_the_document_are_undefined() {
+        for expr in ["env", "$ENV", "halt", "halt_error", "halt_error(1)", "input", "inputs"] {
+            assert!(validate(expr).is_err(), "{}", expr);
+        }
+        assert!(validate("now | floor").is_ok());
+    }
+}
@@ -0,0 +1,308 @@
+//! Thin wrapper over the JSONPath engine
+//!
+//! Every JSONPath goes through [`compile`], which catches the common mistakes
+//! (missing `$`, unbalanced brackets, unterminated quotes) itself and points
+//! at them with a caret and a suggestion. Anything else is left to
+//! `jsonpath_lib`, whose error offset is translated into the same format.
+//!
+//! Paths are evaluated by `jsonpath_lib` unless a rule or the config picks
+//! the RFC 9535 engine (behind the `rfc9535` feature), which differs mainly
+//! in filter and slice handling.
+
+use anyhow::{anyhow, Result};
+use jsonpath_lib::Compiled;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt;
+
+/// JSONPath implementation a rule's paths are evaluated with
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Deserialize, JsonSchema, Serialize)]
+pub enum JsonPathEngine {
+    /// `jsonpath_lib`, the engine the checker has always used
+    #[default]
+    #[serde(rename = "jsonpath_lib")]
+    JsonpathLib,
+    /// Standard RFC 9535 semantics (requires the `rfc9535` feature)
+    #[serde(rename = "rfc9535")]
+    Rfc9535,
+}
+
+impl fmt::Display for JsonPathEngine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JsonPathEngine::JsonpathLib => write!(f, "jsonpath_lib"),
+            JsonPathEngine::Rfc9535 => write!(f, "rfc9535"),
+        }
+    }
+}
+
+/// A JSONPath that doesn't compile, with the position of the problem
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsonPathError {
+    pub path: String,
+    /// 1-based column (in characters) the problem was found at
+    pub column: usize,
+    pub message: String,
+    /// How to fix the path, when there is an obvious fix
+    pub help: Option<String>,
+}
+
+impl fmt::Display for JsonPathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid JSONPath '{}': {} at column {}", self.path, self.message, self.column)?;
+        write!(f, "\n    {}\n    {}^", self.path, " ".repeat(self.column - 1))?;
+        if let Some(help) = &self.help {
+            write!(f, "\n    help: {}", help)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for JsonPathError {}
+
+/// Compile a JSONPath for `jsonpath_lib`, explaining where and why it is invalid
+pub fn compile(path: &str) -> Result<Compiled, JsonPathError> {
+    check_syntax(path, JsonPathEngine::JsonpathLib)?;
+    Compiled::compile(path).map_err(|e| {
+        // jsonpath_lib underlines the path up to where parsing failed
+        let parsed = e.lines().nth(1).map_or(0, |carets| carets.chars().filter(|&c| c == '^').count());
+        error(path, parsed.min(path.chars().count().saturating_sub(1)), "unexpected token", None)
+    })
+}
+
+/// A JSONPath compiled by one of the engines, reusable across documents
+pub enum CompiledPath {
+    JsonpathLib(Compiled),
+    #[cfg(feature = "rfc9535")]
+    Rfc9535(serde_json_path::JsonPath),
+}
+
+impl CompiledPath {
+    /// Select the nodes this path matches in `json`
+    pub fn select<'a>(&self, json: &'a Value) -> Result<Vec<&'a Value>> {
+        match self {
+            CompiledPath::JsonpathLib(compiled) => {
+                compiled.select(json).map_err(|e| anyhow!("JSONPath query failed: {:?}", e))
+            }
+            #[cfg(feature = "rfc9535")]
+            CompiledPath::Rfc9535(path) => Ok(path.query(json).all()),
+        }
+    }
+}
+
+/// Compile `path` with `engine`, explaining where and why it is invalid
+pub fn compile_with(path: &str, engine: JsonPathEngine) -> Result<CompiledPath, JsonPathError> {
+    match engine {
+        JsonPathEngine::JsonpathLib => compile(path).map(CompiledPath::JsonpathLib),
+        JsonPathEngine::Rfc9535 => rfc9535::parse(path),
+    }
+}
+
+/// Check that `path` compiles with `engine`
+pub fn validate(path: &str, engine: JsonPathEngine) -> Result<(), JsonPathError> {
+    compile_with(path, engine).map(|_| ())
+}
+
+/// Select the nodes `path` matches in `json` with `jsonpath_lib`
+pub fn select<'a>(json: &'a Value, path: &str) -> Result<Vec<&'a Value>> {
+    compile(path)?
+        .select(json)
+        .map_err(|e| anyhow!("JSONPath query failed: {}: {:?}", path, e))
+}
+
+/// Select the nodes `path` matches in `json` with `engine`
+pub fn select_with<'a>(json: &'a Value, path: &str, engine: JsonPathEngine) -> Result<Vec<&'a Value>> {
+    match engine {
+        JsonPathEngine::JsonpathLib => select(json, path),
+        JsonPathEngine::Rfc9535 => compile_with(path, engine)?.select(json),
+    }
+}
+
+#[cfg(feature = "rfc9535")]
+mod rfc9535 {
+    use super::*;
+    use serde_json_path::JsonPath;
+
+    pub fn parse(path: &str) -> Result<CompiledPath, JsonPathError> {
+        check_syntax(path, JsonPathEngine::Rfc9535)?;
+        JsonPath::parse(path).map(CompiledPath::Rfc9535).map_err(|e| {
+            // The parser reports a byte offset; columns count characters
+            let index = path.get(..e.position()).map_or(0, |parsed| parsed.chars().count());
+            error(path, index.min(path.chars().count().saturating_sub(1)), e.message(), None)
+        })
+    }
+}
+
+#[cfg(not(feature = "rfc9535"))]
+mod rfc9535 {
+    use super::*;
+
+    pub fn parse(path: &str) -> Result<CompiledPath, JsonPathError> {
+        check_syntax(path, JsonPathEngine::Rfc9535)?;
+        Err(error(
+            path,
+            0,
+            "the rfc9535 engine is not available",
+            Some("build json-checker-rs with the `rfc9535` feature".to_string()),
+        ))
+    }
+}
+
+fn error(path: &str, index: usize, message: &str, help: Option<String>) -> JsonPathError {
+    JsonPathError {
+        path: path.to_string(),
+        column: index + 1,
+        message: message.to_string(),
+        help,
+    }
+}
+
+/// Catch the mistakes `jsonpath_lib` either reports opaquely or silently accepts
+fn check_syntax(path: &str, engine: JsonPathEngine) -> Result<(), JsonPathError> {
+    if path.trim().is_empty() {
+        return Err(error(path, 0, "JSONPath is empty", Some("use '$' to select the whole document".to_string())));
+    }
+    if !path.starts_with('$') {
+        let fixed = if path.starts_with(['.', '[']) { format!("${}", path) } else { format!("$.{}", path) };
+        return Err(error(path, 0, "JSONPath must start with '$'", Some(format!("try '{}'", fixed))));
+    }
+
+    let mut open: Vec<(usize, char)> = Vec::new();
+    let mut quote: Option<(usize, char)> = None;
+    let mut escaped = false;
+    for (i, c) in path.chars().enumerate() {
+        if let Some((_, q)) = quote {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                _ if c == q => quote = None,
+                _ => {}
+            }
+            continue;
+        }
+
+        match c {
+            '\'' | '"' => quote = Some((i, c)),
+            '[' | '(' => open.push((i, c)),
+            ']' | ')' => {
+                let expected = if c == ']' { '[' } else { '(' };
+                match open.pop() {
+                    Some((_, opener)) if opener == expected => {}
+                    Some((at, opener)) => {
+                        return Err(error(
+                            path,
+                            i,
+                            &format!("'{}' at column {} is closed by '{}'", opener, at + 1, c),
+                            Some(format!("add the missing '{}'", closer(opener))),
+                        ));
+                    }
+                    None => {
+                        return Err(error(
+                            path,
+                            i,
+                            &format!("unexpected '{}'", c),
+                            Some(format!("remove it or add the missing '{}'", expected)),
+                        ));
+                    }
+                }
+            }
+            // RFC 9535 allows blank space between segments
+            c if c.is_whitespace() && open.is_empty() && engine == JsonPathEngine::JsonpathLib => {
+                return Err(error(
+                    path,
+                    i,
+                    "unexpected whitespace",
+                    Some("quote keys containing spaces, e.g. $['first name']".to_string()),
+                ));
+            }
+            _ => {}
+        }
+    }
+
+    let last = path.chars().count() - 1;
+    if let Some((at, q)) = quote {
+        return Err(error(path, at, "unterminated string", Some(format!("add the closing {}", q))));
+    }
+    if let Some(&(at, opener)) = open.last() {
+        return Err(error(
+            path,
+            at,
+            &format!("unclosed '{}'", opener),
+            Some(format!("add the missing '{}'", closer(opener))),
+        ));
+    }
+    if path.ends_with("..") {
+        return Err(error(path, last, "expected a key after '..'", Some("use '..*' to select every descendant".to_string())));
+    }
+    if path.ends_with('.') {
+        return Err(error(path, last, "expected a key after '.'", Some(format!("remove the trailing '.' or use '{}*'", path))));
+    }
+
+    Ok(())
+}
+
+fn closer(opener: char) -> char {
+    if opener == '[' { ']' } else { ')' }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_diagnostics() {
+        let err = compile("users[0]").unwrap_err();
+        assert_eq!(err.column, 1);
+        assert_eq!(err.help.as_deref(), Some("try '$.users[0]'"));
+
+        let err = compile("$.users[0").unwrap_err();
+        assert_eq!((err.column, err.message.as_str()), (8, "unclosed '['"));
+        assert_eq!(
+            err.to_string(),
+            "invalid JSONPath '$.users[0': unclosed '[' at column 8\n    $.users[0\n           ^\n    help: add the missing ']'"
+        );
+
+        assert_eq!(compile("$.a]").unwrap_err().message, "unexpected ']'");
+        assert_eq!(compile("$.a[?(@.b == 1]").unwrap_err().message, "'(' at column 6 is closed by ']'");
+        assert_eq!(compile("$['a").unwrap_err().message, "unterminated string");
+        assert_eq!(compile("$.a b").unwrap_err().column, 4);
+        assert_eq!(compile("$.a.").unwrap_err().column, 4);
+
+        // Left to jsonpath_lib, which reports where parsing stopped
+        assert_eq!(compile("$.a[1:2:x]").unwrap_err().column, 9);
+    }
+
+    #[test]
+    fn test_select() {
+        let json = json!({"users": [{"name": "a b", "age": 3}, {"name": "c]", "age": 5}]});
+        assert_eq!(select(&json, "$.users[?(@.age > 4)].name").unwrap(), vec![&json!("c]")]);
+        assert_eq!(select(&json, "$.users[?(@.name == 'a b')].age").unwrap(), vec![&json!(3)]);
+        assert!(select(&json, "$.users[").is_err());
+    }
+
+    #[cfg(feature = "rfc9535")]
+    #[test]
+    fn test_rfc9535_engine() {
+        let json = json!({"a": {"b": 1, "c": {"b": 1}}, "list": [1, 2, 3]});
+        let rfc = |path| select_with(&json, path, JsonPathEngine::Rfc9535).unwrap();
+
+        // Filters apply to the members of an object, not the object itself
+        assert_eq!(rfc("$.a[?@.b == 1]"), vec![&json!({"b": 1})]);
+        assert_eq!(select(&json, "$.a[?(@.b == 1)]").unwrap(), vec![&json["a"]]);
+        assert_eq!(rfc("$.list[?@ > 1]"), vec![&json!(2), &json!(3)]);
+        assert_eq!(rfc("$.list[::-1]"), vec![&json!(3), &json!(2), &json!(1)]);
+        assert!(select(&json, "$.list[::-1]").is_err());
+
+        let err = validate("$.list[?@ >]", JsonPathEngine::Rfc9535).unwrap_err();
+        assert!(err.column > 1);
+    }
+
+    #[cfg(not(feature = "rfc9535"))]
+    #[test]
+    fn test_rfc9535_needs_feature() {
+        let err = validate("$.list[0]", JsonPathEngine::Rfc9535).unwrap_err();
+        assert_eq!(err.help.as_deref(), Some("build json-checker-rs with the `rfc9535` feature"));
+    }
+}
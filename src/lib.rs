@@ -1,21 +1,80 @@
-//! JSON Checker Library
-//! 
-//! A library for validating JSON data against configurable rules using JSONPath queries.
-
-pub mod config;
-pub mod checker;
-pub mod rules;
-pub mod cli;
-pub mod path_finder;
-pub mod json_path_finder;
-
-#[cfg(test)]
-mod tests;
-
-pub use config::{Config, Rule, CheckResult};
-pub use checker::JsonChecker;
-pub use rules::CheckRule;
-
-/// Re-export commonly used types
-pub use anyhow::{Context, Result};
+//! JSON Checker Library
+//! 
+//! A library for validating JSON data against configurable rules using JSONPath queries.
+
+pub mod api;
+pub mod config;
+pub mod baseline;
+pub mod builder;
+pub mod canonicalize;
+pub mod capture;
+pub mod diff;
+pub mod error;
+pub mod checker;
+pub mod command;
+mod compiled;
+pub mod consume;
+pub mod evaluate;
+pub mod explain;
+pub mod explore;
+pub mod expr;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod fix;
+pub mod format;
+pub mod history;
+pub mod html;
+pub mod init;
+pub mod jq;
+pub mod lint;
+#[cfg(feature = "lsp")]
+pub mod lsp;
+pub mod rules;
+pub mod sarif;
+pub mod scan;
+pub mod schema;
+pub mod schedule;
+pub mod serve;
+pub mod templates;
+pub mod transform;
+pub mod cli;
+pub mod patch;
+pub mod path_finder;
+pub mod protobuf;
+pub mod reporter;
+pub mod response;
+pub mod retry;
+#[deprecated(note = "use the path_finder module")]
+pub mod json_path_finder;
+pub mod jsonpath;
+pub mod loader;
+pub mod markdown;
+pub mod messages;
+pub mod number;
+pub mod parser;
+pub mod locate;
+pub mod query;
+pub mod source;
+pub mod stream;
+pub mod suggest;
+pub mod suite;
+pub mod validate;
+pub mod vars;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "watch")]
+pub mod watch;
+
+#[cfg(test)]
+mod tests;
+
+pub use config::{Config, Rule, CheckResult, Severity, Status};
+pub use checker::JsonChecker;
+pub use error::CheckerError;
+pub use evaluate::{evaluate, RuleOutcome};
+pub use reporter::Reporter;
+pub use rules::CheckRule;
+
+/// Re-export commonly used types
+pub use anyhow::{Context, Result};
 pub use serde_json::Value;
\ No newline at end of file
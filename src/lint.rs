@@ -0,0 +1,264 @@
+//! Lint mode: check the files given on the command line
+//!
+//! `lint` runs each rule whose `applies_to` pattern matches a file against
+//! that file, and reports every failure as a `file:line:col: severity:
+//! message` diagnostic, the format pre-commit hooks and editors parse.
+//! Files no rule applies to are skipped, so a hook can pass every staged
+//! file. Disabled rules, and rules whose `depends_on` or `run_if` don't hold
+//! for the file, are skipped as in a run and report nothing.
+
+use regex::Regex;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::LazyLock;
+
+use crate::checker::{self, JsonChecker};
+use crate::config::{CheckResult, Rule, Severity};
+use crate::error::CheckerError;
+use crate::format::DocumentFormat;
+use crate::locate::{self, SourceLocation};
+use crate::parser;
+use crate::sarif::split_line;
+use crate::scan::{self, ScannedFile};
+use crate::schedule;
+
+/// Where serde_json reports a syntax error
+static PARSE_ERROR_AT: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"at line (\d+) column (\d+)").unwrap());
+
+/// One problem found in a file
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub file: String,
+    /// Where the failing node starts; the top of the file when unknown
+    pub location: Option<SourceLocation>,
+    pub severity: Severity,
+    pub rule: String,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let SourceLocation { line, column } = self.location.unwrap_or(SourceLocation { line: 1, column: 1 });
+        let severity = match self.severity {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        write!(f, "{}:{}:{}: {}: {} [{}]", self.file, line, column, severity, self.message, self.rule)
+    }
+}
+
+/// Check each file with the rules that apply to it, returning the
+/// diagnostics in file order
+pub fn lint(checker: &JsonChecker, files: &[String]) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    for file in files {
+        let rules = rules_for(checker, file);
+        if rules.is_empty() {
+            continue;
+        }
+        match std::fs::read_to_string(file) {
+            Ok(text) => diagnostics.extend(lint_text(checker, file, &text)),
+            Err(e) => diagnostics.extend(rules.into_iter().map(|rule| Diagnostic {
+                file: file.clone(),
+                location: None,
+                severity: rule.severity,
+                rule: rule.name.clone(),
+                message: format!("Failed to read {}: {}", file, e),
+            })),
+        }
+    }
+    diagnostics
+}
+
+/// Check `text` as the content of `file`, which may differ from what is on
+/// disk, e.g. an editor's unsaved buffer.
+///
+/// Rules run after their prerequisites, whose results on this file decide
+/// whether they are skipped; a prerequisite that doesn't apply to the file
+/// didn't pass on it.
+pub fn lint_text(checker: &JsonChecker, file: &str, text: &str) -> Vec<Diagnostic> {
+    let rules = &checker.config().rules;
+    let applies: Vec<&str> = rules_for(checker, file).iter().map(|rule| rule.name.as_str()).collect();
+    // Configs with cycles fail validation; lint them in config order meanwhile
+    let order = schedule::execution_order(rules).unwrap_or_else(|_| (0..rules.len()).collect());
+    let mut results: HashMap<&str, CheckResult> = HashMap::new();
+    let mut diagnostics = Vec::new();
+
+    for original in order.into_iter().map(|i| &rules[i]).filter(|rule| applies.contains(&rule.name.as_str())) {
+        if let Some(reason) = schedule::skip_reason(original, |name| results.get(name)) {
+            results.insert(&original.name, CheckResult::skipped(original.name.clone(), &reason));
+            continue;
+        }
+
+        let rule = Rule { json_file: Some(file.to_string()), applies_to: None, ..original.clone() };
+        let format = rule.format.unwrap_or_else(|| DocumentFormat::detect(file));
+        let options = checker.config().parser_for(&rule).for_format(format);
+        let outcome = parser::parse(text, format, rule.csv.as_ref(), options)
+            .map_err(CheckerError::from)
+            .and_then(|document| {
+                if let Some(reason) = checker.unmet_condition(&rule, &document.value)? {
+                    return Ok(CheckResult::skipped(rule.name.clone(), &reason));
+                }
+                let mut result = checker.check_document(&rule, &document.value)?;
+                checker::report_duplicate_keys(&rule, &mut result, &document);
+                Ok(result)
+            });
+        let result = match outcome {
+            Ok(mut result) => {
+                if format.is_json()
+                    && rule.selector.is_none()
+                    && let Ok(locations) = locate::locate(&options.source_text(text), &result.invalid_positions)
+                {
+                    result.locations = locations;
+                }
+                diagnostics.extend(diagnose(file, &rule, &result));
+                result
+            }
+            Err(e) => {
+                let message = format!("{:#}", anyhow::Error::from(e));
+                // A diagnostic is one line, so leave out the quoted source of a syntax error
+                let message = message.lines().next().unwrap_or_default().to_string();
+                diagnostics.push(Diagnostic {
+                    file: file.to_string(),
+                    location: parse_error_location(&message),
+                    severity: rule.severity,
+                    rule: rule.name.clone(),
+                    message: message.clone(),
+                });
+                CheckResult::error(rule.name.clone(), message)
+            }
+        };
+        results.insert(&original.name, result);
+    }
+    diagnostics
+}
+
+/// The enabled rules whose `applies_to` pattern matches `file`. Patterns
+/// with a `/` match its path under the config's scan root, or as given
+/// outside of it.
+pub fn rules_for<'a>(checker: &'a JsonChecker, file: &str) -> Vec<&'a Rule> {
+    let path = file.replace('\\', "/");
+    let path = path.strip_prefix("./").unwrap_or(&path);
+    let relative = checker
+        .config()
+        .scan
+        .as_ref()
+        .and_then(|scan| {
+            // Editors pass absolute paths, while the root is usually relative
+            let root = std::path::absolute(&scan.root).ok()?;
+            let relative = std::path::absolute(path).ok()?.strip_prefix(root).ok()?.to_string_lossy().replace('\\', "/");
+            Some(relative)
+        })
+        .unwrap_or_else(|| path.to_string());
+    let scanned = ScannedFile { relative, path: file.to_string() };
+
+    checker
+        .config()
+        .rules
+        .iter()
+        .filter(|rule| rule.disabled_reason().is_none())
+        .filter(|rule| rule.applies_to.as_deref().is_some_and(|pattern| scan::applies_to(pattern, &scanned)))
+        .collect()
+}
+
+/// One diagnostic per failing node of a failed result, or one for the
+/// whole file when the failure has no nodes
+fn diagnose(file: &str, rule: &Rule, result: &CheckResult) -> Vec<Diagnostic> {
+    if result.passed {
+        return Vec::new();
+    }
+    let diagnostic = |location, message| Diagnostic {
+        file: file.to_string(),
+        location,
+        severity: rule.severity,
+        rule: rule.name.clone(),
+        message,
+    };
+    let headline = result.message.lines().next().unwrap_or_default();
+    // Drop the ✗ the terminal output starts failures with
+    let headline = headline.trim_start_matches(|c: char| !c.is_alphanumeric() && c != '\'');
+    if result.invalid_positions.is_empty() {
+        let location = parse_error_location(&result.message);
+        return vec![diagnostic(location, headline.to_string())];
+    }
+
+    result
+        .invalid_positions
+        .iter()
+        .enumerate()
+        .map(|(i, position)| {
+            let (line, path) = split_line(position);
+            let location = result.location(i).or(line.map(|line| SourceLocation { line, column: 1 }));
+            let message = match result.values_found.get(i) {
+                Some(value) => format!("{}: {} = {}", headline, path, value),
+                None => format!("{}: {}", headline, path),
+            };
+            diagnostic(location, message)
+        })
+        .collect()
+}
+
+fn parse_error_location(message: &str) -> Option<SourceLocation> {
+    let captures = PARSE_ERROR_AT.captures(message)?;
+    Some(SourceLocation { line: captures[1].parse().ok()?, column: captures[2].parse().ok()? })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    #[test]
+    fn test_lint() {
+        let dir = std::env::temp_dir().join(format!("json-checker-rs-lint-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let valid = dir.join("app.settings.json");
+        let invalid = dir.join("web.settings.json");
+        let broken = dir.join("broken.settings.json");
+        std::fs::write(&valid, "{\"port\": 80}").unwrap();
+        std::fs::write(&invalid, "{\n  \"port\": -1\n}").unwrap();
+        std::fs::write(&broken, "{\n  \"port\": }").unwrap();
+
+        let config = Config::from_json(
+            r#"{"rules": [{"name": "Port", "applies_to": "*.settings.json", "jsonpath": "$.port", "check": {"type": "greater_than", "value": 0}}]}"#,
+        )
+        .unwrap();
+        let checker = JsonChecker::from_config(config);
+        let files: Vec<String> = [&valid, &invalid, &broken, &dir.join("other.json")]
+            .iter()
+            .map(|path| path.to_string_lossy().into_owned())
+            .collect();
+        let diagnostics = lint(&checker, &files);
+
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(diagnostics[0].location, Some(SourceLocation { line: 2, column: 11 }));
+        assert_eq!(
+            diagnostics[0].to_string(),
+            format!("{}:2:11: error: Rule 'Port' failed at JSONPath '$.port': $.port = -1 [Port]", files[1])
+        );
+        assert_eq!(diagnostics[1].file, files[2]);
+        assert_eq!(diagnostics[1].location, Some(SourceLocation { line: 2, column: 11 }));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_lint_skips_rules_that_dont_run() {
+        let config = Config::from_json(
+            r#"{"rules": [
+                {"name": "Port", "applies_to": "*.json", "jsonpath": "$.port", "check": {"type": "greater_than", "value": 0}},
+                {"name": "Off", "applies_to": "*.json", "enabled": false, "jsonpath": "$.port", "check": {"type": "equals", "value": 1}},
+                {"name": "High port", "applies_to": "*.json", "depends_on": ["Port"], "jsonpath": "$.port", "check": {"type": "greater_than", "value": 1024}},
+                {"name": "After failure", "applies_to": "*.json", "run_if": {"failed": "Port"}, "jsonpath": "$.port", "check": {"type": "equals", "value": 80}},
+                {"name": "Has TLS", "applies_to": "*.json", "run_if": {"path_exists": "$.tls"}, "jsonpath": "$.tls.cert", "check": {"type": "non_empty"}}
+            ]}"#,
+        )
+        .unwrap();
+        let checker = JsonChecker::from_config(config);
+        assert!(rules_for(&checker, "app.json").iter().all(|rule| rule.name != "Off"));
+
+        let rules = |text: &str| -> Vec<String> { lint_text(&checker, "app.json", text).into_iter().map(|d| d.rule).collect() };
+        assert_eq!(rules(r#"{"port": -1}"#), ["Port", "After failure"]);
+        assert_eq!(rules(r#"{"port": 80}"#), ["High port"]);
+        assert_eq!(rules(r#"{"port": 8443, "tls": {}}"#), ["Has TLS"]);
+    }
+}
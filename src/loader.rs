@@ -0,0 +1,249 @@
+//! Document loading for rules
+//!
+//! With the `async` feature enabled, documents can also be loaded through an
+//! [`AsyncDocumentLoader`], so embedders fetching documents over the network
+//! don't block their runtime.
+
+use anyhow::{bail, Result};
+use serde_json::Value;
+
+use crate::config::Rule;
+use crate::query::QuerySource;
+
+/// Where the document of a rule comes from
+#[derive(Debug, Clone, Copy)]
+pub enum DocumentRef<'a> {
+    /// Document URI from the rule's `json_file`
+    File(&'a str),
+    /// Document from the rule's `json_inline`
+    Inline(&'a Value),
+    /// Rows returned by the rule's `source` query
+    Query(&'a QuerySource),
+}
+
+impl<'a> DocumentRef<'a> {
+    /// Get the document reference of a rule, which must set exactly one of
+    /// `json_file`, `json_inline` and `source`
+    pub fn of(rule: &'a Rule) -> Result<Self> {
+        match (&rule.json_file, &rule.json_inline, &rule.source) {
+            (Some(_), Some(_), _) => {
+                bail!("Rule '{}' sets both json_file and json_inline", rule.name)
+            }
+            (Some(_), None, Some(_)) | (None, Some(_), Some(_)) => {
+                bail!("Rule '{}' sets source as well as json_file or json_inline", rule.name)
+            }
+            (None, None, None) => {
+                bail!("Rule '{}' needs json_file, json_inline or source", rule.name)
+            }
+            (Some(json_file), None, None) => Ok(DocumentRef::File(json_file)),
+            (None, Some(json), None) => Ok(DocumentRef::Inline(json)),
+            (None, None, Some(query)) => Ok(DocumentRef::Query(query)),
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+pub use self::async_loader::*;
+
+#[cfg(feature = "async")]
+mod async_loader {
+    use anyhow::{anyhow, Context, Result};
+    use async_trait::async_trait;
+    use serde_json::Value;
+    use std::sync::Arc;
+
+    use super::DocumentRef;
+    use crate::config::Rule;
+    use crate::error::CheckerError;
+    use crate::format::{Document, DocumentFormat};
+    use crate::parser::{self, ParserOptions};
+    use crate::source::local_path;
+
+    /// Asynchronously loads the JSON document a rule applies to
+    #[async_trait]
+    pub trait AsyncDocumentLoader: Send + Sync {
+        /// Load the document, parsing text with `options`, the rule's
+        /// parser options as the config resolves them
+        async fn load(&self, rule: &Rule, options: ParserOptions) -> Result<Document>;
+    }
+
+    /// Default loader reading local `json_file` paths with `tokio::fs` (or using `json_inline`)
+    ///
+    /// Files are parsed according to the rule's `format` and parser options;
+    /// `source` queries run on tokio's blocking thread pool.
+    pub struct FileLoader;
+
+    #[async_trait]
+    impl AsyncDocumentLoader for FileLoader {
+        async fn load(&self, rule: &Rule, options: ParserOptions) -> Result<Document> {
+            match DocumentRef::of(rule)? {
+                DocumentRef::File(uri) => {
+                    let json_file = local_path(uri)
+                        .ok_or_else(|| anyhow!("FileLoader only reads local files: {}", uri))?;
+                    let json_content = tokio::fs::read_to_string(json_file)
+                        .await
+                        .map_err(|e| CheckerError::read(json_file, e))?;
+                    let format = rule.format.unwrap_or_else(|| DocumentFormat::detect(uri));
+
+                    parser::parse(&json_content, format, rule.csv.as_ref(), options)
+                }
+                DocumentRef::Inline(json) => Ok(Document::new(json.clone())),
+                DocumentRef::Query(query) => {
+                    let query = query.clone();
+                    tokio::task::spawn_blocking(move || crate::query::fetch(&query))
+                        .await
+                        .context("Query source panicked")?
+                        .map(Document::new)
+                }
+            }
+        }
+    }
+
+    /// Adapter running a synchronous loader on tokio's blocking thread pool;
+    /// the loader returns the document's value, so parser options don't apply
+    pub struct BlockingLoader<F> {
+        load: Arc<F>,
+    }
+
+    impl<F> BlockingLoader<F>
+    where
+        F: Fn(&Rule) -> Result<Value> + Send + Sync + 'static,
+    {
+        pub fn new(load: F) -> Self {
+            BlockingLoader { load: Arc::new(load) }
+        }
+    }
+
+    #[async_trait]
+    impl<F> AsyncDocumentLoader for BlockingLoader<F>
+    where
+        F: Fn(&Rule) -> Result<Value> + Send + Sync + 'static,
+    {
+        async fn load(&self, rule: &Rule, _options: ParserOptions) -> Result<Document> {
+            let load = Arc::clone(&self.load);
+            let rule = rule.clone();
+
+            tokio::task::spawn_blocking(move || load(&rule))
+                .await
+                .context("Blocking document loader panicked")?
+                .map(Document::new)
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::checker::JsonChecker;
+        use crate::config::Config;
+        use crate::rules::CheckRule;
+
+        fn block_on<F: std::future::Future>(future: F) -> F::Output {
+            tokio::runtime::Builder::new_current_thread()
+                .enable_time()
+                .build()
+                .unwrap()
+                .block_on(future)
+        }
+
+        #[test]
+        fn test_run_async_with_blocking_loader() {
+            let checker = JsonChecker::from_config(Config {
+                rules: vec![Rule {
+                    name: "Remote status".to_string(),
+                    json_file: Some("https://example.com/status.json".to_string()),
+                    jsonpath: "$.status".to_string(),
+                    check: CheckRule::Equals { value: serde_json::json!("ok"), tolerance: None },
+                    ..Default::default()
+                }],
+                ..Default::default()
+            });
+
+            let loader = BlockingLoader::new(|_: &Rule| Ok(serde_json::json!({"status": "ok"})));
+            let results = block_on(checker.run_async_with(&loader)).unwrap();
+            assert!(results[0].passed);
+
+            // The default loader only reads local files
+            let results = block_on(checker.run_async()).unwrap();
+            assert!(!results[0].passed);
+        }
+
+        #[test]
+        fn test_run_async_checks_like_run() {
+            use crate::parser::ParserOptions;
+            use crate::retry::RetryPolicy;
+            use std::sync::atomic::{AtomicU32, Ordering};
+
+            let dir = std::env::temp_dir().join(format!("json-checker-async-check-{}", std::process::id()));
+            std::fs::create_dir_all(&dir).unwrap();
+            std::fs::write(dir.join("user.json"), "{\"id\": 1,\n \"id\": 2}\n").unwrap();
+            let rule = |name: &str, file: &str| Rule {
+                name: name.to_string(),
+                json_file: Some(dir.join(file).to_string_lossy().into_owned()),
+                jsonpath: "$.id".to_string(),
+                check: CheckRule::Equals { value: serde_json::json!(2), tolerance: None },
+                ..Default::default()
+            };
+            let checker = JsonChecker::from_config(Config {
+                parser: Some(ParserOptions { detect_duplicate_keys: true, ..Default::default() }),
+                rules: vec![rule("Duplicates", "user.json"), rule("Every user", "*.json")],
+                ..Default::default()
+            });
+
+            // Duplicate keys are reported with their location, as by `run`
+            let results = block_on(checker.run_async()).unwrap();
+            assert!(!results[0].passed);
+            assert_eq!(results[0].invalid_positions, vec!["$.id"]);
+            assert!(results[0].message.contains("1 object key(s) appear more than once"));
+            assert_eq!(results[0].locations[0].map(|location| location.line), Some(2));
+
+            // Rules checking several files aren't silently checked against one
+            assert!(!results[1].passed);
+            assert!(results[1].message.contains("uses a glob json_file, which run_async doesn't support"), "{}", results[1].message);
+
+            // Loads are retried according to the rule's policy
+            let loads = Arc::new(AtomicU32::new(0));
+            let counter = Arc::clone(&loads);
+            let loader = BlockingLoader::new(move |_: &Rule| match counter.fetch_add(1, Ordering::SeqCst) {
+                0 => anyhow::bail!("connection reset"),
+                _ => Ok(serde_json::json!({"id": 2})),
+            });
+            let retried = Rule { retry: Some(RetryPolicy { attempts: 2, backoff_ms: 1, timeout_ms: None }), ..rule("Retried", "user.json") };
+            let checker = JsonChecker::from_config(Config { rules: vec![retried], ..Default::default() });
+            assert!(block_on(checker.run_async_with(&loader)).unwrap()[0].passed);
+            assert_eq!(loads.load(Ordering::SeqCst), 2);
+            let _ = std::fs::remove_dir_all(&dir);
+        }
+
+        #[test]
+        fn test_file_loader_uses_parser_options() {
+            use crate::parser::ParserOptions;
+
+            let dir = std::env::temp_dir().join(format!("json-checker-async-parser-{}", std::process::id()));
+            std::fs::create_dir_all(&dir).unwrap();
+            let file = dir.join("settings.json");
+            std::fs::write(&file, "// written by hand\n{\"port\": 8080}\n").unwrap();
+            let checker = |parser: Option<ParserOptions>| {
+                JsonChecker::from_config(Config {
+                    parser,
+                    rules: vec![Rule {
+                        name: "Port".to_string(),
+                        json_file: Some(file.to_string_lossy().into_owned()),
+                        jsonpath: "$.port".to_string(),
+                        check: CheckRule::Equals { value: serde_json::json!(8080), tolerance: None },
+                        ..Default::default()
+                    }],
+                    ..Default::default()
+                })
+            };
+
+            // Async and sync runs agree either way
+            let strict = checker(None);
+            assert!(!block_on(strict.run_async()).unwrap()[0].passed);
+            assert!(!strict.run().unwrap()[0].passed);
+            let comments = checker(Some(ParserOptions { allow_comments: true, ..Default::default() }));
+            assert!(block_on(comments.run_async()).unwrap()[0].passed);
+            assert!(comments.run().unwrap()[0].passed);
+            let _ = std::fs::remove_dir_all(&dir);
+        }
+    }
+}
@@ -0,0 +1,210 @@
+//! Source positions of JSON nodes
+//!
+//! `serde_json::Value` keeps no spans, so failing nodes are re-located by
+//! scanning the document text and tracking the canonical path (as produced by
+//! `path_finder::canonical_paths`) of every value.
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::path_finder::{push_index, push_key};
+
+/// 1-based line and column (in characters) where a JSON value starts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct SourceLocation {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl fmt::Display for SourceLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}, column {}", self.line, self.column)
+    }
+}
+
+/// Byte range of a JSON value in its text, with the offset its member starts
+/// at: the key of an object member, or the value itself otherwise
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Span {
+    pub member: usize,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Find where the values at the given canonical paths start in a JSON text
+///
+/// Returns one entry per path, `None` for paths that don't exist in the document.
+pub fn locate(text: &str, paths: &[String]) -> Result<Vec<Option<SourceLocation>>> {
+    let spans = spans(text, paths)?;
+    let line_starts: Vec<usize> = std::iter::once(0)
+        .chain(text.match_indices('\n').map(|(i, _)| i + 1))
+        .collect();
+    let to_location = |offset: usize| {
+        let line = line_starts.partition_point(|&start| start <= offset);
+        let start = line_starts[line - 1];
+        SourceLocation {
+            line,
+            column: text[start..offset].chars().count() + 1,
+        }
+    };
+
+    Ok(spans.into_iter().map(|span| span.map(|span| to_location(span.start))).collect())
+}
+
+/// Find the spans of the values at the given canonical paths in a JSON text,
+/// `None` for paths that don't exist in the document
+pub(crate) fn spans(text: &str, paths: &[String]) -> Result<Vec<Option<Span>>> {
+    let mut scanner = Scanner {
+        text,
+        pos: 0,
+        wanted: paths.iter().map(|p| (p.as_str(), None)).collect(),
+    };
+    scanner.value(&mut "$".to_string(), None)?;
+
+    Ok(paths.iter().map(|path| scanner.wanted.get(path.as_str()).copied().flatten()).collect())
+}
+
+struct Scanner<'a> {
+    text: &'a str,
+    pos: usize,
+    /// Span of each wanted path, once seen
+    wanted: HashMap<&'a str, Option<Span>>,
+}
+
+impl Scanner<'_> {
+    /// Read the value at `path`, whose member starts at `member` if it is
+    /// an object member
+    fn value(&mut self, path: &mut String, member: Option<usize>) -> Result<()> {
+        self.skip_whitespace();
+        let start = self.pos;
+        self.node(path)?;
+        if let Some(span) = self.wanted.get_mut(path.as_str()) {
+            // Like serde_json, the last of duplicate keys wins
+            *span = Some(Span { member: member.unwrap_or(start), start, end: self.pos });
+        }
+        Ok(())
+    }
+
+    fn node(&mut self, path: &mut String) -> Result<()> {
+        let len = path.len();
+        match self.peek() {
+            Some(b'{') => {
+                self.pos += 1;
+                if self.close(b'}') {
+                    return Ok(());
+                }
+                loop {
+                    self.skip_whitespace();
+                    let member = self.pos;
+                    let key = self.string()?;
+                    self.expect(b':')?;
+                    push_key(path, &key);
+                    self.value(path, Some(member))?;
+                    path.truncate(len);
+                    if self.close(b'}') {
+                        return Ok(());
+                    }
+                    self.expect(b',')?;
+                }
+            }
+            Some(b'[') => {
+                self.pos += 1;
+                if self.close(b']') {
+                    return Ok(());
+                }
+                for index in 0.. {
+                    push_index(path, index);
+                    self.value(path, None)?;
+                    path.truncate(len);
+                    if self.close(b']') {
+                        break;
+                    }
+                    self.expect(b',')?;
+                }
+                Ok(())
+            }
+            Some(b'"') => self.string().map(|_| ()),
+            Some(_) => {
+                // Numbers, booleans and null
+                let rest = &self.text[self.pos..];
+                let end = rest
+                    .find(|c: char| matches!(c, ',' | ']' | '}') || c.is_whitespace())
+                    .unwrap_or(rest.len());
+                if end == 0 {
+                    bail!("Unexpected character at byte {}", self.pos);
+                }
+                self.pos += end;
+                Ok(())
+            }
+            None => bail!("Unexpected end of JSON"),
+        }
+    }
+
+    /// Read a string literal, decoding its escapes
+    fn string(&mut self) -> Result<String> {
+        let start = self.pos;
+        if self.peek() != Some(b'"') {
+            bail!("Expected a string at byte {}", start);
+        }
+
+        let bytes = self.text.as_bytes();
+        let mut i = start + 1;
+        loop {
+            match bytes.get(i) {
+                Some(b'\\') => i += 2,
+                Some(b'"') => break,
+                Some(_) => i += 1,
+                None => bail!("Unterminated string at byte {}", start),
+            }
+        }
+        self.pos = i + 1;
+
+        serde_json::from_str(&self.text[start..self.pos])
+            .with_context(|| format!("Invalid string at byte {}", start))
+    }
+
+    /// Consume `byte` (after whitespace) if it is next
+    fn close(&mut self, byte: u8) -> bool {
+        self.skip_whitespace();
+        let found = self.peek() == Some(byte);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<()> {
+        if self.close(byte) {
+            Ok(())
+        } else {
+            Err(anyhow!("Expected '{}' at byte {}", byte as char, self.pos))
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.text.as_bytes().get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(|b| b.is_ascii_whitespace()) {
+            self.pos += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_locate_nodes() {
+        let text = "{\n  \"users\": [\n    {\"name\": \"Zoë\", \"age\": -1},\n    {\"a b\": true}\n  ]\n}";
+        let paths = ["$.users[0].age", "$.users[1]['a b']", "$", "$.missing"].map(String::from);
+        let located = locate(text, &paths).unwrap();
+
+        let at = |line, column| Some(SourceLocation { line, column });
+        assert_eq!(located, vec![at(3, 28), at(4, 13), at(1, 1), None]);
+    }
+}
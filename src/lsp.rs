@@ -0,0 +1,263 @@
+//! Language server: rule violations as editor diagnostics
+//!
+//! `json-checker-rs lsp` speaks the Language Server Protocol over stdio.
+//! Every open file that a rule's `applies_to` pattern matches is checked as
+//! it is edited, with the same diagnostics `lint` prints, so VS Code and
+//! other editors underline failing nodes while the file is unsaved. Saving
+//! the config, or a file it includes, reloads the rules.
+
+use anyhow::{Context, Result};
+use lsp_server::{Connection, ErrorCode, Message, Notification, Request, Response};
+use lsp_types::notification::{
+    DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument, DidSaveTextDocument,
+    Notification as _, PublishDiagnostics, ShowMessage,
+};
+use lsp_types::{
+    DiagnosticSeverity, DidChangeTextDocumentParams, DidCloseTextDocumentParams, DidOpenTextDocumentParams,
+    DidSaveTextDocumentParams, MessageType, NumberOrString, Position, PublishDiagnosticsParams, Range,
+    ServerCapabilities, ShowMessageParams, TextDocumentSyncCapability, TextDocumentSyncKind, TextDocumentSyncOptions,
+    TextDocumentSyncSaveOptions, Url,
+};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::checker::JsonChecker;
+use crate::config::{Config, Severity};
+use crate::lint::{self, Diagnostic};
+use crate::watch::normalize;
+
+/// Name diagnostics are attributed to in the editor
+const SOURCE: &str = "json-checker-rs";
+
+/// Serve the language server on stdin and stdout until the editor exits
+pub fn serve_stdio(config_path: &str, vars: &HashMap<String, String>, profile: Option<&str>) -> Result<()> {
+    let (connection, io_threads) = Connection::stdio();
+    serve(&connection, config_path, vars, profile)?;
+    // The writer thread stops once the connection's sender is gone
+    drop(connection);
+    io_threads.join().context("Language server I/O failed")?;
+    Ok(())
+}
+
+/// Serve the language server on `connection` until the editor exits
+pub fn serve(connection: &Connection, config_path: &str, vars: &HashMap<String, String>, profile: Option<&str>) -> Result<()> {
+    let capabilities = ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Options(TextDocumentSyncOptions {
+            open_close: Some(true),
+            change: Some(TextDocumentSyncKind::FULL),
+            save: Some(TextDocumentSyncSaveOptions::Supported(true)),
+            ..Default::default()
+        })),
+        ..Default::default()
+    };
+    connection
+        .initialize(serde_json::to_value(capabilities)?)
+        .context("Language server initialization failed")?;
+
+    let mut server = Server {
+        connection,
+        config_path,
+        vars,
+        profile,
+        checker: None,
+        documents: HashMap::new(),
+    };
+    server.reload();
+
+    for message in &connection.receiver {
+        match message {
+            Message::Request(request) => {
+                if connection.handle_shutdown(&request)? {
+                    return Ok(());
+                }
+                server.unsupported(request)?;
+            }
+            Message::Notification(notification) => server.notify(notification)?,
+            Message::Response(_) => {}
+        }
+    }
+    Ok(())
+}
+
+struct Server<'a> {
+    connection: &'a Connection,
+    config_path: &'a str,
+    vars: &'a HashMap<String, String>,
+    profile: Option<&'a str>,
+    /// `None` while the config fails to load
+    checker: Option<JsonChecker>,
+    /// Text of each open document
+    documents: HashMap<Url, String>,
+}
+
+impl Server<'_> {
+    fn notify(&mut self, notification: Notification) -> Result<()> {
+        match notification.method.as_str() {
+            DidOpenTextDocument::METHOD => {
+                let params: DidOpenTextDocumentParams = serde_json::from_value(notification.params)?;
+                let document = params.text_document;
+                self.documents.insert(document.uri.clone(), document.text);
+                self.publish(&document.uri)
+            }
+            DidChangeTextDocument::METHOD => {
+                let params: DidChangeTextDocumentParams = serde_json::from_value(notification.params)?;
+                // Full sync: the last change holds the whole text
+                if let Some(change) = params.content_changes.into_iter().last() {
+                    self.documents.insert(params.text_document.uri.clone(), change.text);
+                }
+                self.publish(&params.text_document.uri)
+            }
+            DidSaveTextDocument::METHOD => {
+                let params: DidSaveTextDocumentParams = serde_json::from_value(notification.params)?;
+                if file_path(&params.text_document.uri).is_some_and(|path| self.is_config_file(&path)) {
+                    self.reload();
+                    let uris: Vec<Url> = self.documents.keys().cloned().collect();
+                    for uri in &uris {
+                        self.publish(uri)?;
+                    }
+                }
+                Ok(())
+            }
+            DidCloseTextDocument::METHOD => {
+                let params: DidCloseTextDocumentParams = serde_json::from_value(notification.params)?;
+                self.documents.remove(&params.text_document.uri);
+                self.send_diagnostics(params.text_document.uri, Vec::new())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn unsupported(&self, request: Request) -> Result<()> {
+        let message = format!("{} is not supported", request.method);
+        let response = Response::new_err(request.id, ErrorCode::MethodNotFound as i32, message);
+        self.connection.sender.send(Message::Response(response))?;
+        Ok(())
+    }
+
+    /// Load the config again, telling the editor if it fails
+    fn reload(&mut self) {
+        match Config::from_file_with_profile(self.config_path, self.vars, self.profile) {
+            Ok(config) => self.checker = Some(JsonChecker::from_config(config)),
+            Err(e) => {
+                self.checker = None;
+                let params = ShowMessageParams {
+                    typ: MessageType::ERROR,
+                    message: format!("json-checker-rs: {:#}", anyhow::Error::from(e)),
+                };
+                let _ = self.connection.sender.send(Message::Notification(Notification::new(ShowMessage::METHOD.to_string(), params)));
+            }
+        }
+    }
+
+    fn is_config_file(&self, path: &Path) -> bool {
+        let path = normalize(path);
+        match &self.checker {
+            Some(checker) => checker.config().files.contains(&path),
+            None => path == normalize(Path::new(self.config_path)),
+        }
+    }
+
+    /// Check an open document and replace its diagnostics in the editor
+    fn publish(&self, uri: &Url) -> Result<()> {
+        let (Some(checker), Some(text), Some(path)) = (&self.checker, self.documents.get(uri), file_path(uri)) else {
+            return Ok(());
+        };
+        let diagnostics = lint::lint_text(checker, &path.to_string_lossy(), text)
+            .iter()
+            .map(|diagnostic| to_lsp(diagnostic, text))
+            .collect();
+        self.send_diagnostics(uri.clone(), diagnostics)
+    }
+
+    fn send_diagnostics(&self, uri: Url, diagnostics: Vec<lsp_types::Diagnostic>) -> Result<()> {
+        let params = PublishDiagnosticsParams { uri, diagnostics, version: None };
+        let notification = Notification::new(PublishDiagnostics::METHOD.to_string(), params);
+        self.connection.sender.send(Message::Notification(notification))?;
+        Ok(())
+    }
+}
+
+/// Local path of a `file://` URI
+fn file_path(uri: &Url) -> Option<PathBuf> {
+    uri.to_file_path().ok()
+}
+
+/// The diagnostic as the editor shows it, underlining from the failing node
+/// to the end of its line
+fn to_lsp(diagnostic: &Diagnostic, text: &str) -> lsp_types::Diagnostic {
+    let (line, column) = diagnostic.location.map_or((0, 0), |location| (location.line - 1, location.column - 1));
+    let line_text = text.lines().nth(line).unwrap_or_default();
+    // Locations count characters, LSP counts UTF-16 code units
+    let utf16 = |chars: usize| line_text.chars().take(chars).map(char::len_utf16).sum::<usize>() as u32;
+    let start = Position { line: line as u32, character: utf16(column) };
+    let end = Position { line: line as u32, character: utf16(usize::MAX).max(start.character) };
+
+    lsp_types::Diagnostic {
+        range: Range { start, end },
+        severity: Some(match diagnostic.severity {
+            Severity::Warning => DiagnosticSeverity::WARNING,
+            Severity::Error => DiagnosticSeverity::ERROR,
+        }),
+        code: Some(NumberOrString::String(diagnostic.rule.clone())),
+        source: Some(SOURCE.to_string()),
+        message: diagnostic.message.clone(),
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lsp_server::RequestId;
+    use lsp_types::request::{Initialize, Request as _, Shutdown};
+    use serde_json::json;
+
+    #[test]
+    fn test_publishes_diagnostics() {
+        let dir = std::env::temp_dir().join(format!("json-checker-rs-lsp-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let config = dir.join("rules.json");
+        std::fs::write(
+            &config,
+            json!({"rules": [
+                {"name": "Port", "applies_to": "*.settings.json", "jsonpath": "$.port", "check": {"type": "greater_than", "value": 0}},
+                // Skipped rules aren't published: one disabled, one whose dependency fails
+                {"name": "Legacy port", "applies_to": "*.settings.json", "enabled": false, "jsonpath": "$.port", "check": {"type": "equals", "value": 8080}},
+                {"name": "Unprivileged port", "applies_to": "*.settings.json", "depends_on": ["Port"], "jsonpath": "$.port", "check": {"type": "greater_than", "value": 1024}},
+            ]})
+            .to_string(),
+        )
+        .unwrap();
+
+        let (server, client) = Connection::memory();
+        let config_path = config.to_string_lossy().into_owned();
+        let thread = std::thread::spawn(move || serve(&server, &config_path, &HashMap::new(), None));
+
+        let request = |id: i32, method: &str, params| Message::Request(Request::new(RequestId::from(id), method.to_string(), params));
+        let notification = |method: &str, params| Message::Notification(Notification::new(method.to_string(), params));
+        client.sender.send(request(1, Initialize::METHOD, json!({"capabilities": {}}))).unwrap();
+        client.receiver.recv().unwrap();
+        client.sender.send(notification("initialized", json!({}))).unwrap();
+
+        let uri = Url::from_file_path(dir.join("web.settings.json")).unwrap();
+        let text = "{\n  \"port\": -1\n}";
+        client
+            .sender
+            .send(notification(
+                DidOpenTextDocument::METHOD,
+                json!({"textDocument": {"uri": uri, "languageId": "json", "version": 1, "text": text}}),
+            ))
+            .unwrap();
+        let Message::Notification(published) = client.receiver.recv().unwrap() else { panic!("expected diagnostics") };
+        let params: PublishDiagnosticsParams = serde_json::from_value(published.params).unwrap();
+        assert_eq!(params.diagnostics.len(), 1);
+        assert_eq!(params.diagnostics[0].range.start, Position { line: 1, character: 10 });
+        assert_eq!(params.diagnostics[0].code, Some(NumberOrString::String("Port".to_string())));
+
+        client.sender.send(request(2, Shutdown::METHOD, json!(null))).unwrap();
+        client.receiver.recv().unwrap();
+        client.sender.send(notification("exit", json!(null))).unwrap();
+        thread.join().unwrap().unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
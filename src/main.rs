@@ -1,19 +1,20 @@
-//! JSON Checker CLI Application
-//! 
-//! A command-line tool for validating JSON data against configurable rules.
-
-use json_checker_rs::cli::Cli;
-use json_checker_rs::Result;
-use std::env;
-
-fn main() -> Result<()> {
-    let args: Vec<String> = env::args().collect();
-    
-    let config_file = if args.len() > 1 {
-        Some(args[1].as_str())
-    } else {
-        None
-    };
-    
-    Cli::run(config_file)
+//! JSON Checker CLI Application
+//! 
+//! A command-line tool for validating JSON data against configurable rules.
+
+use json_checker_rs::cli::{exit_code, Cli, CliOptions};
+use std::env;
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    
+    let code = match CliOptions::parse(&args).and_then(|options| Cli::run(&options)) {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("Error: {:#}", e);
+            exit_code::CONFIG_INVALID
+        }
+    };
+    
+    std::process::exit(code);
 }
\ No newline at end of file
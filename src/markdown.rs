@@ -0,0 +1,130 @@
+//! Markdown summaries for pull request comments
+//!
+//! A compact table with one row per rule, meant to be posted by CI. Long
+//! values are truncated so the comment stays readable.
+
+use std::fmt::Write;
+
+use crate::config::{CheckResult, Severity, Status};
+use crate::suite;
+
+/// Longest sample failure shown in a table cell, in characters
+pub const MAX_SAMPLE_CHARS: usize = 60;
+
+/// Render a Markdown summary table of the results
+pub fn to_markdown(results: &[CheckResult]) -> String {
+    let mut out = String::new();
+    out.push_str("### JSON Checker Results\n\n");
+    out.push_str("| Rule | Status | Failing paths | Sample failure |\n");
+    out.push_str("|------|--------|---------------|----------------|\n");
+
+    let (mut passed, mut failed, mut warnings, mut skipped) = (0, 0, 0, 0);
+    for result in results {
+        let status = match (result.status, result.severity) {
+            (Status::Skipped, _) => {
+                skipped += 1;
+                "⏭ skipped"
+            }
+            (Status::Pass, _) => {
+                passed += 1;
+                "✅ passed"
+            }
+            (_, Severity::Warning) => {
+                warnings += 1;
+                "⚠️ warning"
+            }
+            (_, Severity::Error) => {
+                failed += 1;
+                "❌ failed"
+            }
+        };
+
+        let (count, sample) = match result.status {
+            Status::Pass => (String::new(), String::new()),
+            // The headline says why the rule was skipped
+            Status::Skipped => (String::new(), sample(result)),
+            _ => (result.invalid_positions.len().to_string(), sample(result)),
+        };
+        let _ = writeln!(out, "| {} | {} | {} | {} |", cell(&result.rule_name), status, count, sample);
+    }
+
+    let _ = writeln!(
+        out,
+        "\n**Total: {}** · Passed: {} · Failed: {} · Warnings: {} · Skipped: {}",
+        results.len(),
+        passed,
+        failed,
+        warnings,
+        skipped
+    );
+
+    let suites = suite::summarize(results);
+    if !suites.is_empty() {
+        out.push_str("\n| Suite | Passed | Failed | Warnings | Skipped |\n");
+        out.push_str("|-------|--------|--------|----------|---------|\n");
+        for summary in suites {
+            let _ = writeln!(
+                out,
+                "| {} | {} | {} | {} | {} |",
+                cell(&summary.name),
+                summary.passed,
+                summary.failed,
+                summary.warnings,
+                summary.skipped
+            );
+        }
+    }
+
+    let remediations: Vec<&CheckResult> = results
+        .iter()
+        .filter(|r| !r.passed && (r.hint.is_some() || r.docs_url.is_some()))
+        .collect();
+    if !remediations.is_empty() {
+        out.push_str("\n**How to fix**\n\n");
+        for result in remediations {
+            let _ = write!(out, "- **{}**:", cell(&result.rule_name));
+            if let Some(hint) = &result.hint {
+                let _ = write!(out, " {}", cell(hint));
+            }
+            if let Some(url) = &result.docs_url {
+                let _ = write!(out, " ([docs]({}))", url);
+            }
+            out.push('\n');
+        }
+    }
+    out
+}
+
+/// First failing path with its value, or the failure headline when there is none
+fn sample(result: &CheckResult) -> String {
+    let text = match (result.invalid_positions.first(), result.values_found.first()) {
+        (Some(path), Some(value)) => format!("{} = {}", path, value),
+        (Some(path), None) => path.clone(),
+        _ => result.message.lines().next().unwrap_or_default().to_string(),
+    };
+    format!("`{}`", cell(&truncate(&text, MAX_SAMPLE_CHARS)).replace('`', "'"))
+}
+
+fn truncate(text: &str, max: usize) -> String {
+    match text.char_indices().nth(max) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+/// Make text safe for a single table cell
+fn cell(text: &str) -> String {
+    text.replace('|', "\\|").replace('\n', " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_truncate_and_cell() {
+        assert_eq!(truncate("abcdef", 3), "abc…");
+        assert_eq!(truncate("äbc", 3), "äbc");
+        assert_eq!(cell("a|b\nc"), "a\\|b c");
+    }
+}
@@ -0,0 +1,241 @@
+//! Result messages as structured data, rendered from message templates
+//!
+//! Each line of a [`CheckResult`]'s message is built as a [`Message`]: a
+//! message key with the values it mentions. A [`Catalog`] maps every key to
+//! a template such as `✓ Rule '{rule}' passed`, so output can be localized
+//! (`--lang zh-TW`) or re-templated from a JSON file of the keys to change,
+//! and tests can match on the message instead of its wording.
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+
+use crate::config::CheckResult;
+
+/// One line of a result message
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "key", rename_all = "snake_case")]
+pub enum Message {
+    Passed { rule: String },
+    Failed { rule: String, selector_kind: String, selector: String },
+    UnexpectedResponse { rule: String, uri: String },
+    DuplicateKeys { rule: String, count: usize },
+    PassedNoFiles { rule: String, root: String, pattern: String },
+    PassedForFiles { rule: String, count: usize, pattern: String },
+    PassedForScannedFiles { rule: String, count: usize, root: String, pattern: String },
+    FailedForFiles { rule: String, failed: usize, total: usize, pattern: String },
+    FailedForScannedFiles { rule: String, failed: usize, total: usize, root: String, pattern: String },
+    Skipped { rule: String, reason: String },
+    TimedOut { rule: String, limit_ms: u128 },
+    Error { error: String },
+    PassedInBaseline { rule: String },
+    PassedWithKnownFailures { rule: String, count: usize },
+    /// Detail: failures left out because the baseline knows them
+    BaselineSuppressed { count: usize },
+    /// Detail: value of an `aggregate` check's function
+    Aggregate { function: String, value: f64 },
+    /// Detail: an `aggregate` function that has no value for an empty selection
+    AggregateUndefined { function: String },
+    /// Detail: number of nodes a `match_count` check saw
+    MatchCount { count: usize },
+    /// Detail: how many values a `unique` check found duplicated
+    Duplicates { duplicated: usize, total: usize },
+    /// Detail: how many values a `same_length` check found not of the common length
+    LengthOutliers { outliers: usize, total: usize, length: usize },
+    /// Detail: a `same_length` check selected no arrays at all
+    NoArrays,
+    /// Detail: keys repeated in the document of a rule whose check failed too
+    DuplicateKeysFound { count: usize },
+    /// Detail: summary of the differences from a reference document
+    DiffersFrom { file: String, summary: String },
+    /// Detail: the first line of the result for one of several files
+    FileFailed { file: String, headline: Box<Message> },
+    /// Detail: more invalid nodes than are listed
+    Truncated { total: usize, shown: usize },
+    /// Text that has no message key, such as a failed HTTP assertion
+    Text { text: String },
+}
+
+/// Message templates of the default language
+const ENGLISH: &[(&str, &str)] = &[
+    ("passed", "✓ Rule '{rule}' passed"),
+    ("failed", "✗ Rule '{rule}' failed at {selector_kind} '{selector}'"),
+    ("unexpected_response", "✗ Rule '{rule}' failed: unexpected response from {uri}"),
+    ("duplicate_keys", "✗ Rule '{rule}' failed: {count} object key(s) appear more than once"),
+    ("passed_no_files", "✓ Rule '{rule}' passed (no files under {root} match '{pattern}')"),
+    ("passed_for_files", "✓ Rule '{rule}' passed for {count} files matching '{pattern}'"),
+    ("passed_for_scanned_files", "✓ Rule '{rule}' passed for {count} files under {root} matching '{pattern}'"),
+    ("failed_for_files", "✗ Rule '{rule}' failed for {failed} of {total} files matching '{pattern}'"),
+    ("failed_for_scanned_files", "✗ Rule '{rule}' failed for {failed} of {total} files under {root} matching '{pattern}'"),
+    ("skipped", "⏭ Rule '{rule}' skipped: {reason}"),
+    ("timed_out", "⏱ Rule '{rule}' timed out after {limit_ms}ms"),
+    ("error", "Error: {error}"),
+    ("passed_in_baseline", "✓ Rule '{rule}' passed (failure is in the baseline)"),
+    ("passed_with_known_failures", "✓ Rule '{rule}' passed ({count} known failure(s) in the baseline)"),
+    ("baseline_suppressed", "{count} known failure(s) suppressed by the baseline"),
+    ("aggregate", "{function} = {value}"),
+    ("aggregate_undefined", "{function} of an empty selection is undefined"),
+    ("match_count", "JSONPath matched {count} node(s)"),
+    ("duplicates", "{duplicated} of {total} value(s) are duplicated"),
+    ("length_outliers", "{outliers} of {total} value(s) are not arrays of the common length {length}"),
+    ("no_arrays", "none of the values are arrays"),
+    ("duplicate_keys_found", "{count} object key(s) appear more than once"),
+    ("differs_from", "differs from {file}: {summary}"),
+    ("file_failed", "• {file}: {headline}"),
+    ("truncated", "{total} invalid nodes; showing the first {shown}"),
+    ("text", "{text}"),
+];
+
+/// Message templates in Traditional Chinese
+const TRADITIONAL_CHINESE: &[(&str, &str)] = &[
+    ("passed", "✓ 規則「{rule}」通過"),
+    ("failed", "✗ 規則「{rule}」在 {selector_kind}「{selector}」失敗"),
+    ("unexpected_response", "✗ 規則「{rule}」失敗：來自 {uri} 的回應不符預期"),
+    ("duplicate_keys", "✗ 規則「{rule}」失敗：有 {count} 個物件鍵重複出現"),
+    ("passed_no_files", "✓ 規則「{rule}」通過（{root} 下沒有符合「{pattern}」的檔案）"),
+    ("passed_for_files", "✓ 規則「{rule}」在 {count} 個符合「{pattern}」的檔案上通過"),
+    ("passed_for_scanned_files", "✓ 規則「{rule}」在 {root} 下 {count} 個符合「{pattern}」的檔案上通過"),
+    ("failed_for_files", "✗ 規則「{rule}」在 {total} 個符合「{pattern}」的檔案中有 {failed} 個失敗"),
+    ("failed_for_scanned_files", "✗ 規則「{rule}」在 {root} 下 {total} 個符合「{pattern}」的檔案中有 {failed} 個失敗"),
+    ("skipped", "⏭ 規則「{rule}」已略過：{reason}"),
+    ("timed_out", "⏱ 規則「{rule}」在 {limit_ms}ms 後逾時"),
+    ("error", "錯誤：{error}"),
+    ("passed_in_baseline", "✓ 規則「{rule}」通過（失敗已列於基準中）"),
+    ("passed_with_known_failures", "✓ 規則「{rule}」通過（基準中有 {count} 個已知失敗）"),
+    ("baseline_suppressed", "已依基準略過 {count} 個已知失敗"),
+    ("aggregate_undefined", "空選取的 {function} 沒有定義"),
+    ("match_count", "JSONPath 選取了 {count} 個節點"),
+    ("duplicates", "{total} 個值中有 {duplicated} 個重複"),
+    ("length_outliers", "{total} 個值中有 {outliers} 個不是長度為 {length} 的陣列"),
+    ("no_arrays", "沒有任何值是陣列"),
+    ("duplicate_keys_found", "有 {count} 個物件鍵重複出現"),
+    ("differs_from", "與 {file} 不同：{summary}"),
+    ("truncated", "共 {total} 個無效節點；只列出前 {shown} 個"),
+];
+
+/// Templates for every message key
+#[derive(Debug, Clone)]
+pub struct Catalog {
+    templates: HashMap<String, String>,
+}
+
+impl Default for Catalog {
+    fn default() -> Self {
+        Catalog::english()
+    }
+}
+
+impl Catalog {
+    pub fn english() -> Self {
+        Catalog { templates: HashMap::new() }.with(ENGLISH)
+    }
+
+    /// The built-in catalog for `lang` (`en` or `zh-TW`), or the catalog
+    /// read from a JSON file mapping message keys to templates. Keys the
+    /// file doesn't set keep their English template.
+    pub fn load(lang: &str) -> Result<Self> {
+        match lang {
+            "en" => Ok(Catalog::english()),
+            "zh-TW" => Ok(Catalog::english().with(TRADITIONAL_CHINESE)),
+            path => {
+                let text = std::fs::read_to_string(path)
+                    .with_context(|| format!("Unknown language '{}', expected en, zh-TW or a message file", lang))?;
+                let templates: HashMap<String, String> = serde_json::from_str(&text)
+                    .with_context(|| format!("Failed to parse message file {}", path))?;
+                let mut catalog = Catalog::english();
+                catalog.templates.extend(templates);
+                Ok(catalog)
+            }
+        }
+    }
+
+    fn with(mut self, templates: &[(&str, &str)]) -> Self {
+        self.templates.extend(templates.iter().map(|(key, template)| (key.to_string(), template.to_string())));
+        self
+    }
+
+    /// Render a message: the first line as is, the details indented below it
+    pub fn render(&self, lines: &[Message]) -> String {
+        let mut out = String::new();
+        for (i, line) in lines.iter().enumerate() {
+            if i > 0 {
+                out.push_str("\n   ");
+            }
+            out.push_str(&self.render_line(line));
+        }
+        out
+    }
+
+    pub fn render_line(&self, message: &Message) -> String {
+        match serde_json::to_value(message) {
+            Ok(Value::Object(fields)) => self.fill(&fields),
+            _ => String::new(),
+        }
+    }
+
+    /// Re-render the message of `result` and of its per-document results;
+    /// results without structured lines are left as they are
+    pub fn localize(&self, result: &mut CheckResult) {
+        if !result.lines.is_empty() {
+            result.message = self.render(&result.lines);
+        }
+        for document in &mut result.per_document {
+            self.localize(document);
+        }
+    }
+
+    /// Substitute the `{name}` placeholders of the message's template
+    fn fill(&self, fields: &Map<String, Value>) -> String {
+        let key = fields.get("key").and_then(Value::as_str).unwrap_or_default();
+        let Some(template) = self.templates.get(key) else {
+            return key.to_string();
+        };
+
+        let mut out = String::new();
+        let mut rest = template.as_str();
+        while let Some(start) = rest.find('{') {
+            out.push_str(&rest[..start]);
+            let Some(end) = rest[start..].find('}') else {
+                break;
+            };
+            let name = &rest[start + 1..start + end];
+            match fields.get(name) {
+                Some(Value::String(s)) => out.push_str(s),
+                Some(Value::Object(nested)) => out.push_str(&self.fill(nested)),
+                // Print whole floats like Rust does, `3` rather than `3.0`
+                Some(Value::Number(n)) if n.is_f64() => out.push_str(&n.as_f64().unwrap_or_default().to_string()),
+                Some(value) => out.push_str(&value.to_string()),
+                None => out.push_str(&rest[start..=start + end]),
+            }
+            rest = &rest[start + end + 1..];
+        }
+        out.push_str(rest);
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let lines = [
+            Message::FailedForFiles { rule: "Ids".to_string(), failed: 1, total: 3, pattern: "*.json".to_string() },
+            Message::FileFailed {
+                file: "a.json".to_string(),
+                headline: Box::new(Message::Aggregate { function: "sum".to_string(), value: 3.0 }),
+            },
+        ];
+        assert_eq!(
+            Catalog::english().render(&lines),
+            "✗ Rule 'Ids' failed for 1 of 3 files matching '*.json'\n   • a.json: sum = 3"
+        );
+        assert_eq!(
+            Catalog::load("zh-TW").unwrap().render(&lines[..1]),
+            "✗ 規則「Ids」在 3 個符合「*.json」的檔案中有 1 個失敗"
+        );
+        assert!(Catalog::load("xx").is_err());
+    }
+}
@@ -0,0 +1,141 @@
+//! Exact comparison of JSON numbers
+//!
+//! Numbers are compared by the decimal they are written as rather than by
+//! an `f64`, which can't tell `12345678901234567891` from
+//! `12345678901234567890`. Built with the `arbitrary_precision` feature,
+//! serde_json keeps every number as written, so large IDs and long decimals
+//! compare exactly; without it, numbers beyond the range of `i64` and `u64`
+//! are parsed as `f64` before they get here.
+
+use serde::{Deserialize, Deserializer};
+use serde_json::{Number, Value};
+use std::cmp::Ordering;
+
+/// Compare two numbers by their exact decimal value, so `1` equals `1.0`
+pub fn compare(a: &Number, b: &Number) -> Ordering {
+    match (Decimal::parse(&a.to_string()), Decimal::parse(&b.to_string())) {
+        (Some(a), Some(b)) => a.cmp(&b),
+        // serde_json only writes valid numbers
+        _ => a.as_f64().partial_cmp(&b.as_f64()).unwrap_or(Ordering::Equal),
+    }
+}
+
+/// Compare `value` to `threshold`, or `None` if it isn't a number
+pub fn compare_value(value: &Value, threshold: &Number) -> Option<Ordering> {
+    match value {
+        Value::Number(n) => Some(compare(n, threshold)),
+        _ => None,
+    }
+}
+
+/// Deserialize an `f64` through [`Number`], which serde_json's
+/// `arbitrary_precision` numbers need inside internally tagged enums
+pub(crate) fn deserialize_f64<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+    let number = Number::deserialize(deserializer)?;
+    number.as_f64().ok_or_else(|| serde::de::Error::custom(format!("{} is out of range for a float", number)))
+}
+
+/// [`deserialize_f64`] for an optional field
+pub(crate) fn deserialize_option_f64<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<f64>, D::Error> {
+    match Option::<Number>::deserialize(deserializer)? {
+        Some(number) => match number.as_f64() {
+            Some(f) => Ok(Some(f)),
+            None => Err(serde::de::Error::custom(format!("{} is out of range for a float", number))),
+        },
+        None => Ok(None),
+    }
+}
+
+/// A decimal as `0.digits × 10^point`, with no leading or trailing zeros in
+/// `digits`; zero has no digits
+#[derive(Debug, PartialEq, Eq)]
+struct Decimal {
+    negative: bool,
+    digits: Vec<u8>,
+    point: i64,
+}
+
+impl Decimal {
+    /// Parse a JSON number such as `-12.50e+3`
+    fn parse(text: &str) -> Option<Self> {
+        let (negative, text) = match text.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, text),
+        };
+        let (mantissa, exponent) = match text.split_once(['e', 'E']) {
+            Some((mantissa, exponent)) => (mantissa, exponent.parse::<i64>().ok()?),
+            None => (text, 0),
+        };
+        let (integer, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+        if integer.is_empty() || !integer.bytes().chain(fraction.bytes()).all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+
+        let all: Vec<u8> = integer.bytes().chain(fraction.bytes()).map(|b| b - b'0').collect();
+        let leading = all.iter().take_while(|&&d| d == 0).count();
+        let mut digits = all[leading..].to_vec();
+        while digits.last() == Some(&0) {
+            digits.pop();
+        }
+        let point = integer.len() as i64 - leading as i64 + exponent;
+        Some(Decimal { negative: negative && !digits.is_empty(), digits, point })
+    }
+
+    /// Order of the absolute values
+    fn cmp_magnitude(&self, other: &Self) -> Ordering {
+        match (self.digits.is_empty(), other.digits.is_empty()) {
+            (true, true) => Ordering::Equal,
+            (true, false) => Ordering::Less,
+            (false, true) => Ordering::Greater,
+            // The first digit is never zero, so the point decides first
+            (false, false) => self.point.cmp(&other.point).then_with(|| self.digits.cmp(&other.digits)),
+        }
+    }
+}
+
+impl Ord for Decimal {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, false) => self.cmp_magnitude(other),
+            (true, true) => other.cmp_magnitude(self),
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+        }
+    }
+}
+
+impl PartialOrd for Decimal {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn number(text: &str) -> Number {
+        serde_json::from_str(text).unwrap()
+    }
+
+    #[test]
+    fn test_compare() {
+        assert_eq!(compare(&number("1"), &number("1.0")), Ordering::Equal);
+        assert_eq!(compare(&number("-0.0"), &number("0")), Ordering::Equal);
+        assert_eq!(compare(&number("1.5e3"), &number("1500")), Ordering::Equal);
+        assert_eq!(compare(&number("0.05"), &number("5E-2")), Ordering::Equal);
+        assert_eq!(compare(&number("18446744073709551615"), &number("18446744073709551614")), Ordering::Greater);
+        assert_eq!(compare(&number("-2"), &number("-10")), Ordering::Greater);
+        assert_eq!(compare(&number("-0.001"), &number("0")), Ordering::Less);
+        assert_eq!(compare(&number("99.99"), &number("100")), Ordering::Less);
+        assert_eq!(compare_value(&Value::String("5".to_string()), &number("1")), None);
+    }
+
+    #[cfg(feature = "arbitrary_precision")]
+    #[test]
+    fn test_compare_beyond_f64() {
+        assert_eq!(compare(&number("12345678901234567891234"), &number("12345678901234567891233")), Ordering::Greater);
+        assert_eq!(compare(&number("0.1000000000000000000001"), &number("0.1")), Ordering::Greater);
+        assert_eq!(compare(&number("9007199254740993"), &number("9007199254740992.0")), Ordering::Greater);
+    }
+}
@@ -0,0 +1,386 @@
+//! How strictly JSON documents are parsed
+//!
+//! By default documents are parsed as serde_json does: standard JSON with
+//! nothing but whitespace after the value. `allow_comments`,
+//! `allow_trailing_commas` and `allow_trailing_content` relax that for
+//! hand-written files and logs; JSONC documents allow comments and trailing
+//! commas anyway. Comments and trailing commas are blanked out before the
+//! text is parsed, so lines and columns still point into the file. JSON5
+//! documents are read by the `json5` crate instead.
+//!
+//! serde_json keeps the last value of a key that appears twice in an object,
+//! so `{"id": 1, "id": 2}` silently loads as `{"id": 2}`. With
+//! `detect_duplicate_keys`, documents are read a second time by a
+//! deserializer that only tracks object keys, and every repeated key is
+//! reported as a violation of the rule with its path.
+//!
+//! Syntax errors name the line and column and quote the line with a caret
+//! under the offending character.
+
+use anyhow::{Context, Result};
+use schemars::JsonSchema;
+use serde::de::{self, DeserializeSeed, MapAccess, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::Value;
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::fmt;
+
+use crate::format::{self, CsvOptions, Document, DocumentFormat};
+use crate::path_finder::{push_index, push_key};
+
+/// How strictly JSON documents are parsed
+#[derive(Debug, Default, Deserialize, JsonSchema, Serialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(default, deny_unknown_fields)]
+pub struct ParserOptions {
+    /// Report object keys that appear more than once instead of keeping the last
+    pub detect_duplicate_keys: bool,
+    /// Treat `//` line comments and `/* */` block comments as whitespace
+    pub allow_comments: bool,
+    /// Accept a comma after the last element of an array or object
+    pub allow_trailing_commas: bool,
+    /// Ignore anything after the document's value instead of failing
+    pub allow_trailing_content: bool,
+}
+
+impl ParserOptions {
+    /// Whether documents are parsed the way serde_json does by default
+    pub fn is_lenient(&self) -> bool {
+        *self == ParserOptions::default()
+    }
+
+    /// These options with whatever `format` allows anyway, such as comments in JSONC
+    pub fn for_format(self, format: DocumentFormat) -> Self {
+        match format {
+            DocumentFormat::Jsonc => ParserOptions { allow_comments: true, allow_trailing_commas: true, ..self },
+            _ => self,
+        }
+    }
+
+    /// The text serde_json reads: `text` with the comments and trailing
+    /// commas it may have blanked out, so lines and columns stay where they were
+    pub fn source_text<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        let text = if self.allow_comments { strip_comments(text) } else { Cow::Borrowed(text) };
+        match (self.allow_trailing_commas, text) {
+            (true, Cow::Borrowed(text)) => strip_trailing_commas(text),
+            (true, Cow::Owned(text)) => Cow::Owned(strip_trailing_commas(&text).into_owned()),
+            (false, text) => text,
+        }
+    }
+}
+
+/// A JSON syntax error, quoting the line it is on
+#[derive(Debug)]
+pub struct SyntaxError {
+    pub error: serde_json::Error,
+    /// The offending line, shortened around the error, and a caret under it
+    pub snippet: String,
+}
+
+impl SyntaxError {
+    fn new(error: serde_json::Error, text: &str) -> Self {
+        let snippet = snippet(text, error.line(), error.column());
+        SyntaxError { error, snippet }
+    }
+}
+
+impl fmt::Display for SyntaxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.error)?;
+        if !self.snippet.is_empty() {
+            write!(f, "\n{}", self.snippet)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for SyntaxError {}
+
+/// Parse document text in the given format, applying `options` to JSON,
+/// JSONC and, for duplicate keys, JSON5
+pub fn parse(text: &str, format: DocumentFormat, csv: Option<&CsvOptions>, options: ParserOptions) -> Result<Document> {
+    match format {
+        DocumentFormat::Json | DocumentFormat::Jsonc => {
+            let options = options.for_format(format);
+            let text = options.source_text(text);
+            let mut document = Document::new(parse_json(&text, options).context("Failed to parse JSON")?);
+            if options.detect_duplicate_keys {
+                document.duplicate_keys = duplicate_keys(&text)?;
+            }
+            Ok(document)
+        }
+        DocumentFormat::Json5 => {
+            let mut document = Document::new(json5::from_str(text).context("Failed to parse JSON5")?);
+            if options.detect_duplicate_keys {
+                let mut deserializer = json5::Deserializer::from_str(text).context("Failed to parse JSON5")?;
+                document.duplicate_keys = walk_keys(&mut deserializer).context("Failed to parse JSON5")?;
+            }
+            Ok(document)
+        }
+        _ => format::parse_with(text, format, csv),
+    }
+}
+
+/// Parse a JSON value, with comments already blanked out by
+/// [`ParserOptions::source_text`] if they are allowed
+pub fn parse_json(text: &str, options: ParserOptions) -> std::result::Result<Value, SyntaxError> {
+    let mut deserializer = serde_json::Deserializer::from_str(text);
+    let mut value = Value::deserialize(&mut deserializer);
+    if !options.allow_trailing_content {
+        value = value.and_then(|value| deserializer.end().map(|()| value));
+    }
+    value.map_err(|error| SyntaxError::new(error, text))
+}
+
+/// Paths of the object keys in the first value of `text` that appear more
+/// than once in their object, each listed once, in document order
+pub fn duplicate_keys(text: &str) -> Result<Vec<String>> {
+    let mut deserializer = serde_json::Deserializer::from_str(text);
+    walk_keys(&mut deserializer)
+        .map_err(|error| SyntaxError::new(error, text))
+        .context("Failed to parse JSON")
+}
+
+/// Paths of the repeated keys of the value `deserializer` reads
+fn walk_keys<'de, D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Vec<String>, D::Error> {
+    let mut duplicates = Vec::new();
+    KeyWalker { path: "$".to_string(), duplicates: &mut duplicates }.deserialize(deserializer)?;
+    Ok(duplicates)
+}
+
+/// Most characters quoted on either side of a syntax error
+const SNIPPET_CONTEXT: usize = 40;
+
+/// Line `line` of `text` (both 1-based, as serde_json counts) with a caret
+/// under `column`, or nothing if there is no such line
+fn snippet(text: &str, line: usize, column: usize) -> String {
+    let Some(source) = text.lines().nth(line.saturating_sub(1)) else {
+        return String::new();
+    };
+    // serde_json counts columns in bytes
+    let mut at = column.saturating_sub(1).min(source.len());
+    while !source.is_char_boundary(at) {
+        at -= 1;
+    }
+    let chars: Vec<char> = source.chars().map(|c| if c == '\t' { ' ' } else { c }).collect();
+    let caret = source[..at].chars().count();
+    let start = caret.saturating_sub(SNIPPET_CONTEXT);
+    let end = (caret + SNIPPET_CONTEXT).min(chars.len());
+
+    let mut quoted: String = chars[start..end].iter().collect();
+    let mut offset = caret - start;
+    if start > 0 {
+        quoted.insert(0, '…');
+        offset += 1;
+    }
+    if end < chars.len() {
+        quoted.push('…');
+    }
+    let gutter = " ".repeat(line.to_string().len());
+    format!("  {} | {}\n  {} | {}^", line, quoted, gutter, " ".repeat(offset))
+}
+
+/// `text` with every `//` and `/* */` comment outside a string replaced by
+/// spaces, keeping its line breaks
+pub fn strip_comments(text: &str) -> Cow<'_, str> {
+    if !text.contains('/') {
+        return Cow::Borrowed(text);
+    }
+    let mut bytes = text.as_bytes().to_vec();
+    let mut in_string = false;
+    let mut i = 0;
+    while i < bytes.len() {
+        let end = match (in_string, bytes[i], bytes.get(i + 1)) {
+            (true, b'\\', _) => {
+                i += 2;
+                continue;
+            }
+            (_, b'"', _) => {
+                in_string = !in_string;
+                i += 1;
+                continue;
+            }
+            (false, b'/', Some(b'/')) => bytes[i..].iter().position(|&b| b == b'\n').map_or(bytes.len(), |n| i + n),
+            // An unterminated comment is left for the parser to report
+            (false, b'/', Some(b'*')) => match text[i + 2..].find("*/") {
+                Some(n) => i + 2 + n + 2,
+                None => break,
+            },
+            _ => {
+                i += 1;
+                continue;
+            }
+        };
+        for byte in &mut bytes[i..end] {
+            if *byte != b'\n' {
+                *byte = b' ';
+            }
+        }
+        i = end;
+    }
+    // Only whole comments, which start and end with ASCII, were replaced
+    Cow::Owned(String::from_utf8(bytes).expect("comments are replaced by ASCII spaces"))
+}
+
+/// `text` with every comma directly before a `]` or `}` outside a string
+/// replaced by a space; comments must already be blanked out
+pub fn strip_trailing_commas(text: &str) -> Cow<'_, str> {
+    let bytes = text.as_bytes();
+    let mut trailing = Vec::new();
+    let mut in_string = false;
+    let mut i = 0;
+    while i < bytes.len() {
+        match (in_string, bytes[i]) {
+            (true, b'\\') => i += 1,
+            (_, b'"') => in_string = !in_string,
+            (false, b',') => {
+                let next = bytes[i + 1..].iter().find(|b| !b.is_ascii_whitespace());
+                if matches!(next, Some(b']' | b'}')) {
+                    trailing.push(i);
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    if trailing.is_empty() {
+        return Cow::Borrowed(text);
+    }
+    let mut bytes = bytes.to_vec();
+    for i in trailing {
+        bytes[i] = b' ';
+    }
+    Cow::Owned(String::from_utf8(bytes).expect("commas are replaced by ASCII spaces"))
+}
+
+/// Visits every node of a document without building it, recording the path
+/// of each repeated key
+struct KeyWalker<'a> {
+    path: String,
+    duplicates: &'a mut Vec<String>,
+}
+
+impl<'de> DeserializeSeed<'de> for KeyWalker<'_> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> std::result::Result<(), D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> Visitor<'de> for KeyWalker<'_> {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a JSON value")
+    }
+
+    fn visit_bool<E: de::Error>(self, _: bool) -> std::result::Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_i64<E: de::Error>(self, _: i64) -> std::result::Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_u64<E: de::Error>(self, _: u64) -> std::result::Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_f64<E: de::Error>(self, _: f64) -> std::result::Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_str<E: de::Error>(self, _: &str) -> std::result::Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_unit<E: de::Error>(self) -> std::result::Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> std::result::Result<(), A::Error> {
+        let mut index = 0;
+        loop {
+            let mut path = self.path.clone();
+            push_index(&mut path, index);
+            if seq.next_element_seed(KeyWalker { path, duplicates: &mut *self.duplicates })?.is_none() {
+                return Ok(());
+            }
+            index += 1;
+        }
+    }
+
+    // Under `arbitrary_precision` numbers arrive as single-key maps, which
+    // can't hold a duplicate
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> std::result::Result<(), A::Error> {
+        let mut seen = HashSet::new();
+        let mut reported = HashSet::new();
+        while let Some(key) = map.next_key::<String>()? {
+            let mut path = self.path.clone();
+            push_key(&mut path, &key);
+            if !seen.insert(key.clone()) && reported.insert(key) {
+                self.duplicates.push(path.clone());
+            }
+            map.next_value_seed(KeyWalker { path, duplicates: &mut *self.duplicates })?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_duplicate_keys() {
+        let text = r#"{"id": 1, "tags": [{"a": 1, "a": 2, "a": 3}], "id": 2, "nested": {"id": 1}}"#;
+        assert_eq!(duplicate_keys(text).unwrap(), vec!["$.tags[0].a", "$.id"]);
+        assert!(duplicate_keys(r#"{"a": {"b": 1}, "b": 1.5e3}"#).unwrap().is_empty());
+        assert!(duplicate_keys(r#"{"a": 1"#).is_err());
+    }
+
+    #[test]
+    fn test_comments_and_trailing_content() {
+        let text = "{\n  // the port\n  \"url\": \"http://x/*y*/\", /* é\n */ \"port\": 1\n}\n";
+        let stripped = strip_comments(text);
+        assert_eq!(stripped.lines().count(), text.lines().count());
+        assert!(stripped.contains("http://x/*y*/"));
+        let strict = ParserOptions::default();
+        assert!(parse_json(text, strict).is_err());
+        let comments = ParserOptions { allow_comments: true, ..strict };
+        let value = parse_json(&comments.source_text(text), comments).unwrap();
+        assert_eq!(value, serde_json::json!({"url": "http://x/*y*/", "port": 1}));
+
+        let trailing = "[1, 2]\n<!-- served by nginx -->";
+        let error = parse_json(trailing, strict).unwrap_err();
+        assert_eq!(error.to_string(), "trailing characters at line 2 column 1\n  2 | <!-- served by nginx -->\n    | ^");
+        let allowed = ParserOptions { allow_trailing_content: true, ..strict };
+        assert_eq!(parse_json(trailing, allowed).unwrap(), serde_json::json!([1, 2]));
+    }
+
+    #[test]
+    fn test_jsonc_and_json5() {
+        let text = "{\n  \"list\": [1, 2,], // done\n  \"text\": \"a,]\",\n}";
+        let stripped = ParserOptions::default().for_format(DocumentFormat::Jsonc).source_text(text);
+        assert_eq!(stripped, "{\n  \"list\": [1, 2 ],        \n  \"text\": \"a,]\" \n}");
+        let document = parse(text, DocumentFormat::Jsonc, None, ParserOptions::default()).unwrap();
+        assert_eq!(document.value, serde_json::json!({"list": [1, 2], "text": "a,]"}));
+
+        let options = ParserOptions { detect_duplicate_keys: true, ..ParserOptions::default() };
+        let document = parse("{id: 1, tags: ['a',], id: 0x10}", DocumentFormat::Json5, None, options).unwrap();
+        assert_eq!(document.value, serde_json::json!({"id": 16, "tags": ["a"]}));
+        assert_eq!(document.duplicate_keys, vec!["$.id"]);
+    }
+
+    #[test]
+    fn test_snippet_shortens_long_lines() {
+        let text = format!("{{\"a\": [{}], \"b\" 1}}", "1, ".repeat(30) + "1");
+        let error = parse_json(&text, ParserOptions::default()).unwrap_err();
+        let lines: Vec<&str> = error.snippet.lines().collect();
+        assert!(lines[0].starts_with("  1 | …"));
+        assert!(lines[0].ends_with("\"b\" 1}"));
+        let caret = lines[1].find('^').unwrap();
+        assert_eq!(lines[0].chars().nth(caret), Some('1'));
+    }
+}
@@ -0,0 +1,128 @@
+//! JSON Patch reports for remediation pipelines
+//!
+//! `--report json-patch` prints, for each local JSON document with
+//! failures, an RFC 6902 patch that makes its failing nodes go away: a
+//! `replace` with the suggested fix where one can be derived, a `remove`
+//! of the node otherwise. Review the removals before applying them; they
+//! drop data rather than correct it.
+
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, HashSet};
+
+use crate::config::CheckResult;
+use crate::fix::PatchOperation;
+use crate::format::DocumentFormat;
+use crate::path_finder::to_json_pointer;
+use crate::source::local_path;
+
+/// The patch of each document, keyed by the document's path
+pub fn to_json_patch(results: &[CheckResult]) -> Value {
+    let mut patches: BTreeMap<&str, Vec<PatchOperation>> = BTreeMap::new();
+    let mut pending: Vec<&CheckResult> = results.iter().collect();
+    while let Some(result) = pending.pop() {
+        pending.extend(&result.per_document);
+        if result.passed || !result.per_document.is_empty() {
+            continue;
+        }
+        let Some(document) = result.document.as_deref().filter(|uri| is_patchable(uri)) else {
+            continue;
+        };
+        let patch = patches.entry(document).or_default();
+        for operation in operations(result) {
+            if !patch.contains(&operation) {
+                patch.push(operation);
+            }
+        }
+    }
+
+    let documents: Map<String, Value> = patches
+        .into_iter()
+        .filter(|(_, patch)| !patch.is_empty())
+        .map(|(document, patch)| (document.to_string(), serde_json::to_value(ordered(patch)).unwrap_or_default()))
+        .collect();
+    Value::Object(documents)
+}
+
+/// Whether failing positions of the document are paths into a JSON file
+fn is_patchable(uri: &str) -> bool {
+    local_path(uri).is_some_and(|path| DocumentFormat::detect(path) == DocumentFormat::Json)
+}
+
+/// The fixes of a result, and removals of its other failing nodes
+fn operations(result: &CheckResult) -> Vec<PatchOperation> {
+    let fixed: HashSet<&str> = result.fixes.iter().map(PatchOperation::path).collect();
+    let removals = result
+        .invalid_positions
+        .iter()
+        .filter_map(|position| match position.starts_with('/') {
+            // Rules selecting with `json_pointer` report pointers already
+            true => Some(position.clone()),
+            false => to_json_pointer(position),
+        })
+        // The whole document can't be removed
+        .filter(|path| !path.is_empty() && !fixed.contains(path.as_str()))
+        .map(|path| PatchOperation::Remove { path });
+    result.fixes.iter().cloned().chain(removals).collect()
+}
+
+/// Order the operations so each applies to the document the previous ones
+/// left: replacements first, then removals deepest and last index first,
+/// dropping operations inside a removed node
+fn ordered(patch: Vec<PatchOperation>) -> Vec<PatchOperation> {
+    let (replacements, mut removals): (Vec<_>, Vec<_>) =
+        patch.into_iter().partition(|operation| matches!(operation, PatchOperation::Replace { .. }));
+    let removed: Vec<String> = removals.iter().map(|operation| operation.path().to_string()).collect();
+    let inside_removed = |path: &str| removed.iter().any(|parent| path.starts_with(&format!("{}/", parent)));
+
+    removals.retain(|operation| !inside_removed(operation.path()));
+    // Compare array indices as numbers so `/a/10` goes before `/a/9`
+    removals.sort_by(|a, b| segments(b.path()).cmp(&segments(a.path())));
+    replacements
+        .into_iter()
+        .filter(|operation| !inside_removed(operation.path()))
+        .chain(removals)
+        .collect()
+}
+
+fn segments(pointer: &str) -> Vec<(usize, &str)> {
+    pointer
+        .split('/')
+        .skip(1)
+        .map(|segment| (segment.parse().unwrap_or(usize::MAX), segment))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fix;
+    use serde_json::json;
+
+    #[test]
+    fn test_to_json_patch() {
+        let failed = |document: Option<&str>| {
+            let positions = ["$.ports[9]", "$.ports[10]", "$.ports[2]", "$.ports[10].name", "$.env"];
+            let mut result =
+                CheckResult::with_positions("Ports".to_string(), false, String::new(), positions.map(String::from).to_vec(), Vec::new());
+            result.fixes = vec![PatchOperation::Replace { path: "/env".to_string(), value: json!("production") }];
+            result.document = document.map(String::from);
+            result
+        };
+
+        let report = to_json_patch(&[failed(Some("app.json")), failed(None)]);
+        assert_eq!(
+            report,
+            json!({"app.json": [
+                {"op": "replace", "path": "/env", "value": "production"},
+                {"op": "remove", "path": "/ports/10"},
+                {"op": "remove", "path": "/ports/9"},
+                {"op": "remove", "path": "/ports/2"},
+            ]})
+        );
+
+        let patch: Vec<PatchOperation> = serde_json::from_value(report["app.json"].clone()).unwrap();
+        let mut document = json!({"env": "prod", "ports": (0..11).collect::<Vec<_>>()});
+        fix::apply(&mut document, &patch).unwrap();
+        assert_eq!(document, json!({"env": "production", "ports": [0, 1, 3, 4, 5, 6, 7, 8]}));
+    }
+}
@@ -1,201 +1,529 @@
-//! JSON path finding utilities for locating specific values within JSON structures
-
-use serde_json::Value;
-
-/// Find the path to a specific value within a JSON structure
-/// 
-/// Returns the JSONPath-style path to the target value, or None if not found
-/// 
-/// # Arguments
-/// * `value` - The JSON value to search within
-/// * `target` - The target value to find
-/// * `path` - Current path components (used for recursion)
-/// 
-/// # Examples
-/// ```
-/// use serde_json::Value;
-/// use json_checker_rs::path_finder::find_json_path;
-/// 
-/// let json: Value = serde_json::from_str(r#"{"user": {"name": "Leo"}}"#).unwrap();
-/// let target = Value::String("Leo".to_string());
-/// let path = find_json_path(&json, &target, vec!["$".to_string()]);
-/// assert_eq!(path, Some("$.user.name".to_string()));
-/// ```
-pub fn find_json_path(value: &Value, target: &Value, path: Vec<String>) -> Option<String> {
-    if value == target {
-        return Some(path.join("."));
-    }
-
-    match value {
-        Value::Object(map) => {
-            for (key, val) in map {
-                let mut new_path = path.clone();
-                new_path.push(key.clone());
-                if let Some(found) = find_json_path(val, target, new_path) {
-                    return Some(found);
-                }
-            }
-        }
-        Value::Array(arr) => {
-            for (index, val) in arr.iter().enumerate() {
-                let mut new_path = path.clone();
-                new_path.push(format!("[{}]", index));
-                if let Some(found) = find_json_path(val, target, new_path) {
-                    return Some(found);
-                }
-            }
-        }
-        _ => {}
-    }
-
-    None
-}
-
-/// Find all paths to values that match a predicate function
-/// 
-/// Returns a vector of JSONPath-style paths where the predicate returns true
-/// 
-/// # Arguments
-/// * `value` - The JSON value to search within
-/// * `predicate` - Function that returns true for target values
-/// * `path` - Current path components (used for recursion)
-pub fn find_json_paths_matching<F>(
-    value: &Value, 
-    predicate: F, 
-    path: Vec<String>
-) -> Vec<String> 
-where
-    F: Fn(&Value) -> bool + Copy,
-{
-    let mut results = Vec::new();
-    
-    if predicate(value) {
-        results.push(path.join("."));
-    }
-
-    match value {
-        Value::Object(map) => {
-            for (key, val) in map {
-                let mut new_path = path.clone();
-                new_path.push(key.clone());
-                results.extend(find_json_paths_matching(val, predicate, new_path));
-            }
-        }
-        Value::Array(arr) => {
-            for (index, val) in arr.iter().enumerate() {
-                let mut new_path = path.clone();
-                new_path.push(format!("[{}]", index));
-                results.extend(find_json_paths_matching(val, predicate, new_path));
-            }
-        }
-        _ => {}
-    }
-
-    results
-}
-
-/// Find paths to values that fail a specific validation check
-/// 
-/// This is useful for identifying exactly which nodes in a JSON structure
-/// are causing validation failures
-/// 
-/// # Arguments
-/// * `value` - The JSON value to search within
-/// * `check_fn` - Function that returns false for invalid values
-/// * `path` - Current path components (used for recursion)
-pub fn find_invalid_paths<F>(
-    value: &Value, 
-    check_fn: F, 
-    path: Vec<String>
-) -> Vec<String>
-where
-    F: Fn(&Value) -> bool + Copy,
-{
-    find_json_paths_matching(value, |v| !check_fn(v), path)
-}
-
-/// Get a human-readable path representation
-/// 
-/// Converts a path like "$.user.details[0].name" to a more readable format
-pub fn format_path_readable(path: &str) -> String {
-    path.replace("$.", "")
-        .replace("[", " → item ")
-        .replace("]", "")
-        .replace(".", " → ")
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use serde_json::json;
-
-    #[test]
-    fn test_find_json_path_simple() {
-        let json = json!({"name": "Leo"});
-        let target = Value::String("Leo".to_string());
-        let path = find_json_path(&json, &target, vec!["$".to_string()]);
-        assert_eq!(path, Some("$.name".to_string()));
-    }
-
-    #[test]
-    fn test_find_json_path_nested() {
-        let json = json!({
-            "user": {
-                "details": {
-                    "location": "Taiwan"
-                }
-            }
-        });
-        let target = Value::String("Taiwan".to_string());
-        let path = find_json_path(&json, &target, vec!["$".to_string()]);
-        assert_eq!(path, Some("$.user.details.location".to_string()));
-    }
-
-    #[test]
-    fn test_find_json_path_array() {
-        let json = json!(["first", "second", "third"]);
-        let target = Value::String("second".to_string());
-        let path = find_json_path(&json, &target, vec!["$".to_string()]);
-        assert_eq!(path, Some("$.[1]".to_string()));
-    }
-
-    #[test]
-    fn test_find_json_path_not_found() {
-        let json = json!({"name": "Leo"});
-        let target = Value::String("NotFound".to_string());
-        let path = find_json_path(&json, &target, vec!["$".to_string()]);
-        assert_eq!(path, None);
-    }
-
-    #[test]
-    fn test_find_json_paths_matching() {
-        let json = json!({
-            "numbers": [1, 2, 3, 4, 5],
-            "nested": {
-                "value": 10
-            }
-        });
-        
-        let paths = find_json_paths_matching(
-            &json, 
-            |v| v.as_f64().map_or(false, |n| n > 3.0),
-            vec!["$".to_string()]
-        );
-        
-        assert!(paths.contains(&"$.numbers.[3]".to_string())); // 4
-        assert!(paths.contains(&"$.numbers.[4]".to_string())); // 5
-        assert!(paths.contains(&"$.nested.value".to_string())); // 10
-    }
-
-    #[test]
-    fn test_format_path_readable() {
-        assert_eq!(
-            format_path_readable("$.user.details[0].name"),
-            "user → details → item 0 → name"
-        );
-        assert_eq!(
-            format_path_readable("$.simple"),
-            "simple"
-        );
-    }
+//! JSON path finding utilities for locating specific values within JSON structures
+//!
+//! Every path is a canonical JSONPath: `$.items[2]` for array elements and
+//! `$['weird key']` for keys that aren't plain identifiers. Paths are built as
+//! a [`JsonPath`] of key and index segments, which the string forms are
+//! rendered from and which can navigate back to the node.
+
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
+
+/// One step from a node to its child
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PathSegment {
+    /// Member of an object
+    Key(String),
+    /// Element of an array
+    Index(usize),
+}
+
+/// Location of a node as the segments leading to it from the root
+///
+/// Displays as a canonical JSONPath; [`JsonPath::pointer`] displays it as an
+/// RFC 6901 JSON Pointer instead.
+///
+/// # Examples
+/// ```
+/// use serde_json::json;
+/// use json_checker_rs::path_finder::JsonPath;
+///
+/// let json = json!({"items": [{"a.b": 1}]});
+/// let path = JsonPath::parse("$.items[0]['a.b']").unwrap();
+/// assert_eq!(path.resolve(&json), Some(&json!(1)));
+/// assert_eq!(path.pointer().to_string(), "/items/0/a.b");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct JsonPath {
+    pub segments: Vec<PathSegment>,
+}
+
+impl JsonPath {
+    /// Path of the root node, `$`
+    pub fn root() -> Self {
+        JsonPath::default()
+    }
+
+    /// Path of the member `key` of this node
+    pub fn key(mut self, key: &str) -> Self {
+        self.segments.push(PathSegment::Key(key.to_string()));
+        self
+    }
+
+    /// Path of the element `index` of this node
+    pub fn index(mut self, index: usize) -> Self {
+        self.segments.push(PathSegment::Index(index));
+        self
+    }
+
+    /// Parse a canonical JSONPath such as `$.spec['a/b'][0]`, as produced by
+    /// `Display`. Returns `None` for paths that aren't in canonical form.
+    pub fn parse(path: &str) -> Option<Self> {
+        let mut rest = path.strip_prefix('$')?;
+        let mut segments = Vec::new();
+        while !rest.is_empty() {
+            let segment = if let Some(quoted) = rest.strip_prefix("['") {
+                let mut key = String::new();
+                let mut chars = quoted.char_indices();
+                let end = loop {
+                    match chars.next()? {
+                        (_, '\\') => key.push(chars.next()?.1),
+                        (i, '\'') => break i,
+                        (_, c) => key.push(c),
+                    }
+                };
+                rest = quoted[end + 1..].strip_prefix(']')?;
+                PathSegment::Key(key)
+            } else if let Some(indexed) = rest.strip_prefix('[') {
+                let end = indexed.find(']')?;
+                let index = indexed[..end].parse().ok()?;
+                rest = &indexed[end + 1..];
+                PathSegment::Index(index)
+            } else {
+                let key = rest.strip_prefix('.')?;
+                let end = key.find(['.', '[']).unwrap_or(key.len());
+                if end == 0 {
+                    return None;
+                }
+                rest = &key[end..];
+                PathSegment::Key(key[..end].to_string())
+            };
+            segments.push(segment);
+        }
+        Some(JsonPath { segments })
+    }
+
+    /// The node this path leads to in `root`, if there is one
+    pub fn resolve<'a>(&self, root: &'a Value) -> Option<&'a Value> {
+        self.segments.iter().try_fold(root, |node, segment| match segment {
+            PathSegment::Key(key) => node.as_object()?.get(key),
+            PathSegment::Index(index) => node.as_array()?.get(*index),
+        })
+    }
+
+    /// Displays the path as a JSON Pointer, e.g. `/spec/a~1b/0`
+    pub fn pointer(&self) -> JsonPointer<'_> {
+        JsonPointer(self)
+    }
+}
+
+impl fmt::Display for JsonPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut path = String::from("$");
+        for segment in &self.segments {
+            match segment {
+                PathSegment::Key(key) => push_key(&mut path, key),
+                PathSegment::Index(index) => push_index(&mut path, *index),
+            }
+        }
+        f.write_str(&path)
+    }
+}
+
+/// JSON Pointer form of a [`JsonPath`], from [`JsonPath::pointer`]
+#[derive(Debug, Clone, Copy)]
+pub struct JsonPointer<'a>(&'a JsonPath);
+
+impl fmt::Display for JsonPointer<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for segment in &self.0.segments {
+            match segment {
+                PathSegment::Key(key) => write!(f, "/{}", key.replace('~', "~0").replace('/', "~1"))?,
+                PathSegment::Index(index) => write!(f, "/{}", index)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Find the path to a specific value within a JSON structure
+/// 
+/// Returns the canonical JSONPath of the first node equal to the target, in
+/// document order, or None if not found
+/// 
+/// # Arguments
+/// * `value` - The JSON value to search within
+/// * `target` - The target value to find
+/// * `path` - Path components of `value` itself, usually `vec!["$".to_string()]`
+/// 
+/// # Examples
+/// ```
+/// use serde_json::Value;
+/// use json_checker_rs::path_finder::find_json_path;
+/// 
+/// let json: Value = serde_json::from_str(r#"{"user": {"name": "Leo"}}"#).unwrap();
+/// let target = Value::String("Leo".to_string());
+/// let path = find_json_path(&json, &target, vec!["$".to_string()]);
+/// assert_eq!(path, Some("$.user.name".to_string()));
+/// ```
+pub fn find_json_path(value: &Value, target: &Value, path: Vec<String>) -> Option<String> {
+    find_path(value, target).map(|found| prefixed(&path, &found))
+}
+
+/// Find the path of the first node equal to `target`, in document order
+pub fn find_path(value: &Value, target: &Value) -> Option<JsonPath> {
+    let mut found = None;
+    walk(value, &mut JsonPath::root(), &mut |node, at| {
+        if node == target {
+            found = Some(at.clone());
+        }
+        found.is_none()
+    });
+    found
+}
+
+/// Find the paths of all nodes `predicate` returns true for, in document order
+pub fn find_paths(value: &Value, mut predicate: impl FnMut(&Value) -> bool) -> Vec<JsonPath> {
+    let mut results = Vec::new();
+    walk(value, &mut JsonPath::root(), &mut |node, at| {
+        if predicate(node) {
+            results.push(at.clone());
+        }
+        true
+    });
+    results
+}
+
+/// What [`find_matches`] looks for
+#[derive(Debug, Clone)]
+pub enum Target {
+    /// Nodes equal to the value, of any type
+    Value(Value),
+    /// String leaves containing the text
+    Contains(String),
+    /// String leaves the regex matches
+    Regex(regex::Regex),
+}
+
+impl Target {
+    /// Parse a JSON literal such as `42` or `{"a":1}`; anything that isn't
+    /// valid JSON is taken as a plain string
+    pub fn literal(text: &str) -> Self {
+        Target::Value(serde_json::from_str(text).unwrap_or_else(|_| Value::String(text.to_string())))
+    }
+
+    pub fn matches(&self, value: &Value) -> bool {
+        match (self, value) {
+            (Target::Value(target), value) => value == target,
+            (Target::Contains(text), Value::String(s)) => s.contains(text.as_str()),
+            (Target::Regex(regex), Value::String(s)) => regex.is_match(s),
+            _ => false,
+        }
+    }
+}
+
+/// Find the paths of the nodes `target` matches, in document order; with
+/// `all` false, at most the first one
+pub fn find_matches(value: &Value, target: &Target, all: bool) -> Vec<JsonPath> {
+    let mut results = Vec::new();
+    walk(value, &mut JsonPath::root(), &mut |node, at| {
+        if target.matches(node) {
+            results.push(at.clone());
+        }
+        all || results.is_empty()
+    });
+    results
+}
+
+/// Render `found`, a path relative to a node, after the path components of that node
+fn prefixed(prefix: &[String], found: &JsonPath) -> String {
+    let relative = found.to_string();
+    format!("{}{}", prefix.join("."), &relative[1..])
+}
+
+/// Find all paths to values that match a predicate function
+/// 
+/// Returns the canonical JSONPath of every node where the predicate returns
+/// true, in document order
+/// 
+/// # Arguments
+/// * `value` - The JSON value to search within
+/// * `predicate` - Function that returns true for target values
+/// * `path` - Path components of `value` itself, usually `vec!["$".to_string()]`
+pub fn find_json_paths_matching<F>(
+    value: &Value, 
+    predicate: F, 
+    path: Vec<String>
+) -> Vec<String> 
+where
+    F: Fn(&Value) -> bool + Copy,
+{
+    find_paths(value, predicate).iter().map(|found| prefixed(&path, found)).collect()
+}
+
+/// Find paths to values that fail a specific validation check
+/// 
+/// This is useful for identifying exactly which nodes in a JSON structure
+/// are causing validation failures
+/// 
+/// # Arguments
+/// * `value` - The JSON value to search within
+/// * `check_fn` - Function that returns false for invalid values
+/// * `path` - Path components of `value` itself, usually `vec!["$".to_string()]`
+pub fn find_invalid_paths<F>(
+    value: &Value, 
+    check_fn: F, 
+    path: Vec<String>
+) -> Vec<String>
+where
+    F: Fn(&Value) -> bool + Copy,
+{
+    find_json_paths_matching(value, |v| !check_fn(v), path)
+}
+
+/// Find the canonical paths of nodes selected from `root`
+///
+/// Nodes are matched by identity rather than by value, so the result is exact
+/// even when equal values appear several times or the query used filters,
+/// recursive descent, slices or unions. `selected` must borrow from `root`;
+/// nodes that don't are reported as `None`.
+///
+/// # Examples
+/// ```
+/// use serde_json::json;
+/// use json_checker_rs::path_finder::canonical_paths;
+///
+/// let json = json!({"a": [1, 1], "b c": 1});
+/// let selected = jsonpath_lib::select(&json, "$..*").unwrap();
+/// let paths = canonical_paths(&json, &selected);
+/// assert!(paths.contains(&Some("$.a[1]".to_string())));
+/// assert!(paths.contains(&Some("$['b c']".to_string())));
+/// ```
+pub fn canonical_paths(root: &Value, selected: &[&Value]) -> Vec<Option<String>> {
+    node_paths(root, selected)
+        .into_iter()
+        .map(|path| path.map(|path| path.to_string()))
+        .collect()
+}
+
+/// Like [`canonical_paths`], but returning each path as a [`JsonPath`]
+pub fn node_paths(root: &Value, selected: &[&Value]) -> Vec<Option<JsonPath>> {
+    let mut wanted: HashMap<*const Value, Option<JsonPath>> =
+        selected.iter().map(|v| (*v as *const Value, None)).collect();
+    let mut remaining = wanted.len();
+    if remaining > 0 {
+        walk(root, &mut JsonPath::root(), &mut |node, at| {
+            if let Some(slot) = wanted.get_mut(&(node as *const Value))
+                && slot.is_none()
+            {
+                *slot = Some(at.clone());
+                remaining -= 1;
+            }
+            remaining > 0
+        });
+    }
+
+    selected
+        .iter()
+        .map(|v| wanted.get(&(*v as *const Value)).cloned().flatten())
+        .collect()
+}
+
+/// Visit `value` and its descendants depth-first in document order, each
+/// with its canonical path, until `visit` returns false. Returns whether the
+/// walk ran to the end.
+fn walk<F>(value: &Value, path: &mut JsonPath, visit: &mut F) -> bool
+where
+    F: FnMut(&Value, &JsonPath) -> bool,
+{
+    if !visit(value, path) {
+        return false;
+    }
+
+    let children: Box<dyn Iterator<Item = (PathSegment, &Value)>> = match value {
+        Value::Object(map) => Box::new(map.iter().map(|(key, child)| (PathSegment::Key(key.clone()), child))),
+        Value::Array(arr) => Box::new(arr.iter().enumerate().map(|(index, child)| (PathSegment::Index(index), child))),
+        _ => return true,
+    };
+    for (segment, child) in children {
+        path.segments.push(segment);
+        let finished = walk(child, path, visit);
+        path.segments.pop();
+        if !finished {
+            return false;
+        }
+    }
+    true
+}
+
+/// Append an object key to a path, using bracket notation for keys that
+/// aren't plain identifiers
+pub fn push_key(path: &mut String, key: &str) {
+    let mut chars = key.chars();
+    let is_identifier = chars.next().is_some_and(|c| c.is_alphabetic() || c == '_')
+        && chars.all(|c| c.is_alphanumeric() || c == '_');
+
+    if is_identifier {
+        path.push('.');
+        path.push_str(key);
+    } else {
+        path.push_str("['");
+        path.push_str(&key.replace('\\', "\\\\").replace('\'', "\\'"));
+        path.push_str("']");
+    }
+}
+
+/// Append an array index to a path
+pub fn push_index(path: &mut String, index: usize) {
+    path.push_str(&format!("[{}]", index));
+}
+
+/// Convert a canonical path (as built by `canonical_paths`) to an RFC 6901
+/// JSON Pointer, e.g. `$.spec['a/b'][0]` to `/spec/a~1b/0`.
+///
+/// Returns `None` for paths that aren't in canonical form.
+pub fn to_json_pointer(path: &str) -> Option<String> {
+    JsonPath::parse(path).map(|path| path.pointer().to_string())
+}
+
+/// Get a human-readable path representation
+/// 
+/// Converts a path like "$.user.details[0].name" to a more readable format
+pub fn format_path_readable(path: &str) -> String {
+    path.replace("$.", "")
+        .replace("[", " → item ")
+        .replace("]", "")
+        .replace(".", " → ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_find_json_path_simple() {
+        let json = json!({"name": "Leo"});
+        let target = Value::String("Leo".to_string());
+        let path = find_json_path(&json, &target, vec!["$".to_string()]);
+        assert_eq!(path, Some("$.name".to_string()));
+    }
+
+    #[test]
+    fn test_find_json_path_nested() {
+        let json = json!({
+            "user": {
+                "details": {
+                    "location": "Taiwan"
+                }
+            }
+        });
+        let target = Value::String("Taiwan".to_string());
+        let path = find_json_path(&json, &target, vec!["$".to_string()]);
+        assert_eq!(path, Some("$.user.details.location".to_string()));
+    }
+
+    #[test]
+    fn test_find_json_path_array() {
+        let json = json!(["first", "second", "third"]);
+        let target = Value::String("second".to_string());
+        let path = find_json_path(&json, &target, vec!["$".to_string()]);
+        assert_eq!(path, Some("$[1]".to_string()));
+    }
+
+    #[test]
+    fn test_find_json_path_not_found() {
+        let json = json!({"name": "Leo"});
+        let target = Value::String("NotFound".to_string());
+        let path = find_json_path(&json, &target, vec!["$".to_string()]);
+        assert_eq!(path, None);
+    }
+
+    #[test]
+    fn test_find_json_path_number() {
+        let json = json!({"user": {"name": "Leo", "details": {"age": 30}}});
+        let target = Value::Number(serde_json::Number::from(30));
+        let path = find_json_path(&json, &target, vec!["$".to_string()]);
+        assert_eq!(path, Some("$.user.details.age".to_string()));
+    }
+
+    #[test]
+    fn test_find_json_path_escapes_keys() {
+        let json = json!({"items": [{"weird key": {"it's": 30}}]});
+        let target = json!(30);
+        let path = find_json_path(&json, &target, vec!["$".to_string()]);
+        assert_eq!(path, Some(r"$.items[0]['weird key']['it\'s']".to_string()));
+    }
+
+    #[test]
+    fn test_find_json_paths_matching() {
+        let json = json!({
+            "numbers": [1, 2, 3, 4, 5],
+            "nested": {
+                "value": 10
+            }
+        });
+        
+        let paths = find_json_paths_matching(
+            &json, 
+            |v| v.as_f64().is_some_and(|n| n > 3.0),
+            vec!["$".to_string()]
+        );
+        
+        assert!(paths.contains(&"$.numbers[3]".to_string())); // 4
+        assert!(paths.contains(&"$.numbers[4]".to_string())); // 5
+        assert!(paths.contains(&"$.nested.value".to_string())); // 10
+    }
+
+    #[test]
+    fn test_canonical_paths_distinguish_equal_values() {
+        let json = json!({"users": [{"role": "admin"}, {"role": "user"}, {"role": "admin"}]});
+        let selected = jsonpath_lib::select(&json, "$.users[?(@.role == 'admin')].role").unwrap();
+        assert_eq!(
+            canonical_paths(&json, &selected),
+            vec![Some("$.users[0].role".to_string()), Some("$.users[2].role".to_string())]
+        );
+
+        let other = json!("admin");
+        assert_eq!(canonical_paths(&json, &[&other]), vec![None]);
+    }
+
+    #[test]
+    fn test_to_json_pointer() {
+        assert_eq!(to_json_pointer("$").as_deref(), Some(""));
+        assert_eq!(to_json_pointer("$.spec.containers[0].image").as_deref(), Some("/spec/containers/0/image"));
+        assert_eq!(to_json_pointer(r"$['a/b']['it\'s ~']").as_deref(), Some("/a~1b/it's ~0"));
+        assert_eq!(to_json_pointer("users[0]"), None);
+    }
+
+    #[test]
+    fn test_find_matches() {
+        let json = json!({"a": 42, "b": {"c": 1}, "d": ["x-1", "y-2", 42]});
+        let paths = |target: &Target, all| -> Vec<String> {
+            find_matches(&json, target, all).iter().map(ToString::to_string).collect()
+        };
+        assert_eq!(paths(&Target::literal("42"), true), ["$.a", "$.d[2]"]);
+        assert_eq!(paths(&Target::literal("42"), false), ["$.a"]);
+        assert_eq!(paths(&Target::literal(r#"{"c":1}"#), true), ["$.b"]);
+        assert_eq!(paths(&Target::literal("x-1"), true), ["$.d[0]"]);
+        assert_eq!(paths(&Target::Contains("-".to_string()), true), ["$.d[0]", "$.d[1]"]);
+        assert_eq!(paths(&Target::Regex(regex::Regex::new(r"^y-\d$").unwrap()), true), ["$.d[1]"]);
+    }
+
+    #[test]
+    fn test_json_path_segments() {
+        let json = json!({"a.b": [{"c": true}], "x": 1});
+        let path = find_path(&json, &json!(true)).unwrap();
+        assert_eq!(
+            path.segments,
+            [PathSegment::Key("a.b".to_string()), PathSegment::Index(0), PathSegment::Key("c".to_string())]
+        );
+        assert_eq!(path.to_string(), "$['a.b'][0].c");
+        assert_eq!(JsonPath::parse(&path.to_string()), Some(path.clone()));
+        assert_eq!(path.resolve(&json), Some(&json!(true)));
+        assert_eq!(JsonPath::root().key("x").index(0).resolve(&json), None);
+        assert_eq!(JsonPath::parse("$.a[x]"), None);
+    }
+
+    #[test]
+    fn test_format_path_readable() {
+        assert_eq!(
+            format_path_readable("$.user.details[0].name"),
+            "user → details → item 0 → name"
+        );
+        assert_eq!(
+            format_path_readable("$.simple"),
+            "simple"
+        );
+    }
 }
\ No newline at end of file
@@ -0,0 +1,180 @@
+//! Protobuf JSON mapping
+//!
+//! Protobuf's JSON encoding writes 64-bit integers as strings (`"1234"`),
+//! `google.protobuf.Duration` as seconds with an `s` suffix (`"1.5s"`) and
+//! `google.protobuf.Timestamp` as an RFC 3339 string. With `protobuf_mode`
+//! on a rule whose check compares numbers, such strings are read as the
+//! numbers they stand for: durations in seconds, timestamps in seconds since
+//! the Unix epoch. Enum values are written as their names, which aren't
+//! numbers, so they stay strings.
+
+use serde_json::{Number, Value};
+use std::borrow::Cow;
+
+/// `values` with every protobuf-encoded number in them read as a number;
+/// values without any are left borrowed
+pub fn read_numbers<'a>(values: Vec<Cow<'a, Value>>) -> Vec<Cow<'a, Value>> {
+    values
+        .into_iter()
+        .map(|value| match mapped(&value) {
+            Some(mapped) => Cow::Owned(mapped),
+            None => value,
+        })
+        .collect()
+}
+
+/// `value` with its protobuf-encoded numbers replaced, recursively, or
+/// `None` if it has none
+fn mapped(value: &Value) -> Option<Value> {
+    match value {
+        Value::String(s) => number(s).map(Value::Number),
+        Value::Array(items) => {
+            let mapped: Vec<Option<Value>> = items.iter().map(mapped).collect();
+            mapped.iter().any(Option::is_some).then(|| {
+                Value::Array(mapped.into_iter().zip(items).map(|(m, item)| m.unwrap_or_else(|| item.clone())).collect())
+            })
+        }
+        Value::Object(map) => {
+            let mapped: Vec<Option<Value>> = map.values().map(mapped).collect();
+            mapped.iter().any(Option::is_some).then(|| {
+                Value::Object(
+                    map.iter()
+                        .zip(mapped)
+                        .map(|((key, item), m)| (key.clone(), m.unwrap_or_else(|| item.clone())))
+                        .collect(),
+                )
+            })
+        }
+        _ => None,
+    }
+}
+
+/// The number a protobuf JSON string stands for: a 64-bit integer, a
+/// duration in seconds or a timestamp in seconds since the Unix epoch
+pub fn number(s: &str) -> Option<Number> {
+    if is_integer(s) {
+        return s.parse().ok();
+    }
+    if let Some(seconds) = s.strip_suffix('s')
+        && is_decimal(seconds)
+    {
+        return seconds.parse().ok();
+    }
+    timestamp_nanos(s).and_then(|nanos| seconds_from_nanos(nanos).parse().ok())
+}
+
+/// `-?(0|[1-9][0-9]*)`, the way int64 and uint64 values are written
+fn is_integer(s: &str) -> bool {
+    let digits = s.strip_prefix('-').unwrap_or(s);
+    !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit()) && (digits == "0" || !digits.starts_with('0'))
+}
+
+/// An integer with up to nine decimal places, as durations are written
+fn is_decimal(s: &str) -> bool {
+    match s.split_once('.') {
+        Some((integer, fraction)) => {
+            is_integer(integer) && (1..=9).contains(&fraction.len()) && fraction.bytes().all(|b| b.is_ascii_digit())
+        }
+        None => is_integer(s),
+    }
+}
+
+/// Nanoseconds since the Unix epoch of an RFC 3339 timestamp such as
+/// `2024-02-29T12:30:00.5Z` or `2024-02-29T14:30:00+02:00`
+fn timestamp_nanos(s: &str) -> Option<i128> {
+    let (date, time) = s.split_once(['T', 't'])?;
+    let [year, month, day] = fields(date, '-')?;
+    let (time, offset_seconds) = match time.strip_suffix(['Z', 'z']) {
+        Some(time) => (time, 0),
+        None => {
+            let at = time.rfind(['+', '-'])?;
+            let [hours, minutes] = fields(&time[at + 1..], ':')?;
+            let sign = if time[at..].starts_with('-') { -1 } else { 1 };
+            (&time[..at], sign * (hours * 3600 + minutes * 60))
+        }
+    };
+    let (time, fraction) = time.split_once('.').unwrap_or((time, ""));
+    let [hour, minute, second] = fields(time, ':')?;
+    if !(1..=12).contains(&month) || day < 1 || day > days_in_month(year, month) || hour > 23 || minute > 59 || second > 60 {
+        return None;
+    }
+    if fraction.len() > 9 || !fraction.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let nanos = if fraction.is_empty() { 0 } else { format!("{:0<9}", fraction).parse::<i128>().ok()? };
+
+    let seconds = days_from_civil(year, month, day) * 86_400 + hour * 3600 + minute * 60 + second - offset_seconds;
+    Some(i128::from(seconds) * 1_000_000_000 + nanos)
+}
+
+/// Exactly `N` fixed-width numeric fields separated by `separator`
+fn fields<const N: usize>(s: &str, separator: char) -> Option<[i64; N]> {
+    let mut parts = s.split(separator);
+    let mut fields = [0; N];
+    for (i, field) in fields.iter_mut().enumerate() {
+        let part = parts.next()?;
+        let width = if i == 0 && separator == '-' { 4 } else { 2 };
+        if part.len() != width || !part.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        *field = part.parse().ok()?;
+    }
+    parts.next().is_none().then_some(fields)
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Days since 1970-01-01 of a date in the proleptic Gregorian calendar
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// `nanos` as a decimal number of seconds, e.g. `-0.5`
+fn seconds_from_nanos(nanos: i128) -> String {
+    let sign = if nanos < 0 { "-" } else { "" };
+    let (seconds, fraction) = (nanos.unsigned_abs() / 1_000_000_000, nanos.unsigned_abs() % 1_000_000_000);
+    match fraction {
+        0 => format!("{}{}", sign, seconds),
+        _ => format!("{}{}.{}", sign, seconds, format!("{:09}", fraction).trim_end_matches('0')),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_number() {
+        let number = |s: &str| number(s).and_then(|n| n.as_f64());
+        assert_eq!(number("-9007199254740993"), Some(-9007199254740993.0));
+        assert_eq!(number("1.500s"), Some(1.5));
+        assert_eq!(number("-0.000000001s"), Some(-0.000000001));
+        assert_eq!(number("1970-01-01T00:00:00Z"), Some(0.0));
+        assert_eq!(number("2024-02-29T14:30:00.25+02:00"), Some(1709209800.25));
+        assert_eq!(number("1969-12-31T23:59:59.5Z"), Some(-0.5));
+        for text in ["007", "1e3", "+1", "1.s", "ACTIVE", "2023-02-29T00:00:00Z", "2024-01-01T00:00:00", ""] {
+            assert_eq!(number(text), None, "{}", text);
+        }
+    }
+
+    #[test]
+    fn test_read_numbers() {
+        let values = [json!({"id": "12", "state": "ACTIVE", "ttl": "30s"}), json!(true)];
+        let mapped = read_numbers(values.iter().map(Cow::Borrowed).collect());
+        assert_eq!(*mapped[0], json!({"id": 12, "state": "ACTIVE", "ttl": 30}));
+        assert!(matches!(mapped[1], Cow::Borrowed(_)));
+    }
+}
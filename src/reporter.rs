@@ -0,0 +1,83 @@
+//! Hooks for observing a run as it happens
+//!
+//! Embedders implement [`Reporter`] to stream results into their own logging
+//! or metrics instead of post-processing the final list.
+
+use crate::config::{CheckResult, Rule};
+
+/// Receives events while a [`JsonChecker`](crate::JsonChecker) runs its rules
+///
+/// Every method has an empty default, so implementors only override what they need.
+pub trait Reporter {
+    /// Called before a rule is evaluated or skipped, in execution order
+    fn on_rule_start(&mut self, _rule: &Rule) {}
+
+    /// Called with a rule's result as soon as it is known
+    fn on_rule_result(&mut self, _rule: &Rule, _result: &CheckResult) {}
+
+    /// Called once after the run with every result, in config order
+    fn on_finish(&mut self, _results: &[CheckResult]) {}
+}
+
+/// Reporter that ignores every event
+impl Reporter for () {}
+
+/// Adapts a `(done, total, result)` callback to a [`Reporter`]
+pub(crate) struct ProgressReporter<F> {
+    pub on_result: F,
+    pub done: usize,
+    pub total: usize,
+}
+
+impl<F: FnMut(usize, usize, &CheckResult)> Reporter for ProgressReporter<F> {
+    fn on_rule_result(&mut self, _rule: &Rule, result: &CheckResult) {
+        self.done += 1;
+        (self.on_result)(self.done, self.total, result);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CheckRule, Config, JsonChecker};
+
+    #[derive(Default)]
+    struct Recorder(Vec<String>);
+
+    impl Reporter for Recorder {
+        fn on_rule_start(&mut self, rule: &Rule) {
+            self.0.push(format!("start {}", rule.name));
+        }
+
+        fn on_rule_result(&mut self, rule: &Rule, result: &CheckResult) {
+            self.0.push(format!("{} {}", rule.name, result.passed));
+        }
+
+        fn on_finish(&mut self, results: &[CheckResult]) {
+            self.0.push(format!("finish {}", results.len()));
+        }
+    }
+
+    #[test]
+    fn test_reporter_sees_every_event_in_execution_order() {
+        let rule = |name: &str, depends_on: &[&str]| Rule {
+            name: name.to_string(),
+            json_inline: Some(serde_json::json!({"a": 1})),
+            jsonpath: "$.a".to_string(),
+            check: CheckRule::NonEmpty,
+            depends_on: depends_on.iter().map(|s| s.to_string()).collect(),
+            ..Default::default()
+        };
+        let checker = JsonChecker::from_config(Config {
+            rules: vec![rule("second", &["first"]), rule("first", &[])],
+            ..Default::default()
+        });
+
+        let mut recorder = Recorder::default();
+        checker.run_with_reporter(&mut recorder).unwrap();
+        assert_eq!(
+            recorder.0,
+            vec!["start first", "first true", "start second", "second true", "finish 2"]
+        );
+    }
+}
@@ -0,0 +1,99 @@
+//! Assertions on the HTTP response a document was fetched with
+//!
+//! A rule whose `json_file` is an `http://` or `https://` URL can also check
+//! the response itself with a `response` block. The response is checked
+//! before its body: a failing assertion fails the rule without looking at
+//! the document, which is likely an error page.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Expected properties of the response a rule's document is fetched with
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, JsonSchema, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ResponseAssertions {
+    /// Exact status code, e.g. `200`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+    /// Media type, e.g. `application/json`; parameters such as `charset`
+    /// and letter case are ignored
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+    /// Longest acceptable time from sending the request to reading the whole body
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_latency_ms: Option<u64>,
+}
+
+/// A fetched document with the response metadata assertions check
+#[derive(Debug, Clone)]
+pub struct HttpResponse {
+    pub status: u16,
+    pub content_type: Option<String>,
+    pub latency: Duration,
+    pub body: String,
+}
+
+impl ResponseAssertions {
+    /// Describe every assertion `response` doesn't meet
+    pub fn failures(&self, response: &HttpResponse) -> Vec<String> {
+        let mut failures = Vec::new();
+
+        if let Some(status) = self.status
+            && response.status != status
+        {
+            failures.push(format!("status is {}, expected {}", response.status, status));
+        }
+        if let Some(expected) = &self.content_type {
+            let actual = response.content_type.as_deref().unwrap_or_default();
+            if !media_type(actual).eq_ignore_ascii_case(media_type(expected)) {
+                let actual = if actual.is_empty() { "missing" } else { actual };
+                failures.push(format!("content-type is {}, expected {}", actual, expected));
+            }
+        }
+        if let Some(max) = self.max_latency_ms
+            && response.latency > Duration::from_millis(max)
+        {
+            failures.push(format!("latency is {}ms, expected at most {}ms", response.latency.as_millis(), max));
+        }
+
+        failures
+    }
+}
+
+/// `application/json` of `application/json; charset=utf-8`
+fn media_type(content_type: &str) -> &str {
+    content_type.split(';').next().unwrap_or_default().trim()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_response_failures() {
+        let response = HttpResponse {
+            status: 200,
+            content_type: Some("Application/JSON; charset=utf-8".to_string()),
+            latency: Duration::from_millis(120),
+            body: "{}".to_string(),
+        };
+        let mut assertions = ResponseAssertions {
+            status: Some(200),
+            content_type: Some("application/json".to_string()),
+            max_latency_ms: Some(500),
+        };
+        assert!(assertions.failures(&response).is_empty());
+
+        assertions.status = Some(201);
+        assertions.max_latency_ms = Some(100);
+        assert_eq!(
+            assertions.failures(&response),
+            vec!["status is 200, expected 201", "latency is 120ms, expected at most 100ms"]
+        );
+
+        let untyped = HttpResponse { content_type: None, ..response };
+        let html = ResponseAssertions { content_type: Some("text/html".to_string()), ..Default::default() };
+        assert_eq!(html.failures(&untyped), vec!["content-type is missing, expected text/html"]);
+    }
+}
@@ -0,0 +1,167 @@
+//! Retries for loading documents from flaky sources
+//!
+//! A `retry` policy on a rule (or the whole config) retries a failed load
+//! with a doubling delay, optionally giving up on attempts that take too
+//! long. When every attempt fails, the error carries a log of the attempts,
+//! which ends up in the rule's `CheckResult::retry_log`.
+
+use anyhow::Result;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How often and how patiently to load a document
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, JsonSchema, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RetryPolicy {
+    /// Total number of attempts, including the first
+    pub attempts: u32,
+    /// Delay before the second attempt; doubled before each further one
+    pub backoff_ms: u64,
+    /// Give up on an attempt after this long. The attempt isn't interrupted,
+    /// only abandoned, so its thread may linger until the source returns
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout_ms: Option<u64>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy { attempts: 3, backoff_ms: 500, timeout_ms: None }
+    }
+}
+
+/// Every attempt failed; `log` describes each one, oldest first
+#[derive(Debug)]
+pub struct RetriesExhausted {
+    pub log: Vec<String>,
+    pub last_error: anyhow::Error,
+}
+
+impl fmt::Display for RetriesExhausted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "gave up after {} attempt(s): {:#}", self.log.len(), self.last_error)
+    }
+}
+
+impl std::error::Error for RetriesExhausted {}
+
+/// Run `job` until it succeeds or the policy's attempts are used up
+pub fn run<T, F>(policy: &RetryPolicy, job: F) -> Result<T>
+where
+    T: Send + 'static,
+    F: Fn() -> Result<T> + Send + Sync + 'static,
+{
+    let job = Arc::new(job);
+    let mut log = Vec::new();
+    let mut backoff = Duration::from_millis(policy.backoff_ms);
+
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+        let started = Instant::now();
+        let error = match attempt_once(policy, &job) {
+            Ok(value) => return Ok(value),
+            Err(error) => error,
+        };
+        log.push(format!("attempt {} failed after {}ms: {:#}", attempt, started.elapsed().as_millis(), error));
+        if attempt >= policy.attempts {
+            return Err(RetriesExhausted { log, last_error: error }.into());
+        }
+
+        thread::sleep(backoff);
+        backoff *= 2;
+    }
+}
+
+/// Like `run`, for a job loading its document asynchronously; the backoff
+/// and attempt timeouts use tokio's timer, which the runtime must enable
+#[cfg(feature = "async")]
+pub async fn run_async<T, F, Fut>(policy: &RetryPolicy, mut job: F) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = Result<T>>,
+{
+    let mut log = Vec::new();
+    let mut backoff = Duration::from_millis(policy.backoff_ms);
+
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+        let started = Instant::now();
+        let outcome = match policy.timeout_ms {
+            Some(timeout_ms) => tokio::time::timeout(Duration::from_millis(timeout_ms), job())
+                .await
+                .unwrap_or_else(|_| Err(anyhow::anyhow!("timed out after {}ms", timeout_ms))),
+            None => job().await,
+        };
+        let error = match outcome {
+            Ok(value) => return Ok(value),
+            Err(error) => error,
+        };
+        log.push(format!("attempt {} failed after {}ms: {:#}", attempt, started.elapsed().as_millis(), error));
+        if attempt >= policy.attempts {
+            return Err(RetriesExhausted { log, last_error: error }.into());
+        }
+
+        tokio::time::sleep(backoff).await;
+        backoff *= 2;
+    }
+}
+
+fn attempt_once<T, F>(policy: &RetryPolicy, job: &Arc<F>) -> Result<T>
+where
+    T: Send + 'static,
+    F: Fn() -> Result<T> + Send + Sync + 'static,
+{
+    let Some(timeout_ms) = policy.timeout_ms else {
+        return job();
+    };
+
+    let (sender, receiver) = mpsc::channel();
+    let job = Arc::clone(job);
+    thread::spawn(move || {
+        // The receiver is gone if the attempt timed out
+        let _ = sender.send(job());
+    });
+    receiver
+        .recv_timeout(Duration::from_millis(timeout_ms))
+        .unwrap_or_else(|_| Err(anyhow::anyhow!("timed out after {}ms", timeout_ms)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    #[test]
+    fn test_retries_until_success() {
+        let calls = Arc::new(AtomicU32::new(0));
+        let counter = Arc::clone(&calls);
+        let policy = RetryPolicy { attempts: 3, backoff_ms: 1, timeout_ms: None };
+        let value = run(&policy, move || match counter.fetch_add(1, Ordering::SeqCst) {
+            0 => anyhow::bail!("not yet"),
+            n => Ok(n),
+        });
+        assert_eq!(value.unwrap(), 1);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_exhausted_retries_keep_a_log() {
+        let policy = RetryPolicy { attempts: 2, backoff_ms: 1, timeout_ms: Some(20) };
+        let err = run(&policy, || -> Result<()> {
+            thread::sleep(Duration::from_millis(200));
+            Ok(())
+        })
+        .unwrap_err();
+
+        let exhausted = err.downcast_ref::<RetriesExhausted>().unwrap();
+        assert_eq!(exhausted.log.len(), 2);
+        assert!(exhausted.log[1].starts_with("attempt 2 failed after"), "{:?}", exhausted.log);
+        assert!(err.to_string().ends_with("timed out after 20ms"), "{}", err);
+    }
+}
@@ -1,42 +1,993 @@
-//! Check rule definitions and validation logic
-
-use serde::{Deserialize, Serialize};
-use serde_json::Value;
-
-/// All available check rule types for JSON validation
-#[derive(Debug, Deserialize, Serialize, Clone)]
-#[serde(tag = "type", rename_all = "snake_case")]
-pub enum CheckRule {
-    /// Check if value is empty (null, empty string, empty array, empty object)
-    Empty,
-    /// Check if value is non-empty
-    NonEmpty,
-    /// Check if value equals a specific value
-    Equals { value: Value },
-    /// Check if value does not equal a specific value
-    NotEquals { value: Value },
-    /// Check if container contains a specific value
-    Contains { value: Value },
-    /// Check if value is contained by a container
-    ContainedBy { value: Value },
-    /// PostgreSQL @> operator: left contains right (JSONB)
-    JsonbContains { value: Value },
-    /// PostgreSQL <@ operator: left is contained by right (JSONB)
-    JsonbContainedBy { value: Value },
-    /// PostgreSQL ? operator: check if key exists
-    JsonbExists { key: String },
-    /// PostgreSQL ?| operator: check if any of the keys exist
-    JsonbExistsAny { keys: Vec<String> },
-    /// PostgreSQL ?& operator: check if all keys exist
-    JsonbExistsAll { keys: Vec<String> },
-    /// PostgreSQL @@ operator: JSONPath match (simplified)
-    JsonbPathMatch { path: String },
-    /// Regular expression pattern matching
-    Regex { pattern: String },
-    /// Check if numeric value is greater than threshold
-    GreaterThan { value: f64 },
-    /// Check if numeric value is less than threshold
-    LessThan { value: f64 },
-    /// Check array length constraints
-    ArrayLength { min: Option<usize>, max: Option<usize> },
-}
\ No newline at end of file
+//! Check rule definitions and validation logic
+
+use anyhow::{anyhow, Result};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{Number, Value};
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::jsonpath::{self, JsonPathEngine};
+use crate::number;
+use crate::path_finder::{push_index, push_key};
+use crate::schema;
+
+/// All available check rule types for JSON validation
+#[derive(Debug, Default, Deserialize, JsonSchema, Serialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum CheckRule {
+    /// Check if value is empty (null, empty string, empty array, empty object)
+    Empty,
+    /// Check if value is non-empty
+    #[default]
+    NonEmpty,
+    /// Check if value equals a specific value; numbers may differ from it by
+    /// up to `tolerance`
+    Equals {
+        value: Value,
+        #[serde(default, skip_serializing_if = "Option::is_none", deserialize_with = "number::deserialize_option_f64")]
+        tolerance: Option<f64>,
+    },
+    /// Check if value does not equal a specific value
+    NotEquals { value: Value },
+    /// Check if container contains a specific value
+    Contains { value: Value },
+    /// Check if value is contained by a container
+    ContainedBy { value: Value },
+    /// PostgreSQL @> operator: left contains right (JSONB)
+    JsonbContains { value: Value },
+    /// PostgreSQL <@ operator: left is contained by right (JSONB)
+    JsonbContainedBy { value: Value },
+    /// PostgreSQL ? operator: check if key exists
+    JsonbExists { key: String },
+    /// PostgreSQL ?| operator: check if any of the keys exist
+    JsonbExistsAny { keys: Vec<String> },
+    /// PostgreSQL ?& operator: check if all keys exist
+    JsonbExistsAll { keys: Vec<String> },
+    /// PostgreSQL @@ operator: JSONPath match (simplified)
+    JsonbPathMatch { path: String },
+    /// Regular expression pattern matching
+    Regex { pattern: String },
+    /// Check if a string starts with a prefix
+    StartsWith { value: String },
+    /// Check if a string ends with a suffix
+    EndsWith { value: String },
+    /// Check if a string is in a well-known format such as a UUID or an email address
+    Format { kind: StringFormat },
+    /// Check if a number is within `epsilon` of `value`
+    ApproxEquals {
+        #[serde(deserialize_with = "number::deserialize_f64")]
+        value: f64,
+        #[serde(deserialize_with = "number::deserialize_f64")]
+        epsilon: f64,
+    },
+    /// Check if numeric value is greater than threshold; thresholds compare
+    /// exactly, as the numbers are written
+    GreaterThan { value: Number },
+    /// Check if numeric value is less than threshold
+    LessThan { value: Number },
+    /// Check if numeric value is greater than or equal to threshold
+    #[serde(alias = "gte")]
+    #[schemars(transform = schema::alias("gte"))]
+    GreaterThanOrEqual { value: Number },
+    /// Check if numeric value is less than or equal to threshold
+    #[serde(alias = "lte")]
+    #[schemars(transform = schema::alias("lte"))]
+    LessThanOrEqual { value: Number },
+    /// Check if a number is whole (`3` and `3.0`, but not `3.5`)
+    Integer,
+    /// Check if a number is a multiple of `value`
+    MultipleOf {
+        #[serde(deserialize_with = "number::deserialize_f64")]
+        value: f64,
+    },
+    /// Check if a number has at most `max` digits after the decimal point,
+    /// as written (`1.50` has one)
+    DecimalPlaces { max: u32 },
+    /// Check if a number is an integer a double represents exactly, i.e.
+    /// within ±(2^53 - 1) like JavaScript's `Number.isSafeInteger`
+    SafeInteger,
+    /// Check that arrays are sorted, optionally by a JSONPath into each element
+    Sorted {
+        #[serde(default)]
+        order: SortOrder,
+        #[serde(default)]
+        by: Option<String>,
+    },
+    /// Check that every value exists among the nodes `target_jsonpath` selects
+    /// in another document (referential integrity)
+    RefIntegrity {
+        target_file: String,
+        target_jsonpath: String,
+    },
+    /// Check that values are structurally equal to the document in
+    /// `baseline_file`, except at or inside the nodes `allowed_paths` select
+    /// (JSONPaths rooted at the compared value)
+    Diff {
+        baseline_file: String,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        allowed_paths: Vec<String>,
+    },
+    /// Check that values deep-equal the reference document in `file`, ignoring
+    /// volatile nodes such as timestamps and ids that `ignore_paths` select
+    /// (JSONPaths rooted at the compared value)
+    MatchesDocument {
+        file: String,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        ignore_paths: Vec<String>,
+    },
+    /// Check that the nodes two JSONPaths (rooted at the checked value)
+    /// select are deep-equal; a path ending in `.length()` stands for the
+    /// length of what it selects
+    PathsEqual {
+        left_jsonpath: String,
+        right_jsonpath: String,
+    },
+    /// Check that objects have all the keys, optionally of a given JSON type
+    RequiredKeys { keys: Vec<RequiredKey> },
+    /// Check that none of the keys appear anywhere inside the value (recursively)
+    ForbiddenKeys { keys: Vec<String> },
+    /// Check that every object key at any depth inside the value follows a
+    /// naming convention; `pattern` is the regex of the `regex` convention
+    KeyFormat {
+        convention: KeyConvention,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pattern: Option<String>,
+    },
+    /// Check that no null appears anywhere inside the value (recursively),
+    /// except at or inside the nodes `allowed_paths` select (JSONPaths rooted
+    /// at the checked value)
+    NoNullsAnywhere {
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        allowed_paths: Vec<String>,
+    },
+    /// Check array length constraints
+    ArrayLength { min: Option<usize>, max: Option<usize> },
+    /// Check that values nest at most `depth` levels of arrays and objects
+    MaxDepth { depth: usize },
+    /// Check that values serialize to at most `bytes` bytes of compact JSON
+    MaxSizeBytes { bytes: usize },
+    /// Run an external program with each value as JSON on stdin; exit code 0 passes
+    Command {
+        program: String,
+        #[serde(default)]
+        args: Vec<String>,
+        /// Kill the program after this many milliseconds (default 10s)
+        #[serde(default)]
+        timeout_ms: Option<u64>,
+        /// Run the program with an empty environment (only `PATH` is kept)
+        #[serde(default)]
+        clear_env: bool,
+    },
+    /// Evaluate a boolean expression with each value bound to `value`
+    Expr { expr: String },
+    /// Compare an aggregate of the selected values (e.g. their sum) to a threshold
+    Aggregate {
+        function: AggregateFunction,
+        operator: Comparison,
+        #[serde(deserialize_with = "number::deserialize_f64")]
+        value: f64,
+    },
+    /// Check how many nodes the JSONPath selected
+    MatchCount { min: Option<usize>, max: Option<usize> },
+    /// Check that all selected values are distinct
+    Unique,
+    /// Check that all selected values are arrays of the same length
+    SameLength,
+    /// Reference to a named check in the config's `check_templates`,
+    /// replaced by the template when the config is loaded
+    Template {
+        name: String,
+        #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+        params: HashMap<String, Value>,
+    },
+}
+
+/// Aggregation applied to the selected values as a whole
+#[derive(Debug, Deserialize, JsonSchema, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AggregateFunction {
+    Sum,
+    Avg,
+    Min,
+    Max,
+    /// Number of selected values, whatever their type
+    Count,
+}
+
+impl AggregateFunction {
+    /// Compute the aggregate of a set of values
+    ///
+    /// Returns `None` for `avg`, `min` and `max` of an empty set. All functions
+    /// except `count` require numeric values.
+    pub fn apply(&self, values: &[&Value]) -> Result<Option<f64>> {
+        if *self == AggregateFunction::Count {
+            return Ok(Some(values.len() as f64));
+        }
+
+        let numbers = values
+            .iter()
+            .map(|v| {
+                v.as_f64()
+                    .ok_or_else(|| anyhow!("Aggregate {} requires numeric values, found {}", self, v))
+            })
+            .collect::<Result<Vec<f64>>>()?;
+
+        Ok(match self {
+            AggregateFunction::Sum => Some(numbers.iter().sum()),
+            AggregateFunction::Avg if numbers.is_empty() => None,
+            AggregateFunction::Avg => Some(numbers.iter().sum::<f64>() / numbers.len() as f64),
+            AggregateFunction::Min => numbers.into_iter().reduce(f64::min),
+            AggregateFunction::Max => numbers.into_iter().reduce(f64::max),
+            AggregateFunction::Count => unreachable!(),
+        })
+    }
+}
+
+impl fmt::Display for AggregateFunction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            AggregateFunction::Sum => "sum",
+            AggregateFunction::Avg => "avg",
+            AggregateFunction::Min => "min",
+            AggregateFunction::Max => "max",
+            AggregateFunction::Count => "count",
+        })
+    }
+}
+
+/// Naming conventions for the `key_format` check
+#[derive(Debug, Deserialize, JsonSchema, Serialize, Clone, Copy, PartialEq, Eq)]
+pub enum KeyConvention {
+    /// `user_id`
+    #[serde(rename = "snake_case")]
+    SnakeCase,
+    /// `userId`
+    #[serde(rename = "camelCase")]
+    CamelCase,
+    /// `user-id`
+    #[serde(rename = "kebab-case")]
+    KebabCase,
+    /// Keys match the check's `pattern`
+    #[serde(rename = "regex")]
+    Regex,
+}
+
+impl KeyConvention {
+    /// The regex keys must match: the convention's own, or `pattern` for `regex`
+    pub fn pattern<'a>(&self, pattern: Option<&'a str>) -> Result<&'a str> {
+        match self {
+            KeyConvention::SnakeCase => Ok("^[a-z][a-z0-9]*(_[a-z0-9]+)*$"),
+            KeyConvention::CamelCase => Ok("^[a-z][a-zA-Z0-9]*$"),
+            KeyConvention::KebabCase => Ok("^[a-z][a-z0-9]*(-[a-z0-9]+)*$"),
+            KeyConvention::Regex => pattern.ok_or_else(|| anyhow!("key_format with the regex convention needs a pattern")),
+        }
+    }
+}
+
+/// Built-in string formats for the `format` check
+#[derive(Debug, Deserialize, JsonSchema, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum StringFormat {
+    /// Hyphenated UUID such as `123e4567-e89b-12d3-a456-426614174000`
+    Uuid,
+    /// `local@domain` where the domain is a hostname with at least two labels
+    Email,
+    /// Absolute URL with a scheme and a host, such as `https://example.com/a`
+    Url,
+    Ipv4,
+    Ipv6,
+    /// RFC 1123 hostname
+    Hostname,
+    /// Standard base64 with padding
+    Base64,
+    /// No uppercase characters
+    Lowercase,
+    /// No lowercase characters
+    Uppercase,
+}
+
+impl StringFormat {
+    /// Whether `s` is in this format
+    pub fn matches(&self, s: &str) -> bool {
+        match self {
+            StringFormat::Uuid => is_uuid(s),
+            StringFormat::Email => s
+                .rsplit_once('@')
+                .is_some_and(|(local, domain)| {
+                    !local.is_empty()
+                        && !local.contains(|c: char| c.is_whitespace() || c == '@')
+                        && domain.contains('.')
+                        && is_hostname(domain)
+                }),
+            StringFormat::Url => is_url(s),
+            StringFormat::Ipv4 => s.parse::<std::net::Ipv4Addr>().is_ok(),
+            StringFormat::Ipv6 => s.parse::<std::net::Ipv6Addr>().is_ok(),
+            StringFormat::Hostname => is_hostname(s),
+            StringFormat::Base64 => is_base64(s),
+            StringFormat::Lowercase => !s.chars().any(char::is_uppercase),
+            StringFormat::Uppercase => !s.chars().any(char::is_lowercase),
+        }
+    }
+}
+
+fn is_uuid(s: &str) -> bool {
+    let groups: Vec<&str> = s.split('-').collect();
+    groups.len() == 5
+        && groups.iter().zip([8, 4, 4, 4, 12]).all(|(group, len)| {
+            group.len() == len && group.chars().all(|c| c.is_ascii_hexdigit())
+        })
+}
+
+fn is_hostname(s: &str) -> bool {
+    let s = s.strip_suffix('.').unwrap_or(s);
+    !s.is_empty()
+        && s.len() <= 253
+        && s.split('.').all(|label| {
+            !label.is_empty()
+                && label.len() <= 63
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        })
+}
+
+fn is_url(s: &str) -> bool {
+    let Some((scheme, rest)) = s.split_once("://") else {
+        return false;
+    };
+    let scheme_ok = scheme.chars().next().is_some_and(|c| c.is_ascii_alphabetic())
+        && scheme.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+
+    // Host, with optional userinfo and port
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    let host = authority.rsplit('@').next().unwrap_or_default();
+    let host_ok = match host.strip_prefix('[') {
+        Some(bracketed) => bracketed
+            .split_once(']')
+            .is_some_and(|(ip, _)| ip.parse::<std::net::Ipv6Addr>().is_ok()),
+        None => {
+            let name = match host.rsplit_once(':') {
+                Some((name, port)) if port.chars().all(|c| c.is_ascii_digit()) => name,
+                _ => host,
+            };
+            is_hostname(name)
+        }
+    };
+
+    scheme_ok && host_ok && !s.contains(char::is_whitespace)
+}
+
+fn is_base64(s: &str) -> bool {
+    let data = s.trim_end_matches('=');
+    s.len().is_multiple_of(4)
+        && s.len() - data.len() <= 2
+        && data.chars().all(|c| c.is_ascii_alphanumeric() || c == '+' || c == '/')
+}
+
+/// Direction of a `sorted` check; equal neighbours are allowed either way
+#[derive(Debug, Default, Deserialize, JsonSchema, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+/// Find the first pair of neighbouring elements that is out of order
+///
+/// Returns the index of the first element of the pair. Elements are compared
+/// by the first node `by` selects in each, or by themselves; numbers, strings
+/// and booleans compare among their own type and anything else counts as out
+/// of order.
+pub fn first_unsorted_pair(
+    items: &[Value],
+    order: SortOrder,
+    by: Option<&str>,
+    engine: JsonPathEngine,
+) -> Result<Option<usize>> {
+    let keys = match by {
+        Some(path) => {
+            let path = sort_key_path(path);
+            let compiled = jsonpath::compile_with(&path, engine)?;
+            items
+                .iter()
+                .map(|item| Ok(compiled.select(item)?.first().copied()))
+                .collect::<Result<Vec<Option<&Value>>>>()?
+        }
+        None => items.iter().map(Some).collect(),
+    };
+
+    Ok(keys.windows(2).position(|pair| {
+        let ordering = match (pair[0], pair[1]) {
+            (Some(Value::Number(a)), Some(Value::Number(b))) => Some(number::compare(a, b)),
+            (Some(Value::String(a)), Some(Value::String(b))) => Some(a.cmp(b)),
+            (Some(Value::Bool(a)), Some(Value::Bool(b))) => Some(a.cmp(b)),
+            _ => None,
+        };
+        match (ordering, order) {
+            (Some(std::cmp::Ordering::Greater), SortOrder::Asc) => true,
+            (Some(std::cmp::Ordering::Less), SortOrder::Desc) => true,
+            (Some(_), _) => false,
+            (None, _) => true,
+        }
+    }))
+}
+
+/// JSONPath for a `sorted` check's `by`, which may omit the leading `$` (`.timestamp`)
+pub fn sort_key_path(by: &str) -> String {
+    if by.starts_with('$') {
+        by.to_string()
+    } else {
+        format!("${}", by)
+    }
+}
+
+/// A `paths_equal` JSONPath without its `.length()` suffix, and whether it had one
+pub fn split_length(path: &str) -> (&str, bool) {
+    match path.strip_suffix(".length()") {
+        Some(path) => (path, true),
+        None => (path, false),
+    }
+}
+
+/// The operand of a `paths_equal` check a JSONPath's `nodes` stand for:
+/// the single node selected, an array of several, or `None` for none. With
+/// `length`, the length of the selected array, object or string instead
+/// (the number of nodes if there are several).
+pub fn path_operand(nodes: &[&Value], length: bool) -> Option<Value> {
+    match (nodes, length) {
+        ([], _) => None,
+        ([Value::Array(items)], true) => Some(items.len().into()),
+        ([Value::Object(map)], true) => Some(map.len().into()),
+        ([Value::String(s)], true) => Some(s.chars().count().into()),
+        ([_], true) => None,
+        (nodes, true) => Some(nodes.len().into()),
+        ([node], false) => Some((*node).clone()),
+        (nodes, false) => Some(Value::Array(nodes.iter().map(|node| (*node).clone()).collect())),
+    }
+}
+
+/// A key a `required_keys` check expects
+#[derive(Debug, Deserialize, JsonSchema, Serialize, Clone, PartialEq)]
+pub struct RequiredKey {
+    pub name: String,
+    /// Type the key's value must have; any type is accepted if unset
+    #[serde(default, rename = "type", skip_serializing_if = "Option::is_none")]
+    pub kind: Option<JsonType>,
+}
+
+impl RequiredKey {
+    /// Whether `object` has this key with a value of the expected type
+    pub fn is_satisfied_by(&self, object: &serde_json::Map<String, Value>) -> bool {
+        object
+            .get(&self.name)
+            .is_some_and(|value| self.kind.is_none_or(|kind| kind.matches(value)))
+    }
+}
+
+/// JSON value types, with `integer` for whole numbers
+#[derive(Debug, Deserialize, JsonSchema, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum JsonType {
+    Null,
+    Boolean,
+    Number,
+    Integer,
+    String,
+    Array,
+    Object,
+}
+
+impl JsonType {
+    pub fn matches(&self, value: &Value) -> bool {
+        match self {
+            JsonType::Null => value.is_null(),
+            JsonType::Boolean => value.is_boolean(),
+            JsonType::Number => value.is_number(),
+            JsonType::Integer => is_integer(value),
+            JsonType::String => value.is_string(),
+            JsonType::Array => value.is_array(),
+            JsonType::Object => value.is_object(),
+        }
+    }
+}
+
+impl fmt::Display for JsonType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            JsonType::Null => "null",
+            JsonType::Boolean => "boolean",
+            JsonType::Number => "number",
+            JsonType::Integer => "integer",
+            JsonType::String => "string",
+            JsonType::Array => "array",
+            JsonType::Object => "object",
+        })
+    }
+}
+
+/// Whether a JSON value is a whole number
+pub fn is_integer(value: &Value) -> bool {
+    match value {
+        Value::Number(n) if n.is_i64() || n.is_u64() => true,
+        Value::Number(n) => n.as_f64().is_some_and(|f| f.is_finite() && f.fract() == 0.0),
+        _ => false,
+    }
+}
+
+/// Largest integer every smaller integer is exactly representable below as an `f64`
+pub const MAX_SAFE_INTEGER: u64 = (1 << 53) - 1;
+
+/// Number of digits after the decimal point a JSON number needs, ignoring
+/// trailing zeros and taking an exponent into account (`1.5e-3` needs 4)
+pub fn decimal_places(value: &Value) -> Option<u64> {
+    let Value::Number(n) = value else {
+        return None;
+    };
+    let text = n.to_string();
+    let (mantissa, exponent) = match text.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) => (mantissa, exponent.parse::<i64>().ok()?),
+        None => (text.as_str(), 0),
+    };
+    let fraction = mantissa.split_once('.').map_or("", |(_, fraction)| fraction).trim_end_matches('0');
+    Some((fraction.len() as i64 - exponent).max(0) as u64)
+}
+
+/// Whether a JSON value is a whole number within ±[`MAX_SAFE_INTEGER`]
+pub fn is_safe_integer(value: &Value) -> bool {
+    match value {
+        Value::Number(n) if n.is_i64() => n.as_i64().is_some_and(|i| i.unsigned_abs() <= MAX_SAFE_INTEGER),
+        Value::Number(n) if n.is_u64() => n.as_u64().is_some_and(|u| u <= MAX_SAFE_INTEGER),
+        Value::Number(n) => n
+            .as_f64()
+            .is_some_and(|f| f.fract() == 0.0 && f.abs() <= MAX_SAFE_INTEGER as f64),
+        _ => false,
+    }
+}
+
+/// Whether a JSON value is a number that is a multiple of `divisor`
+///
+/// Integers are checked exactly when the divisor is whole; other numbers
+/// allow for floating point error, so `0.3` is a multiple of `0.1`.
+pub fn is_multiple_of(value: &Value, divisor: f64) -> bool {
+    let Value::Number(n) = value else {
+        return false;
+    };
+    if divisor == 0.0 || !divisor.is_finite() {
+        return false;
+    }
+
+    if divisor.fract() == 0.0 && divisor.abs() <= u64::MAX as f64 {
+        let divisor = divisor.abs() as u64;
+        if let Some(i) = n.as_i64() {
+            return i.unsigned_abs().is_multiple_of(divisor);
+        }
+        if let Some(u) = n.as_u64() {
+            return u.is_multiple_of(divisor);
+        }
+    }
+
+    let Some(f) = n.as_f64() else {
+        return false;
+    };
+    let quotient = f / divisor;
+    (quotient - quotient.round()).abs() <= 1e-9 * quotient.abs().max(1.0)
+}
+
+/// Whether `value` equals `expected`, letting numbers differ by up to
+/// `tolerance`. Without one, numbers are compared exactly by value, so `1`
+/// equals `1.0` but two IDs that round to the same `f64` differ.
+pub fn equals_within(value: &Value, expected: &Value, tolerance: Option<f64>) -> bool {
+    match (value, expected, tolerance) {
+        (Value::Number(a), Value::Number(b), None) => number::compare(a, b).is_eq(),
+        (Value::Number(a), Value::Number(b), Some(tolerance)) => match (a.as_f64(), b.as_f64()) {
+            (Some(a), Some(b)) => (a - b).abs() <= tolerance,
+            _ => false,
+        },
+        _ => value == expected,
+    }
+}
+
+/// Indices of every value that occurs more than once
+pub fn duplicate_indices(values: &[&Value]) -> Vec<usize> {
+    let mut occurrences: HashMap<String, Vec<usize>> = HashMap::new();
+    for (i, value) in values.iter().enumerate() {
+        occurrences.entry(value.to_string()).or_default().push(i);
+    }
+
+    let mut duplicates: Vec<usize> = occurrences
+        .into_values()
+        .filter(|indices| indices.len() > 1)
+        .flatten()
+        .collect();
+    duplicates.sort_unstable();
+    duplicates
+}
+
+/// The length most of the arrays among `values` have (the first one seen
+/// on a tie), and the indices of the values that aren't arrays of it
+pub fn length_outliers(values: &[&Value]) -> (Option<usize>, Vec<usize>) {
+    let lengths: Vec<Option<usize>> = values.iter().map(|value| value.as_array().map(Vec::len)).collect();
+    let mut counts: Vec<(usize, usize)> = Vec::new();
+    for length in lengths.iter().flatten() {
+        match counts.iter_mut().find(|(seen, _)| seen == length) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((*length, 1)),
+        }
+    }
+    // `max_by_key` keeps the last maximum, so search from the back
+    let common = counts.iter().rev().max_by_key(|(_, count)| *count).map(|(length, _)| *length);
+    let outliers = lengths
+        .iter()
+        .enumerate()
+        .filter(|(_, length)| common.is_none() || **length != common)
+        .map(|(i, _)| i)
+        .collect();
+    (common, outliers)
+}
+
+/// Find every member of `value` (at any depth) whose key is in `keys`
+///
+/// Returns the path of each violation, built on `path`, with the value found there.
+pub fn find_forbidden_keys<'a>(value: &'a Value, keys: &[String], path: &str) -> Vec<(String, &'a Value)> {
+    find_keys(value, path, |key| keys.iter().any(|forbidden| forbidden == key))
+}
+
+/// Find every member of `value` (at any depth) whose key `matches`, with
+/// its path built on `path` and its value
+pub fn find_keys<'a>(value: &'a Value, path: &str, matches: impl Fn(&str) -> bool) -> Vec<(String, &'a Value)> {
+    let mut found = Vec::new();
+    collect_keys(value, &matches, &mut path.to_string(), &mut found);
+    found
+}
+
+fn collect_keys<'a>(
+    value: &'a Value,
+    matches: &dyn Fn(&str) -> bool,
+    path: &mut String,
+    found: &mut Vec<(String, &'a Value)>,
+) {
+    let len = path.len();
+    match value {
+        Value::Object(map) => {
+            for (key, child) in map {
+                push_key(path, key);
+                if matches(key) {
+                    found.push((path.clone(), child));
+                }
+                collect_keys(child, matches, path, found);
+                path.truncate(len);
+            }
+        }
+        Value::Array(arr) => {
+            for (index, child) in arr.iter().enumerate() {
+                push_index(path, index);
+                collect_keys(child, matches, path, found);
+                path.truncate(len);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// How many levels of arrays and objects nest in `value`: 0 for a scalar,
+/// 1 for `[1, 2]` and 2 for `{"a": {}}`
+pub fn depth(value: &Value) -> usize {
+    match value {
+        Value::Array(items) => 1 + items.iter().map(depth).max().unwrap_or(0),
+        Value::Object(map) => 1 + map.values().map(depth).max().unwrap_or(0),
+        _ => 0,
+    }
+}
+
+/// Length of `value` serialized as compact JSON, counted without buffering it
+pub fn compact_size(value: &Value) -> usize {
+    struct Counter(usize);
+
+    impl std::io::Write for Counter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0 += buf.len();
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let mut counter = Counter(0);
+    // Writing a `Value` to a writer that never fails can't fail
+    let _ = serde_json::to_writer(&mut counter, value);
+    counter.0
+}
+
+/// Find every null at or inside `value`
+///
+/// Returns the path of each null, built on `path`.
+pub fn find_nulls(value: &Value, path: &str) -> Vec<String> {
+    let mut found = Vec::new();
+    collect_nulls(value, &mut path.to_string(), &mut found);
+    found
+}
+
+fn collect_nulls(value: &Value, path: &mut String, found: &mut Vec<String>) {
+    let len = path.len();
+    match value {
+        Value::Null => found.push(path.clone()),
+        Value::Object(map) => {
+            for (key, child) in map {
+                push_key(path, key);
+                collect_nulls(child, path, found);
+                path.truncate(len);
+            }
+        }
+        Value::Array(arr) => {
+            for (index, child) in arr.iter().enumerate() {
+                push_index(path, index);
+                collect_nulls(child, path, found);
+                path.truncate(len);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Comparison operator, written by name (`lte`) or symbol (`<=`)
+#[derive(Debug, Deserialize, JsonSchema, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Comparison {
+    #[serde(alias = "==")]
+    #[schemars(transform = schema::alias("=="))]
+    Eq,
+    #[serde(alias = "!=")]
+    #[schemars(transform = schema::alias("!="))]
+    Ne,
+    #[serde(alias = "<")]
+    #[schemars(transform = schema::alias("<"))]
+    Lt,
+    #[serde(alias = "<=")]
+    #[schemars(transform = schema::alias("<="))]
+    Lte,
+    #[serde(alias = ">")]
+    #[schemars(transform = schema::alias(">"))]
+    Gt,
+    #[serde(alias = ">=")]
+    #[schemars(transform = schema::alias(">="))]
+    Gte,
+}
+
+impl Comparison {
+    /// Compare `left` to `right` with this operator
+    pub fn compare(&self, left: f64, right: f64) -> bool {
+        match self {
+            Comparison::Eq => left == right,
+            Comparison::Ne => left != right,
+            Comparison::Lt => left < right,
+            Comparison::Lte => left <= right,
+            Comparison::Gt => left > right,
+            Comparison::Gte => left >= right,
+        }
+    }
+}
+
+/// How per-value outcomes combine into the result of a check
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Quantifier {
+    /// The check passes if any selected value passes
+    Any,
+    /// The check passes only if every selected value passes
+    All,
+    /// The check applies to the selected values as a whole
+    Set,
+}
+
+impl CheckRule {
+    /// Get how this check combines the outcomes of the selected values
+    pub fn quantifier(&self) -> Quantifier {
+        match self {
+            CheckRule::Empty
+            | CheckRule::NonEmpty
+            | CheckRule::NotEquals { .. }
+            | CheckRule::ContainedBy { .. }
+            | CheckRule::JsonbContainedBy { .. }
+            | CheckRule::ForbiddenKeys { .. }
+            | CheckRule::NoNullsAnywhere { .. }
+            | CheckRule::KeyFormat { .. }
+            | CheckRule::MaxDepth { .. }
+            | CheckRule::MaxSizeBytes { .. }
+            | CheckRule::RequiredKeys { .. }
+            | CheckRule::StartsWith { .. }
+            | CheckRule::EndsWith { .. }
+            | CheckRule::Format { .. }
+            | CheckRule::Integer
+            | CheckRule::MultipleOf { .. }
+            | CheckRule::DecimalPlaces { .. }
+            | CheckRule::SafeInteger
+            | CheckRule::Sorted { .. }
+            | CheckRule::PathsEqual { .. }
+            | CheckRule::RefIntegrity { .. }
+            | CheckRule::Diff { .. }
+            | CheckRule::MatchesDocument { .. }
+            | CheckRule::Command { .. }
+            | CheckRule::Expr { .. } => Quantifier::All,
+            CheckRule::Aggregate { .. } | CheckRule::MatchCount { .. } | CheckRule::Unique | CheckRule::SameLength => {
+                Quantifier::Set
+            }
+            _ => Quantifier::Any,
+        }
+    }
+
+    /// JSONPaths embedded in this check, besides the rule's own
+    pub fn jsonpaths(&self) -> Vec<String> {
+        match self {
+            CheckRule::JsonbPathMatch { path } => vec![path.clone()],
+            CheckRule::RefIntegrity { target_jsonpath, .. } => vec![target_jsonpath.clone()],
+            CheckRule::Diff { allowed_paths: paths, .. }
+            | CheckRule::MatchesDocument { ignore_paths: paths, .. }
+            | CheckRule::NoNullsAnywhere { allowed_paths: paths } => {
+                paths.clone()
+            }
+            CheckRule::Sorted { by: Some(by), .. } => vec![sort_key_path(by)],
+            CheckRule::PathsEqual { left_jsonpath, right_jsonpath } => {
+                vec![split_length(left_jsonpath).0.to_string(), split_length(right_jsonpath).0.to_string()]
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Document this check reads besides the rule's own, if any
+    pub fn referenced_file(&self) -> Option<&str> {
+        match self {
+            CheckRule::RefIntegrity { target_file, .. } => Some(target_file),
+            CheckRule::Diff { baseline_file: file, .. } | CheckRule::MatchesDocument { file, .. } => Some(file),
+            _ => None,
+        }
+    }
+
+    /// Whether the check compares or measures numbers, so protobuf JSON's
+    /// numbers written as strings should be read as numbers for it
+    pub fn compares_numbers(&self) -> bool {
+        match self {
+            CheckRule::Equals { value, .. } | CheckRule::NotEquals { value } => value.is_number(),
+            CheckRule::ApproxEquals { .. }
+            | CheckRule::GreaterThan { .. }
+            | CheckRule::LessThan { .. }
+            | CheckRule::GreaterThanOrEqual { .. }
+            | CheckRule::LessThanOrEqual { .. }
+            | CheckRule::Integer
+            | CheckRule::MultipleOf { .. }
+            | CheckRule::DecimalPlaces { .. }
+            | CheckRule::SafeInteger
+            | CheckRule::Sorted { .. }
+            | CheckRule::Aggregate { .. } => true,
+            _ => false,
+        }
+    }
+
+    /// Document a `diff` or `matches_document` check compares values with,
+    /// and the JSONPaths of the nodes whose differences don't count
+    pub fn reference_document(&self) -> Option<(&str, &[String])> {
+        match self {
+            CheckRule::Diff { baseline_file: file, allowed_paths: paths }
+            | CheckRule::MatchesDocument { file, ignore_paths: paths } => Some((file, paths)),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_string_formats() {
+        let cases = [
+            (StringFormat::Uuid, "123e4567-e89b-12d3-a456-426614174000", "123e4567-e89b-12d3-a456"),
+            (StringFormat::Email, "dev.ops@example.co.uk", "dev ops@localhost"),
+            (StringFormat::Url, "https://user@example.com:8443/a?b#c", "example.com/a"),
+            (StringFormat::Url, "http://[::1]:8080/", "http://exa mple.com"),
+            (StringFormat::Ipv4, "192.168.0.1", "256.1.1.1"),
+            (StringFormat::Ipv6, "2001:db8::1", "2001:db8::g"),
+            (StringFormat::Hostname, "api-1.example.com", "-api.example.com"),
+            (StringFormat::Base64, "aGVsbG8=", "aGVsbG8"),
+            (StringFormat::Lowercase, "snake_case_1", "camelCase"),
+            (StringFormat::Uppercase, "SCREAMING_CASE", "Mixed"),
+        ];
+        for (format, valid, invalid) in cases {
+            assert!(format.matches(valid), "{:?} should accept {}", format, valid);
+            assert!(!format.matches(invalid), "{:?} should reject {}", format, invalid);
+        }
+    }
+
+    #[test]
+    fn test_equals_within() {
+        assert!(equals_within(&json!(0.30000000000000004), &json!(0.3), Some(1e-9)));
+        assert!(!equals_within(&json!(0.30000000000000004), &json!(0.3), None));
+        assert!(equals_within(&json!(10), &json!(10.5), Some(0.5)));
+        assert!(!equals_within(&json!("10"), &json!(10), Some(1.0)));
+        assert!(equals_within(&json!("a"), &json!("a"), Some(1.0)));
+        assert!(equals_within(&json!(1), &json!(1.0), None));
+        assert!(!equals_within(&json!(9007199254740993_u64), &json!(9007199254740992_u64), None));
+    }
+
+    #[test]
+    fn test_integer_and_multiple_of() {
+        assert!(is_integer(&json!(3)) && is_integer(&json!(u64::MAX)) && is_integer(&json!(3.0)));
+        assert!(!is_integer(&json!(3.5)) && !is_integer(&json!("3")));
+
+        assert!(is_multiple_of(&json!(1250), 25.0));
+        assert!(is_multiple_of(&json!(-1250), 25.0));
+        assert!(is_multiple_of(&json!(u64::MAX), 5.0));
+        assert!(!is_multiple_of(&json!(u64::MAX - 1), 5.0));
+        assert!(is_multiple_of(&json!(0.3), 0.1));
+        assert!(!is_multiple_of(&json!(0.35), 0.1));
+        assert!(!is_multiple_of(&json!(10), 0.0));
+    }
+
+    #[test]
+    fn test_decimal_places_and_safe_integer() {
+        assert_eq!(decimal_places(&json!(19.99)), Some(2));
+        assert_eq!(decimal_places(&json!(1.50)), Some(1));
+        assert_eq!(decimal_places(&json!(42)), Some(0));
+        assert_eq!(decimal_places(&json!(0.1 + 0.2)), Some(17));
+        assert_eq!(decimal_places(&json!(1.5e-7)), Some(8));
+        assert_eq!(decimal_places(&json!("1.5")), None);
+
+        assert!(is_safe_integer(&json!(MAX_SAFE_INTEGER)) && is_safe_integer(&json!(-9007199254740991_i64)));
+        assert!(is_safe_integer(&json!(3.0)));
+        assert!(!is_safe_integer(&json!(MAX_SAFE_INTEGER + 1)) && !is_safe_integer(&json!(1e20)));
+        assert!(!is_safe_integer(&json!(3.5)) && !is_safe_integer(&json!("3")));
+    }
+
+    #[test]
+    fn test_find_forbidden_keys() {
+        let config = json!({"db": {"user": "app", "password": "x"}, "hooks": [{"secret": {"password": 1}}]});
+        let keys = ["password".to_string(), "secret".to_string()];
+        let paths: Vec<String> = find_forbidden_keys(&config, &keys, "$.config")
+            .into_iter()
+            .map(|(path, _)| path)
+            .collect();
+        assert_eq!(paths, vec!["$.config.db.password", "$.config.hooks[0].secret", "$.config.hooks[0].secret.password"]);
+    }
+
+    #[test]
+    fn test_depth_and_compact_size() {
+        assert_eq!(depth(&json!("flat")), 0);
+        assert_eq!(depth(&json!({"a": [1, {"b": []}], "c": {}})), 4);
+        assert_eq!(compact_size(&json!({"a": [1, 2], "b": "é"})), r#"{"a":[1,2],"b":"é"}"#.len());
+    }
+
+    #[test]
+    fn test_first_unsorted_pair() {
+        let items = [json!({"ts": 1}), json!({"ts": 5}), json!({"ts": 3})];
+        assert_eq!(first_unsorted_pair(&items, SortOrder::Asc, Some(".ts"), JsonPathEngine::default()).unwrap(), Some(1));
+        assert_eq!(first_unsorted_pair(&items[1..], SortOrder::Desc, Some("$.ts"), JsonPathEngine::default()).unwrap(), None);
+
+        let names = [json!("a"), json!("b"), json!("b")];
+        assert_eq!(first_unsorted_pair(&names, SortOrder::Asc, None, JsonPathEngine::default()).unwrap(), None);
+        assert_eq!(first_unsorted_pair(&[json!(1), json!("2")], SortOrder::Asc, None, JsonPathEngine::default()).unwrap(), Some(0));
+    }
+
+    #[test]
+    fn test_aggregate_functions() {
+        let (a, b, c) = (json!(1), json!(4), json!(2.5));
+        let values = [&a, &b, &c];
+        assert_eq!(AggregateFunction::Sum.apply(&values).unwrap(), Some(7.5));
+        assert_eq!(AggregateFunction::Avg.apply(&values).unwrap(), Some(2.5));
+        assert_eq!(AggregateFunction::Min.apply(&values).unwrap(), Some(1.0));
+        assert_eq!(AggregateFunction::Max.apply(&values).unwrap(), Some(4.0));
+        assert_eq!(AggregateFunction::Count.apply(&values).unwrap(), Some(3.0));
+        assert_eq!(AggregateFunction::Max.apply(&[]).unwrap(), None);
+        assert!(AggregateFunction::Sum.apply(&[&json!("x")]).is_err());
+    }
+
+    #[test]
+    fn test_comparison_symbols() {
+        let op: Comparison = serde_json::from_value(json!("<=")).unwrap();
+        assert_eq!(op, Comparison::Lte);
+        assert!(op.compare(2.0, 2.0));
+    }
+}
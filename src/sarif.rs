@@ -0,0 +1,154 @@
+//! SARIF 2.1.0 reports for code-scanning integrations
+//!
+//! Every failing invalid node becomes a SARIF result located at the rule's
+//! document and source region, with its JSONPath as the logical location, so
+//! tools like GitHub code scanning show failed checks as annotations.
+
+use serde_json::{json, Map, Value};
+
+use crate::config::{CheckResult, Config, Rule, Severity};
+
+const SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// Build a SARIF log for the results of running a config
+pub fn to_sarif(config: &Config, results: &[CheckResult]) -> Value {
+    let rules: Vec<Value> = config.rules.iter().map(rule_descriptor).collect();
+
+    let mut sarif_results = Vec::new();
+    for result in results.iter().filter(|r| !r.passed) {
+        let rule_index = config.rules.iter().position(|r| r.name == result.rule_name);
+        if result.per_document.is_empty() {
+            push_results(&mut sarif_results, result, &result.rule_name, rule_index);
+        } else {
+            for document in result.per_document.iter().filter(|r| !r.passed) {
+                push_results(&mut sarif_results, document, &result.rule_name, rule_index);
+            }
+        }
+    }
+
+    json!({
+        "$schema": SCHEMA,
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": env!("CARGO_PKG_NAME"),
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": rules,
+                }
+            },
+            "results": sarif_results,
+        }]
+    })
+}
+
+fn rule_descriptor(rule: &Rule) -> Value {
+    let check = serde_json::to_value(&rule.check).unwrap_or(Value::Null);
+    let mut descriptor = json!({
+        "id": rule.name,
+        "name": rule.name,
+        "shortDescription": { "text": rule.name },
+        "defaultConfiguration": { "level": level(rule.severity) },
+        "properties": match (&rule.selector, &rule.json_pointer) {
+            (Some(selector), _) => json!({ "selector": selector, "check": check }),
+            (None, Some(pointer)) => json!({ "json_pointer": pointer, "check": check }),
+            (None, None) => json!({ "jsonpath": rule.jsonpath, "check": check }),
+        }
+    });
+    if let Some(suite) = &rule.suite {
+        descriptor["properties"]["suite"] = json!(suite);
+    }
+    if let Some(hint) = &rule.hint {
+        descriptor["help"] = json!({ "text": hint });
+    }
+    if let Some(url) = &rule.docs_url {
+        descriptor["helpUri"] = json!(url);
+    }
+    descriptor
+}
+
+/// Add one SARIF result per invalid node of a failed check result
+fn push_results(out: &mut Vec<Value>, result: &CheckResult, rule_id: &str, rule_index: Option<usize>) {
+    let headline = result.message.lines().next().unwrap_or_default();
+    let base = |message: String, location: Option<Value>| {
+        let mut sarif_result = Map::new();
+        sarif_result.insert("ruleId".to_string(), json!(rule_id));
+        if let Some(index) = rule_index {
+            sarif_result.insert("ruleIndex".to_string(), json!(index));
+        }
+        sarif_result.insert("level".to_string(), json!(level(result.severity)));
+        sarif_result.insert("message".to_string(), json!({ "text": message }));
+        if let Some(location) = location {
+            sarif_result.insert("locations".to_string(), json!([location]));
+        }
+        Value::Object(sarif_result)
+    };
+
+    if result.invalid_positions.is_empty() {
+        out.push(base(headline.to_string(), location(result.document.as_deref(), None, None)));
+        return;
+    }
+
+    for (i, position) in result.invalid_positions.iter().enumerate() {
+        let (line, path) = split_line(position);
+        let message = match result.values_found.get(i) {
+            Some(value) => format!("{}: {} = {}", headline, path, value),
+            None => format!("{}: {}", headline, path),
+        };
+        let region = match result.location(i) {
+            Some(found) => Some(json!({ "startLine": found.line, "startColumn": found.column })),
+            None => line.map(|line| json!({ "startLine": line })),
+        };
+        out.push(base(message, location(result.document.as_deref(), Some(path), region)));
+    }
+}
+
+fn location(document: Option<&str>, path: Option<&str>, region: Option<Value>) -> Option<Value> {
+    if document.is_none() && path.is_none() {
+        return None;
+    }
+
+    let mut location = Map::new();
+    if let Some(uri) = document {
+        let mut physical = json!({ "artifactLocation": { "uri": uri } });
+        if let Some(region) = region {
+            physical["region"] = region;
+        }
+        location.insert("physicalLocation".to_string(), physical);
+    }
+    if let Some(path) = path {
+        location.insert(
+            "logicalLocations".to_string(),
+            json!([{ "fullyQualifiedName": path, "kind": "member" }]),
+        );
+    }
+
+    Some(Value::Object(location))
+}
+
+/// Split a `line N: path` position into its line and path
+pub(crate) fn split_line(position: &str) -> (Option<usize>, &str) {
+    position
+        .strip_prefix("line ")
+        .and_then(|rest| rest.split_once(": "))
+        .and_then(|(line, path)| Some((Some(line.parse().ok()?), path)))
+        .unwrap_or((None, position))
+}
+
+fn level(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Warning => "warning",
+        Severity::Error => "error",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_line() {
+        assert_eq!(split_line("line 3: $[1].email"), (Some(3), "$[1].email"));
+        assert_eq!(split_line("$.users[0]"), (None, "$.users[0]"));
+    }
+}
@@ -0,0 +1,128 @@
+//! Directory scanning for validating whole config repositories
+//!
+//! A config's `scan` section names a root directory with include/exclude
+//! globs. Every discovered file is checked by the rules whose `applies_to`
+//! pattern matches it.
+
+use anyhow::{Context, Result};
+use glob::{MatchOptions, Pattern};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+/// Directory to search for documents
+#[derive(Debug, Clone, Deserialize, JsonSchema, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScanConfig {
+    /// Directory searched recursively
+    pub root: String,
+    /// Globs (relative to `root`) a file must match to be checked
+    #[serde(default = "default_include")]
+    pub include: Vec<String>,
+    /// Globs (relative to `root`) excluding files and whole directories
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude: Vec<String>,
+}
+
+/// `*` and `?` don't cross directory separators; `**` does
+const MATCH_OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+fn default_include() -> Vec<String> {
+    vec!["**/*.json".to_string()]
+}
+
+/// A file found by a scan
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScannedFile {
+    /// Path relative to the scan root, with `/` separators
+    pub relative: String,
+    /// Path usable as a rule's `json_file`
+    pub path: String,
+}
+
+/// Find every file under the scan root matching the include and exclude globs, sorted by path
+pub fn discover(scan: &ScanConfig) -> Result<Vec<ScannedFile>> {
+    let include = compile(&scan.include)?;
+    let exclude = compile(&scan.exclude)?;
+
+    let mut files = Vec::new();
+    walk(Path::new(&scan.root), "", &include, &exclude, &mut files)?;
+    files.sort_by(|a, b| a.relative.cmp(&b.relative));
+
+    Ok(files)
+}
+
+/// Whether a rule's `applies_to` pattern matches a scanned file.
+///
+/// Patterns without a `/` match the file name; others match the path
+/// relative to the scan root.
+pub fn applies_to(pattern: &str, file: &ScannedFile) -> bool {
+    let Ok(compiled) = Pattern::new(pattern) else {
+        return false;
+    };
+    if pattern.contains('/') {
+        compiled.matches_with(&file.relative, MATCH_OPTIONS)
+    } else {
+        let name = file.relative.rsplit('/').next().unwrap_or_default();
+        compiled.matches_with(name, MATCH_OPTIONS)
+    }
+}
+
+fn compile(patterns: &[String]) -> Result<Vec<Pattern>> {
+    patterns
+        .iter()
+        .map(|p| Pattern::new(p).with_context(|| format!("Invalid scan pattern: {}", p)))
+        .collect()
+}
+
+fn walk(
+    dir: &Path,
+    prefix: &str,
+    include: &[Pattern],
+    exclude: &[Pattern],
+    files: &mut Vec<ScannedFile>,
+) -> Result<()> {
+    let entries = fs::read_dir(dir).with_context(|| format!("Failed to read directory: {}", dir.display()))?;
+
+    for entry in entries {
+        let entry = entry.with_context(|| format!("Failed to read directory: {}", dir.display()))?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let relative = format!("{}{}", prefix, name);
+        if exclude.iter().any(|p| p.matches_with(&relative, MATCH_OPTIONS)) {
+            continue;
+        }
+
+        let path = entry.path();
+        if path.is_dir() {
+            walk(&path, &format!("{}/", relative), include, exclude, files)?;
+        } else if include.iter().any(|p| p.matches_with(&relative, MATCH_OPTIONS)) {
+            files.push(ScannedFile {
+                relative,
+                path: path.to_string_lossy().into_owned(),
+            });
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_applies_to() {
+        let file = ScannedFile {
+            relative: "services/api/app.service.json".to_string(),
+            path: "repo/services/api/app.service.json".to_string(),
+        };
+        assert!(applies_to("*.service.json", &file));
+        assert!(applies_to("services/**/*.json", &file));
+        assert!(!applies_to("api/*.json", &file));
+    }
+}
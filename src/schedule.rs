@@ -0,0 +1,212 @@
+//! Dependency-ordered rule execution
+//!
+//! Rules can name prerequisites with `depends_on` or a `run_if` condition on
+//! another rule. Those rules run first, and a rule is skipped when a
+//! prerequisite didn't pass.
+
+use anyhow::{anyhow, bail, Result};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::config::{CheckResult, Rule, Status};
+
+/// Condition that must hold for a rule to run
+#[derive(Debug, Deserialize, JsonSchema, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum RunCondition {
+    /// Run only if the named rule passed
+    Passed(String),
+    /// Run only if the named rule failed
+    Failed(String),
+    /// Run only if the JSONPath selects at least one node in the rule's document
+    PathExists(String),
+}
+
+impl RunCondition {
+    /// Name of the rule this condition depends on, if any
+    pub fn rule(&self) -> Option<&str> {
+        match self {
+            RunCondition::Passed(name) | RunCondition::Failed(name) => Some(name),
+            RunCondition::PathExists(_) => None,
+        }
+    }
+}
+
+/// Names of the rules that must run before `rule`
+pub fn prerequisites(rule: &Rule) -> impl Iterator<Item = &str> {
+    rule.depends_on
+        .iter()
+        .map(String::as_str)
+        .chain(rule.run_if.as_ref().and_then(RunCondition::rule))
+}
+
+/// Order rules so every rule runs after its prerequisites.
+///
+/// Rules keep their config order where dependencies allow. Fails on unknown
+/// rule names and dependency cycles.
+pub fn execution_order(rules: &[Rule]) -> Result<Vec<usize>> {
+    let index: HashMap<&str, usize> = rules
+        .iter()
+        .enumerate()
+        .map(|(i, rule)| (rule.name.as_str(), i))
+        .collect();
+
+    let mut order = Vec::with_capacity(rules.len());
+    let mut state = vec![Visit::New; rules.len()];
+    let mut stack = Vec::new();
+
+    for i in 0..rules.len() {
+        visit(i, rules, &index, &mut state, &mut stack, &mut order)?;
+    }
+
+    Ok(order)
+}
+
+/// Group rules into levels whose rules only depend on rules in earlier
+/// levels, so the rules of a level can run at the same time.
+///
+/// Within a level, rules are in execution order.
+pub fn execution_levels(rules: &[Rule]) -> Result<Vec<Vec<usize>>> {
+    let index: HashMap<&str, usize> = rules
+        .iter()
+        .enumerate()
+        .map(|(i, rule)| (rule.name.as_str(), i))
+        .collect();
+
+    let mut depth = vec![0; rules.len()];
+    let mut levels: Vec<Vec<usize>> = Vec::new();
+    for i in execution_order(rules)? {
+        // Prerequisites come earlier in the execution order, so their depth is final
+        depth[i] = prerequisites(&rules[i])
+            .map(|name| depth[index[name]] + 1)
+            .max()
+            .unwrap_or(0);
+        if levels.len() <= depth[i] {
+            levels.resize_with(depth[i] + 1, Vec::new);
+        }
+        levels[depth[i]].push(i);
+    }
+
+    Ok(levels)
+}
+
+/// Error for rules that (transitively) depend on themselves
+///
+/// Holds the rule names along the cycle, starting and ending with the same rule.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DependencyCycle(pub Vec<String>);
+
+impl fmt::Display for DependencyCycle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Rule dependency cycle: {}", self.0.join(" -> "))
+    }
+}
+
+impl std::error::Error for DependencyCycle {}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Visit {
+    New,
+    InProgress,
+    Done,
+}
+
+fn visit(
+    i: usize,
+    rules: &[Rule],
+    index: &HashMap<&str, usize>,
+    state: &mut [Visit],
+    stack: &mut Vec<usize>,
+    order: &mut Vec<usize>,
+) -> Result<()> {
+    match state[i] {
+        Visit::Done => return Ok(()),
+        Visit::InProgress => {
+            let start = stack.iter().position(|&j| j == i).unwrap_or(0);
+            let cycle = stack[start..]
+                .iter()
+                .chain(std::iter::once(&i))
+                .map(|&j| rules[j].name.clone())
+                .collect();
+            bail!(DependencyCycle(cycle));
+        }
+        Visit::New => {}
+    }
+
+    state[i] = Visit::InProgress;
+    stack.push(i);
+    for name in prerequisites(&rules[i]) {
+        let &dependency = index.get(name).ok_or_else(|| {
+            anyhow!("Rule '{}' depends on unknown rule '{}'", rules[i].name, name)
+        })?;
+        visit(dependency, rules, index, state, stack, order)?;
+    }
+    stack.pop();
+    state[i] = Visit::Done;
+    order.push(i);
+
+    Ok(())
+}
+
+/// Why a rule must be skipped, judging by the results of its prerequisites
+///
+/// `finished` looks up the result of an already executed rule by name.
+pub fn skip_reason<'a, F>(rule: &Rule, finished: F) -> Option<String>
+where
+    F: Fn(&str) -> Option<&'a CheckResult>,
+{
+    if let Some(reason) = rule.disabled_reason() {
+        return Some(reason);
+    }
+
+    let passed = |name: &str| finished(name).is_some_and(|r| r.status == Status::Pass);
+
+    if let Some(name) = rule.depends_on.iter().find(|name| !passed(name)) {
+        return Some(format!("dependency '{}' did not pass", name));
+    }
+
+    match &rule.run_if {
+        Some(RunCondition::Passed(name)) if !passed(name) => {
+            Some(format!("rule '{}' did not pass", name))
+        }
+        Some(RunCondition::Failed(name)) if finished(name).is_none_or(|r| r.passed) => {
+            Some(format!("rule '{}' did not fail", name))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(name: &str, depends_on: &[&str]) -> Rule {
+        Rule {
+            name: name.to_string(),
+            depends_on: depends_on.iter().map(|s| s.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_execution_order() {
+        let rules = vec![rule("c", &["b"]), rule("a", &[]), rule("b", &["a"])];
+        assert_eq!(execution_order(&rules).unwrap(), vec![1, 2, 0]);
+
+        assert_eq!(execution_levels(&rules).unwrap(), vec![vec![1], vec![2], vec![0]]);
+        let fan_out = vec![rule("a", &[]), rule("b", &["a"]), rule("c", &["a"]), rule("d", &[])];
+        assert_eq!(execution_levels(&fan_out).unwrap(), vec![vec![0, 3], vec![1, 2]]);
+
+        let unknown = vec![rule("a", &["missing"])];
+        assert!(execution_order(&unknown).unwrap_err().to_string().contains("unknown rule 'missing'"));
+    }
+
+    #[test]
+    fn test_cycle_detection() {
+        let rules = vec![rule("a", &["b"]), rule("b", &["c"]), rule("c", &["a"])];
+        let err = execution_order(&rules).unwrap_err();
+        assert_eq!(err.to_string(), "Rule dependency cycle: a -> b -> c -> a");
+    }
+}
@@ -0,0 +1,123 @@
+//! JSON Schema for rule config files
+//!
+//! Editors use the schema for completion and inline errors (point a config's
+//! `$schema` key at it), and CI can validate rule files with any JSON Schema
+//! validator before they are merged. The schema is derived from the config
+//! types with `schemars`; a test checks it against the keys and check types
+//! serde accepts.
+
+use schemars::generate::SchemaSettings;
+use schemars::Schema;
+use serde_json::{json, Value};
+
+use crate::config::Config;
+use crate::vars::{EnvValue, VALUE_FROM_ENV};
+
+/// JSON Schema (draft 2020-12) describing a rules config file
+pub fn config_schema() -> Value {
+    let mut generator = SchemaSettings::draft2020_12().into_generator();
+    let env_value = generator.subschema_for::<EnvValue>().to_value();
+    let mut schema = generator.into_root_schema_for::<Config>().to_value();
+
+    // `value_from_env` is resolved before checks are deserialized, so the
+    // check types don't know it; any check with a `value` takes it instead
+    let checks = schema.pointer_mut("/$defs/CheckRule/oneOf").and_then(Value::as_array_mut);
+    for check in checks.into_iter().flatten().filter(|check| check["properties"].get("value").is_some()) {
+        check["properties"][VALUE_FROM_ENV] = env_value.clone();
+        let Some(required) = check["required"].as_array_mut() else { continue };
+        if required.iter().any(|field| field == "value") {
+            required.retain(|field| field != "value");
+            check["oneOf"] = json!([{"required": ["value"]}, {"required": [VALUE_FROM_ENV]}]);
+        }
+    }
+    schema
+}
+
+/// Schema transform for a variant serde also accepts as `alias`; the
+/// variant's name, or its `type` tag for a check, may be either
+pub(crate) fn alias(alias: &'static str) -> impl FnMut(&mut Schema) {
+    move |schema: &mut Schema| {
+        let Some(object) = schema.as_object_mut() else { return };
+        let name = if object.contains_key("properties") {
+            object.get_mut("properties").and_then(|properties| properties.get_mut("type")).and_then(Value::as_object_mut)
+        } else {
+            Some(object)
+        };
+        if let Some(name) = name
+            && let Some(value) = name.remove("const")
+        {
+            name.insert("enum".to_string(), json!([value, alias]));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Rule;
+    use crate::rules::{CheckRule, Comparison};
+    use serde::de::DeserializeOwned;
+
+    /// Keys or variants serde accepts at a position, read from the error it
+    /// gives for an unknown one (checks without fields ignore extra keys)
+    fn accepted<T: DeserializeOwned>(input: Value) -> Vec<String> {
+        let error = serde_json::from_value::<T>(input).err().map(|e| e.to_string()).unwrap_or_default();
+        let mut names: Vec<String> = error.split('`').skip(3).step_by(2).map(str::to_string).collect();
+        names.sort();
+        names
+    }
+
+    fn properties(schema: &Value) -> Vec<String> {
+        let mut names: Vec<String> = schema["properties"].as_object().unwrap().keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    /// Names a string schema allows, including each `oneOf` alternative's
+    fn names(schema: &Value) -> Vec<String> {
+        let alternatives = schema["oneOf"].as_array().map_or(vec![schema], |all| all.iter().collect());
+        let mut names: Vec<String> = alternatives
+            .into_iter()
+            .flat_map(|alternative| alternative["enum"].as_array().cloned().unwrap_or_else(|| vec![alternative["const"].clone()]))
+            .map(|name| name.as_str().unwrap().to_string())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn test_schema_matches_config_types() {
+        let schema = config_schema();
+        assert_eq!(properties(&schema), accepted::<Config>(json!({"?": 0})));
+        assert_eq!(properties(&schema["$defs"]["Rule"]), accepted::<Rule>(json!({"?": 0})));
+        assert_eq!(names(&schema["$defs"]["Comparison"]), accepted::<Comparison>(json!("?")));
+
+        let checks = schema["$defs"]["CheckRule"]["oneOf"].as_array().unwrap();
+        let mut types: Vec<String> = checks.iter().flat_map(|check| names(&check["properties"]["type"])).collect();
+        types.sort();
+        assert_eq!(types, accepted::<CheckRule>(json!({"type": "?"})));
+
+        // Each check's fields are the ones its variant accepts
+        for check in checks {
+            let name = &names(&check["properties"]["type"])[0];
+            let mut fields = properties(check);
+            fields.retain(|field| field != "type" && field != VALUE_FROM_ENV);
+            let mut input = json!({"type": name, "?": 0});
+            for field in &fields {
+                input[field] = json!(null);
+            }
+            assert_eq!(fields, accepted::<CheckRule>(input), "fields of the {} check", name);
+        }
+    }
+
+    #[test]
+    fn test_value_can_come_from_the_environment() {
+        let schema = config_schema();
+        let checks = schema["$defs"]["CheckRule"]["oneOf"].as_array().unwrap();
+        let equals = checks.iter().find(|check| check["properties"]["type"]["const"] == "equals").unwrap();
+        assert_eq!(equals["required"], json!(["type"]));
+        assert_eq!(equals["oneOf"], json!([{"required": ["value"]}, {"required": ["value_from_env"]}]));
+        assert!(equals["properties"][VALUE_FROM_ENV]["$ref"].is_string());
+        assert!(schema["$defs"]["EnvValue"].is_object());
+    }
+}
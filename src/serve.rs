@@ -0,0 +1,283 @@
+//! Daemon mode: re-run a config on a schedule and serve the latest results
+//!
+//! `GET /healthz` answers 200 while no error-severity rule fails (503
+//! otherwise, or before the first run finished) and `GET /results` returns
+//! the latest results as JSON. The config is reloaded before every run, so
+//! edits are picked up without a restart.
+
+use anyhow::{bail, Context, Result};
+use serde_json::json;
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::checker::JsonChecker;
+use crate::config::{CheckResult, Config, Severity};
+
+/// Address `serve` listens on without `--listen`
+pub const DEFAULT_LISTEN: &str = "127.0.0.1:8080";
+
+/// Time between runs without `--interval`
+pub const DEFAULT_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Results of the latest completed run
+#[derive(Debug)]
+pub struct Snapshot {
+    /// Seconds since the Unix epoch when the run finished
+    pub finished_at: u64,
+    pub results: Vec<CheckResult>,
+}
+
+impl Snapshot {
+    /// Whether the run had no failing error-severity rule
+    pub fn healthy(&self) -> bool {
+        !self.results.iter().any(|r| !r.passed && r.severity == Severity::Error)
+    }
+}
+
+/// An HTTP response: status code, content type and body
+pub type Response = (u16, &'static str, String);
+
+/// Parse an interval like `500ms`, `30s`, `5m`, `1h`, or a plain number of seconds
+pub fn parse_interval(text: &str) -> Result<Duration> {
+    let split = text.find(|c: char| !c.is_ascii_digit()).unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    let Ok(number) = number.parse::<u64>() else {
+        bail!("Invalid interval '{}', expected e.g. 30s, 5m or 1h", text);
+    };
+
+    let seconds = |per_unit: u64| {
+        number.checked_mul(per_unit).map(Duration::from_secs).with_context(|| format!("Interval '{}' is too long", text))
+    };
+    let interval = match unit {
+        "ms" => Duration::from_millis(number),
+        "" | "s" => Duration::from_secs(number),
+        "m" => seconds(60)?,
+        "h" => seconds(3600)?,
+        _ => bail!("Invalid interval '{}', expected e.g. 30s, 5m or 1h", text),
+    };
+    if interval.is_zero() {
+        bail!("Interval must be greater than zero");
+    }
+
+    Ok(interval)
+}
+
+/// Answer a request for `path` from the latest snapshot
+pub fn respond(method: &str, path: &str, snapshot: Option<&Snapshot>) -> Response {
+    if method != "GET" {
+        return (405, "text/plain", "method not allowed\n".to_string());
+    }
+
+    match (path, snapshot) {
+        ("/healthz", None) => (503, "text/plain", "no results yet\n".to_string()),
+        ("/healthz", Some(snapshot)) if snapshot.healthy() => (200, "text/plain", "ok\n".to_string()),
+        ("/healthz", Some(_)) => (503, "text/plain", "failing\n".to_string()),
+        ("/results", None) => (503, "application/json", "{\"results\":null}\n".to_string()),
+        ("/results", Some(snapshot)) => {
+            let body = json!({
+                "finished_at": snapshot.finished_at,
+                "healthy": snapshot.healthy(),
+                "results": snapshot.results,
+            });
+            (200, "application/json", body.to_string() + "\n")
+        }
+        _ => (404, "text/plain", "not found\n".to_string()),
+    }
+}
+
+/// Run the config every `interval` and serve the latest results on `listen`.
+///
+/// Runs until the process is stopped. Failures to load the config or run
+/// the rules go to `on_error`; the previous results keep being served.
+pub fn serve<F, E>(
+    config_file: &str,
+    vars: &HashMap<String, String>,
+    profile: Option<&str>,
+    listen: &str,
+    interval: Duration,
+    mut on_run: F,
+    mut on_error: E,
+) -> Result<()>
+where
+    F: FnMut(&Snapshot),
+    E: FnMut(anyhow::Error),
+{
+    let listener = TcpListener::bind(listen).with_context(|| format!("Failed to listen on {}", listen))?;
+    let latest: Arc<Mutex<Option<Snapshot>>> = Arc::default();
+
+    let shared = Arc::clone(&latest);
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            // Each connection gets its own thread, so a slow client can't hold up the others
+            let shared = Arc::clone(&shared);
+            thread::spawn(move || {
+                // A broken connection only affects its own client
+                let _ = handle(stream, &shared);
+            });
+        }
+    });
+
+    loop {
+        let run = Config::from_file_with_profile(config_file, vars, profile)
+            .and_then(|config| JsonChecker::from_config(config).run());
+        match run {
+            Ok(results) => {
+                let snapshot = Snapshot {
+                    finished_at: SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs()),
+                    results,
+                };
+                on_run(&snapshot);
+                *latest.lock().unwrap_or_else(|e| e.into_inner()) = Some(snapshot);
+            }
+            Err(e) => on_error(e.into()),
+        }
+        thread::sleep(interval);
+    }
+}
+
+fn handle(stream: TcpStream, latest: &Mutex<Option<Snapshot>>) -> Result<()> {
+    let request = read_request(&stream, 0)?;
+    let response = {
+        let latest = latest.lock().unwrap_or_else(|e| e.into_inner());
+        respond(&request.method, &request.path, latest.as_ref())
+    };
+    write_response(&stream, response)
+}
+
+/// The parts of an HTTP request the servers look at
+pub(crate) struct Request {
+    pub method: String,
+    /// Path without its query string
+    pub path: String,
+    pub content_type: Option<String>,
+    pub body: Vec<u8>,
+}
+
+/// Read a request from `stream`, with a body of at most `max_body` bytes
+pub(crate) fn read_request(stream: &TcpStream, max_body: usize) -> Result<Request> {
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    let mut reader = BufReader::new(stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+
+    let mut content_length = 0;
+    let mut content_type = None;
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().context("Invalid Content-Length")?;
+            } else if name.eq_ignore_ascii_case("content-type") {
+                content_type = Some(value.trim().to_string());
+            }
+        }
+        header.clear();
+    }
+    if content_length > max_body {
+        bail!("Request body of {} bytes exceeds the limit of {}", content_length, max_body);
+    }
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body)?;
+
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default().to_string();
+    let path = parts.next().unwrap_or_default();
+    let path = path.split('?').next().unwrap_or_default().to_string();
+    Ok(Request { method, path, content_type, body })
+}
+
+/// Write `response` to `stream` and close the exchange
+pub(crate) fn write_response(stream: &TcpStream, (status, content_type, body): Response) -> Result<()> {
+    let reason = match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        413 => "Content Too Large",
+        415 => "Unsupported Media Type",
+        _ => "Service Unavailable",
+    };
+
+    let mut stream = stream;
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        reason,
+        content_type,
+        body.len(),
+        body
+    )?;
+    stream.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_interval() {
+        assert_eq!(parse_interval("60s").unwrap(), Duration::from_secs(60));
+        assert_eq!(parse_interval("5m").unwrap(), Duration::from_secs(300));
+        assert_eq!(parse_interval("250ms").unwrap(), Duration::from_millis(250));
+        assert_eq!(parse_interval("2").unwrap(), Duration::from_secs(2));
+        assert!(parse_interval("0s").is_err());
+        assert!(parse_interval("soon").is_err());
+        assert!(parse_interval("99999999999999999h").unwrap_err().to_string().contains("too long"));
+        assert!(parse_interval("307445734561825861m").is_err());
+    }
+
+    #[test]
+    fn test_slow_client_does_not_hold_up_others() {
+        let dir = std::env::temp_dir().join(format!("json-checker-rs-serve-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let config = dir.join("rules.json");
+        std::fs::write(&config, r#"{"rules": [{"name": "a", "json_inline": [1], "jsonpath": "$", "check": {"type": "non_empty"}}]}"#).unwrap();
+        let listen = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().to_string();
+
+        let (config, address) = (config.to_string_lossy().into_owned(), listen.clone());
+        thread::spawn(move || serve(&config, &HashMap::new(), None, &address, Duration::from_secs(60), |_| {}, |_| {}));
+        let connect = || loop {
+            if let Ok(stream) = TcpStream::connect(&listen) {
+                break stream;
+            }
+            thread::sleep(Duration::from_millis(10));
+        };
+
+        // Sends nothing, so its request is never complete
+        let _slow = connect();
+        let mut stream = connect();
+        stream.write_all(b"GET /results HTTP/1.1\r\n\r\n").unwrap();
+        stream.set_read_timeout(Some(Duration::from_secs(2))).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 "), "{}", response);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_respond() {
+        assert_eq!(respond("GET", "/healthz", None).0, 503);
+
+        let mut failing = CheckResult::new("rule".to_string(), false, "✗ Rule 'rule' failed".to_string());
+        let snapshot = Snapshot { finished_at: 1, results: vec![failing] };
+        assert_eq!(respond("GET", "/healthz", Some(&snapshot)).0, 503);
+
+        let (status, content_type, body) = respond("GET", "/results", Some(&snapshot));
+        assert_eq!((status, content_type), (200, "application/json"));
+        let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(body["results"][0]["rule_name"], "rule");
+
+        failing = CheckResult::new("rule".to_string(), false, String::new());
+        failing.severity = Severity::Warning;
+        let snapshot = Snapshot { finished_at: 2, results: vec![failing] };
+        assert_eq!(respond("GET", "/healthz", Some(&snapshot)).0, 200);
+        assert_eq!(respond("GET", "/other", Some(&snapshot)).0, 404);
+        assert_eq!(respond("POST", "/healthz", Some(&snapshot)).0, 405);
+    }
+}
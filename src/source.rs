@@ -0,0 +1,333 @@
+//! Pluggable document sources
+//!
+//! A rule's `json_file` is a URI whose scheme selects the [`DocumentSource`]
+//! that resolves it. Plain paths use the `file` scheme and `-` reads stdin.
+//!
+//! | URI | Source |
+//! |-----|--------|
+//! | `data/users.json`, `file://data/users.json` | [`FileSource`] |
+//! | `-`, `stdin://` | [`StdinSource`] |
+//! | `inline://users` | [`InlineSource`] (documents registered in code) |
+//! | `http://…`, `https://…` | `HttpSource` (`http` feature) |
+//! | `s3://bucket/key`, `gs://bucket/key` | `ObjectStoreSource` (`s3` / `gcs` features) |
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::io::Read;
+use std::sync::OnceLock;
+
+use crate::error::CheckerError;
+use crate::parser::{self, ParserOptions};
+use crate::response::HttpResponse;
+#[cfg(any(feature = "s3", feature = "gcs"))]
+use {
+    object_store::ObjectStore,
+    std::sync::{Arc, Mutex},
+};
+
+/// Resolves a document URI to its JSON value
+pub trait DocumentSource: Send + Sync {
+    fn resolve(&self, uri: &str) -> Result<Value>;
+
+    /// Read the raw text behind a URI, used for non-JSON document formats.
+    ///
+    /// Sources that only produce parsed values can keep the default, which
+    /// reports that raw text is unavailable.
+    fn read_text(&self, uri: &str) -> Result<String> {
+        bail!("Document source cannot read raw text: {}", uri)
+    }
+
+    /// Fetch a URI along with the response metadata `response` assertions
+    /// check, whatever its status.
+    ///
+    /// Only sources speaking HTTP have such metadata; the default reports
+    /// that it is unavailable.
+    fn fetch_response(&self, uri: &str) -> Result<HttpResponse> {
+        bail!("Document source has no response to assert on: {}", uri)
+    }
+}
+
+/// Split a document URI into its scheme and the remainder
+pub fn split_uri(uri: &str) -> (&str, &str) {
+    if uri == "-" {
+        return ("stdin", "");
+    }
+
+    match uri.split_once("://") {
+        Some((scheme, rest)) if !scheme.is_empty() && scheme.chars().all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c)) => {
+            (scheme, rest)
+        }
+        _ => ("file", uri),
+    }
+}
+
+/// Local filesystem path of a document URI, if it refers to a file
+pub fn local_path(uri: &str) -> Option<&str> {
+    match split_uri(uri) {
+        ("file", path) => Some(path),
+        _ => None,
+    }
+}
+
+/// Whether a local path is a glob pattern rather than a single file
+pub fn is_glob(path: &str) -> bool {
+    path.contains(['*', '?', '['])
+}
+
+/// Expand a glob pattern to the sorted list of matching files
+pub fn expand_glob(pattern: &str) -> Result<Vec<String>> {
+    let mut paths = glob::glob(pattern)
+        .with_context(|| format!("Invalid file pattern: {}", pattern))?
+        .filter_map(|entry| entry.ok())
+        .filter(|path| path.is_file())
+        .map(|path| path.to_string_lossy().into_owned())
+        .collect::<Vec<_>>();
+    paths.sort();
+
+    Ok(paths)
+}
+
+/// Reads documents from the local filesystem
+pub struct FileSource;
+
+impl DocumentSource for FileSource {
+    fn resolve(&self, uri: &str) -> Result<Value> {
+        parser::parse_json(&self.read_text(uri)?, ParserOptions::default()).context("Failed to parse JSON")
+    }
+
+    fn read_text(&self, uri: &str) -> Result<String> {
+        let path = local_path(uri).unwrap_or(uri);
+        Ok(std::fs::read_to_string(path).map_err(|e| CheckerError::read(path, e))?)
+    }
+}
+
+/// Reads stdin once and shares its content with every rule that uses it
+#[derive(Default)]
+pub struct StdinSource {
+    content: OnceLock<std::result::Result<String, String>>,
+}
+
+impl DocumentSource for StdinSource {
+    fn resolve(&self, uri: &str) -> Result<Value> {
+        parser::parse_json(&self.read_text(uri)?, ParserOptions::default()).context("Failed to parse JSON from stdin")
+    }
+
+    fn read_text(&self, _uri: &str) -> Result<String> {
+        self.content
+            .get_or_init(|| {
+                let mut content = String::new();
+                std::io::stdin()
+                    .read_to_string(&mut content)
+                    .map_err(|e| format!("Failed to read stdin: {}", e))?;
+                Ok(content)
+            })
+            .clone()
+            .map_err(|e| anyhow!(e))
+    }
+}
+
+/// In-memory documents registered by name and addressed as `inline://<name>`
+#[derive(Default)]
+pub struct InlineSource {
+    documents: HashMap<String, Value>,
+}
+
+impl InlineSource {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a document under a name
+    pub fn with_document(mut self, name: &str, document: Value) -> Self {
+        self.documents.insert(name.to_string(), document);
+        self
+    }
+}
+
+impl DocumentSource for InlineSource {
+    fn resolve(&self, uri: &str) -> Result<Value> {
+        let (_, name) = split_uri(uri);
+        self.documents
+            .get(name)
+            .cloned()
+            .ok_or_else(|| anyhow!("Unknown inline document: {}", name))
+    }
+}
+
+/// Fetches documents over HTTP(S)
+#[cfg(feature = "http")]
+pub struct HttpSource;
+
+#[cfg(feature = "http")]
+impl DocumentSource for HttpSource {
+    fn resolve(&self, uri: &str) -> Result<Value> {
+        parser::parse_json(&self.read_text(uri)?, ParserOptions::default()).context("Failed to parse JSON")
+    }
+
+    fn read_text(&self, uri: &str) -> Result<String> {
+        let response = self.fetch_response(uri)?;
+        if response.status >= 400 {
+            bail!("Failed to fetch: {}: HTTP status {}", uri, response.status);
+        }
+        Ok(response.body)
+    }
+
+    fn fetch_response(&self, uri: &str) -> Result<HttpResponse> {
+        let started = std::time::Instant::now();
+        let mut response = ureq::get(uri)
+            .config()
+            .http_status_as_error(false)
+            .build()
+            .call()
+            .context(format!("Failed to fetch: {}", uri))?;
+        let body = response
+            .body_mut()
+            .read_to_string()
+            .context(format!("Failed to read response body: {}", uri))?;
+
+        Ok(HttpResponse {
+            status: response.status().as_u16(),
+            content_type: response
+                .headers()
+                .get("content-type")
+                .and_then(|value| value.to_str().ok())
+                .map(String::from),
+            latency: started.elapsed(),
+            body,
+        })
+    }
+}
+
+/// Reads objects from S3 (`s3://bucket/key`) or Google Cloud Storage
+/// (`gs://bucket/key`).
+///
+/// Credentials come from the provider's standard chain: `AWS_*` variables,
+/// web identity or instance metadata for S3, and application default
+/// credentials for GCS. A client is created per bucket on first use.
+#[cfg(any(feature = "s3", feature = "gcs"))]
+pub struct ObjectStoreSource {
+    provider: ObjectStoreProvider,
+    runtime: OnceLock<std::result::Result<tokio::runtime::Runtime, String>>,
+    buckets: Mutex<HashMap<String, Arc<dyn ObjectStore>>>,
+}
+
+#[cfg(any(feature = "s3", feature = "gcs"))]
+#[derive(Debug, Clone, Copy)]
+enum ObjectStoreProvider {
+    #[cfg(feature = "s3")]
+    S3,
+    #[cfg(feature = "gcs")]
+    Gcs,
+}
+
+#[cfg(any(feature = "s3", feature = "gcs"))]
+impl ObjectStoreSource {
+    /// Source for `s3://` URIs
+    #[cfg(feature = "s3")]
+    pub fn s3() -> Self {
+        Self::new(ObjectStoreProvider::S3)
+    }
+
+    /// Source for `gs://` URIs
+    #[cfg(feature = "gcs")]
+    pub fn gcs() -> Self {
+        Self::new(ObjectStoreProvider::Gcs)
+    }
+
+    fn new(provider: ObjectStoreProvider) -> Self {
+        ObjectStoreSource {
+            provider,
+            runtime: OnceLock::new(),
+            buckets: Default::default(),
+        }
+    }
+
+    fn bucket(&self, name: &str) -> Result<Arc<dyn ObjectStore>> {
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(store) = buckets.get(name) {
+            return Ok(store.clone());
+        }
+
+        let store: Arc<dyn ObjectStore> = match self.provider {
+            #[cfg(feature = "s3")]
+            ObjectStoreProvider::S3 => Arc::new(
+                object_store::aws::AmazonS3Builder::from_env().with_bucket_name(name).build()?,
+            ),
+            #[cfg(feature = "gcs")]
+            ObjectStoreProvider::Gcs => Arc::new(
+                object_store::gcp::GoogleCloudStorageBuilder::from_env().with_bucket_name(name).build()?,
+            ),
+        };
+        buckets.insert(name.to_string(), store.clone());
+        Ok(store)
+    }
+}
+
+#[cfg(any(feature = "s3", feature = "gcs"))]
+impl DocumentSource for ObjectStoreSource {
+    fn resolve(&self, uri: &str) -> Result<Value> {
+        parser::parse_json(&self.read_text(uri)?, ParserOptions::default()).context("Failed to parse JSON")
+    }
+
+    fn read_text(&self, uri: &str) -> Result<String> {
+        let (bucket, key) = split_object_uri(uri)?;
+        let store = self.bucket(bucket).with_context(|| format!("Failed to configure client for {}", uri))?;
+        let runtime = self
+            .runtime
+            .get_or_init(|| {
+                tokio::runtime::Builder::new_current_thread()
+                    .enable_all()
+                    .build()
+                    .map_err(|e| format!("Failed to start async runtime: {}", e))
+            })
+            .as_ref()
+            .map_err(|e| anyhow!(e.clone()))?;
+
+        let bytes = runtime
+            .block_on(async { store.get(&object_store::path::Path::from(key)).await?.bytes().await })
+            .with_context(|| format!("Failed to fetch: {}", uri))?;
+        String::from_utf8(bytes.to_vec()).with_context(|| format!("Object is not UTF-8 text: {}", uri))
+    }
+}
+
+/// Split `s3://bucket/key` into its bucket and object key
+#[cfg(any(feature = "s3", feature = "gcs"))]
+fn split_object_uri(uri: &str) -> Result<(&str, &str)> {
+    let (_, rest) = split_uri(uri);
+    match rest.split_once('/') {
+        Some((bucket, key)) if !bucket.is_empty() && !key.is_empty() => Ok((bucket, key)),
+        _ => bail!("Expected a URI like s3://bucket/key, got {}", uri),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_split_uri() {
+        assert_eq!(split_uri("data/users.json"), ("file", "data/users.json"));
+        assert_eq!(split_uri("file:///tmp/a.json"), ("file", "/tmp/a.json"));
+        assert_eq!(split_uri("-"), ("stdin", ""));
+        assert_eq!(split_uri("https://example.com/a.json"), ("https", "example.com/a.json"));
+        assert_eq!(local_path("inline://users"), None);
+    }
+
+    #[test]
+    fn test_inline_source() {
+        let source = InlineSource::new().with_document("users", json!({"users": []}));
+        assert_eq!(source.resolve("inline://users").unwrap(), json!({"users": []}));
+        assert!(source.resolve("inline://missing").is_err());
+    }
+
+    #[cfg(any(feature = "s3", feature = "gcs"))]
+    #[test]
+    fn test_split_object_uri() {
+        assert_eq!(split_object_uri("s3://lake/exports/2024/users.json").unwrap(), ("lake", "exports/2024/users.json"));
+        assert_eq!(split_object_uri("gs://lake/a.ndjson").unwrap(), ("lake", "a.ndjson"));
+        assert!(split_object_uri("s3://lake").is_err());
+        assert!(split_object_uri("s3:///users.json").is_err());
+    }
+}
@@ -0,0 +1,296 @@
+//! Streaming validation for very large documents
+//!
+//! Rules whose JSONPath iterates a top-level array (`$[*]…` or `$.key[*]…`)
+//! can be checked element by element while the file is read, so the whole
+//! document never has to be held in memory.
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde::de::{self, DeserializeSeed, Deserializer, IgnoredAny, MapAccess, SeqAccess, Visitor};
+use serde_json::Value;
+use std::borrow::Cow;
+use std::fmt;
+use std::fs::File;
+use std::io::BufReader;
+
+use crate::canonicalize::Canonicalize;
+use crate::capture::CaptureValues;
+use crate::checker::JsonChecker;
+use crate::config::{CheckResult, Rule};
+use crate::error::CheckerError;
+use crate::format::DocumentFormat;
+use crate::jsonpath::{self, CompiledPath};
+use crate::loader::DocumentRef;
+use crate::messages::Message;
+use crate::path_finder::canonical_paths;
+use crate::protobuf;
+use crate::rules::{CheckRule, Quantifier};
+use crate::source::local_path;
+use crate::transform::{self, Transform};
+
+/// Failing nodes listed in a streamed result when the checker sets no limit
+const MAX_REPORTED_FAILURES: usize = 100;
+
+/// A JSONPath split into the streamed array and the path applied to each element
+#[derive(Debug, PartialEq)]
+pub struct StreamPath {
+    /// Key of the array in the top-level object, or `None` for a top-level array
+    pub key: Option<String>,
+    /// JSONPath applied to every element, starting at `$`
+    pub element_path: String,
+}
+
+impl StreamPath {
+    /// Split a JSONPath of the form `$[*]…` or `$.key[*]…`
+    pub fn parse(jsonpath: &str) -> Option<Self> {
+        if let Some(rest) = jsonpath.strip_prefix("$[*]") {
+            return Some(StreamPath { key: None, element_path: format!("${}", rest) });
+        }
+
+        let rest = jsonpath.strip_prefix("$.")?;
+        let end = rest.find("[*]")?;
+        let key = &rest[..end];
+        if key.is_empty() || !key.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '-') {
+            return None;
+        }
+
+        Some(StreamPath {
+            key: Some(key.to_string()),
+            element_path: format!("${}", &rest[end + 3..]),
+        })
+    }
+
+    /// Path prefix of the element at `index`
+    fn element_prefix(&self, index: usize) -> String {
+        match &self.key {
+            Some(key) => format!("$.{}[{}]", key, index),
+            None => format!("$[{}]", index),
+        }
+    }
+}
+
+/// Running outcome of a streamed check
+struct StreamState<'a> {
+    checker: &'a JsonChecker,
+    /// The rule's check with its values in canonical form
+    check: CheckRule,
+    canonicalize: Canonicalize,
+    transforms: &'a [Transform],
+    /// Whether protobuf JSON's numbers are read as numbers
+    protobuf: bool,
+    decode_embedded_json: bool,
+    capture: CaptureValues,
+    path: &'a StreamPath,
+    /// `path.element_path`, compiled once for every element
+    element_path: CompiledPath,
+    selected: usize,
+    any_passed: bool,
+    all_passed: bool,
+    failures: Vec<(String, Value)>,
+    failure_count: usize,
+}
+
+impl StreamState<'_> {
+    fn visit_element(&mut self, index: usize, mut element: Value) -> Result<()> {
+        self.checker.check_cancelled()?;
+        if self.decode_embedded_json {
+            transform::decode_embedded_json(&mut element, &self.path.element_prefix(index))?;
+        }
+        let values = self.element_path.select(&element)?;
+        let mut transformed = transform::apply_all(self.transforms, &values);
+        if self.protobuf {
+            transformed = protobuf::read_numbers(transformed);
+        }
+        if !self.canonicalize.is_identity() {
+            for value in &mut transformed {
+                *value = Cow::Owned(self.canonicalize.apply(value));
+            }
+        }
+
+        for (value, checked) in values.iter().zip(&transformed) {
+            self.selected += 1;
+            let passed = self.checker.apply_check_with(&[checked.as_ref()], &self.check, &self.canonicalize)?;
+            self.any_passed |= passed;
+            self.all_passed &= passed;
+
+            if !passed {
+                self.failure_count += 1;
+                if self.failures.len() < self.checker.max_failures_shown.unwrap_or(MAX_REPORTED_FAILURES) {
+                    let inner = canonical_paths(&element, std::slice::from_ref(value))
+                        .pop()
+                        .flatten()
+                        .unwrap_or_else(|| "$".to_string());
+                    let path = format!("{}{}", self.path.element_prefix(index), &inner[1..]);
+                    self.failures.push((path, self.capture.capture(checked.as_ref())));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn passed(&self) -> bool {
+        if self.selected == 0 {
+            return matches!(self.check, CheckRule::Empty);
+        }
+        match self.check.quantifier() {
+            Quantifier::Any => self.any_passed,
+            Quantifier::All => self.all_passed,
+            Quantifier::Set => unreachable!("set checks are not streamed"),
+        }
+    }
+}
+
+/// Check a rule by streaming the elements of its document's top-level array
+pub fn check_streaming(checker: &JsonChecker, rule: &Rule) -> Result<CheckResult> {
+    if rule.json_pointer.is_some() || rule.selector.is_some() {
+        let (kind, _) = rule.selected_by();
+        bail!("Rules selecting with a {} cannot be streamed; use a JSONPath like $[*]…", kind);
+    }
+    let path = StreamPath::parse(&rule.jsonpath).ok_or_else(|| {
+        anyhow!(
+            "JSONPath '{}' cannot be streamed; use $[*]… or $.key[*]…",
+            rule.jsonpath
+        )
+    })?;
+
+    if rule.check.quantifier() == Quantifier::Set {
+        bail!("Check type applies to the whole selection and cannot be streamed");
+    }
+
+    let file = match DocumentRef::of(rule)? {
+        DocumentRef::File(uri) => local_path(uri)
+            .ok_or_else(|| anyhow!("Streaming requires a local file: {}", uri))?,
+        DocumentRef::Inline(_) => bail!("Streaming requires json_file, not json_inline"),
+        DocumentRef::Query(_) => bail!("Streaming requires json_file, not a query source"),
+    };
+    if rule.format.unwrap_or_else(|| DocumentFormat::detect(file)) != DocumentFormat::Json {
+        bail!("Streaming only supports JSON documents: {}", file);
+    }
+    let reader = BufReader::new(File::open(file).map_err(|e| CheckerError::read(file, e))?);
+
+    let mut state = StreamState {
+        checker,
+        check: rule.canonicalize.apply_to_check(&rule.check),
+        canonicalize: rule.canonicalize,
+        transforms: &rule.transform,
+        protobuf: rule.protobuf_mode && rule.check.compares_numbers(),
+        decode_embedded_json: rule.decode_embedded_json,
+        capture: rule.capture_values.unwrap_or_default(),
+        path: &path,
+        element_path: jsonpath::compile_with(&path.element_path, checker.config().engine_for(rule))?,
+        selected: 0,
+        any_passed: false,
+        all_passed: true,
+        failures: Vec::new(),
+        failure_count: 0,
+    };
+
+    let mut deserializer = serde_json::Deserializer::from_reader(reader);
+    match &path.key {
+        None => deserializer.deserialize_seq(ElementVisitor { state: &mut state }),
+        Some(key) => deserializer.deserialize_map(KeyVisitor { key, state: &mut state }),
+    }
+    .context("Failed to parse JSON")?;
+    deserializer.end().context("Failed to parse JSON")?;
+
+    if state.passed() {
+        let message = Message::Passed { rule: rule.name.clone() };
+        return Ok(CheckResult::from_lines(rule.name.clone(), true, vec![message]));
+    }
+
+    let mut lines = vec![Message::Failed {
+        rule: rule.name.clone(),
+        selector_kind: "JSONPath".to_string(),
+        selector: rule.jsonpath.clone(),
+    }];
+    if state.failure_count > state.failures.len() {
+        lines.push(Message::Truncated { total: state.failure_count, shown: state.failures.len() });
+    }
+    let (invalid_positions, values_found) = state.failures.into_iter().unzip();
+
+    let mut result = CheckResult::with_positions(
+        rule.name.clone(),
+        false,
+        String::new(),
+        invalid_positions,
+        values_found,
+    );
+    result.set_lines(lines);
+    Ok(result)
+}
+
+/// Visits the streamed array, checking each element as it is parsed
+struct ElementVisitor<'s, 'a> {
+    state: &'s mut StreamState<'a>,
+}
+
+impl<'de> Visitor<'de> for ElementVisitor<'_, '_> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("an array")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        let mut index = 0;
+        while let Some(element) = seq.next_element::<Value>()? {
+            self.state
+                .visit_element(index, element)
+                .map_err(de::Error::custom)?;
+            index += 1;
+        }
+        Ok(())
+    }
+}
+
+impl<'de> DeserializeSeed<'de> for ElementVisitor<'_, '_> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+/// Visits the top-level object, streaming the array under `key` and skipping the rest
+struct KeyVisitor<'s, 'a> {
+    key: &'s str,
+    state: &'s mut StreamState<'a>,
+}
+
+impl<'de> Visitor<'de> for KeyVisitor<'_, '_> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("an object")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        while let Some(key) = map.next_key::<String>()? {
+            if key == self.key {
+                map.next_value_seed(ElementVisitor { state: &mut *self.state })?;
+            } else {
+                map.next_value::<IgnoredAny>()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_stream_path() {
+        assert_eq!(
+            StreamPath::parse("$[*].email"),
+            Some(StreamPath { key: None, element_path: "$.email".to_string() })
+        );
+        assert_eq!(
+            StreamPath::parse("$.users[*]"),
+            Some(StreamPath { key: Some("users".to_string()), element_path: "$".to_string() })
+        );
+        assert_eq!(StreamPath::parse("$.data.users[*]"), None);
+        assert_eq!(StreamPath::parse("$..email"), None);
+    }
+}
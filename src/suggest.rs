@@ -0,0 +1,50 @@
+//! "Did you mean" suggestions for misspelled names
+
+/// The candidate closest to `name`, if it is close enough to be a likely typo
+pub fn closest<'a>(name: &str, candidates: impl IntoIterator<Item = &'a str>) -> Option<&'a str> {
+    let limit = (name.chars().count() / 3).max(1);
+    candidates
+        .into_iter()
+        .map(|candidate| (distance(name, candidate), candidate))
+        .filter(|&(distance, _)| distance <= limit)
+        .min_by_key(|&(distance, _)| distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// Edit distance where swapping two adjacent characters counts as one edit
+/// (optimal string alignment), counting characters rather than bytes
+fn distance(a: &str, b: &str) -> usize {
+    let (a, b): (Vec<char>, Vec<char>) = (a.chars().collect(), b.chars().collect());
+    // rows[i][j] is the distance between the first i characters of a and the first j of b
+    let mut rows = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in rows.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    rows[0] = (0..=b.len()).collect();
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut best = (rows[i - 1][j - 1] + cost).min(rows[i - 1][j] + 1).min(rows[i][j - 1] + 1);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                best = best.min(rows[i - 2][j - 2] + 1);
+            }
+            rows[i][j] = best;
+        }
+    }
+    rows[a.len()][b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_closest() {
+        let keys = ["json_file", "jsonpath", "json_pointer", "severity"];
+        assert_eq!(closest("jsonpth", keys), Some("jsonpath"));
+        assert_eq!(closest("json-file", keys), Some("json_file"));
+        assert_eq!(closest("sevrity", keys), Some("severity"));
+        assert_eq!(closest("mni", ["min", "max"]), Some("min"));
+        assert_eq!(closest("timeout", keys), None);
+    }
+}
@@ -0,0 +1,97 @@
+//! Suites: named groups of rules with their own summary
+//!
+//! A config can list `suites` next to its flat `rules`. When the config is
+//! loaded, every suite's rules join `Config::rules` marked with the suite's
+//! name, so they run like any other rule, and results are summarized per
+//! suite. `--suite payments` runs only the rules of that suite.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::config::{CheckResult, Rule, Severity, Status};
+
+/// A named group of rules
+#[derive(Debug, Default, Deserialize, JsonSchema, Serialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct Suite {
+    pub name: String,
+    /// What the suite's rules guard, shown in reports
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// The suite's rules as written; a loaded config has moved them into
+    /// `Config::rules`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rules: Vec<Rule>,
+}
+
+impl Suite {
+    /// Take the suite's rules out, each marked with the suite's name
+    pub fn take_rules(&mut self) -> Vec<Rule> {
+        let mut rules = std::mem::take(&mut self.rules);
+        for rule in &mut rules {
+            rule.suite = Some(self.name.clone());
+        }
+        rules
+    }
+}
+
+/// Outcome counts of the rules of one suite
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+pub struct SuiteSummary {
+    pub name: String,
+    pub total: usize,
+    pub passed: usize,
+    pub failed: usize,
+    pub warnings: usize,
+    pub skipped: usize,
+}
+
+/// Summarize results per suite, in the order suites first appear; results
+/// of rules outside any suite are left out
+pub fn summarize(results: &[CheckResult]) -> Vec<SuiteSummary> {
+    let mut summaries: Vec<SuiteSummary> = Vec::new();
+    for result in results {
+        let Some(name) = &result.suite else {
+            continue;
+        };
+        let index = match summaries.iter().position(|s| &s.name == name) {
+            Some(index) => index,
+            None => {
+                summaries.push(SuiteSummary { name: name.clone(), ..Default::default() });
+                summaries.len() - 1
+            }
+        };
+        let summary = &mut summaries[index];
+        summary.total += 1;
+        match result.status {
+            Status::Skipped => summary.skipped += 1,
+            Status::Pass => summary.passed += 1,
+            _ if result.severity == Severity::Warning => summary.warnings += 1,
+            _ => summary.failed += 1,
+        }
+    }
+    summaries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summarize() {
+        let result = |name: &str, suite: Option<&str>, passed: bool| CheckResult {
+            suite: suite.map(str::to_string),
+            ..CheckResult::new(name.to_string(), passed, String::new())
+        };
+        let results = [
+            result("a", Some("payments"), true),
+            result("b", None, false),
+            result("c", Some("search"), false),
+            result("d", Some("payments"), false),
+        ];
+        let summaries = summarize(&results);
+        assert_eq!(summaries.len(), 2);
+        assert_eq!(summaries[0], SuiteSummary { name: "payments".to_string(), total: 2, passed: 1, failed: 1, ..Default::default() });
+        assert_eq!(summaries[1].name, "search");
+    }
+}
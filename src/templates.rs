@@ -0,0 +1,100 @@
+//! Reusable check definitions
+//!
+//! A config can declare named checks under `check_templates` and rules refer
+//! to them with `{"type": "template", "name": "...", "params": {...}}`.
+//! Templates may use `${param}` placeholders; a string consisting of a single
+//! placeholder is replaced by the parameter value itself, keeping its type.
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde_json::Value;
+use std::collections::HashMap;
+
+use crate::rules::CheckRule;
+use crate::vars;
+
+/// Expand a template definition with the given parameters.
+///
+/// Placeholders not covered by `params` fall back to `vars` (and then the
+/// environment), like `${NAME}` references anywhere else in a config.
+pub fn expand(
+    templates: &HashMap<String, Value>,
+    name: &str,
+    params: &HashMap<String, Value>,
+    vars: &HashMap<String, String>,
+) -> Result<CheckRule> {
+    let template = templates
+        .get(name)
+        .ok_or_else(|| anyhow!("Unknown check template '{}'", name))?;
+
+    let mut lookup = vars.clone();
+    for (key, value) in params {
+        let text = match value {
+            Value::String(s) => s.clone(),
+            other => other.to_string(),
+        };
+        lookup.insert(key.clone(), text);
+    }
+
+    let expanded = substitute_params(template, params, &lookup)
+        .with_context(|| format!("Failed to expand check template '{}'", name))?;
+    let check: CheckRule = serde_json::from_value(expanded)
+        .with_context(|| format!("Check template '{}' is not a valid check", name))?;
+    if matches!(check, CheckRule::Template { .. }) {
+        bail!("Check template '{}' refers to another template", name);
+    }
+
+    Ok(check)
+}
+
+fn substitute_params(
+    value: &Value,
+    params: &HashMap<String, Value>,
+    lookup: &HashMap<String, String>,
+) -> Result<Value> {
+    Ok(match value {
+        Value::String(s) => {
+            let whole = s
+                .strip_prefix("${")
+                .and_then(|rest| rest.strip_suffix('}'))
+                .and_then(|name| params.get(name));
+            match whole {
+                Some(param) => param.clone(),
+                None => Value::String(vars::substitute(s, lookup)?),
+            }
+        }
+        Value::Array(arr) => Value::Array(
+            arr.iter()
+                .map(|v| substitute_params(v, params, lookup))
+                .collect::<Result<_>>()?,
+        ),
+        Value::Object(obj) => Value::Object(
+            obj.iter()
+                .map(|(k, v)| Ok((k.clone(), substitute_params(v, params, lookup)?)))
+                .collect::<Result<_>>()?,
+        ),
+        other => other.clone(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_expand_keeps_param_types() {
+        let templates = HashMap::from([
+            ("positive".to_string(), json!({"type": "greater_than", "value": "${min}"})),
+            ("prefixed".to_string(), json!({"type": "regex", "pattern": "^${prefix}-[0-9]+$"})),
+        ]);
+        let params = |key: &str, value: Value| HashMap::from([(key.to_string(), value)]);
+
+        let check = expand(&templates, "positive", &params("min", json!(0)), &HashMap::new()).unwrap();
+        assert!(matches!(check, CheckRule::GreaterThan { value } if value.as_f64() == Some(0.0)));
+
+        let check = expand(&templates, "prefixed", &params("prefix", json!("ORD")), &HashMap::new()).unwrap();
+        assert!(matches!(check, CheckRule::Regex { pattern } if pattern == "^ORD-[0-9]+$"));
+
+        assert!(expand(&templates, "missing", &HashMap::new(), &HashMap::new()).is_err());
+    }
+}
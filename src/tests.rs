@@ -1,353 +1,3139 @@
-//! Comprehensive test suite for JSON validation rules
-
-#[cfg(test)]
-mod tests {
-    use crate::{JsonChecker, Config, CheckRule};
-    use serde_json::Value;
-
-    fn create_test_checker() -> JsonChecker {
-        JsonChecker::from_config(Config { rules: vec![] })
-    }
-
-    // Helper function to create test values and avoid borrow checker issues
-    fn test_apply_check(checker: &JsonChecker, values: Vec<Value>, check: &CheckRule) -> bool {
-        let value_refs: Vec<&Value> = values.iter().collect();
-        checker.apply_check(&value_refs, check).unwrap()
-    }
-
-    #[test]
-    fn test_empty_check() {
-        let checker = create_test_checker();
-        let check = CheckRule::Empty;
-        
-        // Test empty values
-        let empty_values = vec![
-            serde_json::json!(null),
-            serde_json::json!(""),
-            serde_json::json!([]),
-            serde_json::json!({}),
-        ];
-        assert!(test_apply_check(&checker, empty_values, &check));
-        
-        // Test non-empty values
-        let non_empty_values = vec![
-            serde_json::json!("test"),
-            serde_json::json!([1, 2]),
-            serde_json::json!({"key": "value"}),
-            serde_json::json!(42),
-        ];
-        assert!(!test_apply_check(&checker, non_empty_values, &check));
-        
-        // Test no values found (should pass for Empty check)
-        assert!(test_apply_check(&checker, vec![], &check));
-    }
-
-    #[test]
-    fn test_non_empty_check() {
-        let checker = create_test_checker();
-        let check = CheckRule::NonEmpty;
-        
-        // Test non-empty values
-        let non_empty_values = vec![
-            serde_json::json!("test"),
-            serde_json::json!([1, 2]),
-            serde_json::json!({"key": "value"}),
-            serde_json::json!(42),
-            serde_json::json!(true),
-        ];
-        assert!(test_apply_check(&checker, non_empty_values, &check));
-        
-        // Test empty values
-        let empty_values = vec![
-            serde_json::json!(null),
-            serde_json::json!(""),
-            serde_json::json!([]),
-            serde_json::json!({}),
-        ];
-        assert!(!test_apply_check(&checker, empty_values, &check));
-        
-        // Test mixed values (should fail if any are empty)
-        let mixed_values = vec![
-            serde_json::json!("test"),
-            serde_json::json!(""),
-        ];
-        assert!(!test_apply_check(&checker, mixed_values, &check));
-    }
-
-    #[test]
-    fn test_equals_check() {
-        let checker = create_test_checker();
-        
-        let check = CheckRule::Equals { value: serde_json::json!("admin") };
-        
-        // Test matching values (should pass if any match)
-        let matching_values = vec![
-            serde_json::json!("admin"),
-            serde_json::json!("user"),
-        ];
-        assert!(test_apply_check(&checker, matching_values, &check));
-        
-        // Test non-matching values
-        let non_matching_values = vec![
-            serde_json::json!("user"),
-            serde_json::json!("guest"),
-        ];
-        assert!(!test_apply_check(&checker, non_matching_values, &check));
-        
-        // Test with numbers
-        let number_check = CheckRule::Equals { value: serde_json::json!(42) };
-        let number_values = vec![serde_json::json!(42)];
-        assert!(test_apply_check(&checker, number_values, &number_check));
-    }
-
-    #[test]
-    fn test_not_equals_check() {
-        let checker = create_test_checker();
-        let check = CheckRule::NotEquals { value: serde_json::json!("admin") };
-        
-        // Test non-matching values (should pass)
-        let non_matching_values = vec![
-            serde_json::json!("user"),
-            serde_json::json!("guest"),
-        ];
-        assert!(test_apply_check(&checker, non_matching_values, &check));
-        
-        // Test matching values (should fail if any match)
-        let matching_values = vec![
-            serde_json::json!("admin"),
-            serde_json::json!("user"),
-        ];
-        assert!(!test_apply_check(&checker, matching_values, &check));
-    }
-
-    #[test]
-    fn test_contains_check() {
-        let checker = create_test_checker();
-        
-        // Test array contains
-        let array_check = CheckRule::Contains { value: serde_json::json!("search") };
-        let array_values = vec![serde_json::json!(["search", "auth", "api"])];
-        assert!(test_apply_check(&checker, array_values, &array_check));
-        
-        let array_values_no_match = vec![serde_json::json!(["auth", "api"])];
-        assert!(!test_apply_check(&checker, array_values_no_match, &array_check));
-        
-        // Test string contains
-        let string_check = CheckRule::Contains { value: serde_json::json!("test") };
-        let string_values = vec![serde_json::json!("testing123")];
-        assert!(test_apply_check(&checker, string_values, &string_check));
-        
-        let string_values_no_match = vec![serde_json::json!("example")];
-        assert!(!test_apply_check(&checker, string_values_no_match, &string_check));
-        
-        // Test object contains
-        let object_check = CheckRule::Contains { value: serde_json::json!({"name": "John"}) };
-        let object_values = vec![serde_json::json!({"name": "John", "age": 30})];
-        assert!(test_apply_check(&checker, object_values, &object_check));
-    }
-
-    #[test]
-    fn test_contained_by_check() {
-        let checker = create_test_checker();
-        let check = CheckRule::ContainedBy { value: serde_json::json!(["a", "b", "c", "d"]) };
-        
-        // Test value contained by larger array
-        let contained_values = vec![serde_json::json!("b")];
-        assert!(test_apply_check(&checker, contained_values, &check));
-        
-        // Test value not contained
-        let not_contained_values = vec![serde_json::json!("z")];
-        assert!(!test_apply_check(&checker, not_contained_values, &check));
-    }
-
-    #[test]
-    fn test_jsonb_contains() {
-        let checker = create_test_checker();
-
-        // Test the helper function directly
-        let left = serde_json::json!({"a": 1, "b": 2});
-        let right = serde_json::json!({"a": 1});
-        assert!(checker.jsonb_contains(&left, &right));
-
-        let left = serde_json::json!({"a": 1});
-        let right = serde_json::json!({"a": 1, "b": 2});
-        assert!(!checker.jsonb_contains(&left, &right));
-        
-        // Test with check rule
-        let check = CheckRule::JsonbContains { 
-            value: serde_json::json!({"database": {"host": "localhost"}}) 
-        };
-        let values = vec![serde_json::json!({"database": {"host": "localhost", "port": 5432}})];
-        assert!(test_apply_check(&checker, values, &check));
-    }
-
-    #[test]
-    fn test_regex_check() {
-        let checker = create_test_checker();
-        let check = CheckRule::Regex { 
-            pattern: r"^[a-zA-Z0-9._%+-]+@[a-zA-Z0-9.-]+\.[a-zA-Z]{2,}$".to_string() 
-        };
-        
-        // Test valid emails
-        let valid_emails = vec![serde_json::json!("user@example.com")];
-        assert!(test_apply_check(&checker, valid_emails, &check));
-        
-        // Test invalid emails
-        let invalid_emails = vec![serde_json::json!("invalid-email")];
-        assert!(!test_apply_check(&checker, invalid_emails, &check));
-    }
-
-    #[test]
-    fn test_greater_than_check() {
-        let checker = create_test_checker();
-        let check = CheckRule::GreaterThan { value: 18.0 };
-        
-        let greater_values = vec![serde_json::json!(25)];
-        assert!(test_apply_check(&checker, greater_values, &check));
-        
-        let lesser_values = vec![serde_json::json!(15)];
-        assert!(!test_apply_check(&checker, lesser_values, &check));
-    }
-
-    #[test]
-    fn test_less_than_check() {
-        let checker = create_test_checker();
-        let check = CheckRule::LessThan { value: 100.0 };
-        
-        let lesser_values = vec![serde_json::json!(50)];
-        assert!(test_apply_check(&checker, lesser_values, &check));
-        
-        let greater_values = vec![serde_json::json!(150)];
-        assert!(!test_apply_check(&checker, greater_values, &check));
-    }
-
-    #[test]
-    fn test_array_length_check() {
-        let checker = create_test_checker();
-        let check = CheckRule::ArrayLength { min: Some(1), max: Some(5) };
-        
-        let valid_arrays = vec![serde_json::json!([1, 2, 3])];
-        assert!(test_apply_check(&checker, valid_arrays, &check));
-        
-        let invalid_arrays = vec![serde_json::json!([])];
-        assert!(!test_apply_check(&checker, invalid_arrays, &check));
-    }
-
-    #[test]
-    fn test_jsonb_exists_checks() {
-        let checker = create_test_checker();
-        
-        // Test JsonbExists
-        let exists_check = CheckRule::JsonbExists { key: "email".to_string() };
-        let with_key = vec![serde_json::json!({"email": "test@example.com"})];
-        assert!(test_apply_check(&checker, with_key, &exists_check));
-        
-        let without_key = vec![serde_json::json!({"name": "John"})];
-        assert!(!test_apply_check(&checker, without_key, &exists_check));
-        
-        // Test JsonbExistsAll
-        let exists_all_check = CheckRule::JsonbExistsAll { 
-            keys: vec!["email".to_string(), "name".to_string()] 
-        };
-        let with_all_keys = vec![serde_json::json!({"email": "test@example.com", "name": "John"})];
-        assert!(test_apply_check(&checker, with_all_keys, &exists_all_check));
-        
-        let with_some_keys = vec![serde_json::json!({"email": "test@example.com"})];
-        assert!(!test_apply_check(&checker, with_some_keys, &exists_all_check));
-    }
-
-    #[test]
-    fn test_jsonb_contained_by_check() {
-        let checker = create_test_checker();
-        let check = CheckRule::JsonbContainedBy { 
-            value: serde_json::json!({"a": 1, "b": 2, "c": 3}) 
-        };
-        
-        let contained_values = vec![serde_json::json!({"a": 1})];
-        assert!(test_apply_check(&checker, contained_values, &check));
-        
-        let not_contained_values = vec![serde_json::json!({"a": 1, "b": 2, "d": 4})];
-        assert!(!test_apply_check(&checker, not_contained_values, &check));
-    }
-
-    #[test]
-    fn test_jsonb_exists_any_check() {
-        let checker = create_test_checker();
-        let check = CheckRule::JsonbExistsAny { 
-            keys: vec!["email".to_string(), "phone".to_string()] 
-        };
-        
-        let with_email = vec![serde_json::json!({"email": "test@example.com", "name": "John"})];
-        assert!(test_apply_check(&checker, with_email, &check));
-        
-        let with_phone = vec![serde_json::json!({"phone": "123-456-7890", "name": "John"})];
-        assert!(test_apply_check(&checker, with_phone, &check));
-        
-        let without_keys = vec![serde_json::json!({"name": "John"})];
-        assert!(!test_apply_check(&checker, without_keys, &check));
-    }
-
-    #[test]
-    fn test_jsonb_path_match_check() {
-        let checker = create_test_checker();
-        let check = CheckRule::JsonbPathMatch { path: "$.users[*].email".to_string() };
-        
-        let values = vec![serde_json::json!({"users": [{"email": "test@example.com"}]})];
-        // Currently always returns true - this is a simplified implementation
-        assert!(test_apply_check(&checker, values, &check));
-    }
-
-    #[test]
-    fn test_edge_cases_and_comprehensive_coverage() {
-        let checker = create_test_checker();
-        
-        // Test empty values array for different checks
-        assert!(test_apply_check(&checker, vec![], &CheckRule::Empty));
-        assert!(!test_apply_check(&checker, vec![], &CheckRule::NonEmpty));
-        
-        // Test array length with edge cases
-        let array_check = CheckRule::ArrayLength { min: None, max: Some(3) };
-        let small_array = vec![serde_json::json!([1, 2])];
-        assert!(test_apply_check(&checker, small_array, &array_check));
-        
-        // Test regex with non-string values (should fail)
-        let regex_check = CheckRule::Regex { pattern: "test".to_string() };
-        let non_string = vec![serde_json::json!(42)];
-        assert!(!test_apply_check(&checker, non_string, &regex_check));
-        
-        // Test numeric comparisons with non-numeric values
-        let gt_check = CheckRule::GreaterThan { value: 10.0 };
-        let non_numeric = vec![serde_json::json!("not_a_number")];
-        assert!(!test_apply_check(&checker, non_numeric, &gt_check));
-    }
-
-    #[test]
-    fn test_helper_functions() {
-        let checker = create_test_checker();
-
-        // Test array contains
-        let arr = serde_json::json!([1, 2, 3]);
-        let val = serde_json::json!(2);
-        assert!(checker.contains(&arr, &val));
-        
-        // Test string contains
-        let container = serde_json::json!("hello world");
-        let contained = serde_json::json!("world");
-        assert!(checker.contains(&container, &contained));
-        
-        // Test object contains
-        let obj_container = serde_json::json!({"a": 1, "b": 2, "c": 3});
-        let obj_contained = serde_json::json!({"a": 1, "b": 2});
-        assert!(checker.contains(&obj_container, &obj_contained));
-        
-        // Test is_empty_value
-        assert!(checker.is_empty_value(&serde_json::json!(null)));
-        assert!(checker.is_empty_value(&serde_json::json!("")));
-        assert!(checker.is_empty_value(&serde_json::json!([])));
-        assert!(checker.is_empty_value(&serde_json::json!({})));
-        assert!(!checker.is_empty_value(&serde_json::json!("test")));
-        assert!(!checker.is_empty_value(&serde_json::json!(42)));
-        assert!(!checker.is_empty_value(&serde_json::json!(true)));
-        assert!(!checker.is_empty_value(&serde_json::json!(false)));
-    }
-}
\ No newline at end of file
+//! Comprehensive test suite for JSON validation rules
+
+use crate::{JsonChecker, Config, CheckRule};
+use serde_json::Value;
+
+fn create_test_checker() -> JsonChecker {
+    JsonChecker::from_config(Config::default())
+}
+
+// Helper function to create test values and avoid borrow checker issues
+fn test_apply_check(checker: &JsonChecker, values: Vec<Value>, check: &CheckRule) -> bool {
+    let value_refs: Vec<&Value> = values.iter().collect();
+    checker.apply_check(&value_refs, check).unwrap()
+}
+
+#[test]
+fn test_empty_check() {
+    let checker = create_test_checker();
+    let check = CheckRule::Empty;
+    
+    // Test empty values
+    let empty_values = vec![
+        serde_json::json!(null),
+        serde_json::json!(""),
+        serde_json::json!([]),
+        serde_json::json!({}),
+    ];
+    assert!(test_apply_check(&checker, empty_values, &check));
+    
+    // Test non-empty values
+    let non_empty_values = vec![
+        serde_json::json!("test"),
+        serde_json::json!([1, 2]),
+        serde_json::json!({"key": "value"}),
+        serde_json::json!(42),
+    ];
+    assert!(!test_apply_check(&checker, non_empty_values, &check));
+    
+    // Test no values found (should pass for Empty check)
+    assert!(test_apply_check(&checker, vec![], &check));
+}
+
+#[test]
+fn test_non_empty_check() {
+    let checker = create_test_checker();
+    let check = CheckRule::NonEmpty;
+    
+    // Test non-empty values
+    let non_empty_values = vec![
+        serde_json::json!("test"),
+        serde_json::json!([1, 2]),
+        serde_json::json!({"key": "value"}),
+        serde_json::json!(42),
+        serde_json::json!(true),
+    ];
+    assert!(test_apply_check(&checker, non_empty_values, &check));
+    
+    // Test empty values
+    let empty_values = vec![
+        serde_json::json!(null),
+        serde_json::json!(""),
+        serde_json::json!([]),
+        serde_json::json!({}),
+    ];
+    assert!(!test_apply_check(&checker, empty_values, &check));
+    
+    // Test mixed values (should fail if any are empty)
+    let mixed_values = vec![
+        serde_json::json!("test"),
+        serde_json::json!(""),
+    ];
+    assert!(!test_apply_check(&checker, mixed_values, &check));
+}
+
+#[test]
+fn test_equals_check() {
+    let checker = create_test_checker();
+    
+    let check = CheckRule::Equals { value: serde_json::json!("admin"), tolerance: None };
+    
+    // Test matching values (should pass if any match)
+    let matching_values = vec![
+        serde_json::json!("admin"),
+        serde_json::json!("user"),
+    ];
+    assert!(test_apply_check(&checker, matching_values, &check));
+    
+    // Test non-matching values
+    let non_matching_values = vec![
+        serde_json::json!("user"),
+        serde_json::json!("guest"),
+    ];
+    assert!(!test_apply_check(&checker, non_matching_values, &check));
+    
+    // Test with numbers
+    let number_check = CheckRule::Equals { value: serde_json::json!(42), tolerance: None };
+    let number_values = vec![serde_json::json!(42)];
+    assert!(test_apply_check(&checker, number_values, &number_check));
+}
+
+#[test]
+fn test_not_equals_check() {
+    let checker = create_test_checker();
+    let check = CheckRule::NotEquals { value: serde_json::json!("admin") };
+    
+    // Test non-matching values (should pass)
+    let non_matching_values = vec![
+        serde_json::json!("user"),
+        serde_json::json!("guest"),
+    ];
+    assert!(test_apply_check(&checker, non_matching_values, &check));
+    
+    // Test matching values (should fail if any match)
+    let matching_values = vec![
+        serde_json::json!("admin"),
+        serde_json::json!("user"),
+    ];
+    assert!(!test_apply_check(&checker, matching_values, &check));
+}
+
+#[test]
+fn test_contains_check() {
+    let checker = create_test_checker();
+    
+    // Test array contains
+    let array_check = CheckRule::Contains { value: serde_json::json!("search") };
+    let array_values = vec![serde_json::json!(["search", "auth", "api"])];
+    assert!(test_apply_check(&checker, array_values, &array_check));
+    
+    let array_values_no_match = vec![serde_json::json!(["auth", "api"])];
+    assert!(!test_apply_check(&checker, array_values_no_match, &array_check));
+    
+    // Test string contains
+    let string_check = CheckRule::Contains { value: serde_json::json!("test") };
+    let string_values = vec![serde_json::json!("testing123")];
+    assert!(test_apply_check(&checker, string_values, &string_check));
+    
+    let string_values_no_match = vec![serde_json::json!("example")];
+    assert!(!test_apply_check(&checker, string_values_no_match, &string_check));
+    
+    // Test object contains
+    let object_check = CheckRule::Contains { value: serde_json::json!({"name": "John"}) };
+    let object_values = vec![serde_json::json!({"name": "John", "age": 30})];
+    assert!(test_apply_check(&checker, object_values, &object_check));
+}
+
+#[test]
+fn test_contained_by_check() {
+    let checker = create_test_checker();
+    let check = CheckRule::ContainedBy { value: serde_json::json!(["a", "b", "c", "d"]) };
+    
+    // Test value contained by larger array
+    let contained_values = vec![serde_json::json!("b")];
+    assert!(test_apply_check(&checker, contained_values, &check));
+    
+    // Test value not contained
+    let not_contained_values = vec![serde_json::json!("z")];
+    assert!(!test_apply_check(&checker, not_contained_values, &check));
+}
+
+#[test]
+fn test_jsonb_contains() {
+    let checker = create_test_checker();
+
+    // Test the helper function directly
+    let left = serde_json::json!({"a": 1, "b": 2});
+    let right = serde_json::json!({"a": 1});
+    assert!(checker.jsonb_contains(&left, &right));
+
+    let left = serde_json::json!({"a": 1});
+    let right = serde_json::json!({"a": 1, "b": 2});
+    assert!(!checker.jsonb_contains(&left, &right));
+    
+    // Test with check rule
+    let check = CheckRule::JsonbContains { 
+        value: serde_json::json!({"database": {"host": "localhost"}}) 
+    };
+    let values = vec![serde_json::json!({"database": {"host": "localhost", "port": 5432}})];
+    assert!(test_apply_check(&checker, values, &check));
+}
+
+#[test]
+fn test_regex_check() {
+    let checker = create_test_checker();
+    let check = CheckRule::Regex { 
+        pattern: r"^[a-zA-Z0-9._%+-]+@[a-zA-Z0-9.-]+\.[a-zA-Z]{2,}$".to_string() 
+    };
+    
+    // Test valid emails
+    let valid_emails = vec![serde_json::json!("user@example.com")];
+    assert!(test_apply_check(&checker, valid_emails, &check));
+    
+    // Test invalid emails
+    let invalid_emails = vec![serde_json::json!("invalid-email")];
+    assert!(!test_apply_check(&checker, invalid_emails, &check));
+}
+
+#[test]
+fn test_string_prefix_suffix_and_format_checks() {
+    let checker = create_test_checker();
+    let values = [
+        serde_json::json!("ORD-123.json"),
+        serde_json::json!("INV-9.json"),
+        serde_json::json!(42),
+    ];
+    let refs: Vec<&Value> = values.iter().collect();
+
+    let starts = CheckRule::StartsWith { value: "ORD-".to_string() };
+    assert!(!checker.apply_check(&refs, &starts).unwrap());
+    assert_eq!(checker.find_invalid_value_indices(&refs, &starts), vec![1, 2]);
+
+    let ends = CheckRule::EndsWith { value: ".json".to_string() };
+    assert_eq!(checker.find_invalid_value_indices(&refs, &ends), vec![2]);
+
+    let parsed: CheckRule = serde_json::from_str(r#"{"type": "format", "kind": "uuid"}"#).unwrap();
+    assert!(!checker.apply_check(&refs, &parsed).unwrap());
+    assert_eq!(checker.find_invalid_value_indices(&refs, &parsed), vec![0, 1, 2]);
+
+    // One valid address doesn't excuse the others, and each invalid one is reported
+    let config = r#"{"rules": [{"name": "Emails", "json_inline": {"users": [{"email": "a@b.co"}, {"email": "nope"}, {"email": 7}]},
+        "jsonpath": "$.users[*].email", "check": {"type": "format", "kind": "email"}}]}"#;
+    let result = checker.run_rule(&crate::Config::from_json(config).unwrap().rules[0]);
+    assert!(!result.passed);
+    assert_eq!(result.invalid_positions, ["$.users[1].email", "$.users[2].email"]);
+    let ids = [serde_json::json!("ORD-1"), serde_json::json!("ORD-2")];
+    assert!(checker.apply_check(&ids.iter().collect::<Vec<_>>(), &starts).unwrap());
+}
+
+#[test]
+fn test_greater_than_check() {
+    let checker = create_test_checker();
+    let check = CheckRule::GreaterThan { value: 18.into() };
+    
+    let greater_values = vec![serde_json::json!(25)];
+    assert!(test_apply_check(&checker, greater_values, &check));
+    
+    let lesser_values = vec![serde_json::json!(15)];
+    assert!(!test_apply_check(&checker, lesser_values, &check));
+}
+
+#[test]
+fn test_less_than_check() {
+    let checker = create_test_checker();
+    let check = CheckRule::LessThan { value: 100.into() };
+    
+    let lesser_values = vec![serde_json::json!(50)];
+    assert!(test_apply_check(&checker, lesser_values, &check));
+    
+    let greater_values = vec![serde_json::json!(150)];
+    assert!(!test_apply_check(&checker, greater_values, &check));
+}
+
+#[test]
+fn test_inclusive_comparison_checks() {
+    let checker = create_test_checker();
+    let gte = CheckRule::GreaterThanOrEqual { value: 0.into() };
+    assert!(test_apply_check(&checker, vec![serde_json::json!(0)], &gte));
+    assert!(!test_apply_check(&checker, vec![serde_json::json!(-0.5)], &gte));
+
+    let lte = CheckRule::LessThanOrEqual { value: 100.into() };
+    assert!(test_apply_check(&checker, vec![serde_json::json!(100)], &lte));
+    assert!(!test_apply_check(&checker, vec![serde_json::json!(101)], &lte));
+
+    let values = [serde_json::json!(0), serde_json::json!(-1), serde_json::json!("0")];
+    let refs: Vec<&Value> = values.iter().collect();
+    assert_eq!(checker.find_invalid_value_indices(&refs, &gte), vec![1, 2]);
+
+    let parsed: CheckRule = serde_json::from_str(r#"{"type": "gte", "value": 1}"#).unwrap();
+    assert!(matches!(parsed, CheckRule::GreaterThanOrEqual { value } if value.as_f64() == Some(1.0)));
+}
+
+#[test]
+fn test_integer_and_multiple_of_checks() {
+    let checker = create_test_checker();
+    let values = [serde_json::json!(8080), serde_json::json!(99.5), serde_json::json!(1250)];
+    let refs: Vec<&Value> = values.iter().collect();
+
+    assert_eq!(checker.find_invalid_value_indices(&refs, &CheckRule::Integer), vec![1]);
+
+    let cents: CheckRule = serde_json::from_str(r#"{"type": "multiple_of", "value": 25}"#).unwrap();
+    assert!(!checker.apply_check(&refs, &cents).unwrap());
+    assert_eq!(checker.find_invalid_value_indices(&refs, &cents), vec![0, 1]);
+
+    // A whole number among the values doesn't make the others whole
+    assert!(!test_apply_check(&checker, vec![serde_json::json!(1), serde_json::json!(2.5)], &CheckRule::Integer));
+    assert!(test_apply_check(&checker, vec![serde_json::json!(1), serde_json::json!(2)], &CheckRule::Integer));
+    assert!(test_apply_check(&checker, vec![serde_json::json!(50), serde_json::json!(1250)], &cents));
+}
+
+#[test]
+fn test_thresholds_compare_exactly() {
+    let checker = create_test_checker();
+    // Both round to the same f64, 9007199254740992
+    let config = r#"{"rules": [{"name": "Id", "json_inline": {"id": 9007199254740993}, "jsonpath": "$.id",
+        "check": {"type": "greater_than", "value": 9007199254740992}}]}"#;
+    let config = crate::Config::from_json(config).unwrap();
+    assert!(checker.run_rule(&config.rules[0]).passed);
+
+    let values: Vec<Value> = serde_json::from_str("[9007199254740993, 1.0]").unwrap();
+    let refs: Vec<&Value> = values.iter().collect();
+    let equals: CheckRule = serde_json::from_str(r#"{"type": "equals", "value": 9007199254740992}"#).unwrap();
+    assert_eq!(checker.find_invalid_value_indices(&refs, &equals), vec![0, 1]);
+    let one: CheckRule = serde_json::from_str(r#"{"type": "not_equals", "value": 1}"#).unwrap();
+    assert_eq!(checker.find_invalid_value_indices(&refs, &one), vec![1]);
+}
+
+#[test]
+fn test_decimal_places_and_safe_integer_checks() {
+    let checker = create_test_checker();
+    let values: Vec<Value> = serde_json::from_str("[19.99, 5, 0.125, 9007199254740993, 123456789012345678901234567890]").unwrap();
+    let refs: Vec<&Value> = values.iter().collect();
+
+    let money: CheckRule = serde_json::from_str(r#"{"type": "decimal_places", "max": 2}"#).unwrap();
+    assert_eq!(checker.find_invalid_value_indices(&refs, &money), vec![2]);
+    assert_eq!(checker.find_invalid_value_indices(&refs, &CheckRule::SafeInteger), vec![0, 2, 3, 4]);
+
+    // Every value must qualify; one that does doesn't make up for the others
+    assert!(!checker.apply_check(&refs, &money).unwrap());
+    let config = r#"{"rules": [{"name": "Prices", "json_inline": {"items": [{"price": 9.99}, {"price": 1.005}, {"price": 0.125}]},
+        "jsonpath": "$.items[*].price", "check": {"type": "decimal_places", "max": 2}}]}"#;
+    let result = checker.run_rule(&crate::Config::from_json(config).unwrap().rules[0]);
+    assert!(!result.passed);
+    assert_eq!(result.invalid_positions, ["$.items[1].price", "$.items[2].price"]);
+}
+
+#[test]
+fn test_array_length_check() {
+    let checker = create_test_checker();
+    let check = CheckRule::ArrayLength { min: Some(1), max: Some(5) };
+    
+    let valid_arrays = vec![serde_json::json!([1, 2, 3])];
+    assert!(test_apply_check(&checker, valid_arrays, &check));
+    
+    let invalid_arrays = vec![serde_json::json!([])];
+    assert!(!test_apply_check(&checker, invalid_arrays, &check));
+}
+
+#[test]
+fn test_paths_equal_check() {
+    let checker = create_test_checker();
+    let check = |left: &str, right: &str| CheckRule::PathsEqual {
+        left_jsonpath: left.to_string(),
+        right_jsonpath: right.to_string(),
+    };
+    let page = serde_json::json!({"meta": {"count": 2, "ids": [1, 2]}, "items": [{"id": 1}, {"id": 2}]});
+
+    assert!(test_apply_check(&checker, vec![page.clone()], &check("$.meta.count", "$.items.length()")));
+    assert!(test_apply_check(&checker, vec![page.clone()], &check("$.meta.ids", "$.items[*].id")));
+    assert!(!test_apply_check(&checker, vec![page.clone()], &check("$.meta.count", "$.meta.ids[0]")));
+    // A path selecting nothing equals nothing, not even another missing node
+    assert!(!test_apply_check(&checker, vec![page], &check("$.missing", "$.absent")));
+}
+
+#[test]
+fn test_jsonb_exists_checks() {
+    let checker = create_test_checker();
+    
+    // Test JsonbExists
+    let exists_check = CheckRule::JsonbExists { key: "email".to_string() };
+    let with_key = vec![serde_json::json!({"email": "test@example.com"})];
+    assert!(test_apply_check(&checker, with_key, &exists_check));
+    
+    let without_key = vec![serde_json::json!({"name": "John"})];
+    assert!(!test_apply_check(&checker, without_key, &exists_check));
+    
+    // Test JsonbExistsAll
+    let exists_all_check = CheckRule::JsonbExistsAll { 
+        keys: vec!["email".to_string(), "name".to_string()] 
+    };
+    let with_all_keys = vec![serde_json::json!({"email": "test@example.com", "name": "John"})];
+    assert!(test_apply_check(&checker, with_all_keys, &exists_all_check));
+    
+    let with_some_keys = vec![serde_json::json!({"email": "test@example.com"})];
+    assert!(!test_apply_check(&checker, with_some_keys, &exists_all_check));
+}
+
+#[test]
+fn test_jsonb_contained_by_check() {
+    let checker = create_test_checker();
+    let check = CheckRule::JsonbContainedBy { 
+        value: serde_json::json!({"a": 1, "b": 2, "c": 3}) 
+    };
+    
+    let contained_values = vec![serde_json::json!({"a": 1})];
+    assert!(test_apply_check(&checker, contained_values, &check));
+    
+    let not_contained_values = vec![serde_json::json!({"a": 1, "b": 2, "d": 4})];
+    assert!(!test_apply_check(&checker, not_contained_values, &check));
+}
+
+#[test]
+fn test_jsonb_exists_any_check() {
+    let checker = create_test_checker();
+    let check = CheckRule::JsonbExistsAny { 
+        keys: vec!["email".to_string(), "phone".to_string()] 
+    };
+    
+    let with_email = vec![serde_json::json!({"email": "test@example.com", "name": "John"})];
+    assert!(test_apply_check(&checker, with_email, &check));
+    
+    let with_phone = vec![serde_json::json!({"phone": "123-456-7890", "name": "John"})];
+    assert!(test_apply_check(&checker, with_phone, &check));
+    
+    let without_keys = vec![serde_json::json!({"name": "John"})];
+    assert!(!test_apply_check(&checker, without_keys, &check));
+}
+
+#[test]
+fn test_jsonb_path_match_check() {
+    let checker = create_test_checker();
+    let check = CheckRule::JsonbPathMatch { path: "$.users[*].email".to_string() };
+    
+    let values = vec![serde_json::json!({"users": [{"email": "test@example.com"}]})];
+    // Currently always returns true - this is a simplified implementation
+    assert!(test_apply_check(&checker, values, &check));
+}
+
+#[test]
+fn test_edge_cases_and_comprehensive_coverage() {
+    let checker = create_test_checker();
+    
+    // Test empty values array for different checks
+    assert!(test_apply_check(&checker, vec![], &CheckRule::Empty));
+    assert!(!test_apply_check(&checker, vec![], &CheckRule::NonEmpty));
+    
+    // Test array length with edge cases
+    let array_check = CheckRule::ArrayLength { min: None, max: Some(3) };
+    let small_array = vec![serde_json::json!([1, 2])];
+    assert!(test_apply_check(&checker, small_array, &array_check));
+    
+    // Test regex with non-string values (should fail)
+    let regex_check = CheckRule::Regex { pattern: "test".to_string() };
+    let non_string = vec![serde_json::json!(42)];
+    assert!(!test_apply_check(&checker, non_string, &regex_check));
+    
+    // Test numeric comparisons with non-numeric values
+    let gt_check = CheckRule::GreaterThan { value: 10.into() };
+    let non_numeric = vec![serde_json::json!("not_a_number")];
+    assert!(!test_apply_check(&checker, non_numeric, &gt_check));
+}
+
+#[test]
+fn test_helper_functions() {
+    let checker = create_test_checker();
+
+    // Test array contains
+    let arr = serde_json::json!([1, 2, 3]);
+    let val = serde_json::json!(2);
+    assert!(checker.contains(&arr, &val));
+    
+    // Test string contains
+    let container = serde_json::json!("hello world");
+    let contained = serde_json::json!("world");
+    assert!(checker.contains(&container, &contained));
+    
+    // Test object contains
+    let obj_container = serde_json::json!({"a": 1, "b": 2, "c": 3});
+    let obj_contained = serde_json::json!({"a": 1, "b": 2});
+    assert!(checker.contains(&obj_container, &obj_contained));
+    
+    // Test is_empty_value
+    assert!(checker.is_empty_value(&serde_json::json!(null)));
+    assert!(checker.is_empty_value(&serde_json::json!("")));
+    assert!(checker.is_empty_value(&serde_json::json!([])));
+    assert!(checker.is_empty_value(&serde_json::json!({})));
+    assert!(!checker.is_empty_value(&serde_json::json!("test")));
+    assert!(!checker.is_empty_value(&serde_json::json!(42)));
+    assert!(!checker.is_empty_value(&serde_json::json!(true)));
+    assert!(!checker.is_empty_value(&serde_json::json!(false)));
+}
+
+// Helper to lay out config files in a fresh temporary directory
+fn write_temp_files(name: &str, files: &[(&str, &str)]) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("json-checker-rs-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    for (path, content) in files {
+        let path = dir.join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+    dir
+}
+
+#[test]
+fn test_config_include_merges_rules_in_order() {
+    let rule = |name: &str| format!(
+        r#"{{"name": "{}", "json_file": "d.json", "jsonpath": "$", "check": {{"type": "non_empty"}}}}"#,
+        name
+    );
+    let dir = write_temp_files("include", &[
+        ("rules.json", &format!(r#"{{"include": ["rules/*.json"], "rules": [{}]}}"#, rule("main"))),
+        ("rules/b.json", &format!(r#"{{"include": ["shared.json"], "rules": [{}]}}"#, rule("b"))),
+        ("rules/a.json", &format!(r#"{{"include": ["shared.json"], "rules": [{}]}}"#, rule("a"))),
+        ("rules/shared.json", &format!(r#"{{"rules": [{}]}}"#, rule("shared"))),
+    ]);
+
+    let config = Config::from_file(dir.join("rules.json").to_str().unwrap()).unwrap();
+    let names: Vec<&str> = config.rules.iter().map(|r| r.name.as_str()).collect();
+    assert_eq!(names, vec!["shared", "a", "b", "main"]);
+}
+
+#[test]
+fn test_config_include_errors() {
+    let dir = write_temp_files("include-cycle", &[
+        ("a.json", r#"{"include": ["b.json"], "rules": []}"#),
+        ("b.json", r#"{"include": ["a.json"], "rules": []}"#),
+    ]);
+    let err = Config::from_file(dir.join("a.json").to_str().unwrap()).unwrap_err();
+    assert!(err.to_string().contains("cycle"));
+
+    let rule = r#"{"name": "dup", "json_file": "d.json", "jsonpath": "$", "check": {"type": "empty"}}"#;
+    let dir = write_temp_files("include-dup", &[
+        ("a.json", &format!(r#"{{"include": ["b.json"], "rules": [{}]}}"#, rule)),
+        ("b.json", &format!(r#"{{"rules": [{}]}}"#, rule)),
+    ]);
+    let err = Config::from_file(dir.join("a.json").to_str().unwrap()).unwrap_err();
+    assert!(err.to_string().contains("Duplicate rule name 'dup'"));
+}
+
+#[test]
+fn test_config_reports_invalid_jsonpaths_on_load() {
+    let dir = write_temp_files("bad-jsonpaths", &[
+        ("rules.json", r#"{"rules": [
+            {"name": "no root", "json_file": "d.json", "jsonpath": "users[*]", "check": {"type": "non_empty"}},
+            {"name": "ok", "json_file": "d.json", "jsonpath": "$.users[*]", "check": {"type": "non_empty"}},
+            {"name": "sort key", "json_file": "d.json", "jsonpath": "$.users", "check": {"type": "sorted", "by": ".tags[0"}}
+        ]}"#),
+    ]);
+    let err = Config::from_file(dir.join("rules.json").to_str().unwrap()).unwrap_err().to_string();
+    assert!(err.starts_with("Rule 'no root': invalid JSONPath 'users[*]': JSONPath must start with '$' at column 1"));
+    assert!(err.contains("help: try '$.users[*]'"));
+    assert!(err.contains("Rule 'sort key': invalid JSONPath '$.tags[0': unclosed '[' at column 7"));
+    assert!(!err.contains("Rule 'ok'"));
+}
+
+#[test]
+fn test_jsonpath_engine_selection() {
+    use crate::jsonpath::JsonPathEngine;
+
+    let dir = write_temp_files("jsonpath-engine", &[
+        ("rules.json", r#"{
+            "jsonpath_engine": "rfc9535",
+            "rules": [
+                {"name": "standard", "json_inline": {}, "jsonpath": "$", "check": {"type": "non_empty"}},
+                {"name": "legacy", "json_inline": {}, "jsonpath": "$", "check": {"type": "non_empty"}, "jsonpath_engine": "jsonpath_lib"}
+            ]
+        }"#),
+    ]);
+    let config = Config::from_file(dir.join("rules.json").to_str().unwrap());
+    if cfg!(feature = "rfc9535") {
+        let config = config.unwrap();
+        assert_eq!(config.engine_for(&config.rules[0]), JsonPathEngine::Rfc9535);
+        assert_eq!(config.engine_for(&config.rules[1]), JsonPathEngine::JsonpathLib);
+    } else {
+        let err = config.unwrap_err().to_string();
+        assert!(err.contains("Rule 'standard': invalid JSONPath '$': the rfc9535 engine is not available"));
+        assert!(!err.contains("Rule 'legacy'"));
+    }
+}
+
+#[test]
+fn test_config_variable_substitution() {
+    let dir = write_temp_files("vars", &[
+        ("rules.json", r#"{
+            "vars": {"DATA_DIR": "data", "ROLE": "admin"},
+            "rules": [{
+                "name": "Role check",
+                "json_file": "${DATA_DIR}/users.json",
+                "jsonpath": "$.users[?(@.role=='${ROLE}')].role",
+                "check": {"type": "equals", "value": "${ROLE}"}
+            }]
+        }"#),
+    ]);
+    let path = dir.join("rules.json");
+
+    let overrides = std::collections::HashMap::from([("DATA_DIR".to_string(), "fixtures".to_string())]);
+    let config = Config::from_file_with_vars(path.to_str().unwrap(), &overrides).unwrap();
+    let rule = &config.rules[0];
+    assert_eq!(rule.json_file.as_deref(), Some("fixtures/users.json"));
+    assert_eq!(rule.jsonpath, "$.users[?(@.role=='admin')].role");
+    assert!(matches!(&rule.check, CheckRule::Equals { value, .. } if value == "admin"));
+
+    let dir = write_temp_files("vars-undefined", &[
+        ("rules.json", r#"{"rules": [{"name": "r", "json_file": "${JSON_CHECKER_MISSING}/a.json", "jsonpath": "$", "check": {"type": "empty"}}]}"#),
+    ]);
+    let err = Config::from_file(dir.join("rules.json").to_str().unwrap()).unwrap_err();
+    assert!(format!("{:#}", err).contains("Undefined variable 'JSON_CHECKER_MISSING'"));
+}
+
+#[test]
+fn test_cli_exit_code_policy() {
+    use crate::cli::{exit_code, Cli, CliOptions};
+    use crate::{CheckResult, Rule, Severity};
+
+    let dir = write_temp_files("exit-code", &[("data.json", "{}")]);
+    let rule = |name: &str, file: &str, severity: Severity| Rule {
+        name: name.to_string(),
+        json_file: Some(dir.join(file).to_string_lossy().into_owned()),
+        jsonpath: "$".to_string(),
+        severity,
+        ..Default::default()
+    };
+    let failed = |name: &str, severity: Severity| {
+        let mut result = CheckResult::new(name.to_string(), false, String::new());
+        result.severity = severity;
+        result
+    };
+    let config = Config {
+        rules: vec![
+            rule("warn", "data.json", Severity::Warning),
+            rule("err", "data.json", Severity::Error),
+            rule("missing", "missing.json", Severity::Error),
+        ],
+        ..Default::default()
+    };
+
+    let args = |list: &[&str]| CliOptions::parse(&list.iter().map(|s| s.to_string()).collect::<Vec<_>>()).unwrap();
+
+    let warning_only = vec![failed("warn", Severity::Warning)];
+    assert_eq!(Cli::exit_code(&args(&[]), &config, &warning_only), exit_code::SUCCESS);
+    assert_eq!(Cli::exit_code(&args(&["--fail-on", "warning"]), &config, &warning_only), exit_code::RULES_FAILED);
+
+    let errors = vec![failed("err", Severity::Error), failed("warn", Severity::Warning)];
+    assert_eq!(Cli::exit_code(&args(&[]), &config, &errors), exit_code::RULES_FAILED);
+    assert_eq!(Cli::exit_code(&args(&["--max-failures", "1"]), &config, &errors), exit_code::SUCCESS);
+
+    let missing = vec![failed("missing", Severity::Error)];
+    assert_eq!(Cli::exit_code(&args(&["--max-failures", "5"]), &config, &missing), exit_code::FILE_MISSING);
+
+    assert!(CliOptions::parse(&["--fail-on".to_string(), "fatal".to_string()]).is_err());
+}
+
+#[test]
+fn test_inline_json_document() {
+    use crate::Rule;
+
+    let checker = create_test_checker();
+    let rule = Rule {
+        name: "Inline roles".to_string(),
+        json_inline: Some(serde_json::json!({"users": [{"role": "admin"}]})),
+        jsonpath: "$.users[*].role".to_string(),
+        check: CheckRule::Equals { value: serde_json::json!("admin"), tolerance: None },
+        ..Default::default()
+    };
+    assert!(checker.run_rule(&rule).passed);
+
+    let neither = Rule { json_inline: None, ..rule.clone() };
+    let result = checker.run_rule(&neither);
+    assert!(!result.passed);
+    assert!(result.message.contains("needs json_file, json_inline or source"));
+
+    let both = Rule { json_file: Some("data.json".to_string()), ..rule };
+    assert!(checker.run_rule(&both).message.contains("sets both json_file and json_inline"));
+}
+
+#[test]
+fn test_custom_document_source() {
+    use crate::source::{DocumentSource, InlineSource};
+    use crate::Rule;
+
+    struct FixtureSource;
+    impl DocumentSource for FixtureSource {
+        fn resolve(&self, uri: &str) -> anyhow::Result<Value> {
+            Ok(serde_json::json!({"uri": uri}))
+        }
+    }
+
+    let rule = |name: &str, uri: &str, expected: &str| Rule {
+        name: name.to_string(),
+        json_file: Some(uri.to_string()),
+        jsonpath: "$.uri".to_string(),
+        check: CheckRule::Equals { value: serde_json::json!(expected), tolerance: None },
+        ..Default::default()
+    };
+    let checker = JsonChecker::from_config(Config {
+        rules: vec![
+            rule("fixture", "db://users/1", "db://users/1"),
+            rule("inline", "inline://doc", "inline"),
+            rule("unknown", "mq://orders", "mq://orders"),
+        ],
+        ..Default::default()
+    })
+    .with_source("db", FixtureSource)
+    .with_source("inline", InlineSource::new().with_document("doc", serde_json::json!({"uri": "inline"})));
+
+    let results = checker.run().unwrap();
+    assert!(results[0].passed);
+    assert!(results[1].passed);
+    assert!(results[2].message.contains("No document source registered for 'mq://'"));
+}
+
+#[test]
+fn test_ndjson_reports_failing_lines() {
+    use crate::Rule;
+
+    let dir = write_temp_files("ndjson", &[(
+        "users.jsonl",
+        "{\"email\": \"a@example.com\"}\n\n{\"email\": \"\"}\n{\"email\": \"c@example.com\"}\n",
+    )]);
+    let checker = create_test_checker();
+    let rule = Rule {
+        name: "Emails present".to_string(),
+        json_file: Some(dir.join("users.jsonl").to_string_lossy().into_owned()),
+        jsonpath: "$[*].email".to_string(),
+        check: CheckRule::NonEmpty,
+        ..Default::default()
+    };
+
+    let result = checker.run_rule(&rule);
+    assert!(!result.passed);
+    assert_eq!(result.invalid_positions, vec!["line 3: $[1].email".to_string()]);
+}
+
+#[test]
+fn test_streaming_matches_in_memory_check() {
+    use crate::Rule;
+
+    let users: Vec<Value> = (0..500)
+        .map(|i| serde_json::json!({"id": i, "email": if i % 100 == 7 { "" } else { "user@example.com" }}))
+        .collect();
+    let document = serde_json::json!({"meta": {"count": 500}, "users": users}).to_string();
+    let dir = write_temp_files("stream", &[("users.json", &document)]);
+    let checker = create_test_checker();
+
+    let rule = |jsonpath: &str, check: CheckRule, stream: bool| Rule {
+        name: "Streamed".to_string(),
+        json_file: Some(dir.join("users.json").to_string_lossy().into_owned()),
+        jsonpath: jsonpath.to_string(),
+        check,
+        stream,
+        ..Default::default()
+    };
+
+    let non_empty = checker.run_rule(&rule("$.users[*].email", CheckRule::NonEmpty, true));
+    assert!(!non_empty.passed);
+    assert_eq!(
+        non_empty.invalid_positions,
+        vec!["$.users[7].email", "$.users[107].email", "$.users[207].email", "$.users[307].email", "$.users[407].email"]
+    );
+    assert_eq!(non_empty.values_found, vec![serde_json::json!(""); 5]);
+
+    for check in [
+        CheckRule::Equals { value: serde_json::json!(""), tolerance: None },
+        CheckRule::GreaterThan { value: 498.into() },
+        CheckRule::LessThan { value: 0.into() },
+    ] {
+        let streamed = checker.run_rule(&rule("$.users[*].id", check.clone(), true)).passed;
+        let in_memory = checker.run_rule(&rule("$.users[*].id", check, false)).passed;
+        assert_eq!(streamed, in_memory);
+    }
+
+    let unsupported = checker.run_rule(&rule("$..email", CheckRule::NonEmpty, true));
+    assert!(unsupported.message.contains("cannot be streamed"));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_command_check_reports_failing_values() {
+    use crate::Rule;
+
+    let checker = create_test_checker();
+    let rule = Rule {
+        name: "Emails checked externally".to_string(),
+        json_inline: Some(serde_json::json!({"users": [{"email": "a@example.com"}, {"email": "nope"}]})),
+        jsonpath: "$.users[*].email".to_string(),
+        check: CheckRule::Command {
+            program: "sh".to_string(),
+            args: vec!["-c".to_string(), "grep -q @".to_string()],
+            timeout_ms: None,
+            clear_env: true,
+        },
+        ..Default::default()
+    };
+
+    let result = checker.run_rule(&rule);
+    assert!(!result.passed);
+    assert_eq!(result.invalid_positions, vec!["$.users[1].email".to_string()]);
+    assert_eq!(result.values_found, vec![serde_json::json!("nope")]);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_command_check_runs_once_per_value() {
+    use crate::config::Status;
+    use crate::Rule;
+
+    let dir = write_temp_files("command-runs", &[("runs.log", "")]);
+    let log = dir.join("runs.log").to_string_lossy().into_owned();
+    let checker = create_test_checker();
+    let rule = |script: &str, timeout_ms: Option<u64>| Rule {
+        name: "Counted".to_string(),
+        json_inline: Some(serde_json::json!({"emails": ["nope", "a@example.com", "also nope"]})),
+        jsonpath: "$.emails[*]".to_string(),
+        check: CheckRule::Command {
+            program: "sh".to_string(),
+            args: vec!["-c".to_string(), script.to_string(), log.clone()],
+            timeout_ms,
+            clear_env: true,
+        },
+        ..Default::default()
+    };
+
+    // The verdict and the failing values come from the same runs
+    let result = checker.run_rule(&rule("echo run >> \"$0\"; grep -q @", None));
+    assert!(!result.passed);
+    assert_eq!(result.invalid_positions, vec!["$.emails[0]", "$.emails[2]"]);
+    assert_eq!(std::fs::read_to_string(&log).unwrap().lines().count(), 3);
+
+    // A program that times out is an error, not a failing value
+    let result = checker.run_rule(&rule("sleep 5", Some(50)));
+    assert_eq!(result.status, Status::Error);
+    assert!(result.message.contains("timed out after 50ms"), "{}", result.message);
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[cfg(feature = "expr")]
+#[test]
+fn test_expr_check_per_node() {
+    use crate::Rule;
+
+    let checker = create_test_checker();
+    let rule = Rule {
+        name: "Order totals".to_string(),
+        json_inline: Some(serde_json::json!({"orders": [
+            {"price": 2, "qty": 3, "total": 6},
+            {"price": 5, "qty": 2, "total": 12}
+        ]})),
+        jsonpath: "$.orders[*]".to_string(),
+        check: CheckRule::Expr { expr: "value.price * value.qty == value.total".to_string() },
+        ..Default::default()
+    };
+
+    let result = checker.run_rule(&rule);
+    assert!(!result.passed);
+    assert_eq!(result.invalid_positions, vec!["$.orders[1]".to_string()]);
+}
+
+#[test]
+fn test_aggregate_check() {
+    use crate::Rule;
+    use crate::rules::{AggregateFunction, Comparison};
+
+    let checker = create_test_checker();
+    let rule = |function, operator, value| Rule {
+        name: "Order amounts".to_string(),
+        json_inline: Some(serde_json::json!({"orders": [{"amount": 4000}, {"amount": 7000}]})),
+        jsonpath: "$.orders[*].amount".to_string(),
+        check: CheckRule::Aggregate { function, operator, value },
+        ..Default::default()
+    };
+
+    let result = checker.run_rule(&rule(AggregateFunction::Sum, Comparison::Lt, 10000.0));
+    assert!(!result.passed);
+    assert!(result.message.contains("sum = 11000"));
+
+    assert!(checker.run_rule(&rule(AggregateFunction::Count, Comparison::Eq, 2.0)).passed);
+    assert!(checker.run_rule(&rule(AggregateFunction::Max, Comparison::Gte, 7000.0)).passed);
+}
+
+#[test]
+fn test_match_count_check() {
+    use crate::Rule;
+
+    let checker = create_test_checker();
+    let rule = |min, max| Rule {
+        name: "Admins".to_string(),
+        json_inline: Some(serde_json::json!({"users": [
+            {"name": "a", "role": "admin"},
+            {"name": "b", "role": "user"},
+            {"name": "c", "role": "admin"}
+        ]})),
+        jsonpath: "$.users[?(@.role == 'admin')]".to_string(),
+        check: CheckRule::MatchCount { min, max },
+        ..Default::default()
+    };
+
+    assert!(checker.run_rule(&rule(Some(2), Some(2))).passed);
+    assert!(checker.run_rule(&rule(Some(1), None)).passed);
+
+    let result = checker.run_rule(&rule(Some(3), Some(3)));
+    assert!(!result.passed);
+    assert!(result.message.contains("matched 2 node(s)"));
+}
+
+#[test]
+fn test_equals_failure_reports_structural_diff() {
+    use crate::Rule;
+    use crate::diff::Difference;
+
+    let checker = create_test_checker();
+    let rule = Rule {
+        name: "Server config".to_string(),
+        json_inline: Some(serde_json::json!({"server": {"host": "0.0.0.0", "port": 8080, "debug": true}})),
+        jsonpath: "$.server".to_string(),
+        check: CheckRule::Equals { value: serde_json::json!({"host": "0.0.0.0", "port": 443}), tolerance: None },
+        ..Default::default()
+    };
+
+    let result = checker.run_rule(&rule);
+    assert!(!result.passed);
+    assert_eq!(result.invalid_positions, vec!["$.server".to_string()]);
+    assert_eq!(
+        result.differences,
+        vec![
+            Difference::Changed {
+                path: "$.server.port".to_string(),
+                expected: serde_json::json!(443),
+                actual: serde_json::json!(8080),
+            },
+            Difference::Extra { path: "$.server.debug".to_string(), actual: serde_json::json!(true) },
+        ]
+    );
+}
+
+#[test]
+fn test_invalid_paths_are_canonical_for_complex_queries() {
+    use crate::Rule;
+
+    let checker = create_test_checker();
+    let json = serde_json::json!({
+        "teams": [
+            {"members": [{"email": "a@example.com"}, {"email": ""}]},
+            {"members": [{"email": ""}, {"email": "b@example.com"}]}
+        ],
+        "owner": {"email": ""}
+    });
+    let rule = |jsonpath: &str| Rule {
+        name: "Emails".to_string(),
+        jsonpath: jsonpath.to_string(),
+        check: CheckRule::NonEmpty,
+        ..Default::default()
+    };
+
+    let paths = |jsonpath: &str| -> Vec<String> {
+        checker
+            .invalid_paths(&json, &rule(jsonpath))
+            .unwrap()
+            .into_iter()
+            .map(|(path, _)| path)
+            .collect()
+    };
+
+    assert_eq!(
+        paths("$..email"),
+        vec!["$.teams[0].members[1].email", "$.teams[1].members[0].email", "$.owner.email"]
+    );
+    assert_eq!(
+        paths("$.teams[*].members[*].email"),
+        vec!["$.teams[0].members[1].email", "$.teams[1].members[0].email"]
+    );
+    assert_eq!(paths("$.teams[1:].members[0].email"), vec!["$.teams[1].members[0].email"]);
+}
+
+#[test]
+fn test_render_results_quiet_and_colors() {
+    use crate::cli::{Cli, CliOptions, Style, Theme};
+    use crate::{CheckResult, Severity};
+
+    let mut warning = CheckResult::new("Optional".to_string(), false, "✗ Rule 'Optional' failed".to_string());
+    warning.severity = Severity::Warning;
+    let results = vec![
+        CheckResult::new("Required".to_string(), true, "✓ Rule 'Required' passed".to_string()),
+        warning,
+    ];
+
+    let quiet = Cli::render_results(&results, &Style { theme: None, quiet: true });
+    assert!(!quiet.contains("Required"));
+    assert!(quiet.starts_with("✗ Rule 'Optional' failed\n"));
+    assert!(quiet.contains("Warnings: 1 ⚠️"));
+    assert!(!quiet.contains('\x1b'));
+
+    let colored = Cli::render_results(&results, &Style { theme: Some(Theme::default()), quiet: false });
+    assert!(colored.contains("\x1b[32m✓ Rule 'Required' passed\x1b[0m"));
+    assert!(colored.contains("\x1b[33m✗ Rule 'Optional' failed\x1b[0m"));
+
+    let options = CliOptions::parse(&["--no-color".to_string(), "-q".to_string()]).unwrap();
+    let style = Style::from_options(&options);
+    assert!(style.theme.is_none() && style.quiet);
+}
+
+#[test]
+fn test_rule_timings() {
+    use crate::cli::{Cli, CliOptions, DEFAULT_TIMINGS};
+    use crate::CheckResult;
+    use std::time::Duration;
+
+    let rule = |name: &str| crate::Rule {
+        name: name.to_string(),
+        json_inline: Some(serde_json::json!({"a": 1})),
+        jsonpath: "$.a".to_string(),
+        ..Default::default()
+    };
+    let checker = JsonChecker::from_config(Config {
+        rules: vec![rule("first"), rule("second")],
+        ..Default::default()
+    });
+    let mut progress = Vec::new();
+    let results = checker
+        .run_with_progress(|done, total, result| progress.push((done, total, result.rule_name.clone())))
+        .unwrap();
+    assert_eq!(progress, vec![(1, 2, "first".to_string()), (2, 2, "second".to_string())]);
+    assert_eq!(results.len(), 2);
+
+    let timed = |name: &str, ms: u64| {
+        let mut result = CheckResult::new(name.to_string(), true, String::new());
+        result.duration = Duration::from_millis(ms);
+        result
+    };
+    let rendered = Cli::render_timings(&[timed("fast", 1), timed("slow", 30), timed("medium", 5)], 2);
+    assert!(rendered.contains("total 36.0ms"));
+    let slow = rendered.find("slow").unwrap();
+    let medium = rendered.find("medium").unwrap();
+    assert!(slow < medium);
+    assert!(!rendered.contains("fast"));
+
+    let parse = |list: &[&str]| CliOptions::parse(&list.iter().map(|s| s.to_string()).collect::<Vec<_>>()).unwrap();
+    assert_eq!(parse(&["--timings"]).timings, Some(DEFAULT_TIMINGS));
+    let options = parse(&["--timings", "3", "rules.json"]);
+    assert_eq!(options.timings, Some(3));
+    assert_eq!(options.config_file.as_deref(), Some("rules.json"));
+}
+
+#[test]
+fn test_rule_dependencies_and_conditions() {
+    use crate::schedule::RunCondition;
+    use crate::validate::validate_config;
+    use crate::{Rule, Status};
+
+    let document = serde_json::json!({"schema_version": 2, "users": []});
+    let rule = |name: &str, check: CheckRule| Rule {
+        name: name.to_string(),
+        json_inline: Some(document.clone()),
+        jsonpath: "$.schema_version".to_string(),
+        check,
+        ..Default::default()
+    };
+    let config = Config {
+        rules: vec![
+            Rule { depends_on: vec!["schema".to_string()], ..rule("expensive", CheckRule::NonEmpty) },
+            rule("schema", CheckRule::Equals { value: serde_json::json!(3), tolerance: None }),
+            Rule { run_if: Some(RunCondition::Failed("schema".to_string())), ..rule("legacy", CheckRule::NonEmpty) },
+            Rule {
+                run_if: Some(RunCondition::PathExists("$.users[0]".to_string())),
+                ..rule("user checks", CheckRule::Empty)
+            },
+        ],
+        ..Default::default()
+    };
+    assert!(validate_config(&config).is_empty());
+
+    let results = JsonChecker::from_config(config).run().unwrap();
+    let names: Vec<&str> = results.iter().map(|r| r.rule_name.as_str()).collect();
+    assert_eq!(names, vec!["expensive", "schema", "legacy", "user checks"]);
+
+    assert_eq!(results[0].status, Status::Skipped);
+    assert!(results[0].message.contains("dependency 'schema' did not pass"));
+    assert!(!results[1].passed);
+    assert_eq!(results[2].status, Status::Pass);
+    assert_eq!(results[3].status, Status::Skipped);
+
+    let cyclic = Config {
+        rules: vec![
+            Rule { depends_on: vec!["b".to_string()], ..rule("a", CheckRule::NonEmpty) },
+            Rule { depends_on: vec!["a".to_string()], ..rule("b", CheckRule::NonEmpty) },
+        ],
+        ..Default::default()
+    };
+    let issues = validate_config(&cyclic);
+    assert_eq!(issues.len(), 1);
+    assert_eq!(issues[0].to_string(), "Rule 'a': dependency cycle a -> b -> a");
+    assert!(JsonChecker::from_config(cyclic).run().is_err());
+}
+
+#[test]
+fn test_check_templates_expand_on_load() {
+    let dir = write_temp_files("templates", &[
+        ("common.json", r#"{
+            "check_templates": {
+                "valid_email": {"type": "regex", "pattern": "^[^@]+@${domain}$"},
+                "at_least": {"type": "greater_than", "value": "${min}"}
+            }
+        }"#),
+        ("rules.json", r#"{
+            "include": ["common.json"],
+            "vars": {"domain": "example.com"},
+            "rules": [
+                {"name": "Emails", "json_file": "d.json", "jsonpath": "$.users[*].email",
+                 "check": {"type": "template", "name": "valid_email"}},
+                {"name": "Ages", "json_file": "d.json", "jsonpath": "$.users[*].age",
+                 "check": {"type": "template", "name": "at_least", "params": {"min": 17}}}
+            ]
+        }"#),
+    ]);
+
+    let config = Config::from_file(dir.join("rules.json").to_str().unwrap()).unwrap();
+    assert!(matches!(&config.rules[0].check, CheckRule::Regex { pattern } if pattern == "^[^@]+@example.com$"));
+    assert!(matches!(&config.rules[1].check, CheckRule::GreaterThan { value } if value.as_f64() == Some(17.0)));
+
+    std::fs::write(
+        dir.join("rules.json"),
+        r#"{"rules": [{"name": "Bad", "json_file": "d.json", "jsonpath": "$",
+            "check": {"type": "template", "name": "nope"}}]}"#,
+    )
+    .unwrap();
+    let err = Config::from_file(dir.join("rules.json").to_str().unwrap()).unwrap_err();
+    assert!(format!("{:#}", err).contains("Unknown check template 'nope'"));
+}
+
+#[test]
+fn test_json_file_glob_checks_every_file() {
+    use crate::Rule;
+
+    let dir = write_temp_files("glob", &[
+        ("data/a.json", r#"{"status": "ok"}"#),
+        ("data/b.json", r#"{"status": "down"}"#),
+        ("data/c.json", r#"{"status": "ok"}"#),
+        ("data/notes.txt", "not json"),
+    ]);
+    let checker = create_test_checker();
+    let rule = |pattern: &str| Rule {
+        name: "Status".to_string(),
+        json_file: Some(dir.join(pattern).to_string_lossy().into_owned()),
+        jsonpath: "$.status".to_string(),
+        check: CheckRule::Equals { value: serde_json::json!("ok"), tolerance: None },
+        ..Default::default()
+    };
+
+    let result = checker.run_rule(&rule("data/*.json"));
+    assert!(!result.passed);
+    assert_eq!(result.per_document.len(), 3);
+    assert!(result.message.contains("failed for 1 of 3 files"));
+    let b = dir.join("data/b.json").to_string_lossy().into_owned();
+    assert_eq!(result.invalid_positions, vec![format!("{}: $.status", b)]);
+    assert_eq!(result.per_document[1].rule_name, format!("Status [{}]", b));
+
+    assert!(checker.run_rule(&rule("data/[ac].json")).passed);
+    assert!(checker.run_rule(&rule("data/*.yaml")).message.contains("No files match"));
+}
+
+#[test]
+fn test_scan_checks_discovered_files_by_pattern() {
+    use crate::scan::ScanConfig;
+    use crate::validate::validate_config;
+    use crate::Rule;
+
+    let dir = write_temp_files("scan", &[
+        ("repo/api/app.service.json", r#"{"port": 8080}"#),
+        ("repo/web/site.service.json", r#"{"port": 0}"#),
+        ("repo/web/site.settings.json", r#"{"theme": "dark"}"#),
+        ("repo/vendor/lib.service.json", r#"{"port": 0}"#),
+    ]);
+    let config = Config {
+        scan: Some(ScanConfig {
+            root: dir.join("repo").to_string_lossy().into_owned(),
+            include: vec!["**/*.json".to_string()],
+            exclude: vec!["vendor".to_string()],
+        }),
+        rules: vec![
+            Rule {
+                name: "Services listen on a port".to_string(),
+                applies_to: Some("*.service.json".to_string()),
+                jsonpath: "$.port".to_string(),
+                check: CheckRule::GreaterThan { value: 0.into() },
+                ..Default::default()
+            },
+            Rule {
+                name: "Settings have a theme".to_string(),
+                applies_to: Some("web/*.settings.json".to_string()),
+                jsonpath: "$.theme".to_string(),
+                check: CheckRule::NonEmpty,
+                ..Default::default()
+            },
+        ],
+        ..Default::default()
+    };
+    assert!(validate_config(&config).is_empty());
+
+    let results = JsonChecker::from_config(config).run().unwrap();
+    assert!(!results[0].passed);
+    assert!(results[0].message.contains("failed for 1 of 2 files"));
+    assert_eq!(results[0].per_document.len(), 2);
+    assert!(results[0].invalid_positions[0].ends_with("site.service.json: $.port"));
+    assert!(results[1].passed);
+    assert_eq!(results[1].per_document.len(), 1);
+}
+
+#[test]
+fn test_sarif_report_locates_invalid_nodes() {
+    use crate::cli::{CliOptions, ReportFormat};
+    use crate::sarif::to_sarif;
+    use crate::{Rule, Severity};
+
+    let dir = write_temp_files("sarif", &[
+        ("data/a.json", r#"{"users": [{"age": 0}, {"age": -1}]}"#),
+        ("data/b.json", r#"{"users": [{"age": 0}]}"#),
+    ]);
+    let config = Config {
+        rules: vec![
+            Rule {
+                name: "Ages are positive".to_string(),
+                json_file: Some(dir.join("data/*.json").to_string_lossy().into_owned()),
+                jsonpath: "$.users[*].age".to_string(),
+                check: CheckRule::GreaterThan { value: 0.into() },
+                severity: Severity::Warning,
+                ..Default::default()
+            },
+            Rule {
+                name: "Has users".to_string(),
+                json_inline: Some(serde_json::json!({"users": []})),
+                jsonpath: "$.users".to_string(),
+                check: CheckRule::NonEmpty,
+                ..Default::default()
+            },
+        ],
+        ..Default::default()
+    };
+    let checker = JsonChecker::from_config(config);
+    let results = checker.run().unwrap();
+    let log = to_sarif(checker.config(), &results);
+
+    assert_eq!(log["version"], "2.1.0");
+    let run = &log["runs"][0];
+    assert_eq!(run["tool"]["driver"]["rules"][1]["id"], "Has users");
+    let sarif_results = run["results"].as_array().unwrap();
+    assert_eq!(sarif_results.len(), 4, "{:#}", log);
+
+    let first = &sarif_results[0];
+    assert_eq!(first["ruleId"], "Ages are positive");
+    assert_eq!(first["level"], "warning");
+    let location = &first["locations"][0];
+    assert!(location["physicalLocation"]["artifactLocation"]["uri"].as_str().unwrap().ends_with("a.json"));
+    assert_eq!(location["logicalLocations"][0]["fullyQualifiedName"], "$.users[0].age");
+    assert_eq!(location["physicalLocation"]["region"], serde_json::json!({"startLine": 1, "startColumn": 20}));
+    assert_eq!(sarif_results[1]["locations"][0]["logicalLocations"][0]["fullyQualifiedName"], "$.users[1].age");
+    assert!(sarif_results[2]["locations"][0]["physicalLocation"]["artifactLocation"]["uri"]
+        .as_str()
+        .unwrap()
+        .ends_with("b.json"));
+    assert_eq!(sarif_results[3]["ruleIndex"], 1);
+    assert_eq!(sarif_results[3]["level"], "error");
+
+    let options = CliOptions::parse(&["--report".to_string(), "sarif".to_string()]).unwrap();
+    assert_eq!(options.report, ReportFormat::Sarif);
+    assert!(CliOptions::parse(&["--report".to_string(), "xml".to_string()]).is_err());
+}
+
+#[test]
+fn test_invalid_positions_carry_source_locations() {
+    use crate::cli::{Cli, Style};
+    use crate::locate::SourceLocation;
+    use crate::Rule;
+
+    let dir = write_temp_files("locations", &[(
+        "users.json",
+        "{\n  \"users\": [\n    {\"email\": \"a@example.com\"},\n    {\"email\": \"not-an-email\"}\n  ]\n}\n",
+    )]);
+    let rule = Rule {
+        name: "Emails".to_string(),
+        json_file: Some(dir.join("users.json").to_string_lossy().into_owned()),
+        jsonpath: "$.users[*].email".to_string(),
+        check: CheckRule::Format { kind: crate::rules::StringFormat::Email },
+        ..Default::default()
+    };
+    let checker = create_test_checker();
+
+    let result = checker.run_rule(&rule);
+    assert_eq!(result.invalid_positions, vec!["$.users[1].email"]);
+    assert_eq!(result.location(0), Some(SourceLocation { line: 4, column: 15 }));
+
+    let rendered = Cli::render_results(&[result], &Style::default());
+    assert!(rendered.contains("• $.users[1].email (line 4, column 15)"), "{}", rendered);
+
+    let inline = Rule {
+        json_file: None,
+        json_inline: Some(serde_json::json!({"users": [{"email": "nope"}]})),
+        ..rule
+    };
+    assert!(checker.run_rule(&inline).locations.is_empty());
+}
+
+#[test]
+fn test_forbidden_keys_reports_each_violation() {
+    use crate::Rule;
+
+    let rule = Rule {
+        name: "No secrets in exported config".to_string(),
+        json_inline: Some(serde_json::json!({
+            "config": {
+                "db": {"host": "db.internal", "password": "hunter2"},
+                "plugins": [{"name": "audit"}, {"name": "s3", "credentials": {"secret": "abc"}}]
+            }
+        })),
+        jsonpath: "$.config".to_string(),
+        check: serde_json::from_str(r#"{"type": "forbidden_keys", "keys": ["password", "secret"]}"#).unwrap(),
+        ..Default::default()
+    };
+
+    let result = create_test_checker().run_rule(&rule);
+    assert!(!result.passed);
+    assert_eq!(
+        result.invalid_positions,
+        vec!["$.config.db.password", "$.config.plugins[1].credentials.secret"]
+    );
+    assert_eq!(result.values_found, vec![serde_json::json!("hunter2"), serde_json::json!("abc")]);
+}
+
+#[test]
+fn test_key_format_reports_misnamed_keys() {
+    use crate::Rule;
+
+    let rule = |check: &str| Rule {
+        name: "API style".to_string(),
+        json_inline: Some(serde_json::json!({
+            "user": {"user_id": 1, "displayName": "Ada", "addresses": [{"zip-code": "N1"}]}
+        })),
+        jsonpath: "$.user".to_string(),
+        check: serde_json::from_str(check).unwrap(),
+        ..Default::default()
+    };
+    let checker = create_test_checker();
+
+    let result = checker.run_rule(&rule(r#"{"type": "key_format", "convention": "snake_case"}"#));
+    assert!(!result.passed);
+    assert_eq!(result.invalid_positions, vec!["$.user.displayName", "$.user.addresses[0]['zip-code']"]);
+
+    let result = checker.run_rule(&rule(r#"{"type": "key_format", "convention": "camelCase"}"#));
+    assert_eq!(result.invalid_positions, vec!["$.user.user_id", "$.user.addresses[0]['zip-code']"]);
+
+    let custom = r#"{"type": "key_format", "convention": "regex", "pattern": "^[a-zA-Z_-]+$"}"#;
+    assert!(checker.run_rule(&rule(custom)).passed);
+    let missing = checker.run_rule(&rule(r#"{"type": "key_format", "convention": "regex"}"#));
+    assert!(missing.message.contains("needs a pattern"));
+}
+
+#[test]
+fn test_no_nulls_anywhere_reports_each_null() {
+    use crate::Rule;
+
+    let rule = Rule {
+        name: "No nulls".to_string(),
+        json_inline: Some(serde_json::json!({
+            "user": {
+                "name": "Ada",
+                "email": null,
+                "addresses": [{"city": "London", "zip": null}],
+                "metadata": {"deleted_at": null, "tags": [null]}
+            }
+        })),
+        jsonpath: "$.user".to_string(),
+        check: serde_json::from_str(r#"{"type": "no_nulls_anywhere", "allowed_paths": ["$.metadata"]}"#).unwrap(),
+        ..Default::default()
+    };
+
+    let result = create_test_checker().run_rule(&rule);
+    assert!(!result.passed);
+    assert_eq!(result.invalid_positions, vec!["$.user.email", "$.user.addresses[0].zip"]);
+
+    let check = serde_json::from_str(r#"{"type": "no_nulls_anywhere", "allowed_paths": ["$.email", "$.addresses[*].zip", "$.metadata"]}"#).unwrap();
+    assert!(create_test_checker().run_rule(&Rule { check, ..rule }).passed);
+}
+
+#[test]
+fn test_depth_and_size_limits() {
+    use crate::diff::Difference;
+    use crate::Rule;
+
+    let rule = |check: &str| Rule {
+        name: "Payload limits".to_string(),
+        json_inline: Some(serde_json::json!({
+            "events": [
+                {"payload": {"id": 1}},
+                {"payload": {"nested": {"deeper": {"deepest": [1]}}}}
+            ]
+        })),
+        jsonpath: "$.events[*].payload".to_string(),
+        check: serde_json::from_str(check).unwrap(),
+        ..Default::default()
+    };
+    let checker = create_test_checker();
+
+    let result = checker.run_rule(&rule(r#"{"type": "max_depth", "depth": 3}"#));
+    assert!(!result.passed);
+    assert_eq!(result.invalid_positions, vec!["$.events[1].payload"]);
+    assert_eq!(
+        result.differences,
+        vec![Difference::Changed { path: "$.events[1].payload".to_string(), expected: 3.into(), actual: 4.into() }]
+    );
+    assert!(checker.run_rule(&rule(r#"{"type": "max_depth", "depth": 4}"#)).passed);
+
+    // `{"id":1}` is 8 bytes of compact JSON
+    let result = checker.run_rule(&rule(r#"{"type": "max_size_bytes", "bytes": 8}"#));
+    assert_eq!(result.invalid_positions, vec!["$.events[1].payload"]);
+}
+
+#[test]
+fn test_json_pointer_selector() {
+    use crate::Rule;
+
+    let dir = write_temp_files("json-pointer", &[
+        ("pod.json", "{\n  \"spec\": {\"containers\": [{\"image\": \"nginx:latest\", \"env\": {\"API_TOKEN\": \"x\"}}]}\n}"),
+    ]);
+    let rule = |pointer: &str, check: &str| Rule {
+        name: "Pinned image".to_string(),
+        json_file: Some(dir.join("pod.json").to_string_lossy().into_owned()),
+        json_pointer: Some(pointer.to_string()),
+        check: serde_json::from_str(check).unwrap(),
+        ..Default::default()
+    };
+    let checker = create_test_checker();
+
+    let result = checker.run_rule(&rule("/spec/containers/0/image", r#"{"type": "regex", "pattern": "@sha256:"}"#));
+    assert!(!result.passed);
+    assert!(result.message.starts_with("✗ Rule 'Pinned image' failed at JSON Pointer '/spec/containers/0/image'"));
+    assert_eq!(result.invalid_positions, vec!["/spec/containers/0/image"]);
+    assert_eq!(result.location(0).map(|l| l.line), Some(2));
+
+    let result = checker.run_rule(&rule("/spec/containers/0", r#"{"type": "forbidden_keys", "keys": ["API_TOKEN"]}"#));
+    assert_eq!(result.invalid_positions, vec!["/spec/containers/0/env/API_TOKEN"]);
+
+    // A pointer to a missing node selects nothing
+    assert!(!checker.run_rule(&rule("/spec/volumes", r#"{"type": "non_empty"}"#)).passed);
+}
+
+#[test]
+fn test_jq_selector() {
+    use crate::Rule;
+
+    let rule: Rule = serde_json::from_value(serde_json::json!({
+        "name": "Every customer has at most one open order",
+        "json_inline": {"orders": [
+            {"customer": "a", "open": true}, {"customer": "b", "open": true},
+            {"customer": "a", "open": false}, {"customer": "b", "open": true}
+        ]},
+        "selector": {"engine": "jq", "expr": "[.orders[] | select(.open)] | group_by(.customer)[] | {customer: .[0].customer, open: length}"},
+        "check": {"type": "expr", "expr": "value.open <= 1"}
+    }))
+    .unwrap();
+
+    let result = create_test_checker().run_rule(&rule);
+    assert!(!result.passed);
+    if cfg!(all(feature = "jq", feature = "expr")) {
+        assert!(result.message.contains("failed at jq filter '[.orders[]"));
+        assert_eq!(result.invalid_positions, vec!["$[1]"]);
+        assert_eq!(result.values_found, vec![serde_json::json!({"customer": "b", "open": 2})]);
+    } else if cfg!(feature = "jq") {
+        assert!(result.message.contains("needs json-checker-rs built with the `expr` feature"), "{}", result.message);
+    } else {
+        assert!(result.message.contains("needs json-checker-rs built with the `jq` feature"), "{}", result.message);
+    }
+}
+
+#[test]
+fn test_transforms_before_check() {
+    use crate::Rule;
+
+    let json = serde_json::json!({"events": [
+        {"status": " Active ", "payload": "{\"user\": \"a\"}"},
+        {"status": "ACTIVE", "payload": "{\"user\": \"b\", \"password\": \"x\"}"},
+        {"status": "archived", "payload": "not json"}
+    ]});
+    let rule = |jsonpath: &str, transform: &str, check: &str| Rule {
+        name: "Events".to_string(),
+        json_inline: Some(json.clone()),
+        jsonpath: jsonpath.to_string(),
+        transform: serde_json::from_str(transform).unwrap(),
+        check: serde_json::from_str(check).unwrap(),
+        ..Default::default()
+    };
+    let checker = create_test_checker();
+
+    let result = checker.run_rule(&rule(
+        "$.events[*].status",
+        r#"["trim", "lowercase"]"#,
+        r#"{"type": "contained_by", "value": ["active", "inactive"]}"#,
+    ));
+    assert_eq!(result.invalid_positions, vec!["$.events[2].status"]);
+
+    // Embedded JSON is checked as JSON; paths continue into the parsed value
+    let result = checker.run_rule(&rule(
+        "$.events[*].payload",
+        r#"["parse_json"]"#,
+        r#"{"type": "forbidden_keys", "keys": ["password"]}"#,
+    ));
+    assert_eq!(result.invalid_positions, vec!["$.events[1].payload.password"]);
+
+    // A value a transform can't parse is checked as it is
+    let result = checker.run_rule(&rule("$.events[*].payload", r#"["parse_json"]"#, r#"{"type": "required_keys", "keys": [{"name": "user"}]}"#));
+    assert_eq!(result.values_found, vec![serde_json::json!("not json")]);
+}
+
+#[cfg(feature = "expr")]
+#[test]
+fn test_decode_embedded_json() {
+    use crate::Rule;
+
+    let rule = |payload: &str| Rule {
+        name: "Webhook amounts".to_string(),
+        json_inline: Some(serde_json::json!({"events": [{"payload": "{\"amount\": 5}"}, {"payload": payload}]})),
+        jsonpath: "$.events[*].payload.amount".to_string(),
+        decode_embedded_json: true,
+        check: serde_json::from_str(r#"{"type": "expr", "expr": "value > 0"}"#).unwrap(),
+        ..Default::default()
+    };
+    let checker = create_test_checker();
+
+    let result = checker.run_rule(&rule("{\"amount\": -1}"));
+    assert_eq!(result.invalid_positions, vec!["$.events[1].payload.amount"]);
+
+    let result = checker.run_rule(&rule("{\"amount\": "));
+    assert!(!result.passed);
+    assert!(result.message.contains("Invalid embedded JSON at $.events[1].payload"), "{}", result.message);
+}
+
+#[test]
+fn test_required_keys_with_types() {
+    use crate::diff::Difference;
+    use crate::Rule;
+
+    let check: CheckRule = serde_json::from_str(
+        r#"{"type": "required_keys", "keys": [{"name": "id", "type": "integer"}, {"name": "email", "type": "string"}, {"name": "tags"}]}"#,
+    )
+    .unwrap();
+    let rule = Rule {
+        name: "Users are complete".to_string(),
+        json_inline: Some(serde_json::json!({"users": [
+            {"id": 1, "email": "a@example.com", "tags": []},
+            {"id": "2", "email": "b@example.com"},
+            "not an object"
+        ]})),
+        jsonpath: "$.users[*]".to_string(),
+        check,
+        ..Default::default()
+    };
+
+    let result = create_test_checker().run_rule(&rule);
+    assert!(!result.passed);
+    assert_eq!(result.invalid_positions, vec!["$.users[1]", "$.users[2]"]);
+    assert_eq!(
+        result.differences,
+        vec![
+            Difference::Changed {
+                path: "$.users[1].id".to_string(),
+                expected: serde_json::json!("integer"),
+                actual: serde_json::json!("2"),
+            },
+            Difference::Missing {
+                path: "$.users[1].tags".to_string(),
+                expected: serde_json::json!("any value"),
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_sorted_reports_first_out_of_order_pair() {
+    use crate::Rule;
+
+    let rule = |check: &str| Rule {
+        name: "Events are chronological".to_string(),
+        json_inline: Some(serde_json::json!({"events": [
+            {"id": "a", "timestamp": 100},
+            {"id": "b", "timestamp": 300},
+            {"id": "c", "timestamp": 200},
+            {"id": "d", "timestamp": 50}
+        ]})),
+        jsonpath: "$.events".to_string(),
+        check: serde_json::from_str(check).unwrap(),
+        ..Default::default()
+    };
+    let checker = create_test_checker();
+
+    let result = checker.run_rule(&rule(r#"{"type": "sorted", "by": ".timestamp"}"#));
+    assert!(!result.passed);
+    assert_eq!(result.invalid_positions, vec!["$.events[1]", "$.events[2]"]);
+    assert_eq!(result.values_found[1]["id"], "c");
+
+    assert!(checker.run_rule(&rule(r#"{"type": "sorted", "order": "asc", "by": "$.id"}"#)).passed);
+    assert!(!checker.run_rule(&rule(r#"{"type": "sorted", "order": "desc", "by": "$.id"}"#)).passed);
+}
+
+#[test]
+fn test_ref_integrity_reports_dangling_references() {
+    use crate::validate::validate_config;
+    use crate::Rule;
+
+    let dir = write_temp_files("refs", &[
+        ("customers.json", r#"{"customers": [{"id": "c1"}, {"id": "c2"}]}"#),
+        ("orders.json", r#"{"orders": [{"customer_id": "c1"}, {"customer_id": "c9"}, {"customer_id": "c2"}]}"#),
+    ]);
+    let path = |name: &str| dir.join(name).to_string_lossy().into_owned();
+    let rule = |target: &str| Rule {
+        name: "Orders reference customers".to_string(),
+        json_file: Some(path("orders.json")),
+        jsonpath: "$.orders[*].customer_id".to_string(),
+        check: CheckRule::RefIntegrity {
+            target_file: target.to_string(),
+            target_jsonpath: "$.customers[*].id".to_string(),
+        },
+        ..Default::default()
+    };
+    let checker = create_test_checker();
+
+    let result = checker.run_rule(&rule(&path("customers.json")));
+    assert!(!result.passed);
+    assert_eq!(result.invalid_positions, vec!["$.orders[1].customer_id"]);
+    assert_eq!(result.values_found, vec![serde_json::json!("c9")]);
+
+    let missing = rule(&path("nope.json"));
+    assert!(checker.run_rule(&missing).message.contains("Failed to load reference target"));
+    let config = Config { rules: vec![missing], ..Default::default() };
+    assert!(validate_config(&config)[0].message.contains("reference target file not found"));
+}
+
+#[test]
+fn test_unique_spans_the_whole_selection() {
+    use crate::Rule;
+
+    let rule = Rule {
+        name: "Emails are unique".to_string(),
+        json_inline: Some(serde_json::json!({
+            "teams": [
+                {"members": [{"email": "a@example.com"}, {"email": "b@example.com"}]},
+                {"members": [{"email": "c@example.com"}, {"email": "a@example.com"}]}
+            ]
+        })),
+        jsonpath: "$.teams[*].members[*].email".to_string(),
+        check: serde_json::from_str(r#"{"type": "unique"}"#).unwrap(),
+        ..Default::default()
+    };
+
+    let result = create_test_checker().run_rule(&rule);
+    assert!(!result.passed);
+    assert!(result.message.contains("2 of 4 value(s) are duplicated"));
+    assert_eq!(
+        result.invalid_positions,
+        vec!["$.teams[0].members[0].email", "$.teams[1].members[1].email"]
+    );
+
+    let empty = Rule { jsonpath: "$.nothing[*]".to_string(), ..rule };
+    assert!(create_test_checker().run_rule(&empty).passed);
+}
+
+#[test]
+fn test_same_length_reports_outliers() {
+    use crate::diff::Difference;
+    use crate::Rule;
+
+    let rule = Rule {
+        name: "Series are aligned".to_string(),
+        json_inline: Some(serde_json::json!({
+            "series": [
+                {"points": [1, 2, 3]},
+                {"points": [4, 5]},
+                {"points": [6, 7, 8]},
+                {"points": null}
+            ]
+        })),
+        jsonpath: "$.series[*].points".to_string(),
+        check: serde_json::from_str(r#"{"type": "same_length"}"#).unwrap(),
+        ..Default::default()
+    };
+
+    let result = create_test_checker().run_rule(&rule);
+    assert!(!result.passed);
+    assert!(result.message.contains("2 of 4 value(s) are not arrays of the common length 3"));
+    assert_eq!(result.invalid_positions, vec!["$.series[1].points", "$.series[3].points"]);
+    assert_eq!(
+        result.differences[0],
+        Difference::Changed { path: "$.series[1].points".to_string(), expected: 3.into(), actual: 2.into() }
+    );
+
+    let aligned = Rule { jsonpath: "$.series[0,2].points".to_string(), ..rule };
+    assert!(create_test_checker().run_rule(&aligned).passed);
+}
+
+#[cfg(feature = "expr")]
+#[test]
+fn test_baseline_round_trip_and_options() {
+    use crate::baseline::Baseline;
+    use crate::cli::CliOptions;
+    use crate::Rule;
+
+    let dir = write_temp_files("baseline", &[("ages.json", r#"{"ages": [30, -1]}"#)]);
+    let rule = Rule {
+        name: "Ages are positive".to_string(),
+        json_file: Some(dir.join("ages.json").to_string_lossy().into_owned()),
+        jsonpath: "$.ages[*]".to_string(),
+        check: CheckRule::Expr { expr: "value > 0".to_string() },
+        ..Default::default()
+    };
+    let checker = JsonChecker::from_config(Config { rules: vec![rule], ..Default::default() });
+    let path = dir.join("baseline.json");
+    Baseline::from_results(&checker.run().unwrap()).save(&path).unwrap();
+
+    std::fs::write(dir.join("ages.json"), r#"{"ages": [30, -1, -5]}"#).unwrap();
+    let mut results = checker.run().unwrap();
+    Baseline::load(&path).unwrap().apply(&mut results);
+    assert!(!results[0].passed);
+    assert_eq!(results[0].invalid_positions, vec!["$.ages[2]"]);
+    assert_eq!(results[0].baselined, 1);
+
+    let args = |list: &[&str]| CliOptions::parse(&list.iter().map(|s| s.to_string()).collect::<Vec<_>>());
+    let options = args(&["--baseline", "known.json", "--update-baseline"]).unwrap();
+    assert_eq!(options.baseline.as_deref(), Some("known.json"));
+    assert!(options.update_baseline);
+    assert!(args(&["--update-baseline"]).is_err());
+}
+
+#[test]
+fn test_baseline_sees_failures_past_max_failures_shown() {
+    use crate::cli::{exit_code, Cli, CliOptions};
+    use serde_json::json;
+
+    let dir = write_temp_files("baseline_shown", &[("ages.json", r#"{"ages": [-1, -2]}"#)]);
+    let rules = json!({"rules": [{
+        "name": "Ages",
+        "json_file": dir.join("ages.json"),
+        "jsonpath": "$.ages[*]",
+        "check": {"type": "greater_than", "value": 0},
+    }]});
+    std::fs::write(dir.join("rules.json"), rules.to_string()).unwrap();
+    let run = |extra: &[&str]| {
+        let mut list = vec!["--config".to_string(), dir.join("rules.json").display().to_string(), "--quiet".to_string()];
+        list.extend(["--baseline".to_string(), dir.join("baseline.json").display().to_string()]);
+        list.extend(["--max-failures-shown", "1", "--output"].map(String::from));
+        list.push(dir.join("report.txt").display().to_string());
+        list.extend(extra.iter().map(|s| s.to_string()));
+        Cli::run(&CliOptions::parse(&list).unwrap()).unwrap()
+    };
+
+    assert_eq!(run(&["--update-baseline"]), exit_code::SUCCESS);
+    assert_eq!(run(&[]), exit_code::SUCCESS);
+    std::fs::write(dir.join("ages.json"), r#"{"ages": [-1, -2, -99]}"#).unwrap();
+    assert_eq!(run(&[]), exit_code::RULES_FAILED);
+    let report = std::fs::read_to_string(dir.join("report.txt")).unwrap();
+    assert!(report.contains("$.ages[2]"), "{}", report);
+}
+
+#[test]
+fn test_history_and_trends_report() {
+    use crate::cli::{Cli, CliOptions, Command};
+    use crate::history::{self, RunRecord};
+    use crate::CheckResult;
+
+    let dir = write_temp_files("history", &[]);
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("history.ndjson");
+    let result = |name: &str, passed: bool| CheckResult::new(name.to_string(), passed, String::new());
+    for outcomes in [[true, true], [true, false], [true, true], [true, false]] {
+        let results = [result("schema", outcomes[0]), result("totals", outcomes[1])];
+        history::append(&path, &RunRecord::from_results(&results)).unwrap();
+    }
+
+    let runs = history::load(&path).unwrap();
+    assert_eq!(runs.len(), 4);
+    let rendered = Cli::render_trends(&runs[1..]);
+    assert!(rendered.starts_with("📈 Trends over the last 3 run(s):"));
+    let totals = rendered.find("🔻 regressed totals: failed 2 of 3 run(s), 2 flip(s)").unwrap();
+    let schema = rendered.find("✅ stable schema").unwrap();
+    assert!(totals < schema);
+    assert!(rendered.contains("Flaky: 1, Regressed: 1"));
+
+    let args = |list: &[&str]| CliOptions::parse(&list.iter().map(|s| s.to_string()).collect::<Vec<_>>());
+    let options = args(&["report", "trends", "--history", "h.ndjson", "--last", "5"]).unwrap();
+    assert_eq!(options.command, Command::Trends);
+    assert_eq!(options.history.as_deref(), Some("h.ndjson"));
+    assert_eq!(options.last, Some(5));
+    assert!(args(&["report", "weekly"]).is_err());
+}
+
+#[test]
+fn test_parse_serve_options() {
+    use crate::cli::{CliOptions, Command};
+    use std::time::Duration;
+
+    let args = |list: &[&str]| CliOptions::parse(&list.iter().map(|s| s.to_string()).collect::<Vec<_>>());
+    let options = args(&["serve", "--interval", "5m", "--listen", "0.0.0.0:9000"]).unwrap();
+    assert_eq!(options.command, Command::Serve);
+    assert_eq!(options.interval, Some(Duration::from_secs(300)));
+    assert_eq!(options.listen.as_deref(), Some("0.0.0.0:9000"));
+    assert!(args(&["serve", "--interval", "often"]).is_err());
+}
+
+#[test]
+fn test_html_report() {
+    use crate::cli::{CliOptions, ReportFormat};
+    use crate::html::to_html;
+    use crate::Rule;
+
+    let rule = |name: &str, jsonpath: &str| Rule {
+        name: name.to_string(),
+        json_inline: Some(serde_json::json!({"users": [{"bio": "<script>"}], "ok": 1})),
+        jsonpath: jsonpath.to_string(),
+        check: CheckRule::Equals { value: serde_json::json!(1), tolerance: None },
+        ..Default::default()
+    };
+    let checker = JsonChecker::from_config(Config {
+        rules: vec![rule("Bios are plain", "$.users[*].bio"), rule("Ok", "$.ok")],
+        ..Default::default()
+    });
+    let html = to_html(checker.config(), &checker.run().unwrap());
+
+    assert!(html.starts_with("<!DOCTYPE html>"));
+    assert!(html.contains("<details class=\"failed\" open>"));
+    assert!(html.contains("<details class=\"passed\">"));
+    assert!(html.contains("<code class=\"path\">$.users[0].bio</code>"));
+    assert!(html.contains("<pre>&quot;&lt;script&gt;&quot;</pre>"));
+    assert!(!html.contains("<script>"));
+
+    let options = CliOptions::parse(&["--report", "html", "-o", "report.html"].map(String::from)).unwrap();
+    assert_eq!(options.report, ReportFormat::Html);
+    assert_eq!(options.output.as_deref(), Some("report.html"));
+}
+
+#[cfg(feature = "expr")]
+#[test]
+fn test_markdown_summary() {
+    use crate::markdown::to_markdown;
+    use crate::Rule;
+
+    let long = "x".repeat(100);
+    let rule = |name: &str, check: CheckRule| Rule {
+        name: name.to_string(),
+        json_inline: Some(serde_json::json!({"ids": ["a|b", long.clone()]})),
+        jsonpath: "$.ids[*]".to_string(),
+        check,
+        ..Default::default()
+    };
+    let checker = JsonChecker::from_config(Config {
+        rules: vec![
+            rule("Short ids", CheckRule::Expr { expr: "value.len() < 10".to_string() }),
+            rule("Ids present", CheckRule::NonEmpty),
+        ],
+        ..Default::default()
+    });
+    let markdown = to_markdown(&checker.run().unwrap());
+
+    assert!(markdown.contains("| Rule | Status | Failing paths | Sample failure |"));
+    let failed = markdown.lines().find(|l| l.starts_with("| Short ids")).unwrap();
+    assert!(failed.starts_with("| Short ids | ❌ failed | 1 | `$.ids[1] = \"xxx"));
+    assert!(failed.ends_with("…` |"));
+    assert!(markdown.contains("| Ids present | ✅ passed |  |  |"));
+    assert!(markdown.contains("**Total: 2** · Passed: 1 · Failed: 1 · Warnings: 0 · Skipped: 0"));
+}
+
+#[cfg(feature = "expr")]
+#[test]
+fn test_csv_documents() {
+    use crate::Rule;
+
+    let dir = write_temp_files("csv", &[
+        ("users.csv", "id,email,age\n1,a@example.com,34\n2,not-an-email,\n3,c@example.com,29\n"),
+        ("orders.txt", "order;total\nA-1;12.50\nA-2;-3\n"),
+    ]);
+    let checker = create_test_checker();
+
+    let emails = Rule {
+        name: "Emails".to_string(),
+        json_file: Some(dir.join("users.csv").to_string_lossy().into_owned()),
+        jsonpath: "$.[*].email".to_string(),
+        check: CheckRule::Expr { expr: r#"value.contains("@")"#.to_string() },
+        ..Default::default()
+    };
+    let result = checker.run_rule(&emails);
+    assert!(!result.passed);
+    assert_eq!(result.invalid_positions, vec!["line 3: $[1].email"]);
+
+    // Types are inferred, so numeric checks work on CSV columns
+    let ages = Rule {
+        name: "Ages".to_string(),
+        jsonpath: "$[*].age".to_string(),
+        check: CheckRule::Expr { expr: "value >= 18".to_string() },
+        ..emails.clone()
+    };
+    assert_eq!(checker.run_rule(&ages).invalid_positions, vec!["line 3: $[1].age"]);
+
+    let totals: Rule = serde_json::from_value(serde_json::json!({
+        "name": "Totals",
+        "json_file": dir.join("orders.txt"),
+        "format": "csv",
+        "csv": {"delimiter": ";"},
+        "jsonpath": "$[*].total",
+        "check": {"type": "expr", "expr": "value >= 0"}
+    }))
+    .unwrap();
+    assert_eq!(checker.run_rule(&totals).invalid_positions, vec!["line 3: $[1].total"]);
+}
+
+#[cfg(feature = "expr")]
+#[test]
+fn test_yaml_and_toml_documents() {
+    use crate::Rule;
+
+    let dir = write_temp_files("yaml-toml", &[
+        (
+            "deployment.yaml",
+            "kind: Deployment\nspec:\n  template:\n    spec:\n      containers:\n        - name: app\n          image: app:1.4\n        - name: sidecar\n          image: proxy:latest\n",
+        ),
+        ("Cargo.toml", "[package]\nname = \"demo\"\nedition = \"2021\"\n\n[dependencies]\nserde = \"1\"\n"),
+    ]);
+    let checker = create_test_checker();
+
+    let pinned = Rule {
+        name: "Images are pinned".to_string(),
+        json_file: Some(dir.join("deployment.yaml").to_string_lossy().into_owned()),
+        jsonpath: "$.spec.template.spec.containers[*].image".to_string(),
+        check: CheckRule::Expr { expr: r#"!value.ends_with(":latest")"#.to_string() },
+        ..Default::default()
+    };
+    let result = checker.run_rule(&pinned);
+    assert_eq!(result.invalid_positions, vec!["$.spec.template.spec.containers[1].image"]);
+    assert!(result.locations.is_empty());
+
+    let edition = Rule {
+        name: "Edition 2024".to_string(),
+        json_file: Some(dir.join("Cargo.toml").to_string_lossy().into_owned()),
+        jsonpath: "$.package.edition".to_string(),
+        check: CheckRule::Equals { value: serde_json::json!("2024"), tolerance: None },
+        ..Default::default()
+    };
+    let result = checker.run_rule(&edition);
+    assert!(!result.passed);
+    assert_eq!(result.values_found, vec![serde_json::json!("2021")]);
+}
+
+#[test]
+fn test_multi_document_yaml() {
+    use crate::Rule;
+
+    let manifests = "apiVersion: v1\nkind: Service\n---\napiVersion: apps/v1\nkind: Deployment\nspec:\n  template:\n    spec:\n      containers:\n        - name: app\n          resources:\n            limits: {cpu: 500m}\n---\nkind: Deployment\nspec:\n  template:\n    spec:\n      containers:\n        - name: worker\n";
+    let dir = write_temp_files("multi-yaml", &[("app.yaml", manifests)]);
+
+    let rule = Rule {
+        name: "Deployments set resource limits".to_string(),
+        json_file: Some(dir.join("app.yaml").to_string_lossy().into_owned()),
+        jsonpath: "$[?(@.kind == 'Deployment')].spec.template.spec.containers[*]".to_string(),
+        check: serde_json::from_str(r#"{"type": "required_keys", "keys": [{"name": "resources", "type": "object"}]}"#).unwrap(),
+        ..Default::default()
+    };
+    let result = create_test_checker().run_rule(&rule);
+    assert!(!result.passed);
+    assert_eq!(result.invalid_positions, vec!["line 14: $[2].spec.template.spec.containers[0]"]);
+}
+
+#[test]
+fn test_query_source_rules() {
+    use crate::query::QuerySource;
+    use crate::validate::validate_config;
+
+    let config = serde_json::json!({
+        "vars": {"DB": "host=127.0.0.1 port=1 connect_timeout=1"},
+        "rules": [{
+            "name": "Event payloads have a type",
+            "source": {"postgres": {"url": "${DB}", "query": "SELECT payload FROM events"}},
+            "jsonpath": "$[*].payload.type",
+            "check": {"type": "non_empty"}
+        }]
+    });
+    let dir = write_temp_files("query-source", &[("rules.json", &config.to_string())]);
+    let config = Config::from_file(&dir.join("rules.json").to_string_lossy()).unwrap();
+    let rule = &config.rules[0];
+    let Some(QuerySource::Postgres(query)) = &rule.source else { panic!("expected a postgres source") };
+    assert_eq!(query.url, "host=127.0.0.1 port=1 connect_timeout=1");
+    assert!(validate_config(&config).is_empty());
+
+    // Without the feature or without a server, the rule fails to load its rows
+    let result = create_test_checker().run_rule(rule);
+    assert!(!result.passed);
+    if !cfg!(feature = "postgres") {
+        assert!(result.message.contains("`postgres` feature"), "{}", result.message);
+    }
+
+    let mut both = config;
+    both.rules[0].json_file = Some("data.json".to_string());
+    let issues = validate_config(&both);
+    assert!(issues.iter().any(|i| i.message.contains("source can't be combined")), "{:?}", issues);
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn test_response_assertions() {
+    use crate::Rule;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+
+    // Answers each request with the next canned status line, content type and body
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/status.json", listener.local_addr().unwrap());
+    let responses = [
+        ("200 OK", "application/json; charset=utf-8", r#"{"status": "up"}"#),
+        ("503 Service Unavailable", "text/html", "<h1>down</h1>"),
+    ];
+    std::thread::spawn(move || {
+        for ((status, content_type, body), stream) in responses.into_iter().zip(listener.incoming()) {
+            let mut stream = stream.unwrap();
+            let mut line = String::new();
+            let mut reader = BufReader::new(&stream);
+            while reader.read_line(&mut line).unwrap() > 2 {
+                line.clear();
+            }
+            write!(
+                stream,
+                "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                content_type,
+                body.len(),
+                body
+            )
+            .unwrap();
+        }
+    });
+
+    let rule: Rule = serde_json::from_value(serde_json::json!({
+        "name": "Status endpoint",
+        "json_file": url,
+        "response": {"status": 200, "content_type": "application/json", "max_latency_ms": 5000},
+        "jsonpath": "$.status",
+        "check": {"type": "equals", "value": "up"}
+    }))
+    .unwrap();
+    let checker = create_test_checker();
+    assert!(checker.run_rule(&rule).passed);
+
+    let result = checker.run_rule(&rule);
+    assert!(!result.passed);
+    assert!(result.message.contains("unexpected response"), "{}", result.message);
+    assert!(result.message.contains("status is 503, expected 200"), "{}", result.message);
+    assert!(result.message.contains("content-type is text/html, expected application/json"), "{}", result.message);
+}
+
+#[test]
+fn test_retry_flaky_source() {
+    use crate::retry::RetryPolicy;
+    use crate::source::DocumentSource;
+    use crate::Rule;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    /// Fails until it has been asked `failures` times
+    struct Flaky {
+        calls: AtomicU32,
+        failures: u32,
+    }
+    impl DocumentSource for Flaky {
+        fn resolve(&self, _uri: &str) -> anyhow::Result<Value> {
+            if self.calls.fetch_add(1, Ordering::SeqCst) < self.failures {
+                anyhow::bail!("503 from replica");
+            }
+            Ok(serde_json::json!({"ready": true}))
+        }
+    }
+
+    let rule = Rule {
+        name: "Eventually ready".to_string(),
+        json_file: Some("flaky://status".to_string()),
+        jsonpath: "$.ready".to_string(),
+        check: CheckRule::Equals { value: serde_json::json!(true), tolerance: None },
+        retry: Some(RetryPolicy { attempts: 3, backoff_ms: 1, timeout_ms: None }),
+        ..Default::default()
+    };
+    let checker = |failures| {
+        create_test_checker().with_source("flaky", Flaky { calls: AtomicU32::new(0), failures })
+    };
+
+    let result = checker(2).run_rule(&rule);
+    assert!(result.passed, "{}", result.message);
+    assert!(result.retry_log.is_empty());
+
+    let result = checker(5).run_rule(&rule);
+    assert!(!result.passed);
+    assert_eq!(result.message, "Error: gave up after 3 attempt(s): 503 from replica");
+    assert_eq!(result.retry_log.len(), 3);
+    assert!(result.retry_log[0].starts_with("attempt 1 failed after"));
+
+    let without_retry = Rule { retry: None, ..rule };
+    assert_eq!(checker(1).run_rule(&without_retry).message, "Error: 503 from replica");
+}
+
+#[test]
+fn test_rule_timeout() {
+    use crate::cli::CliOptions;
+    use crate::source::DocumentSource;
+    use crate::{Rule, Status};
+    use std::time::Duration;
+
+    /// Takes a while to answer
+    struct Slow;
+    impl DocumentSource for Slow {
+        fn resolve(&self, _uri: &str) -> anyhow::Result<Value> {
+            std::thread::sleep(Duration::from_millis(300));
+            Ok(serde_json::json!({"ready": true}))
+        }
+    }
+
+    let rule = Rule {
+        name: "Slow status".to_string(),
+        json_file: Some("slow://status".to_string()),
+        jsonpath: "$.ready".to_string(),
+        check: CheckRule::Equals { value: serde_json::json!(true), tolerance: None },
+        timeout_ms: Some(20),
+        ..Default::default()
+    };
+    let checker = JsonChecker::from_config(Config { timeout_ms: Some(5_000), ..Default::default() }).with_source("slow", Slow);
+
+    let result = checker.run_rule(&rule);
+    assert!(!result.passed && result.status == Status::Timeout);
+    assert_eq!(result.message, "⏱ Rule 'Slow status' timed out after 20ms");
+    assert!(result.duration < Duration::from_millis(300));
+
+    // Without its own limit, the rule gets the config's
+    let result = checker.run_rule(&Rule { timeout_ms: None, ..rule });
+    assert_eq!(result.status, Status::Pass, "{}", result.message);
+
+    let options = CliOptions::parse(&["--timeout".to_string(), "30s".to_string()]).unwrap();
+    assert_eq!(options.timeout, Some(Duration::from_secs(30)));
+}
+
+#[test]
+fn test_result_statuses() {
+    use crate::cli::{Cli, Style};
+    use crate::{Rule, Status};
+
+    let rule = |name: &str, check: CheckRule| Rule {
+        name: name.to_string(),
+        json_inline: Some(serde_json::json!({"count": 3})),
+        jsonpath: "$.count".to_string(),
+        check,
+        ..Default::default()
+    };
+    let config = Config {
+        rules: vec![
+            rule("Counted", CheckRule::GreaterThan { value: 0.into() }),
+            rule("Too few", CheckRule::GreaterThan { value: 5.into() }),
+            Rule {
+                json_inline: None,
+                json_file: Some("/nonexistent/status.json".to_string()),
+                ..rule("Unreadable", CheckRule::NonEmpty)
+            },
+            Rule { depends_on: vec!["Too few".to_string()], ..rule("After too few", CheckRule::NonEmpty) },
+        ],
+        ..Default::default()
+    };
+    let results = JsonChecker::from_config(config).run().unwrap();
+    let statuses: Vec<Status> = results.iter().map(|r| r.status).collect();
+    assert_eq!(statuses, [Status::Pass, Status::Fail, Status::Error, Status::Skipped]);
+    assert_eq!(serde_json::to_value(&results[2]).unwrap()["status"], "error");
+
+    let report = Cli::render_results(&results, &Style::default());
+    for line in ["Passed: 1", "Failed: 1", "Errors: 1", "Skipped: 1"] {
+        assert!(report.contains(line), "{}", report);
+    }
+}
+
+#[test]
+fn test_checker_error_kinds() {
+    use crate::{CheckerError, Rule};
+
+    let dir = write_temp_files(
+        "error-kinds",
+        &[
+            ("broken.json", r#"{"rules": [}"#),
+            ("bad-path.json", r#"{"rules": [{"name": "typo", "json_file": "d.json", "jsonpath": "users[*]", "check": {"type": "non_empty"}}]}"#),
+        ],
+    );
+    let load = |file: &str| Config::from_file(dir.join(file).to_str().unwrap()).unwrap_err();
+
+    assert!(matches!(load("broken.json"), CheckerError::ConfigParse { .. }));
+    assert!(matches!(load("missing.json"), CheckerError::FileNotFound { .. }));
+    match load("bad-path.json") {
+        CheckerError::JsonPath(errors) => assert_eq!(errors[0].0, "typo"),
+        other => panic!("expected a JSONPath error, got {:?}", other),
+    }
+
+    let checker = create_test_checker();
+    let rule = Rule {
+        name: "Missing document".to_string(),
+        json_file: Some(dir.join("users.json").to_string_lossy().into_owned()),
+        jsonpath: "$".to_string(),
+        check: CheckRule::NonEmpty,
+        ..Default::default()
+    };
+    let err = checker.check_rule(&rule).unwrap_err();
+    assert!(matches!(err.kind(), CheckerError::FileNotFound { .. }) && err.is_transient());
+
+    let regex = CheckRule::Regex { pattern: "(".to_string() };
+    let inline = Rule { json_file: None, json_inline: Some(serde_json::json!("x")), check: regex, ..rule };
+    assert!(matches!(checker.check_rule(&inline).unwrap_err().kind(), CheckerError::Regex { pattern, .. } if pattern == "("));
+}
+
+#[test]
+fn test_checker_builder() {
+    use crate::source::DocumentSource;
+    use crate::{Rule, Status};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+
+    /// Counts reads and takes a while to answer each
+    struct Counted(Arc<AtomicUsize>);
+    impl DocumentSource for Counted {
+        fn resolve(&self, _uri: &str) -> anyhow::Result<Value> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            std::thread::sleep(Duration::from_millis(100));
+            Ok(serde_json::json!({"users": [{"age": 30}, {"age": 40}]}))
+        }
+    }
+
+    let rule = |name: &str, uri: &str| {
+        Rule::builder().name(name).file(uri).path("$.users[*].age").check(CheckRule::GreaterThan { value: 18.into() })
+    };
+    let config = Config::builder()
+        .rule(rule("a", "count://users").build().unwrap())
+        .rule(rule("b", "count://users").build().unwrap())
+        .rule(rule("c", "count://other").build().unwrap())
+        .rule(rule("d", "count://users").depends_on("c").build().unwrap())
+        .build()
+        .unwrap();
+
+    let reads = Arc::new(AtomicUsize::new(0));
+    let checker = JsonChecker::builder()
+        .config(config)
+        .source("count", Counted(Arc::clone(&reads)))
+        .cache_documents(true)
+        .parallelism(4)
+        .build();
+    let started = Instant::now();
+    let results = checker.run().unwrap();
+
+    let names: Vec<&str> = results.iter().map(|r| r.rule_name.as_str()).collect();
+    assert_eq!(names, ["a", "b", "c", "d"]);
+    assert!(results.iter().all(|r| r.status == Status::Pass));
+    // Each document is read once, and the first level's reads overlap
+    assert_eq!(reads.load(Ordering::SeqCst), 2);
+    assert!(started.elapsed() < Duration::from_millis(400), "{:?}", started.elapsed());
+}
+
+#[test]
+fn test_unknown_config_keys() {
+    use crate::CheckerError;
+
+    let dir = write_temp_files(
+        "unknown-keys",
+        &[
+            ("field.json", "{\"rules\": [{\n  \"name\": \"a\",\n  \"json_file\": \"d.json\",\n  \"jsonpth\": \"$.a\",\n  \"check\": {\"type\": \"non_empty\"}\n}]}"),
+            ("check.json", r#"{"rules": [{"name": "a", "json_file": "d.json", "jsonpath": "$", "check": {"type": "non_empy"}}]}"#),
+            ("option.json", r#"{"rules": [{"name": "a", "json_file": "d.json", "jsonpath": "$", "check": {"type": "match_count", "mni": 1}}]}"#),
+            ("nonsense.json", r#"{"rulez": []}"#),
+        ],
+    );
+    let load = |file: &str| Config::from_file(dir.join(file).to_str().unwrap()).unwrap_err();
+
+    let error = load("field.json");
+    assert_eq!(error.to_string(), format!("Unknown field `jsonpth` in {}:4, did you mean `jsonpath`?", dir.join("field.json").display()));
+    match error {
+        CheckerError::UnknownKey { what, expected, .. } => {
+            assert_eq!(what, "field");
+            assert!(expected.iter().any(|key| key == "json_pointer"));
+        }
+        other => panic!("expected an unknown key, got {:?}", other),
+    }
+    assert!(load("check.json").to_string().starts_with("Unknown variant `non_empy`"));
+    assert!(load("check.json").to_string().ends_with("did you mean `non_empty`?"));
+    let option = load("option.json").to_string();
+    assert!(option.ends_with("did you mean `min`?"), "{}", option);
+    assert!(matches!(load("nonsense.json"), CheckerError::UnknownKey { suggestion: Some(s), .. } if s == "rules"));
+}
+
+#[test]
+fn test_schema_command() {
+    use crate::cli::{Cli, CliOptions, Command};
+
+    let dir = write_temp_files(
+        "schema",
+        &[("rules.json", r#"{"$schema": "./rules.schema.json", "rules": [{"name": "a", "json_inline": [1], "jsonpath": "$", "check": {"type": "non_empty"}}]}"#)],
+    );
+    let output = dir.join("rules.schema.json");
+    let options = CliOptions::parse(&["schema".to_string(), "-o".to_string(), output.display().to_string()]).unwrap();
+    assert_eq!(options.command, Command::Schema);
+    Cli::run(&options).unwrap();
+
+    let schema: Value = serde_json::from_str(&std::fs::read_to_string(&output).unwrap()).unwrap();
+    assert_eq!(schema["properties"]["rules"]["items"]["$ref"], "#/$defs/Rule");
+    // Configs can point editors at the schema
+    let config = Config::from_file(dir.join("rules.json").to_str().unwrap()).unwrap();
+    assert_eq!(config.rules.len(), 1);
+}
+
+#[test]
+fn test_init_wizard() {
+    use crate::cli::{Cli, CliOptions, Command};
+
+    let dir = write_temp_files(
+        "init",
+        &[("users.json", r#"{"users": [{"id": "123e4567-e89b-12d3-a456-426614174000", "age": 31}]}"#)],
+    );
+    let sample = dir.join("users.json").display().to_string();
+    let output = dir.join("rules.json").display().to_string();
+    let options = CliOptions::parse(&["init".to_string(), sample.clone(), "-o".to_string(), output.clone()]).unwrap();
+    assert_eq!(options.command, Command::Init);
+    assert_eq!(options.sample.as_deref(), Some(sample.as_str()));
+
+    // Paths: 1 $, 2 $.users, 3 $.users[*], 4 $.users[*].id, 5 $.users[*].age
+    let answers = "2 4 5\n2\n\n\n\n2\n\nIds are UUIDs\n1\n18\n\n";
+    let mut prompts = Vec::new();
+    Cli::init(&options, answers.as_bytes(), &mut prompts).unwrap();
+    let prompts = String::from_utf8(prompts).unwrap();
+    assert!(prompts.contains("      $.users[*].id"), "{}", prompts);
+    assert!(prompts.contains("Format (uuid, email, url, ipv4, ipv6, hostname, base64, lowercase, uppercase) [uuid]"), "{}", prompts);
+
+    let checker = JsonChecker::new(&output).unwrap();
+    let rules = &checker.config().rules;
+    let names: Vec<&str> = rules.iter().map(|r| r.name.as_str()).collect();
+    assert_eq!(names, ["users array_length", "Ids are UUIDs", "users[*].age greater_than_or_equal"]);
+    assert!(matches!(rules[0].check, CheckRule::ArrayLength { min: Some(1), max: None }));
+    assert!(matches!(&rules[2].check, CheckRule::GreaterThanOrEqual { value } if value.as_f64() == Some(18.0)));
+    assert!(checker.run().unwrap().iter().all(|r| r.passed));
+
+    // An existing config is never overwritten
+    assert!(Cli::init(&options, "".as_bytes(), Vec::new()).unwrap_err().to_string().contains("already exists"));
+}
+
+#[test]
+fn test_explore_saves_rules() {
+    use crate::cli::{Cli, CliOptions, Command};
+
+    let dir = write_temp_files(
+        "explore",
+        &[
+            ("users.json", r#"{"users": [{"email": "a@example.com"}, {"email": ""}]}"#),
+            ("rules.json", r#"{"vars": {"DIR": "."}, "rules": []}"#),
+        ],
+    );
+    let sample = dir.join("users.json").display().to_string();
+    let config = dir.join("rules.json").display().to_string();
+    let options = CliOptions::parse(&["explore".to_string(), sample, "--config".to_string(), config.clone()]).unwrap();
+    assert_eq!(options.command, Command::Explore);
+
+    let commands = "$.users[*].email\n$.users[\n:save \"Emails set\" non_empty\n:save emails {\"type\": \"regex\", \"pattern\": \"@\"}\n:save emails unique\n:quit\n";
+    let mut output = Vec::new();
+    Cli::explore(&options, commands.as_bytes(), &mut output).unwrap();
+    let output = String::from_utf8(output).unwrap();
+    assert!(output.contains("2 match(es)\n  $.users[0].email = \"a@example.com\"\n  $.users[1].email = \"\""), "{}", output);
+    assert!(output.contains("❌ invalid JSONPath"), "{}", output);
+    assert!(output.contains("💾 Saved rule 'Emails set'"), "{}", output);
+    assert!(output.contains("already has a rule named 'emails'"), "{}", output);
+
+    // Saved rules query the last path that compiled, and the rest of the config is kept
+    let saved: Value = serde_json::from_str(&std::fs::read_to_string(&config).unwrap()).unwrap();
+    assert_eq!(saved["vars"]["DIR"], ".");
+    let checker = JsonChecker::new(&config).unwrap();
+    let results = checker.run().unwrap();
+    assert_eq!(results.len(), 2);
+    assert_eq!(checker.config().rules[0].jsonpath, "$.users[*].email");
+}
+
+#[test]
+fn test_explain_rule() {
+    use crate::cli::{Cli, CliOptions, Style};
+    use crate::rules::Quantifier;
+    use crate::Rule;
+
+    let rule = |name: &str, check: CheckRule| Rule {
+        name: name.to_string(),
+        json_inline: Some(serde_json::json!({"users": [{"age": 31}, {"age": 12}]})),
+        jsonpath: "$.users[*].age".to_string(),
+        check,
+        ..Default::default()
+    };
+    let config = Config {
+        rules: vec![
+            rule("Adults", CheckRule::GreaterThan { value: 18.into() }),
+            rule("Set", CheckRule::NonEmpty),
+            rule("Count", CheckRule::MatchCount { min: Some(3), max: None }),
+        ],
+        ..Default::default()
+    };
+    let checker = JsonChecker::from_config(config);
+
+    // `greater_than` passes if any node does, although one is below the threshold
+    let explanation = checker.explain_rule(&checker.config().rules[0]).unwrap();
+    assert!(explanation.passed);
+    assert_eq!(explanation.quantifier, Quantifier::Any);
+    let outcomes: Vec<_> = explanation.nodes.iter().map(|n| (n.path.as_deref().unwrap(), n.passed)).collect();
+    assert_eq!(outcomes, [("$.users[0].age", Some(true)), ("$.users[1].age", Some(false))]);
+    assert_eq!(
+        explanation.to_string(),
+        "🔬 Rule 'Adults': JSONPath '$.users[*].age' selected 2 node(s), check greater_than\n   \
+         ✓ $.users[0].age = 31\n   ✗ $.users[1].age = 12\n   → passed: passes if any node passes; 1 of 2 did\n"
+    );
+
+    let count = checker.explain_rule(&checker.config().rules[2]).unwrap();
+    assert!(!count.passed && count.nodes.iter().all(|n| n.passed.is_none()));
+
+    let options = CliOptions::parse(&["--explain".to_string(), "--quiet".to_string()]).unwrap();
+    assert!(options.explain);
+    let results = checker.run().unwrap();
+    let style = Style { theme: None, quiet: true };
+    let report = Cli::render_explanations(&checker, &results, &style);
+    assert!(report.contains("Rule 'Count'") && !report.contains("Rule 'Adults'"), "{}", report);
+}
+
+#[test]
+fn test_select_dry_run() {
+    use crate::cli::{Cli, CliOptions, Command};
+    use crate::Rule;
+
+    let dir = write_temp_files(
+        "select_dry_run",
+        &[("a.json", r#"{"users": [{"email": "a@x.io"}]}"#), ("b.json", r#"{"users": []}"#)],
+    );
+    let config = Config {
+        rules: vec![
+            Rule {
+                name: "Emails".to_string(),
+                json_file: Some(format!("{}/*.json", dir.display())),
+                jsonpath: "$.users[*].email".to_string(),
+                // Never applied by a dry run
+                check: CheckRule::Equals { value: serde_json::json!("nobody"), tolerance: None },
+                ..Default::default()
+            },
+            Rule {
+                name: "Inline".to_string(),
+                json_inline: Some(serde_json::json!({"a": 1})),
+                jsonpath: "$.a".to_string(),
+                check: CheckRule::NonEmpty,
+                ..Default::default()
+            },
+        ],
+        ..Default::default()
+    };
+    let checker = JsonChecker::from_config(config);
+
+    let selections = checker.select_rule(&checker.config().rules[0]).unwrap();
+    assert_eq!(selections.len(), 2);
+    assert_eq!(selections[0].nodes, [(Some("$.users[0].email".to_string()), serde_json::json!("a@x.io"))]);
+    assert!(selections[1].nodes.is_empty());
+
+    let args: Vec<String> = ["select", "--rule", "Inline"].iter().map(|s| s.to_string()).collect();
+    let options = CliOptions::parse(&args).unwrap();
+    assert_eq!(options.command, Command::Select);
+    let report = Cli::render_selections(&checker, &options.rules).unwrap();
+    assert_eq!(report, "📌 Rule 'Inline': JSONPath '$.a' selected 1 node(s)\n   $.a = 1\n");
+
+    let error = Cli::render_selections(&checker, &["Emials".to_string()]).unwrap_err();
+    assert_eq!(error.to_string(), "No rule named 'Emials', did you mean 'Emails'?");
+}
+
+#[test]
+fn test_find_path_command() {
+    use crate::cli::{Cli, CliOptions, Command};
+
+    let dir = write_temp_files("find_path_command", &[("doc.json", r#"{"ids": [42, 7, 42], "name": "id-42"}"#)]);
+    let file = dir.join("doc.json").display().to_string();
+    let parse = |extra: &[&str]| {
+        let mut args = vec!["find-path".to_string(), file.clone()];
+        args.extend(extra.iter().map(|s| s.to_string()));
+        CliOptions::parse(&args).unwrap()
+    };
+
+    let options = parse(&["--target", "42", "--all"]);
+    assert_eq!(options.command, Command::FindPath);
+    assert_eq!(options.sample.as_deref(), Some(file.as_str()));
+    let found = Cli::find_json_node_path(&file, options.target.as_ref().unwrap(), options.all).unwrap();
+    let found: Vec<String> = found.iter().map(ToString::to_string).collect();
+    assert_eq!(found, ["$.ids[0]", "$.ids[2]"]);
+
+    let options = parse(&["--regex", r"^id-\d+$"]);
+    let found = Cli::find_json_node_path(&file, options.target.as_ref().unwrap(), options.all).unwrap();
+    assert_eq!(found[0].to_string(), "$.name");
+
+    let args: Vec<String> = ["find-path", "--contains", "a", "--target", "1"].iter().map(|s| s.to_string()).collect();
+    assert!(CliOptions::parse(&args).is_err());
+}
+
+#[test]
+fn test_get_command() {
+    use crate::cli::{exit_code, Cli, CliOptions, Command};
+
+    let dir = write_temp_files("get_command", &[("doc.json", r#"{"users": [{"email": "a@x.io"}, {"email": "b@x.io"}]}"#)]);
+    let file = dir.join("doc.json").display().to_string();
+    let get = |path: &str| {
+        let options = CliOptions::parse(&["get".to_string(), file.clone(), path.to_string()]).unwrap();
+        assert_eq!(options.command, Command::Get);
+        let mut output = Vec::new();
+        let code = Cli::get(&options, &mut output).unwrap();
+        (code, String::from_utf8(output).unwrap())
+    };
+
+    assert_eq!(get("$.users[1].email"), (exit_code::SUCCESS, "\"b@x.io\"\n".to_string()));
+    assert_eq!(get("$.users[0]"), (exit_code::SUCCESS, "{\n  \"email\": \"a@x.io\"\n}\n".to_string()));
+    assert_eq!(get("$.users[*].email").1, "\"a@x.io\"\n\"b@x.io\"\n");
+    assert_eq!(get("$.users[3].email"), (exit_code::RULES_FAILED, String::new()));
+}
+
+#[test]
+fn test_diff_documents() {
+    use crate::cli::{exit_code, Cli, CliOptions};
+    use crate::diff::Difference;
+    use crate::Rule;
+
+    let dir = write_temp_files("diff_documents", &[
+        ("old.json", r#"{"version": 1, "updated": "mon", "items": [{"id": 1, "tags": ["a"]}]}"#),
+        ("new.json", r#"{"version": 1, "updated": "tue", "items": [{"id": 2, "tags": ["a", "b"]}]}"#),
+    ]);
+    let path = |name: &str| dir.join(name).to_string_lossy().into_owned();
+
+    let mut args = vec!["diff".to_string(), path("old.json"), path("new.json"), "--ignore-case".to_string()];
+    let options = CliOptions::parse(&args).unwrap();
+    assert!(options.canonicalize.ignore_case);
+    args.pop();
+    let options = CliOptions::parse(&args).unwrap();
+    let mut output = Vec::new();
+    assert_eq!(Cli::diff(&options, &mut output).unwrap(), exit_code::RULES_FAILED);
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        "~ $.updated: expected \"mon\", found \"tue\"\n~ $.items[0].id: expected 1, found 2\n\
+         + $.items[0].tags[1]: \"b\"\n🔀 3 difference(s): 2 changed, 1 extra\n"
+    );
+
+    let rule = |allowed: &[&str]| Rule {
+        name: "Matches the baseline".to_string(),
+        json_file: Some(path("new.json")),
+        jsonpath: "$".to_string(),
+        check: CheckRule::Diff {
+            baseline_file: path("old.json"),
+            allowed_paths: allowed.iter().map(|s| s.to_string()).collect(),
+        },
+        ..Default::default()
+    };
+    let checker = create_test_checker();
+
+    let result = checker.run_rule(&rule(&["$.updated", "$.items[*].tags"]));
+    assert!(!result.passed);
+    assert_eq!(result.invalid_positions, vec!["$"]);
+    assert_eq!(
+        result.differences,
+        vec![Difference::Changed { path: "$.items[0].id".to_string(), expected: serde_json::json!(1), actual: serde_json::json!(2) }]
+    );
+    assert!(checker.run_rule(&rule(&["$.updated", "$.items"])).passed);
+}
+
+#[test]
+fn test_matches_document_ignores_volatile_paths() {
+    use crate::validate::validate_config;
+    use crate::Rule;
+
+    let dir = write_temp_files("matches_document", &[
+        ("fixture.json", r#"{"id": 0, "created_at": "", "user": {"name": "Ada", "roles": ["admin"]}}"#),
+        ("responses.json", r#"[
+            {"id": 17, "created_at": "2024-05-01T10:00:00Z", "user": {"name": "Ada", "roles": ["admin"]}},
+            {"id": 18, "created_at": "2024-05-01T10:00:01Z", "user": {"name": "Ada", "roles": []}}
+        ]"#),
+    ]);
+    let path = |name: &str| dir.join(name).to_string_lossy().into_owned();
+    let rule = |file: &str| Rule {
+        name: "Responses match the fixture".to_string(),
+        json_file: Some(path("responses.json")),
+        jsonpath: "$[*]".to_string(),
+        check: serde_json::from_value(serde_json::json!({
+            "type": "matches_document",
+            "file": file,
+            "ignore_paths": ["$.id", "$..created_at"]
+        }))
+        .unwrap(),
+        ..Default::default()
+    };
+    let checker = create_test_checker();
+
+    let result = checker.run_rule(&rule(&path("fixture.json")));
+    assert!(!result.passed);
+    assert_eq!(result.invalid_positions, vec!["$[1]"]);
+    let paths: Vec<&str> = result.differences.iter().map(|d| d.path()).collect();
+    assert_eq!(paths, ["$[1].user.roles[0]"]);
+
+    let config = Config { rules: vec![rule(&path("nope.json"))], ..Default::default() };
+    assert!(validate_config(&config)[0].message.contains("reference document not found"));
+}
+
+#[test]
+fn test_numeric_tolerance() {
+    use crate::validate::validate_config;
+    use crate::Rule;
+
+    let rule = |check: &str| Rule {
+        name: "Revenue share".to_string(),
+        json_inline: Some(serde_json::json!({"shares": [0.1, 0.2, 0.30000000000000004]})),
+        jsonpath: "$.shares[2]".to_string(),
+        check: serde_json::from_str(check).unwrap(),
+        ..Default::default()
+    };
+    let checker = create_test_checker();
+
+    assert!(!checker.run_rule(&rule(r#"{"type": "equals", "value": 0.3}"#)).passed);
+    assert!(checker.run_rule(&rule(r#"{"type": "equals", "value": 0.3, "tolerance": 1e-9}"#)).passed);
+    assert!(checker.run_rule(&rule(r#"{"type": "approx_equals", "value": 0.3, "epsilon": 1e-9}"#)).passed);
+
+    let result = checker.run_rule(&rule(r#"{"type": "approx_equals", "value": 0.25, "epsilon": 0.01}"#));
+    assert!(!result.passed);
+    assert_eq!(result.invalid_positions, vec!["$.shares[2]"]);
+
+    let config = Config { rules: vec![rule(r#"{"type": "approx_equals", "value": 0.3, "epsilon": -1}"#)], ..Default::default() };
+    assert!(validate_config(&config)[0].message.contains("tolerance must be a number of at least 0"));
+}
+
+#[test]
+fn test_canonicalized_comparisons() {
+    use crate::Rule;
+
+    let dir = write_temp_files("canonicalize", &[("baseline.json", r#"{"Role": "ADMIN", "level": 2}"#)]);
+    let rule = |check: serde_json::Value, canonicalize: serde_json::Value| -> Rule {
+        serde_json::from_value(serde_json::json!({
+            "name": "Canonical",
+            "json_inline": {"user": {"role": " Admin ", "level": 2.0}, "ids": [{"b": 1, "a": 2}, {"a": 2.0, "b": 1}]},
+            "jsonpath": "$.user.role",
+            "check": check,
+            "canonicalize": canonicalize,
+        }))
+        .unwrap()
+    };
+    let checker = create_test_checker();
+
+    let equals = serde_json::json!({"type": "equals", "value": "ADMIN"});
+    assert!(!checker.run_rule(&rule(equals.clone(), serde_json::json!({}))).passed);
+    assert!(checker.run_rule(&rule(equals, serde_json::json!({"trim": true, "ignore_case": true}))).passed);
+
+    let mut unique = rule(serde_json::json!({"type": "unique"}), serde_json::json!({"normalize_numbers": true}));
+    unique.jsonpath = "$.ids[*]".to_string();
+    assert!(checker.run_rule(&unique).passed);
+    unique.canonicalize.sort_keys = true;
+    assert!(!checker.run_rule(&unique).passed);
+
+    let baseline = dir.join("baseline.json").to_string_lossy().into_owned();
+    let mut matches = rule(
+        serde_json::json!({"type": "matches_document", "file": baseline}),
+        serde_json::json!({"trim": true, "ignore_case": true, "normalize_numbers": true}),
+    );
+    matches.jsonpath = "$.user".to_string();
+    let result = checker.run_rule(&matches);
+    let paths: Vec<&str> = result.differences.iter().map(|d| d.path()).collect();
+    assert_eq!(paths, ["$.user.Role", "$.user.role"]);
+}
+
+#[test]
+fn test_localized_messages() {
+    use crate::cli::CliOptions;
+    use crate::messages::{Catalog, Message};
+    use crate::Rule;
+
+    let dir = write_temp_files("messages", &[("a.json", r#"{"ids": [1, 1]}"#), ("b.json", r#"{"ids": [2]}"#)]);
+    let rule = Rule {
+        name: "Unique ids".to_string(),
+        json_file: Some(dir.join("*.json").to_string_lossy().into_owned()),
+        jsonpath: "$.ids[*]".to_string(),
+        check: CheckRule::Unique,
+        ..Default::default()
+    };
+    let mut result = create_test_checker().run_rule(&rule);
+    assert!(!result.passed);
+    assert!(matches!(&result.lines[0], Message::FailedForFiles { failed: 1, total: 2, .. }));
+    let file = &result.per_document[0];
+    assert_eq!(file.lines[1], Message::Duplicates { duplicated: 2, total: 2 });
+    assert!(file.message.ends_with("\n   2 of 2 value(s) are duplicated"));
+
+    Catalog::load("zh-TW").unwrap().localize(&mut result);
+    assert!(result.message.starts_with("✗ 規則「Unique ids」在 2 個符合"));
+    assert!(result.message.contains("a.json: ✗ 規則「Unique ids [") && result.message.contains("」在 JSONPath「$.ids[*]」失敗"));
+    assert_eq!(result.per_document[0].message.lines().nth(1), Some("   2 個值中有 2 個重複"));
+
+    std::fs::write(dir.join("messages.json"), r#"{"passed": "OK {rule}"}"#).unwrap();
+    let catalog = Catalog::load(&dir.join("messages.json").to_string_lossy()).unwrap();
+    assert_eq!(catalog.render_line(&Message::Passed { rule: "Ids".to_string() }), "OK Ids");
+    assert_eq!(catalog.render_line(&Message::MatchCount { count: 3 }), "JSONPath matched 3 node(s)");
+
+    let options = CliOptions::parse(&["--lang".to_string(), "zh-TW".to_string()]).unwrap();
+    assert_eq!(options.lang.as_deref(), Some("zh-TW"));
+}
+
+#[test]
+fn test_remediation_hints() {
+    use crate::cli::{Cli, Style};
+    use crate::markdown::to_markdown;
+    use crate::sarif::to_sarif;
+    use crate::Rule;
+
+    let rule = Rule::builder()
+        .name("Replicas")
+        .inline(serde_json::json!({"replicas": 1}))
+        .path("$.replicas")
+        .check(CheckRule::Equals { value: serde_json::json!(3), tolerance: None })
+        .hint("Scale the deployment back to 3 replicas")
+        .docs_url("https://runbooks.example.com/replicas")
+        .build()
+        .unwrap();
+    let checker = JsonChecker::from_config(Config { rules: vec![rule], ..Default::default() });
+    let results = checker.run().unwrap();
+    assert_eq!(results[0].hint.as_deref(), Some("Scale the deployment back to 3 replicas"));
+
+    let rendered = Cli::render_results(&results, &Style::default());
+    assert!(rendered.contains("   💡 Hint: Scale the deployment back to 3 replicas\n   📖 Docs: https://runbooks.example.com/replicas"));
+    let markdown = to_markdown(&results);
+    assert!(markdown.contains("- **Replicas**: Scale the deployment back to 3 replicas ([docs](https://runbooks.example.com/replicas))"));
+    let descriptor = &to_sarif(checker.config(), &results)["runs"][0]["tool"]["driver"]["rules"][0];
+    assert_eq!(descriptor["helpUri"], "https://runbooks.example.com/replicas");
+}
+
+#[test]
+fn test_suites() {
+    use crate::cli::{Cli, CliOptions, Style};
+    use crate::markdown::to_markdown;
+
+    let rule = |name: &str, value: i64| serde_json::json!({
+        "name": name,
+        "json_inline": {"amount": 10},
+        "jsonpath": "$.amount",
+        "check": {"type": "equals", "value": value},
+    });
+    let mut refund = rule("Refund", 10);
+    refund["depends_on"] = serde_json::json!(["Ledger"]);
+    let dir = write_temp_files("suites", &[
+        ("search.json", &serde_json::json!({"suites": [{"name": "payments", "rules": [rule("Capture", 11)]}]}).to_string()),
+        ("rules.json", &serde_json::json!({
+            "include": ["search.json"],
+            "suites": [{"name": "payments", "description": "Money movement", "rules": [refund]}],
+            "rules": [rule("Ledger", 10)],
+        }).to_string()),
+    ]);
+    let path = dir.join("rules.json").to_string_lossy().into_owned();
+    let config = Config::from_file(&path).unwrap();
+    let names: Vec<(&str, Option<&str>)> = config.rules.iter().map(|r| (r.name.as_str(), r.suite.as_deref())).collect();
+    assert_eq!(names, [("Capture", Some("payments")), ("Ledger", None), ("Refund", Some("payments"))]);
+    assert_eq!(config.suites.len(), 1);
+    assert_eq!(config.suites[0].description.as_deref(), Some("Money movement"));
+
+    let results = JsonChecker::from_config(config).run().unwrap();
+    let rendered = Cli::render_results(&results, &Style::default());
+    assert!(rendered.contains("📦 Suites:\n   payments: 1 of 2 passed, 1 failed\n"), "{}", rendered);
+    assert!(to_markdown(&results).contains("| payments | 1 | 1 | 0 | 0 |"));
+
+    let mut config = Config::from_file(&path).unwrap();
+    config.select_suite("payments").unwrap();
+    let names: Vec<&str> = config.rules.iter().map(|r| r.name.as_str()).collect();
+    assert_eq!(names, ["Capture", "Ledger", "Refund"]);
+    let err = config.select_suite("payment").unwrap_err().to_string();
+    assert!(err.contains("No suite named 'payment', did you mean 'payments'?"), "{}", err);
+
+    let options = CliOptions::parse(&["--suite".to_string(), "payments".to_string()]).unwrap();
+    assert_eq!(options.suite.as_deref(), Some("payments"));
+}
+
+#[test]
+fn test_disabled_rules_are_skipped() {
+    use crate::markdown::to_markdown;
+    use crate::validate::config_warnings;
+    use crate::{Rule, Status};
+
+    let rule = |name: &str| Rule {
+        name: name.to_string(),
+        json_inline: Some(serde_json::json!({"status": "down"})),
+        jsonpath: "$.status".to_string(),
+        check: CheckRule::Equals { value: serde_json::json!("up"), tolerance: None },
+        ..Default::default()
+    };
+    let disabled = Rule { enabled: Some(false), skip_reason: Some("upstream outage, INC-42".to_string()), ..rule("Status") };
+    let dependent = Rule { depends_on: vec!["Status".to_string()], ..rule("Details") };
+    let config = Config { rules: vec![disabled.clone(), dependent], ..Default::default() };
+    let checker = JsonChecker::from_config(config);
+
+    let results = checker.run().unwrap();
+    assert_eq!(results[0].status, Status::Skipped);
+    assert!(results[0].passed);
+    assert_eq!(results[0].message, "⏭ Rule 'Status' skipped: disabled (upstream outage, INC-42)");
+    assert_eq!(results[1].status, Status::Skipped);
+    assert!(to_markdown(&results).contains("| Status | ⏭ skipped |  | `⏭ Rule 'Status' skipped: disabled (upstream outage, INC-42)` |"));
+    assert_eq!(checker.run_rule(&disabled).status, Status::Skipped);
+
+    let enabled = Rule { skip_reason: Some("stale".to_string()), ..rule("Status") };
+    let warnings = config_warnings(&Config { rules: vec![enabled], ..Default::default() });
+    assert_eq!(warnings[0].message, "skip_reason is ignored because the rule is enabled");
+}
+
+#[test]
+fn test_profiles_override_rules() {
+    use crate::cli::CliOptions;
+    use crate::Severity;
+
+    let dir = write_temp_files("profiles", &[
+        ("dev.json", r#"{"replicas": 1}"#),
+        ("prod.json", r#"{"replicas": 3}"#),
+        ("rules.json", r#"{
+            "vars": {"env": "dev"},
+            "profiles": {
+                "prod": {
+                    "vars": {"env": "prod"},
+                    "rules": {
+                        "Replicas": {"severity": "error", "check": {"value": 3}},
+                        "Debug off": {"enabled": false}
+                    }
+                }
+            },
+            "rules": [
+                {"name": "Replicas", "json_file": "DIR/${env}.json", "jsonpath": "$.replicas", "check": {"type": "equals", "value": 1}, "severity": "warning"},
+                {"name": "Debug off", "json_inline": {"debug": true}, "jsonpath": "$.debug", "check": {"type": "equals", "value": false}}
+            ]
+        }"#),
+    ]);
+    let path = dir.join("rules.json");
+    let content = std::fs::read_to_string(&path).unwrap().replace("DIR", &dir.to_string_lossy());
+    std::fs::write(&path, content).unwrap();
+    let path = path.to_string_lossy().into_owned();
+    let vars = std::collections::HashMap::new();
+
+    let dev = Config::from_file(&path).unwrap();
+    assert_eq!(dev.rules[0].severity, Severity::Warning);
+    let results = JsonChecker::from_config(dev).run().unwrap();
+    assert!(results[0].passed && !results[1].passed);
+
+    let prod = Config::from_file_with_profile(&path, &vars, Some("prod")).unwrap();
+    assert_eq!(prod.rules[0].json_file.as_deref(), Some(&*dir.join("prod.json").to_string_lossy()));
+    assert_eq!(prod.rules[0].severity, Severity::Error);
+    assert!(matches!(&prod.rules[0].check, CheckRule::Equals { value, .. } if value == &serde_json::json!(3)));
+    let results = JsonChecker::from_config(prod).run().unwrap();
+    assert!(results[0].passed && results[1].status == crate::Status::Skipped);
+
+    let err = Config::from_file_with_profile(&path, &vars, Some("prd")).unwrap_err().to_string();
+    assert!(err.contains("No profile named 'prd', did you mean 'prod'?"), "{}", err);
+
+    let options = CliOptions::parse(&["--profile".to_string(), "prod".to_string()]).unwrap();
+    assert_eq!(options.profile.as_deref(), Some("prod"));
+}
+
+#[test]
+fn test_check_values_from_env() {
+    let rules = serde_json::json!({"rules": [{
+        "name": "Published version",
+        "json_inline": {"version": "2.4.0", "replicas": 3},
+        "jsonpath": "$.version",
+        "check": {"type": "equals", "value_from_env": {"name": "JSON_CHECKER_UNSET_VERSION", "default": "2.4.0"}},
+    }]});
+    let dir = write_temp_files("value_from_env", &[
+        ("rules.json", &rules.to_string()),
+        ("bad.json", r#"{"rules": [{"name": "x", "jsonpath": "$", "check": {"type": "equals", "value_from_env": "JSON_CHECKER_UNSET_VERSION"}}]}"#),
+    ]);
+
+    let config = Config::from_file(&dir.join("rules.json").to_string_lossy()).unwrap();
+    assert!(matches!(&config.rules[0].check, CheckRule::Equals { value, .. } if value == "2.4.0"));
+    assert!(JsonChecker::from_config(config).run().unwrap()[0].passed);
+
+    let err = Config::from_file(&dir.join("bad.json").to_string_lossy()).unwrap_err().to_string();
+    assert!(err.contains("Undefined environment variable 'JSON_CHECKER_UNSET_VERSION'"), "{}", err);
+}
+
+#[test]
+fn test_evaluate_without_a_config() {
+    use crate::diff::Difference;
+    use crate::messages::Message;
+    use crate::rules::{AggregateFunction, Comparison};
+
+    let check = CheckRule::Aggregate { function: AggregateFunction::Sum, operator: Comparison::Lt, value: 10.0 };
+    let outcome = crate::evaluate(&serde_json::json!([4, 8]), &check);
+    assert!(!outcome.passed);
+    assert_eq!(outcome.reasons, vec![Message::Aggregate { function: "sum".to_string(), value: 12.0 }]);
+
+    let expected = CheckRule::Equals { value: serde_json::json!({"plan": "pro", "seats": 5}), tolerance: None };
+    let outcome = crate::evaluate(&serde_json::json!({"plan": "pro", "seats": 3}), &expected);
+    assert_eq!(outcome.invalid, vec![("$".to_string(), serde_json::json!({"plan": "pro", "seats": 3}))]);
+    assert!(matches!(&outcome.differences[..], [Difference::Changed { path, .. }] if path == "$.seats"));
+}
+
+#[test]
+fn test_checker_is_shared_across_threads() {
+    use crate::Rule;
+    use std::sync::Arc;
+
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<JsonChecker>();
+
+    let rule = Rule {
+        name: "Codes".to_string(),
+        json_inline: Some(serde_json::json!({"codes": ["AB-1", "CD-2", "bad"]})),
+        jsonpath: "$.codes[*]".to_string(),
+        check: CheckRule::Regex { pattern: r"^[A-Z]{2}-\d$".to_string() },
+        ..Default::default()
+    };
+    let checker = Arc::new(JsonChecker::from_config(Config { rules: vec![rule], ..Default::default() }));
+    let workers: Vec<_> = (0..4)
+        .map(|_| {
+            let checker = Arc::clone(&checker);
+            std::thread::spawn(move || checker.run().unwrap())
+        })
+        .collect();
+    for worker in workers {
+        let results = worker.join().unwrap();
+        assert!(results[0].passed);
+    }
+
+    let clone = (*checker).clone();
+    assert!(clone.run_rule(&clone.config().rules[0]).passed);
+}
+
+#[test]
+fn test_max_failures_shown() {
+    use crate::messages::Message;
+    use crate::Rule;
+
+    let items: Vec<&str> = (0..1000).map(|i| if i < 10 { "x" } else { "" }).collect();
+    let rule = Rule {
+        name: "Small".to_string(),
+        json_inline: Some(serde_json::json!({"items": items})),
+        jsonpath: "$.items[*]".to_string(),
+        check: CheckRule::NonEmpty,
+        ..Default::default()
+    };
+    let config = Config { rules: vec![rule], ..Default::default() };
+    let checker = JsonChecker::builder().config(config).max_failures_shown(3).build();
+    let result = &checker.run().unwrap()[0];
+
+    assert!(!result.passed);
+    assert_eq!(result.invalid_positions, ["$.items[10]", "$.items[11]", "$.items[12]"]);
+    assert!(result.lines.contains(&Message::Truncated { total: 990, shown: 3 }));
+    assert!(result.message.contains("990 invalid nodes; showing the first 3"));
+
+    use crate::cli::CliOptions;
+    let options = CliOptions::parse(&["--max-failures-shown".to_string(), "3".to_string()]).unwrap();
+    assert_eq!(options.max_failures_shown, Some(3));
+    assert!(CliOptions::parse(&["--max-failures-shown".to_string(), "many".to_string()]).is_err());
+}
+
+#[test]
+fn test_capture_values() {
+    use crate::capture::CaptureValues;
+    use crate::Rule;
+
+    let big: Vec<i64> = (0..1000).collect();
+    let rule = |capture_values| Rule {
+        name: "Small arrays".to_string(),
+        json_inline: Some(serde_json::json!({"a": big, "b": [1, 2]})),
+        jsonpath: "$.*".to_string(),
+        check: CheckRule::Empty,
+        capture_values,
+        ..Default::default()
+    };
+    let config = Config { rules: vec![rule(None), rule(Some(CaptureValues::Full))], ..Default::default() };
+    let results = JsonChecker::from_config(config).run().unwrap();
+
+    assert_eq!(results[0].invalid_positions, ["$.a", "$.b"]);
+    assert_eq!(results[0].values_found[0]["type"], "array");
+    assert_eq!(results[0].values_found[0]["length"], 1000);
+    assert_eq!(results[0].values_found[1], serde_json::json!([1, 2]));
+    assert!(results[1].values_found[0] == serde_json::json!(big));
+}
+
+#[test]
+fn test_suggested_fixes() {
+    use crate::cli::Cli;
+    use crate::Rule;
+
+    let dir = write_temp_files("fixes", &[("app.json", r#"{"env": "Production", "port": "8080", "name": "api"}"#)]);
+    let file = dir.join("app.json").to_string_lossy().into_owned();
+    let rule = |name: &str, jsonpath: &str, check| Rule {
+        name: name.to_string(),
+        json_file: Some(file.clone()),
+        jsonpath: jsonpath.to_string(),
+        check,
+        ..Default::default()
+    };
+    let config = Config {
+        rules: vec![
+            rule("Env", "$.env", CheckRule::ContainedBy { value: serde_json::json!(["staging", "production"]) }),
+            rule("Port", "$.port", CheckRule::GreaterThan { value: 0.into() }),
+            rule("Name", "$.name", CheckRule::Regex { pattern: "^svc-".to_string() }),
+        ],
+        ..Default::default()
+    };
+    let results = JsonChecker::from_config(config).run().unwrap();
+    let fixes: Vec<_> = results.iter().map(|result| serde_json::to_value(&result.fixes).unwrap()).collect();
+    assert_eq!(fixes[0], serde_json::json!([{"op": "replace", "path": "/env", "value": "production"}]));
+    assert_eq!(fixes[1], serde_json::json!([{"op": "replace", "path": "/port", "value": 8080}]));
+    assert_eq!(fixes[2], serde_json::json!([]));
+
+    assert_eq!(Cli::apply_fixes(&results, &mut "n\n".as_bytes()).unwrap(), 0);
+    assert_eq!(Cli::apply_fixes(&results, &mut "y\n".as_bytes()).unwrap(), 1);
+    // Only the fixed values change; key order and layout are kept
+    assert_eq!(std::fs::read_to_string(&file).unwrap(), r#"{"env": "production", "port": 8080, "name": "api"}"#);
+
+    std::fs::write(&file, "{\"env\": \"Production\", // set by deploy\n \"port\": 8080, \"name\": \"api\"}").unwrap();
+    assert_eq!(Cli::apply_fixes(&results, &mut "y\n".as_bytes()).unwrap(), 0);
+    assert!(std::fs::read_to_string(&file).unwrap().contains("// set by deploy"));
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_parser_reports_duplicate_keys() {
+    use crate::parser::ParserOptions;
+    use crate::Rule;
+
+    let dir = write_temp_files("duplicate-keys", &[("user.json", r#"{"id": 1, "name": "Ada", "id": 2, "roles": [{"name": "admin", "name": "owner"}]}"#)]);
+    let file = dir.join("user.json").to_string_lossy().into_owned();
+    let rule = |check: &str| Rule {
+        name: "User".to_string(),
+        json_file: Some(file.clone()),
+        jsonpath: "$.id".to_string(),
+        check: serde_json::from_str(check).unwrap(),
+        ..Default::default()
+    };
+
+    // serde_json keeps the last value, so the document loads without complaint
+    let lenient = JsonChecker::from_config(Config::default());
+    assert!(lenient.run_rule(&rule(r#"{"type": "equals", "value": 2}"#)).passed);
+
+    let strict = JsonChecker::from_config(Config {
+        parser: Some(ParserOptions { detect_duplicate_keys: true, ..Default::default() }),
+        ..Default::default()
+    });
+    let result = strict.run_rule(&rule(r#"{"type": "equals", "value": 2}"#));
+    assert!(!result.passed);
+    assert_eq!(result.invalid_positions, vec!["$.id", "$.roles[0].name"]);
+    assert_eq!(result.values_found, vec![serde_json::json!(2), serde_json::json!("owner")]);
+    assert_eq!(result.locations[0].map(|location| location.line), Some(1));
+    assert!(result.message.contains("2 object key(s) appear more than once"));
+
+    // A rule's own options override the config's
+    let relaxed = Rule { parser: Some(ParserOptions::default()), ..rule(r#"{"type": "equals", "value": 2}"#) };
+    assert!(strict.run_rule(&relaxed).passed);
+
+    let result = strict.run_rule(&rule(r#"{"type": "equals", "value": 1}"#));
+    assert_eq!(result.invalid_positions, vec!["$.id", "$.id", "$.roles[0].name"]);
+    assert_eq!(result.lines.first(), Some(&crate::messages::Message::Failed {
+        rule: "User".to_string(),
+        selector_kind: "JSONPath".to_string(),
+        selector: "$.id".to_string(),
+    }));
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[test]
+fn test_parser_options_relax_syntax() {
+    use crate::parser::ParserOptions;
+    use crate::Rule;
+
+    let text = "{\n  // seconds\n  \"timeout\": 30,\n  \"retries\": -1\n}\n<!-- generated -->\n";
+    let dir = write_temp_files("parser-options", &[("settings.json", text)]);
+    let rule = |parser: Option<ParserOptions>| Rule {
+        name: "Retries".to_string(),
+        json_file: Some(dir.join("settings.json").to_string_lossy().into_owned()),
+        jsonpath: "$.retries".to_string(),
+        check: serde_json::from_str(r#"{"type": "greater_than_or_equal", "value": 0}"#).unwrap(),
+        parser,
+        ..Default::default()
+    };
+    let checker = create_test_checker();
+
+    let result = checker.run_rule(&rule(None));
+    assert_eq!(result.status, crate::config::Status::Error);
+    assert!(result.message.contains("at line 2 column 3\n  2 |   // seconds\n    |   ^"), "{}", result.message);
+
+    let relaxed = ParserOptions { allow_comments: true, allow_trailing_content: true, ..Default::default() };
+    let result = checker.run_rule(&rule(Some(relaxed)));
+    assert!(!result.passed);
+    assert_eq!(result.invalid_positions, vec!["$.retries"]);
+    assert_eq!(result.location(0).map(|location| location.line), Some(4));
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[test]
+fn test_jsonc_and_json5_configs_and_documents() {
+    let dir = write_temp_files("jsonc", &[
+        ("settings.jsonc", "{\n  // 4 spaces\n  \"tabSize\": 2,\n}\n"),
+        ("limits.json5", "{maxUsers: 100, owner: 'ops',}"),
+        ("rules.jsonc", r#"{
+  "rules": [
+    // Trailing commas are fine here too
+    { "name": "Tab size", "json_file": "settings.jsonc", "jsonpath": "$.tabSize", "check": { "type": "equals", "value": 4 } },
+  ],
+}"#),
+        ("rules.json5", r#"{
+  rules: [
+    { name: 'Max users', json_file: 'limits.json5', jsonpath: '$.maxUsers', check: { type: 'less_than', value: 1000 } },
+  ],
+}"#),
+    ]);
+    let path = |name: &str| dir.join(name).to_string_lossy().into_owned();
+
+    let checker = create_test_checker();
+    let mut rule = Config::from_file(&path("rules.jsonc")).unwrap().rules.remove(0);
+    rule.json_file = Some(path("settings.jsonc"));
+    let result = checker.run_rule(&rule);
+    assert!(!result.passed);
+    assert_eq!(result.values_found, vec![serde_json::json!(2)]);
+    assert_eq!(result.location(0).map(|location| (location.line, location.column)), Some((3, 14)));
+
+    let mut rule = Config::from_file(&path("rules.json5")).unwrap().rules.remove(0);
+    rule.json_file = Some(path("limits.json5"));
+    assert!(checker.run_rule(&rule).passed);
+
+    std::fs::write(dir.join("broken.json5"), "{rules: [}").unwrap();
+    let error = Config::from_file(&path("broken.json5")).unwrap_err();
+    assert!(error.to_string().contains("Failed to parse config"), "{}", error);
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[test]
+fn test_protobuf_mode_reads_encoded_numbers() {
+    use crate::Rule;
+
+    let rule = |jsonpath: &str, check: &str, protobuf_mode: bool| Rule {
+        name: "Order".to_string(),
+        json_inline: Some(serde_json::json!({
+            "order": {
+                "id": "9007199254740993",
+                "status": "ORDER_STATUS_SHIPPED",
+                "quantities": ["3", "12", "40"],
+                "ttl": "86400s",
+                "createdAt": "2024-05-01T08:00:00.500Z"
+            }
+        })),
+        jsonpath: jsonpath.to_string(),
+        check: serde_json::from_str(check).unwrap(),
+        protobuf_mode,
+        ..Default::default()
+    };
+    let checker = create_test_checker();
+
+    let over = r#"{"type": "greater_than", "value": 9007199254740992}"#;
+    assert!(!checker.run_rule(&rule("$.order.id", over, false)).passed);
+    assert!(checker.run_rule(&rule("$.order.id", over, true)).passed);
+    assert!(checker.run_rule(&rule("$.order.quantities", r#"{"type": "sorted"}"#, true)).passed);
+    assert!(!checker.run_rule(&rule("$.order.quantities", r#"{"type": "sorted"}"#, false)).passed);
+    assert!(checker.run_rule(&rule("$.order.ttl", r#"{"type": "lte", "value": 86400}"#, true)).passed);
+    assert!(checker.run_rule(&rule("$.order.createdAt", r#"{"type": "gte", "value": 1714550400.5}"#, true)).passed);
+
+    // Enum names and checks on strings see the values as written
+    assert!(checker.run_rule(&rule("$.order.status", r#"{"type": "regex", "pattern": "^ORDER_STATUS_"}"#, true)).passed);
+    let result = checker.run_rule(&rule("$.order.id", r#"{"type": "regex", "pattern": "^9"}"#, true));
+    assert!(result.passed);
+
+    let result = checker.run_rule(&rule("$.order.ttl", r#"{"type": "less_than", "value": 3600}"#, true));
+    assert_eq!(result.invalid_positions, vec!["$.order.ttl"]);
+    assert_eq!(result.values_found, vec![serde_json::json!(86400)]);
+    assert!(result.fixes.is_empty());
+}
@@ -0,0 +1,148 @@
+//! Value transformations applied before a check
+//!
+//! A rule's `transform` list normalizes each selected value (casing,
+//! whitespace, JSON or numbers embedded in strings) so checks can stay
+//! simple. Transforms map one value to one value, so failures are still
+//! reported at the path of the node they came from.
+//!
+//! `decode_embedded_json` instead rewrites the whole document before
+//! selection, so JSONPaths can reach into stringified JSON.
+
+use anyhow::{anyhow, Result};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::borrow::Cow;
+
+use crate::path_finder::{push_index, push_key};
+
+/// A transformation of a selected value.
+///
+/// Transforms that don't apply to a value (e.g. `lowercase` on a number or
+/// `parse_number` on `"n/a"`) leave it unchanged, so the check sees and
+/// reports the original value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, JsonSchema, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Transform {
+    Lowercase,
+    Uppercase,
+    /// Remove leading and trailing whitespace from strings
+    Trim,
+    /// Parse a numeric string such as `"42"` or `" 1.5e3 "` into a number
+    ParseNumber,
+    /// Parse a string holding a JSON document
+    ParseJson,
+    /// Flatten nested arrays into one array
+    Flatten,
+}
+
+impl Transform {
+    /// Apply the transform, or return `None` if it doesn't apply to `value`
+    pub fn apply(&self, value: &Value) -> Option<Value> {
+        match (self, value) {
+            (Transform::Lowercase, Value::String(s)) => Some(Value::String(s.to_lowercase())),
+            (Transform::Uppercase, Value::String(s)) => Some(Value::String(s.to_uppercase())),
+            (Transform::Trim, Value::String(s)) => Some(Value::String(s.trim().to_string())),
+            (Transform::ParseNumber, Value::String(s)) => {
+                serde_json::from_str::<serde_json::Number>(s.trim()).ok().map(Value::Number)
+            }
+            (Transform::ParseJson, Value::String(s)) => serde_json::from_str(s).ok(),
+            (Transform::Flatten, Value::Array(items)) => {
+                let mut flat = Vec::new();
+                flatten_into(items, &mut flat);
+                Some(Value::Array(flat))
+            }
+            _ => None,
+        }
+    }
+}
+
+fn flatten_into(items: &[Value], flat: &mut Vec<Value>) {
+    for item in items {
+        match item {
+            Value::Array(nested) => flatten_into(nested, flat),
+            _ => flat.push(item.clone()),
+        }
+    }
+}
+
+/// Apply `transforms` in order to each value; values are only copied when
+/// a transform changes them
+pub fn apply_all<'a>(transforms: &[Transform], values: &[&'a Value]) -> Vec<Cow<'a, Value>> {
+    values
+        .iter()
+        .map(|&value| {
+            transforms.iter().fold(Cow::Borrowed(value), |value, transform| {
+                transform.apply(&value).map_or(value, Cow::Owned)
+            })
+        })
+        .collect()
+}
+
+/// Replace every string in `value` that holds a JSON object or array with the
+/// parsed value, recursively, so JSONPaths can reach into it.
+///
+/// Only strings starting with `{` or `[` are decoded; one that doesn't parse
+/// is an error naming its path, which starts from `path`.
+pub fn decode_embedded_json(value: &mut Value, path: &str) -> Result<()> {
+    decode_at(value, &mut path.to_string())
+}
+
+fn decode_at(value: &mut Value, path: &mut String) -> Result<()> {
+    let len = path.len();
+    match value {
+        Value::String(s) if s.trim_start().starts_with(['{', '[']) => {
+            *value = serde_json::from_str(s).map_err(|e| anyhow!("Invalid embedded JSON at {}: {}", path, e))?;
+            decode_at(value, path)?;
+        }
+        Value::Object(map) => {
+            for (key, child) in map {
+                push_key(path, key);
+                decode_at(child, path)?;
+                path.truncate(len);
+            }
+        }
+        Value::Array(items) => {
+            for (index, child) in items.iter_mut().enumerate() {
+                push_index(path, index);
+                decode_at(child, path)?;
+                path.truncate(len);
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_transforms() {
+        let payload = json!(" {\"Role\": \"ADMIN\"} ");
+        let steps = [Transform::Trim, Transform::ParseJson];
+        assert_eq!(*apply_all(&steps, &[&payload])[0], json!({"Role": "ADMIN"}));
+
+        let values = [json!(" 1.5e3 "), json!("n/a"), json!(7)];
+        let parsed = apply_all(&[Transform::ParseNumber], &[&values[0], &values[1], &values[2]]);
+        assert_eq!(parsed[0].as_f64(), Some(1500.0));
+        assert!(matches!(parsed[1], Cow::Borrowed(_)));
+        assert_eq!(*parsed[2], json!(7));
+
+        assert_eq!(Transform::Flatten.apply(&json!([1, [2, [3]], []])), Some(json!([1, 2, 3])));
+        assert_eq!(Transform::Lowercase.apply(&json!("MiXeD")), Some(json!("mixed")));
+    }
+
+    #[test]
+    fn test_decode_embedded_json() {
+        let mut doc = json!({"id": "42", "payload": "{\"a\": 1, \"inner\": \"[true]\"}"});
+        decode_embedded_json(&mut doc, "$").unwrap();
+        assert_eq!(doc, json!({"id": "42", "payload": {"a": 1, "inner": [true]}}));
+
+        let mut doc = json!({"events": [{"payload": "{\"a\": }"}]});
+        let err = decode_embedded_json(&mut doc, "$").unwrap_err().to_string();
+        assert!(err.starts_with("Invalid embedded JSON at $.events[0].payload: expected value"), "{}", err);
+    }
+}
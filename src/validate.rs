@@ -0,0 +1,394 @@
+//! Static validation of rule configurations
+//!
+//! Checks everything that can be verified without evaluating rules, so
+//! mistakes like a bad regex surface before a run instead of in the middle of it.
+//! Settings that are allowed but have no effect are reported as warnings.
+
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use crate::config::{Config, Rule};
+use crate::expr::Expr;
+use crate::format::DocumentFormat;
+use crate::jq;
+use crate::jsonpath;
+use crate::rules::CheckRule;
+use crate::schedule::{self, DependencyCycle};
+use crate::source::{expand_glob, is_glob, local_path, split_uri};
+
+/// A problem found in a configuration
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigIssue {
+    /// Name of the rule the problem belongs to
+    pub rule_name: String,
+    pub message: String,
+}
+
+impl fmt::Display for ConfigIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Rule '{}': {}", self.rule_name, self.message)
+    }
+}
+
+/// A setting that is valid but most likely not doing what was intended
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigWarning {
+    /// Name of the rule the warning belongs to, if it belongs to one
+    pub rule_name: Option<String>,
+    pub message: String,
+}
+
+impl fmt::Display for ConfigWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.rule_name {
+            Some(name) => write!(f, "Rule '{}': {}", name, self.message),
+            None => f.write_str(&self.message),
+        }
+    }
+}
+
+/// Validate a configuration without evaluating any rule.
+///
+/// Verifies that rule names are unique, JSONPaths and regexes compile,
+/// referenced JSON files exist and rule dependencies are known and acyclic.
+/// Returns every issue found.
+pub fn validate_config(config: &Config) -> Vec<ConfigIssue> {
+    let mut issues = Vec::new();
+    let mut names = HashSet::new();
+
+    for rule in &config.rules {
+        if !names.insert(rule.name.as_str()) {
+            issues.push(issue(rule, "duplicate rule name".to_string()));
+        }
+        validate_rule(config, rule, &mut issues);
+        if rule.applies_to.is_some() && config.scan.is_none() {
+            issues.push(issue(rule, "uses applies_to but the config has no scan section".to_string()));
+        }
+    }
+
+    let mut unknown_dependency = false;
+    for rule in &config.rules {
+        for name in schedule::prerequisites(rule) {
+            if !names.contains(name) {
+                unknown_dependency = true;
+                issues.push(issue(rule, format!("depends on unknown rule '{}'", name)));
+            }
+        }
+    }
+    if !unknown_dependency
+        && let Err(e) = schedule::execution_order(&config.rules)
+        && let Some(DependencyCycle(cycle)) = e.downcast_ref::<DependencyCycle>()
+    {
+        issues.push(ConfigIssue {
+            rule_name: cycle[0].clone(),
+            message: format!("dependency cycle {}", cycle.join(" -> ")),
+        });
+    }
+
+    issues
+}
+
+/// Find settings in a configuration that have no effect: variables no config
+/// file refers to, and rule options that don't apply to the rule's document.
+pub fn config_warnings(config: &Config) -> Vec<ConfigWarning> {
+    let mut warnings = Vec::new();
+
+    // Substitution has already happened, so look for references in the files themselves
+    let sources: Vec<String> = config.files.iter().filter_map(|path| fs::read_to_string(path).ok()).collect();
+    if !sources.is_empty() {
+        let mut vars: Vec<&String> = config.vars.keys().collect();
+        vars.sort();
+        for name in vars {
+            let reference = format!("${{{}}}", name);
+            if !sources.iter().any(|source| source.contains(&reference)) {
+                warnings.push(ConfigWarning { rule_name: None, message: format!("variable '{}' is never used", name) });
+            }
+        }
+    }
+
+    for rule in &config.rules {
+        let mut warn = |message: &str| {
+            warnings.push(ConfigWarning { rule_name: Some(rule.name.clone()), message: message.to_string() });
+        };
+        let format = rule.format.or_else(|| rule.json_file.as_deref().map(DocumentFormat::detect));
+        if rule.csv.is_some() && format != Some(DocumentFormat::Csv) {
+            warn("csv options are ignored because the document isn't CSV");
+        }
+        if rule.parser.is_some_and(|parser| !parser.is_lenient()) && (rule.stream || format.is_some_and(|f| !matches!(f, DocumentFormat::Json | DocumentFormat::Jsonc | DocumentFormat::Json5))) {
+            warn("parser options are ignored because the document isn't parsed as a whole JSON document");
+        }
+        if rule.retry.is_some() && rule.json_file.is_none() && rule.applies_to.is_none() {
+            warn("retry is ignored because the rule doesn't load a json_file");
+        }
+        if rule.jsonpath_engine.is_some()
+            && (rule.json_pointer.is_some() || rule.selector.is_some())
+            && !matches!(rule.run_if, Some(schedule::RunCondition::PathExists(_)))
+        {
+            warn("jsonpath_engine is ignored because the rule doesn't use a JSONPath");
+        }
+        if rule.protobuf_mode && !rule.check.compares_numbers() {
+            warn("protobuf_mode is ignored because the check doesn't compare numbers");
+        }
+        if rule.skip_reason.is_some() && rule.enabled != Some(false) {
+            warn("skip_reason is ignored because the rule is enabled");
+        }
+    }
+
+    warnings
+}
+
+fn validate_rule(config: &Config, rule: &Rule, issues: &mut Vec<ConfigIssue>) {
+    for (path, engine) in config.jsonpaths_of(rule) {
+        if let Err(e) = jsonpath::validate(&path, engine) {
+            issues.push(issue(rule, e.to_string()));
+        }
+    }
+    if let Some(selector) = &rule.selector {
+        if !rule.jsonpath.is_empty() || rule.json_pointer.is_some() {
+            issues.push(issue(rule, "sets a selector as well as jsonpath or json_pointer".to_string()));
+        }
+        if let Err(e) = jq::validate(&selector.expr) {
+            issues.push(issue(rule, e.to_string()));
+        }
+        if rule.stream {
+            issues.push(issue(rule, "selector rules cannot be streamed".to_string()));
+        }
+    }
+    if let Some(pointer) = &rule.json_pointer {
+        if !rule.jsonpath.is_empty() {
+            issues.push(issue(rule, "sets both jsonpath and json_pointer".to_string()));
+        }
+        if let Err(reason) = check_json_pointer(pointer) {
+            issues.push(issue(rule, format!("invalid JSON Pointer '{}': {}", pointer, reason)));
+        }
+        if rule.stream {
+            issues.push(issue(rule, "json_pointer rules cannot be streamed".to_string()));
+        }
+    }
+
+    if let Some(csv) = &rule.csv
+        && !csv.delimiter.is_ascii()
+    {
+        issues.push(issue(rule, format!("CSV delimiter '{}' is not a single ASCII character", csv.delimiter)));
+    }
+
+    if let Some(policy) = config.retry_for(rule) {
+        if policy.attempts == 0 {
+            issues.push(issue(rule, "retry attempts must be at least 1".to_string()));
+        }
+        if policy.timeout_ms == Some(0) {
+            issues.push(issue(rule, "retry timeout_ms must be greater than 0".to_string()));
+        }
+    }
+    if config.timeout_for(rule).is_some_and(|limit| limit.is_zero()) {
+        issues.push(issue(rule, "timeout_ms must be greater than 0".to_string()));
+    }
+
+    if rule.response.is_some() {
+        let scheme = rule.json_file.as_deref().map(|uri| split_uri(uri).0);
+        if !matches!(scheme, Some("http" | "https")) {
+            issues.push(issue(rule, "response assertions need an http:// or https:// json_file".to_string()));
+        }
+        if rule.stream {
+            issues.push(issue(rule, "response assertions can't be combined with stream".to_string()));
+        }
+    }
+
+    if rule.source.is_some() {
+        if rule.json_file.is_some() || rule.json_inline.is_some() || rule.applies_to.is_some() {
+            issues.push(issue(rule, "source can't be combined with json_file, json_inline or applies_to".to_string()));
+        }
+        if rule.stream {
+            issues.push(issue(rule, "source rules cannot be streamed".to_string()));
+        }
+    }
+
+    match (&rule.json_file, &rule.json_inline) {
+        (Some(_), Some(_)) => {
+            issues.push(issue(rule, "sets both json_file and json_inline".to_string()));
+        }
+        (None, None) if rule.applies_to.is_none() && rule.source.is_none() => {
+            issues.push(issue(rule, "needs json_file, json_inline or source".to_string()));
+        }
+        (Some(_), None) | (None, Some(_)) if rule.applies_to.is_some() => {
+            issues.push(issue(rule, "applies_to can't be combined with json_file or json_inline".to_string()));
+        }
+        (Some(uri), None) => {
+            if let Some(path) = local_path(uri) {
+                if is_glob(path) {
+                    if expand_glob(path).map_or(true, |files| files.is_empty()) {
+                        issues.push(issue(rule, format!("no JSON files match '{}'", path)));
+                    }
+                } else if !Path::new(path).exists() {
+                    issues.push(issue(rule, format!("JSON file not found: {}", path)));
+                }
+            }
+        }
+        _ => {}
+    }
+
+    match &rule.check {
+        CheckRule::Regex { pattern } => {
+            if let Err(e) = regex::Regex::new(pattern) {
+                issues.push(issue(rule, format!("invalid regex '{}': {}", pattern, e)));
+            }
+        }
+        CheckRule::KeyFormat { convention, pattern } => match convention.pattern(pattern.as_deref()) {
+            Ok(pattern) => {
+                if let Err(e) = regex::Regex::new(pattern) {
+                    issues.push(issue(rule, format!("invalid regex '{}': {}", pattern, e)));
+                }
+            }
+            Err(e) => issues.push(issue(rule, e.to_string())),
+        },
+        CheckRule::RefIntegrity { target_file, .. } => {
+            if let Some(path) = local_path(target_file)
+                && !Path::new(path).exists()
+            {
+                issues.push(issue(rule, format!("reference target file not found: {}", path)));
+            }
+        }
+        CheckRule::Diff { baseline_file: file, .. } | CheckRule::MatchesDocument { file, .. } => {
+            if let Some(path) = local_path(file)
+                && !Path::new(path).exists()
+            {
+                issues.push(issue(rule, format!("reference document not found: {}", path)));
+            }
+        }
+        CheckRule::Equals { tolerance: Some(epsilon), .. } | CheckRule::ApproxEquals { epsilon, .. }
+            if !(epsilon.is_finite() && *epsilon >= 0.0) =>
+        {
+            issues.push(issue(rule, format!("tolerance must be a number of at least 0, found {}", epsilon)));
+        }
+        CheckRule::MultipleOf { value } if !(value.is_finite() && *value > 0.0) => {
+            issues.push(issue(rule, format!("multiple_of value must be greater than 0, found {}", value)));
+        }
+        CheckRule::MatchCount { min: Some(min), max: Some(max) } if min > max => {
+            issues.push(issue(rule, format!("match_count min {} is greater than max {}", min, max)));
+        }
+        CheckRule::Expr { expr } => {
+            if let Err(e) = Expr::compile(expr) {
+                issues.push(issue(rule, e.to_string()));
+            }
+        }
+        CheckRule::Template { name, .. } => {
+            issues.push(issue(rule, format!("check template '{}' was not expanded", name)));
+        }
+        CheckRule::Command { program, timeout_ms, .. } => {
+            if program.trim().is_empty() {
+                issues.push(issue(rule, "command check has an empty program".to_string()));
+            }
+            if *timeout_ms == Some(0) {
+                issues.push(issue(rule, "command check timeout_ms must be greater than 0".to_string()));
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Check RFC 6901 syntax: empty, or `/`-separated tokens where `~` only
+/// appears as `~0` or `~1`
+fn check_json_pointer(pointer: &str) -> Result<(), &'static str> {
+    if !pointer.is_empty() && !pointer.starts_with('/') {
+        return Err("must be empty or start with '/'");
+    }
+    let mut chars = pointer.chars();
+    while let Some(c) = chars.next() {
+        if c == '~' && !matches!(chars.next(), Some('0' | '1')) {
+            return Err("'~' must be escaped as '~0'");
+        }
+    }
+    Ok(())
+}
+
+fn issue(rule: &Rule, message: String) -> ConfigIssue {
+    ConfigIssue {
+        rule_name: rule.name.clone(),
+        message,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(name: &str, jsonpath: &str, check: CheckRule) -> Rule {
+        Rule {
+            name: name.to_string(),
+            json_file: Some("Cargo.toml".to_string()),
+            jsonpath: jsonpath.to_string(),
+            check,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_valid_config_has_no_issues() {
+        let config = Config {
+            rules: vec![rule("ok", "$.users[*].email", CheckRule::Regex { pattern: "^a+$".to_string() })],
+            ..Default::default()
+        };
+        assert!(validate_config(&config).is_empty());
+    }
+
+    #[test]
+    fn test_reports_every_problem() {
+        let mut missing_file = rule("missing", "$", CheckRule::NonEmpty);
+        missing_file.json_file = Some("does/not/exist.json".to_string());
+        let config = Config {
+            rules: vec![
+                rule("bad regex", "$", CheckRule::Regex { pattern: "(".to_string() }),
+                rule("bad path", "$.users[", CheckRule::NonEmpty),
+                rule("bad path", "$", CheckRule::NonEmpty),
+                missing_file,
+            ],
+            ..Default::default()
+        };
+
+        let issues = validate_config(&config);
+        let messages: Vec<String> = issues.iter().map(|i| i.to_string()).collect();
+        assert_eq!(issues.len(), 4, "{:?}", messages);
+        assert!(messages[0].starts_with("Rule 'bad regex': invalid regex"));
+        assert!(messages[1].starts_with("Rule 'bad path': invalid JSONPath"));
+        assert_eq!(messages[2], "Rule 'bad path': duplicate rule name");
+        assert!(messages[3].contains("JSON file not found"));
+    }
+
+    #[test]
+    fn test_warns_about_ignored_settings() {
+        use crate::format::CsvOptions;
+        use crate::retry::RetryPolicy;
+
+        let config = Config {
+            rules: vec![
+                Rule { csv: Some(CsvOptions::default()), ..rule("csv", "$", CheckRule::NonEmpty) },
+                Rule {
+                    json_file: None,
+                    json_inline: Some(serde_json::json!([])),
+                    retry: Some(RetryPolicy::default()),
+                    ..rule("inline", "$", CheckRule::NonEmpty)
+                },
+            ],
+            ..Default::default()
+        };
+
+        let warnings: Vec<String> = config_warnings(&config).iter().map(|w| w.to_string()).collect();
+        assert_eq!(
+            warnings,
+            [
+                "Rule 'csv': csv options are ignored because the document isn't CSV",
+                "Rule 'inline': retry is ignored because the rule doesn't load a json_file",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_json_pointer_syntax() {
+        assert!(check_json_pointer("").is_ok());
+        assert!(check_json_pointer("/spec/a~1b/0").is_ok());
+        assert!(check_json_pointer("spec/containers").is_err());
+        assert!(check_json_pointer("/a~2").is_err());
+    }
+}
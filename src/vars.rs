@@ -0,0 +1,266 @@
+//! `${NAME}` variable substitution for rule configurations
+//!
+//! Variables are looked up in the user-defined map first and fall back to
+//! environment variables. `$${NAME}` escapes a literal `${NAME}`.
+//!
+//! Check values can also come from the environment with their type intact:
+//! `"value_from_env": "EXPECTED_VERSION"` stands for `"value"` set to the
+//! variable's text, and `{"name": "MIN_REPLICAS", "type": "integer"}`
+//! converts it first.
+
+use anyhow::{anyhow, bail, Context, Result};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Key that takes a check's `value` from an environment variable
+pub const VALUE_FROM_ENV: &str = "value_from_env";
+
+/// An environment variable a check value is read from
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(untagged)]
+pub(crate) enum EnvValue {
+    Name(String),
+    Typed(TypedEnvValue),
+}
+
+/// An environment variable converted to a type, with a fallback
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct TypedEnvValue {
+    name: String,
+    #[serde(default, rename = "type")]
+    kind: EnvType,
+    /// Used when the variable isn't set
+    #[serde(default)]
+    default: Option<Value>,
+}
+
+/// Type an environment variable's text is converted to
+#[derive(Debug, Default, Clone, Copy, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum EnvType {
+    #[default]
+    String,
+    Integer,
+    Number,
+    Boolean,
+    /// Parse the text as a JSON document
+    Json,
+}
+
+impl EnvType {
+    fn description(self) -> &'static str {
+        match self {
+            EnvType::String => "a string",
+            EnvType::Integer => "an integer",
+            EnvType::Number => "a number",
+            EnvType::Boolean => "a boolean",
+            EnvType::Json => "valid JSON",
+        }
+    }
+}
+
+/// Replace every `value_from_env` in a raw config with the `value` it
+/// stands for; embedded `json_inline` documents are left alone
+pub fn resolve_env_values(config: &mut Value) -> Result<()> {
+    resolve_with(config, &|name| std::env::var(name).ok())
+}
+
+fn resolve_with(config: &mut Value, env: &dyn Fn(&str) -> Option<String>) -> Result<()> {
+    match config {
+        Value::Array(items) => items.iter_mut().try_for_each(|item| resolve_with(item, env)),
+        Value::Object(map) => {
+            if let Some(reference) = map.remove(VALUE_FROM_ENV) {
+                let value = env_value(reference, env)?;
+                map.insert("value".to_string(), value);
+            }
+            map.iter_mut()
+                .filter(|(key, _)| key.as_str() != "json_inline")
+                .try_for_each(|(_, value)| resolve_with(value, env))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn env_value(reference: Value, env: &dyn Fn(&str) -> Option<String>) -> Result<Value> {
+    let reference: EnvValue = serde_json::from_value(reference)
+        .with_context(|| format!("{} must be a variable name or {{\"name\", \"type\", \"default\"}}", VALUE_FROM_ENV))?;
+    let (name, kind, default) = match reference {
+        EnvValue::Name(name) => (name, EnvType::String, None),
+        EnvValue::Typed(TypedEnvValue { name, kind, default }) => (name, kind, default),
+    };
+    let text = match env(&name) {
+        Some(text) => text,
+        None => return default.ok_or_else(|| anyhow!("Undefined environment variable '{}'", name)),
+    };
+    let parsed = match kind {
+        EnvType::String => Some(Value::String(text.clone())),
+        EnvType::Integer => text.trim().parse::<i64>().ok().map(Value::from),
+        EnvType::Number => text.trim().parse::<f64>().ok().and_then(|n| serde_json::Number::from_f64(n).map(Value::Number)),
+        EnvType::Boolean => match text.trim() {
+            "true" | "1" => Some(Value::Bool(true)),
+            "false" | "0" => Some(Value::Bool(false)),
+            _ => None,
+        },
+        EnvType::Json => serde_json::from_str(&text).ok(),
+    };
+    parsed.ok_or_else(|| anyhow!("Environment variable '{}' = '{}' is not {}", name, text, kind.description()))
+}
+
+/// Replace every `${NAME}` reference in `input`
+pub fn substitute(input: &str, vars: &HashMap<String, String>) -> Result<String> {
+    let mut output = String::with_capacity(input.len());
+    let mut rest = input;
+
+    while let Some(start) = rest.find('$') {
+        output.push_str(&rest[..start]);
+        let after = &rest[start..];
+
+        if after.starts_with("$${") {
+            output.push('$');
+            rest = &after[2..];
+            continue;
+        }
+
+        if !after.starts_with("${") {
+            output.push('$');
+            rest = &after[1..];
+            continue;
+        }
+
+        let Some(end) = after.find('}') else {
+            bail!("Unterminated variable reference in '{}'", input);
+        };
+        let name = &after[2..end];
+        if name.is_empty() {
+            bail!("Empty variable reference in '{}'", input);
+        }
+
+        match lookup(name, vars) {
+            Some(value) => output.push_str(&value),
+            None => bail!("Undefined variable '{}'", name),
+        }
+        rest = &after[end + 1..];
+    }
+
+    output.push_str(rest);
+    Ok(output)
+}
+
+/// Names of the `${NAME}` references in `input`, without `$${NAME}` escapes
+pub fn references(input: &str) -> Vec<&str> {
+    let mut names = Vec::new();
+    let mut rest = input;
+
+    while let Some(start) = rest.find('$') {
+        let after = &rest[start..];
+        if after.starts_with("$${") {
+            rest = &after[2..];
+        } else if let Some(end) = after.strip_prefix("${").and_then(|name| name.find('}')) {
+            names.push(&after[2..end + 2]);
+            rest = &after[end + 3..];
+        } else {
+            rest = &after[1..];
+        }
+    }
+
+    names
+}
+
+/// Replace variable references in every string inside a JSON value
+pub fn substitute_value(value: &Value, vars: &HashMap<String, String>) -> Result<Value> {
+    Ok(match value {
+        Value::String(s) => Value::String(substitute(s, vars)?),
+        Value::Array(arr) => Value::Array(
+            arr.iter()
+                .map(|v| substitute_value(v, vars))
+                .collect::<Result<_>>()?,
+        ),
+        Value::Object(obj) => Value::Object(
+            obj.iter()
+                .map(|(k, v)| Ok((k.clone(), substitute_value(v, vars)?)))
+                .collect::<Result<_>>()?,
+        ),
+        other => other.clone(),
+    })
+}
+
+fn lookup(name: &str, vars: &HashMap<String, String>) -> Option<String> {
+    vars.get(name)
+        .cloned()
+        .or_else(|| std::env::var(name).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn vars() -> HashMap<String, String> {
+        HashMap::from([("DATA_DIR".to_string(), "data".to_string())])
+    }
+
+    #[test]
+    fn test_substitute_user_variable() {
+        let result = substitute("${DATA_DIR}/users.json", &vars()).unwrap();
+        assert_eq!(result, "data/users.json");
+    }
+
+    #[test]
+    fn test_jsonpath_dollar_is_untouched() {
+        let result = substitute("$.users[*].email", &vars()).unwrap();
+        assert_eq!(result, "$.users[*].email");
+        assert_eq!(substitute("$${DATA_DIR}", &vars()).unwrap(), "${DATA_DIR}");
+    }
+
+    #[test]
+    fn test_undefined_variable_errors() {
+        let err = substitute("${JSON_CHECKER_UNDEFINED_VAR}", &vars()).unwrap_err();
+        assert!(err.to_string().contains("JSON_CHECKER_UNDEFINED_VAR"));
+    }
+
+    #[test]
+    fn test_references() {
+        assert_eq!(references("${DATA_DIR}/${HOME}.json"), ["DATA_DIR", "HOME"]);
+        assert!(references("$.users[*] $${HOME} ${").is_empty());
+    }
+
+    #[test]
+    fn test_resolve_env_values() {
+        let env = |name: &str| match name {
+            "VERSION" => Some("1.2.3".to_string()),
+            "REPLICAS" => Some(" 3".to_string()),
+            _ => None,
+        };
+        let mut config = json!({"rules": [
+            {"check": {"type": "equals", "value_from_env": "VERSION"}},
+            {"check": {"type": "equals", "value_from_env": {"name": "REPLICAS", "type": "integer"}},
+             "json_inline": {"value_from_env": "kept"}},
+            {"check": {"type": "equals", "value_from_env": {"name": "MISSING", "default": false}}},
+        ]});
+        resolve_with(&mut config, &env).unwrap();
+        assert_eq!(config["rules"][0]["check"], json!({"type": "equals", "value": "1.2.3"}));
+        assert_eq!(config["rules"][1]["check"]["value"], json!(3));
+        assert_eq!(config["rules"][1]["json_inline"], json!({"value_from_env": "kept"}));
+        assert_eq!(config["rules"][2]["check"]["value"], json!(false));
+
+        let mut bad = json!({"value_from_env": {"name": "VERSION", "type": "number"}});
+        let err = resolve_with(&mut bad, &env).unwrap_err();
+        assert_eq!(err.to_string(), "Environment variable 'VERSION' = '1.2.3' is not a number");
+        assert!(resolve_with(&mut json!({"value_from_env": "MISSING"}), &env).is_err());
+
+        // A misspelt key isn't mistaken for a string variable
+        let mut typo = json!({"value_from_env": {"name": "REPLICAS", "tpye": "integer"}});
+        let err = resolve_with(&mut typo, &env).unwrap_err();
+        assert!(err.to_string().starts_with("value_from_env must be a variable name"), "{}", err);
+    }
+
+    #[test]
+    fn test_substitute_value_nested() {
+        let value = json!({"dir": ["${DATA_DIR}", 1]});
+        let result = substitute_value(&value, &vars()).unwrap();
+        assert_eq!(result, json!({"dir": ["data", 1]}));
+    }
+}
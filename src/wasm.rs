@@ -0,0 +1,54 @@
+//! JavaScript bindings for WebAssembly builds
+//!
+//! Built for `wasm32-unknown-unknown` with the `wasm` feature (e.g. with
+//! `wasm-pack build --features wasm`), the crate exports
+//! `check(configJson, documentsJson)`, so browser tooling and Node CI steps
+//! run the same rules as the CLI. Documents are passed in, keyed by the
+//! `json_file` of the rules reading them; nothing is read from disk or the
+//! network, and `include` isn't allowed.
+
+use serde_json::Value;
+use std::collections::HashMap;
+use wasm_bindgen::prelude::*;
+
+use crate::checker::JsonChecker;
+use crate::config::Config;
+use crate::error::{self, CheckerError};
+use crate::source::InlineSource;
+
+/// Run the rules of `configJson` against `documentsJson`, an object mapping
+/// each rule's `json_file` to its document, and return the results as a
+/// JSON array
+#[wasm_bindgen]
+pub fn check(config_json: &str, documents_json: &str) -> Result<String, JsError> {
+    check_json(config_json, documents_json).map_err(|e| JsError::new(&format!("{:#}", anyhow::Error::from(e))))
+}
+
+/// What [`check`] does, callable from Rust on any target
+pub fn check_json(config_json: &str, documents_json: &str) -> error::Result<String> {
+    let config = Config::from_json(config_json)?;
+    let documents: HashMap<String, Value> = serde_json::from_str(documents_json)
+        .map_err(|e| CheckerError::InvalidConfig(format!("documentsJson must map file names to documents: {}", e)))?;
+
+    let source = documents
+        .into_iter()
+        .fold(InlineSource::new(), |source, (name, document)| source.with_document(&name, document));
+    let results = JsonChecker::from_config(config).with_source("file", source).run()?;
+    serde_json::to_string(&results).map_err(|e| CheckerError::Other(e.into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_json() {
+        let config = r#"{"rules": [{"name": "Has users", "json_file": "users.json", "jsonpath": "$.users", "check": {"type": "non_empty"}}]}"#;
+        let results: Value = serde_json::from_str(&check_json(config, r#"{"users.json": {"users": [1]}}"#).unwrap()).unwrap();
+        assert_eq!(results[0]["status"], "pass");
+
+        let results: Value = serde_json::from_str(&check_json(config, "{}").unwrap()).unwrap();
+        assert_eq!(results[0]["status"], "error");
+        assert!(check_json("{", "{}").is_err());
+    }
+}
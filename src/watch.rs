@@ -0,0 +1,198 @@
+//! Watch mode: re-run rules when the config or referenced JSON files change
+
+use anyhow::{Context, Result};
+use notify::{Event, RecursiveMode, Watcher};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::Duration;
+
+use crate::checker::JsonChecker;
+use crate::config::{CheckResult, Config, Rule};
+use crate::source::local_path;
+
+/// How long to wait for more file events before re-running rules
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// A batch of results produced by watch mode
+pub struct WatchUpdate<'a> {
+    /// Files whose change triggered this run (empty for the initial run)
+    pub changed: &'a [PathBuf],
+    /// Results of the rules that were re-run
+    pub results: &'a [CheckResult],
+}
+
+/// Watch the config and every referenced JSON file, re-running affected rules on change.
+///
+/// A change to any config file reloads the configuration and re-runs all rules;
+/// a change to a JSON file only re-runs the rules reading it. Reload errors are
+/// passed to `on_error` and the previous configuration stays active. This only
+/// returns if the watcher fails.
+pub fn watch<F, E>(
+    config_path: &str,
+    vars: &HashMap<String, String>,
+    profile: Option<&str>,
+    mut on_update: F,
+    mut on_error: E,
+) -> Result<()>
+where
+    F: FnMut(WatchUpdate),
+    E: FnMut(anyhow::Error),
+{
+    let (tx, rx) = mpsc::channel::<notify::Result<Event>>();
+    let mut watcher = notify::recommended_watcher(tx).context("Failed to start file watcher")?;
+
+    let mut checker = JsonChecker::from_config(Config::from_file_with_profile(config_path, vars, profile)?);
+    let mut watched_dirs = HashSet::new();
+    watch_dirs(&mut watcher, &mut watched_dirs, checker.config())?;
+
+    let results = checker.run()?;
+    on_update(WatchUpdate { changed: &[], results: &results });
+
+    loop {
+        let Ok(first) = rx.recv() else {
+            return Ok(());
+        };
+        let mut events = vec![first];
+        while let Ok(event) = rx.recv_timeout(DEBOUNCE) {
+            events.push(event);
+        }
+
+        let mut changed: Vec<PathBuf> = Vec::new();
+        for event in events {
+            match event {
+                Ok(event) if !event.kind.is_access() => {
+                    for path in event.paths {
+                        let path = normalize(&path);
+                        if !changed.contains(&path) {
+                            changed.push(path);
+                        }
+                    }
+                }
+                Ok(_) => {}
+                Err(e) => on_error(e.into()),
+            }
+        }
+
+        let config_changed = changed.iter().any(|p| checker.config().files.contains(p));
+        if config_changed {
+            match Config::from_file_with_profile(config_path, vars, profile) {
+                Ok(config) => {
+                    checker = JsonChecker::from_config(config);
+                    watch_dirs(&mut watcher, &mut watched_dirs, checker.config())?;
+                }
+                Err(e) => {
+                    on_error(e.into());
+                    continue;
+                }
+            }
+        }
+
+        let rules = &checker.config().rules;
+        let affected: Vec<&Rule> = rules
+            .iter()
+            .filter(|rule| changed.iter().any(|path| reads_file(rule, path)))
+            .collect();
+        // Dependent rules need their prerequisites' results, so re-run everything
+        let uses_dependencies = rules.iter().any(|r| !r.depends_on.is_empty() || r.run_if.is_some());
+
+        let results: Vec<CheckResult> = if config_changed || (uses_dependencies && !affected.is_empty()) {
+            match checker.run() {
+                Ok(results) => results,
+                Err(e) => {
+                    on_error(e.into());
+                    continue;
+                }
+            }
+        } else {
+            affected.into_iter().map(|rule| checker.run_rule(rule)).collect()
+        };
+
+        if !results.is_empty() {
+            changed.retain(|p| config_changed || is_watched_file(checker.config(), p));
+            on_update(WatchUpdate { changed: &changed, results: &results });
+        }
+    }
+}
+
+/// Whether a path is one of the config files or a rule's JSON file
+fn is_watched_file(config: &Config, path: &Path) -> bool {
+    config.files.iter().any(|f| f == path)
+        || config.rules.iter().any(|r| reads_file(r, path))
+}
+
+/// Whether a rule reads the given file, directly or through a glob pattern
+fn reads_file(rule: &Rule, path: &Path) -> bool {
+    rule_files(rule).any(|file| {
+        file == path
+            || glob::Pattern::new(&file.to_string_lossy()).is_ok_and(|pattern| pattern.matches_path(path))
+    })
+}
+
+/// Normalized paths of the local JSON files a rule reads, including files its check references
+fn rule_files(rule: &Rule) -> impl Iterator<Item = PathBuf> + '_ {
+    rule.json_file
+        .as_deref()
+        .into_iter()
+        .chain(rule.check.referenced_file())
+        .filter_map(local_path)
+        .map(|f| normalize(Path::new(f)))
+}
+
+/// Watch the parent directory of every relevant file.
+///
+/// Directories are watched instead of files so editors that replace files on
+/// save (write to a temp file, then rename) keep triggering events.
+fn watch_dirs(
+    watcher: &mut impl Watcher,
+    watched: &mut HashSet<PathBuf>,
+    config: &Config,
+) -> Result<()> {
+    let files = config
+        .files
+        .iter()
+        .cloned()
+        .chain(config.rules.iter().flat_map(rule_files));
+
+    for file in files {
+        let Some(dir) = file.parent() else { continue };
+        if dir.is_dir() && watched.insert(dir.to_path_buf()) {
+            watcher
+                .watch(dir, RecursiveMode::NonRecursive)
+                .with_context(|| format!("Failed to watch directory: {}", dir.display()))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Make a path absolute and canonical so event paths and rule paths compare equal,
+/// even when the file itself does not exist (yet)
+pub fn normalize(path: &Path) -> PathBuf {
+    if let Ok(canonical) = path.canonicalize() {
+        return canonical;
+    }
+
+    match (path.parent(), path.file_name()) {
+        (Some(parent), Some(name)) => {
+            let parent = if parent.as_os_str().is_empty() { Path::new(".") } else { parent };
+            match parent.canonicalize() {
+                Ok(parent) => parent.join(name),
+                Err(_) => std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf()),
+            }
+        }
+        _ => std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_missing_file_uses_canonical_parent() {
+        let dir = std::env::temp_dir().canonicalize().unwrap();
+        let missing = std::env::temp_dir().join("json-checker-rs-watch-missing.json");
+        assert_eq!(normalize(&missing), dir.join("json-checker-rs-watch-missing.json"));
+    }
+}